@@ -1,8 +1,24 @@
 use clap::{Parser, ValueEnum};
 #[cfg(unix)]
-use fd_rdd::config::default_socket_path;
+use fd_rdd::config::{default_snapshot_path, default_socket_path};
 use std::path::PathBuf;
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum SortOrderArg {
+    Asc,
+    Desc,
+}
+
+impl SortOrderArg {
+    #[cfg(unix)]
+    fn as_protocol(self) -> &'static str {
+        match self {
+            Self::Asc => "asc",
+            Self::Desc => "desc",
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
 enum QueryModeArg {
     Exact,
@@ -35,10 +51,38 @@ struct Args {
     #[arg(long, value_enum, default_value_t = QueryModeArg::Exact)]
     mode: QueryModeArg,
 
+    /// 排序列：score / name / path / size / ext / dm / dc / da（默认按相关度）
+    #[arg(long, value_name = "COLUMN")]
+    sort: Option<String>,
+
+    /// 排序方向
+    #[arg(long, value_enum, default_value_t = SortOrderArg::Asc)]
+    order: SortOrderArg,
+
+    /// 以 NUL 分隔输出路径（配合 `xargs -0`，文件名含换行也安全）
+    #[arg(short = '0', long, conflicts_with = "json")]
+    print0: bool,
+
+    /// 每行输出一个 JSON 对象：path / size / mtime（unix 毫秒）/ is_dir / score / highlights
+    #[arg(long)]
+    json: bool,
+
     /// 若连不上 socket，尝试自动拉起 fd-rdd Daemon（`fd-rdd --uds-socket <PATH> --root <PATH>`）
     #[arg(long)]
     spawn: bool,
 
+    /// 不连接 daemon，直接只读加载快照并回放 WAL 后在进程内查询（结果可能过时）
+    #[arg(long, conflicts_with = "spawn")]
+    offline: bool,
+
+    /// 连不上 socket 时直接报错，不自动回退到离线查询
+    #[arg(long, conflicts_with = "offline")]
+    no_fallback: bool,
+
+    /// 离线查询读取的快照路径（需与 fd-rdd 的 --snapshot-path 一致）
+    #[arg(long, value_name = "PATH")]
+    snapshot_path: Option<PathBuf>,
+
     /// spawn 时传给 fd-rdd 的索引根目录（可重复；--spawn 时必须至少指定一个）
     #[arg(long = "root", value_name = "PATH")]
     roots: Vec<PathBuf>,
@@ -50,18 +94,32 @@ struct Args {
 #[cfg(unix)]
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    use fd_rdd::query::UDS_PROTOCOL_V1;
     use std::time::Duration;
-    use tokio::io::{self, AsyncWriteExt};
+    use tokio::io::{self, AsyncBufReadExt, AsyncWriteExt, BufReader};
     use tokio::net::UnixStream;
 
     let args = Args::parse();
-    let socket = args.socket.unwrap_or_else(default_socket_path);
-    let req = format!(
-        "q:{}\nlimit:{}\nmode:{}\n",
+    let socket = args.socket.clone().unwrap_or_else(default_socket_path);
+    let format = if args.json {
+        "json"
+    } else if args.print0 {
+        "nul"
+    } else {
+        "lines"
+    };
+    let body = format!(
+        "q:{}\nlimit:{}\nmode:{}\nsort:{}\norder:{}\nformat:{}\n",
         args.query,
         args.limit,
-        args.mode.as_protocol()
+        args.mode.as_protocol(),
+        args.sort.as_deref().unwrap_or("score"),
+        args.order.as_protocol(),
+        format
     );
+    if args.offline {
+        return run_offline(&args, &body, None);
+    }
 
     let mut stream = match UnixStream::connect(&socket).await {
         Ok(s) => s,
@@ -98,14 +156,86 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         }
+        Err(e) if !args.no_fallback => {
+            let reason = format!("cannot connect to {}: {}", socket.display(), e);
+            return run_offline(&args, &body, Some(&reason));
+        }
         Err(e) => return Err(e.into()),
     };
 
+    let req = format!("{UDS_PROTOCOL_V1}\n{body}");
     stream.write_all(req.as_bytes()).await?;
     stream.shutdown().await?;
 
+    // 首行为状态行：`FDRDD/1 ok` 或 `FDRDD/1 error <message>`
+    let mut reader = BufReader::new(stream);
+    let mut status = String::new();
+    reader.read_line(&mut status).await?;
+    let status = status.trim_end();
+    if let Some(msg) = status
+        .strip_prefix(UDS_PROTOCOL_V1)
+        .and_then(|rest| rest.trim_start().strip_prefix("error"))
+    {
+        anyhow::bail!("fd-rdd: {}", msg.trim());
+    }
+    if status != format!("{UDS_PROTOCOL_V1} ok") {
+        anyhow::bail!(
+            "unexpected response from {}: {:?}",
+            socket.display(),
+            status
+        );
+    }
+
     let mut stdout = io::stdout();
-    tokio::io::copy(&mut stream, &mut stdout).await?;
+    tokio::io::copy(&mut reader, &mut stdout).await?;
+    stdout.flush().await?;
+    Ok(())
+}
+
+/// 离线查询：只读加载快照 + WAL，在进程内走与 daemon 相同的请求解析与查询路径。
+/// 结果只反映最后一次落盘的状态，因此总是在 stderr 上提示可能过时。
+#[cfg(unix)]
+fn run_offline(args: &Args, body: &str, reason: Option<&str>) -> anyhow::Result<()> {
+    use fd_rdd::index::TieredIndex;
+    use std::io::Write;
+
+    let snapshot_path = args
+        .snapshot_path
+        .clone()
+        .unwrap_or_else(default_snapshot_path);
+    let Some((index, report)) = TieredIndex::load_read_only(&snapshot_path, args.roots.clone())?
+    else {
+        match reason {
+            Some(reason) => anyhow::bail!(
+                "{reason}; offline fallback found no snapshot for {}",
+                snapshot_path.display()
+            ),
+            None => anyhow::bail!("no usable snapshot for {}", snapshot_path.display()),
+        }
+    };
+
+    let age = report
+        .snapshot_mtime
+        .and_then(|t| t.elapsed().ok())
+        .map(|d| format!("saved {}s ago", d.as_secs()))
+        .unwrap_or_else(|| "save time unknown".to_string());
+    let mut warning = String::from("fd-rdd-query: ");
+    if let Some(reason) = reason {
+        warning.push_str(&format!("{reason}; "));
+    }
+    warning.push_str(&format!(
+        "offline results from {} snapshot {} ({}, {} WAL events replayed) may be stale",
+        report.snapshot_source,
+        report.snapshot_path.display(),
+        age,
+        report.wal_events_replayed
+    ));
+    eprintln!("{warning}");
+
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    fd_rdd::query::execute_v1_request(&index, body, &mut out)?;
+    out.flush()?;
     Ok(())
 }
 
@@ -4,8 +4,11 @@
 //! Config path: `~/.config/fd-rdd/config.toml`
 
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
+use crate::core::path_rules::PathRulesConfig;
+use crate::stats::query_profile::{DEFAULT_SLOW_QUERY_CAPACITY, DEFAULT_SLOW_QUERY_THRESHOLD_MS};
 use crate::util::{default_exclude_dirs, normalize_exclude_dirs};
 
 /// Returns the platform-appropriate default socket path (user-isolated).
@@ -145,6 +148,11 @@ pub struct Config {
     pub log_level: String,
     /// HTTP query port.
     pub http_port: u16,
+    /// HTTP listen address: `host:port` for TCP or `unix:<path>` for a Unix socket.
+    /// Takes precedence over `http_port`; unset keeps `127.0.0.1:<http_port>`.
+    pub http_bind: Option<String>,
+    /// HTTP API authentication (bearer token or cookie file).
+    pub http_auth: HttpAuthConfig,
     /// Snapshot write interval in seconds.
     pub snapshot_interval_secs: u64,
     /// Include hidden (dot) files.
@@ -163,14 +171,36 @@ pub struct Config {
     pub startup_repair_enabled: bool,
     /// Startup repair mode: `dirty-only`, `always`, or `never`.
     pub startup_repair_mode: String,
-    /// Maximum roots repaired during startup.
+    /// Directories scanned per startup repair batch (budget and progress are checked between batches).
     pub startup_repair_max_dirs: usize,
-    /// Soft startup repair budget in milliseconds. First phase records this as policy.
+    /// Foreground startup repair budget in milliseconds; remaining directories are repaired
+    /// in the background from a persisted cursor while queries are already served.
     pub startup_repair_budget_ms: u64,
     /// If repair failure ratio exceeds this value, full rebuild may be scheduled.
     pub startup_repair_force_rebuild_ratio: f32,
     /// Directory names that are never indexed, regardless of .gitignore rules.
     pub exclude_dirs: Vec<String>,
+    /// Glob patterns that are never indexed (`*.o`, `**/.terraform/**`). Patterns without `/`
+    /// match the file name; others match the path relative to its root (`/...` is absolute).
+    pub exclude_globs: Vec<String>,
+    /// Per-directory allow-lists: under each directory only files matching one of its globs are
+    /// indexed (`"~/media" = ["*.{jpg,mp4}"]`). Directories themselves are still indexed.
+    pub include_globs: BTreeMap<PathBuf, Vec<String>>,
+    /// Opt-in content trigram index backing `content:` queries (text files up to 10 MB).
+    pub content_index: bool,
+    /// Queries slower than this (total, in milliseconds) are kept in the `/slow-queries` log.
+    /// `0` records every query.
+    pub slow_query_threshold_ms: u64,
+    /// Capacity of the slow-query ring buffer. `0` disables the log.
+    pub slow_query_log_size: usize,
+    /// Run the background consistency auditor (sampled directory checks against the filesystem).
+    pub audit_enabled: bool,
+    /// Seconds between audit rounds.
+    pub audit_interval_secs: u64,
+    /// Indexed directories sampled per audit round.
+    pub audit_sample_dirs: usize,
+    /// Time budget of one audit round in milliseconds; the round stops early once it is spent.
+    pub audit_budget_ms: u64,
 }
 
 #[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
@@ -182,6 +212,31 @@ pub enum WatchMode {
     Off,
 }
 
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HttpAuthMode {
+    /// No authentication (legacy behaviour).
+    #[default]
+    None,
+    /// Static bearer tokens from `token` / `read_token`.
+    Token,
+    /// A random admin token is generated at startup and written to `cookie_path` (mode 0600).
+    Cookie,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HttpAuthConfig {
+    /// Authentication scheme for the HTTP API.
+    pub mode: HttpAuthMode,
+    /// Bearer token granting read and admin scopes (`token` mode).
+    pub token: Option<String>,
+    /// Bearer token granting only the read scope (`/scan` and `/trim` are refused).
+    pub read_token: Option<String>,
+    /// Cookie file location for `cookie` mode. Defaults to `http.cookie` next to the snapshot.
+    pub cookie_path: Option<PathBuf>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct TieredWatchConfig {
@@ -243,6 +298,8 @@ impl Default for Config {
             ignore_enabled: true,
             log_level: "info".to_string(),
             http_port: 6060,
+            http_bind: None,
+            http_auth: HttpAuthConfig::default(),
             snapshot_interval_secs: 300,
             include_hidden: false,
             follow_symlinks: false,
@@ -256,11 +313,28 @@ impl Default for Config {
             startup_repair_budget_ms: 10_000,
             startup_repair_force_rebuild_ratio: 0.25,
             exclude_dirs: default_exclude_dirs(),
+            exclude_globs: Vec::new(),
+            include_globs: BTreeMap::new(),
+            content_index: false,
+            slow_query_threshold_ms: DEFAULT_SLOW_QUERY_THRESHOLD_MS,
+            slow_query_log_size: DEFAULT_SLOW_QUERY_CAPACITY,
+            audit_enabled: true,
+            audit_interval_secs: 300,
+            audit_sample_dirs: 64,
+            audit_budget_ms: 200,
         }
     }
 }
 
 impl Config {
+    /// Index-time glob rules (`exclude_globs` / `include_globs`).
+    pub fn path_rules(&self) -> PathRulesConfig {
+        PathRulesConfig {
+            exclude_globs: self.exclude_globs.clone(),
+            include_globs: self.include_globs.clone(),
+        }
+    }
+
     /// Standard config file location: `~/.config/fd-rdd/config.toml`.
     pub fn config_path() -> Option<PathBuf> {
         dirs::config_dir().map(|d| d.join("fd-rdd").join("config.toml"))
@@ -288,6 +362,10 @@ impl Config {
             append_missing_exclude_dirs(path, &text, &cfg.exclude_dirs)?;
         }
         cfg.roots = cfg.roots.into_iter().map(expand_tilde_path).collect();
+        cfg.include_globs = std::mem::take(&mut cfg.include_globs)
+            .into_iter()
+            .map(|(dir, globs)| (expand_tilde_path(dir), globs))
+            .collect();
         cfg.tiered_watch.hot_dirs = cfg
             .tiered_watch
             .hot_dirs
@@ -297,6 +375,9 @@ impl Config {
         if let Some(socket) = cfg.socket_path.take() {
             cfg.socket_path = Some(expand_tilde_path(socket));
         }
+        if let Some(cookie) = cfg.http_auth.cookie_path.take() {
+            cfg.http_auth.cookie_path = Some(expand_tilde_path(cookie));
+        }
         Ok(cfg)
     }
 
@@ -387,6 +468,27 @@ http_port = 6060
         let _ = std::fs::remove_dir_all(root);
     }
 
+    #[test]
+    fn http_bind_and_auth_parse_from_config() {
+        let cfg: Config = toml::from_str(
+            r#"
+http_bind = "unix:/run/fd-rdd/http.sock"
+
+[http_auth]
+mode = "token"
+token = "admin-secret"
+read_token = "read-secret"
+"#,
+        )
+        .expect("config should parse");
+
+        assert_eq!(cfg.http_bind.as_deref(), Some("unix:/run/fd-rdd/http.sock"));
+        assert_eq!(cfg.http_auth.mode, HttpAuthMode::Token);
+        assert_eq!(cfg.http_auth.token.as_deref(), Some("admin-secret"));
+        assert_eq!(cfg.http_auth.read_token.as_deref(), Some("read-secret"));
+        assert_eq!(Config::default().http_auth.mode, HttpAuthMode::None);
+    }
+
     #[test]
     fn explicit_exclude_dirs_are_normalized_after_load_step() {
         let mut cfg: Config = toml::from_str(
@@ -1,8 +1,12 @@
 pub mod adaptive;
 pub mod lineage;
 pub mod partition;
+pub mod path_rules;
 pub mod rdd;
 
 pub use adaptive::{AdaptiveScheduler, ExecutionStrategy, Task};
 pub use lineage::{EventRecord, EventType, FileIdentifier};
-pub use rdd::{BuildLineage, BuildRDD, FileKey, FileKeyEntry, FileMeta, FsScanRDD, Partition};
+pub use rdd::{
+    link_count, BuildLineage, BuildRDD, FileKey, FileKeyEntry, FileKind, FileMeta, FsScanRDD,
+    LinkTarget, Partition,
+};
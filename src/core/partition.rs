@@ -1,2 +1,65 @@
-// v0.2: Partition 定义已移至 rdd.rs，此文件保留为兼容重导出
-pub use crate::core::rdd::Partition;
+//! 按 root 划分索引分区：每个 root 一个分区（独立的路径表、trigram posting 与快照段）。
+//!
+//! 路径按"最长 root 前缀"归属；不在任何 root 下的路径（例如 `/scan` 扫到的 root 外目录）
+//! 落入 `/` 兜底分区，与 `PersistentIndex` 的 `/` root_id 兜底保持一致。
+//!
+//! v0.2: Partition 定义已移至 rdd.rs，此处在其上补充分区路由。
+use std::path::{Path, PathBuf};
+
+pub use crate::core::rdd::Partition;
+
+/// 兜底分区的 root。
+pub const FALLBACK_PARTITION_ROOT: &str = "/";
+
+impl Partition {
+    pub fn for_root(id: usize, root: PathBuf) -> Self {
+        Self {
+            id,
+            root,
+            max_depth: 255,
+        }
+    }
+
+    pub fn is_fallback(&self) -> bool {
+        self.root == Path::new(FALLBACK_PARTITION_ROOT)
+    }
+
+    /// 快照段文件名使用的稳定键：root 路径字节的 xxh3（与 root 顺序无关，增删其他 root 不影响）。
+    pub fn segment_key(&self) -> String {
+        segment_key_for(&self.root)
+    }
+}
+
+pub fn segment_key_for(root: &Path) -> String {
+    format!(
+        "{:016x}",
+        xxhash_rust::xxh3::xxh3_64(root.as_os_str().as_encoded_bytes())
+    )
+}
+
+/// 按 roots 生成分区列表（去重，保持给定顺序，id 即下标）。
+pub fn partitions_for_roots(roots: &[PathBuf]) -> Vec<Partition> {
+    let mut out: Vec<Partition> = Vec::with_capacity(roots.len());
+    for root in roots {
+        if out.iter().any(|p| &p.root == root) {
+            continue;
+        }
+        out.push(Partition::for_root(out.len(), root.clone()));
+    }
+    out
+}
+
+/// 路径所属分区在 `roots` 中的下标：最长前缀匹配（roots 允许嵌套，内层优先）。
+pub fn route_path<'a>(roots: impl IntoIterator<Item = &'a Path>, path: &Path) -> Option<usize> {
+    let mut best: Option<(usize, usize)> = None;
+    for (i, root) in roots.into_iter().enumerate() {
+        if !path.starts_with(root) {
+            continue;
+        }
+        let len = root.as_os_str().len();
+        if best.is_none_or(|(_, best_len)| len > best_len) {
+            best = Some((i, len));
+        }
+    }
+    best.map(|(i, _)| i)
+}
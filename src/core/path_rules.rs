@@ -0,0 +1,262 @@
+//! 索引期 glob 规则：`exclude_globs` 全局排除，`include_globs` 按目录限定只收录匹配的文件。
+//!
+//! 模式写法（与 .gitignore 相近）：
+//! - 不含 `/` 的模式只匹配文件名，例如 `*.o`、`*.{jpg,mp4}`；
+//! - 以 `/` 开头的模式匹配绝对路径；
+//! - 其余模式匹配相对路径（exclude 相对于所属 root，include 相对于其目录键），
+//!   `*` 不跨目录，`**` 跨任意层，例如 `**/.terraform/**`。
+//!
+//! 被排除的目录连同子树一起跳过；include 只约束文件，目录照常遍历与收录。
+//! 扫描（FsScanRDD / fast sync / startup repair）与事件管道共用同一份规则。
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+/// 规则的配置形态；同时作为持久化的“上一次生效规则”，用于启动时比对增减。
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathRulesConfig {
+    #[serde(default)]
+    pub exclude_globs: Vec<String>,
+    #[serde(default)]
+    pub include_globs: BTreeMap<PathBuf, Vec<String>>,
+}
+
+impl PathRulesConfig {
+    pub fn is_empty(&self) -> bool {
+        self.exclude_globs.is_empty() && self.include_globs.values().all(|v| v.is_empty())
+    }
+
+    /// 从 `self` 换到 `next` 时可能重新可见的范围：`None` 表示无，
+    /// `Some(vec![])` 表示整个索引（删掉了某条 exclude），否则为放宽了 include 的目录。
+    pub fn loosened_scopes(&self, next: &PathRulesConfig) -> Option<Vec<PathBuf>> {
+        if self
+            .exclude_globs
+            .iter()
+            .any(|g| !next.exclude_globs.contains(g))
+        {
+            return Some(Vec::new());
+        }
+        let dirs: Vec<PathBuf> = self
+            .include_globs
+            .iter()
+            .filter(|(_, old)| !old.is_empty())
+            .filter(|(dir, old)| match next.include_globs.get(*dir) {
+                Some(new) if !new.is_empty() => new.iter().any(|g| !old.contains(g)),
+                _ => true,
+            })
+            .map(|(dir, _)| dir.clone())
+            .collect();
+        (!dirs.is_empty()).then_some(dirs)
+    }
+}
+
+/// 编译后的一组模式：按文件名 / 相对路径 / 绝对路径分别匹配。
+#[derive(Clone, Debug)]
+struct GlobPatterns {
+    name: GlobSet,
+    rel: GlobSet,
+    abs: GlobSet,
+}
+
+impl GlobPatterns {
+    fn new(patterns: &[String]) -> Result<Self, globset::Error> {
+        let mut name = GlobSetBuilder::new();
+        let mut rel = GlobSetBuilder::new();
+        let mut abs = GlobSetBuilder::new();
+        for pattern in patterns {
+            let pattern = pattern.trim();
+            if pattern.is_empty() {
+                continue;
+            }
+            let glob = GlobBuilder::new(pattern).literal_separator(true).build()?;
+            if pattern.starts_with('/') {
+                abs.add(glob);
+            } else if pattern.contains('/') {
+                rel.add(glob);
+            } else {
+                name.add(glob);
+            }
+        }
+        Ok(Self {
+            name: name.build()?,
+            rel: rel.build()?,
+            abs: abs.build()?,
+        })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.name.is_empty() && self.rel.is_empty() && self.abs.is_empty()
+    }
+
+    fn is_match(&self, base: Option<&Path>, path: &Path) -> bool {
+        if !self.name.is_empty() && path.file_name().is_some_and(|n| self.name.is_match(n)) {
+            return true;
+        }
+        if !self.rel.is_empty() {
+            if let Some(rel) = base.and_then(|b| path.strip_prefix(b).ok()) {
+                if !rel.as_os_str().is_empty() && self.rel.is_match(rel) {
+                    return true;
+                }
+            }
+        }
+        !self.abs.is_empty() && self.abs.is_match(path)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PathRules {
+    exclude: Option<GlobPatterns>,
+    /// 目录键最深者在前，首个前缀匹配即为生效的 include 范围。
+    include: Vec<(PathBuf, GlobPatterns)>,
+    config: PathRulesConfig,
+}
+
+impl PathRules {
+    pub fn new(config: PathRulesConfig) -> anyhow::Result<Self> {
+        let exclude = GlobPatterns::new(&config.exclude_globs)
+            .map_err(|e| anyhow::anyhow!("invalid exclude_globs: {e}"))?;
+        let mut include = Vec::with_capacity(config.include_globs.len());
+        for (dir, patterns) in &config.include_globs {
+            let set = GlobPatterns::new(patterns)
+                .map_err(|e| anyhow::anyhow!("invalid include_globs for {}: {e}", dir.display()))?;
+            if !set.is_empty() {
+                include.push((dir.clone(), set));
+            }
+        }
+        include.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.components().count()));
+        Ok(Self {
+            exclude: (!exclude.is_empty()).then_some(exclude),
+            include,
+            config,
+        })
+    }
+
+    pub fn config(&self) -> &PathRulesConfig {
+        &self.config
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exclude.is_none() && self.include.is_empty()
+    }
+
+    /// 条目本身命中 exclude（不看祖先）：供 walker 的 `filter_entry` 剪枝，祖先已在上层被剪掉。
+    pub fn excludes_entry(&self, root: Option<&Path>, path: &Path) -> bool {
+        self.exclude
+            .as_ref()
+            .is_some_and(|set| set.is_match(root, path))
+    }
+
+    /// 文件是否在 include 范围内（目录与 include 范围外的路径总是通过）。
+    pub fn includes_file(&self, path: &Path, is_dir: bool) -> bool {
+        if is_dir {
+            return true;
+        }
+        match self.include.iter().find(|(dir, _)| path.starts_with(dir)) {
+            Some((dir, set)) => set.is_match(Some(dir), path),
+            None => true,
+        }
+    }
+
+    /// 单条路径的完整判定（事件管道 / 重过滤用）：自身或 root 之下任一祖先被排除即拒绝。
+    /// `is_dir` 只在路径落入 include 范围时才求值。
+    pub fn allows(&self, root: Option<&Path>, path: &Path, is_dir: impl FnOnce() -> bool) -> bool {
+        if let Some(set) = &self.exclude {
+            let base = root.unwrap_or(Path::new("/"));
+            let rel = path.strip_prefix(base).unwrap_or(path);
+            let mut prefix = base.to_path_buf();
+            for component in rel.components() {
+                if !matches!(component, Component::Normal(_)) {
+                    continue;
+                }
+                prefix.push(component);
+                if set.is_match(root, &prefix) {
+                    return false;
+                }
+            }
+        }
+        if !self.include.iter().any(|(dir, _)| path.starts_with(dir)) {
+            return true;
+        }
+        self.includes_file(path, is_dir())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(exclude: &[&str], include: &[(&str, &[&str])]) -> PathRules {
+        PathRules::new(PathRulesConfig {
+            exclude_globs: exclude.iter().map(|s| s.to_string()).collect(),
+            include_globs: include
+                .iter()
+                .map(|(dir, globs)| {
+                    (
+                        PathBuf::from(dir),
+                        globs.iter().map(|s| s.to_string()).collect(),
+                    )
+                })
+                .collect(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn exclude_globs_match_names_relative_and_absolute_paths() {
+        let r = rules(
+            &["*.o", "**/.terraform/**", "build/*.log", "/srv/tmp/**"],
+            &[],
+        );
+        let root = Some(Path::new("/work"));
+        let dir = || false;
+        assert!(!r.allows(root, Path::new("/work/src/main.o"), dir));
+        assert!(r.allows(root, Path::new("/work/src/main.rs"), dir));
+        assert!(!r.allows(root, Path::new("/work/infra/.terraform/plugins/x"), dir));
+        assert!(!r.allows(root, Path::new("/work/build/out.log"), dir));
+        // `*` 不跨目录
+        assert!(r.allows(root, Path::new("/work/build/sub/out.log"), dir));
+        assert!(!r.allows(None, Path::new("/srv/tmp/cache/a"), dir));
+        // 被排除的目录连同子树
+        let r = rules(&["*.cache"], &[]);
+        assert!(r.excludes_entry(root, Path::new("/work/x.cache")));
+        assert!(!r.allows(root, Path::new("/work/x.cache/inner.txt"), dir));
+    }
+
+    #[test]
+    fn include_globs_restrict_files_only_inside_their_directory() {
+        let r = rules(&[], &[("/home/u/media", &["*.{jpg,mp4}"])]);
+        let root = Some(Path::new("/home/u"));
+        assert!(r.allows(root, Path::new("/home/u/media/a/trip.jpg"), || false));
+        assert!(!r.allows(root, Path::new("/home/u/media/a/notes.txt"), || false));
+        assert!(r.allows(root, Path::new("/home/u/media/a"), || true));
+        assert!(r.allows(root, Path::new("/home/u/docs/notes.txt"), || false));
+    }
+
+    #[test]
+    fn loosened_scopes_reports_removed_rules() {
+        let old = rules(&["*.o"], &[("/m", &["*.jpg"])]).config().clone();
+        let same = old.clone();
+        assert_eq!(old.loosened_scopes(&same), None);
+
+        let mut tighter = old.clone();
+        tighter.exclude_globs.push("*.pyc".into());
+        assert_eq!(old.loosened_scopes(&tighter), None);
+
+        let mut wider_include = old.clone();
+        wider_include
+            .include_globs
+            .insert(PathBuf::from("/m"), vec!["*.jpg".into(), "*.png".into()]);
+        assert_eq!(
+            old.loosened_scopes(&wider_include),
+            Some(vec![PathBuf::from("/m")])
+        );
+
+        assert_eq!(
+            old.loosened_scopes(&PathRulesConfig::default()),
+            Some(Vec::new())
+        );
+    }
+}
@@ -1,9 +1,10 @@
 #[cfg(feature = "rkyv")]
 use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 
+use crate::core::path_rules::PathRules;
 use crate::util::path_has_excluded_component;
 
 /// 文件身份：Linux 上用 (dev, ino, generation) 做主键，rename 时 ino 不变，
@@ -103,6 +104,79 @@ pub struct FileKeyEntry {
     pub doc_id: u64,
 }
 
+/// 条目类型：普通文件、目录或符号链接（目录与链接同样作为一等条目入索引）。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FileKind {
+    #[default]
+    File,
+    Dir,
+    Symlink,
+}
+
+impl FileKind {
+    pub fn from_file_type(ft: std::fs::FileType) -> Self {
+        if ft.is_dir() {
+            Self::Dir
+        } else if ft.is_symlink() {
+            Self::Symlink
+        } else {
+            Self::File
+        }
+    }
+
+    pub fn is_dir(self) -> bool {
+        self == Self::Dir
+    }
+
+    pub fn is_symlink(self) -> bool {
+        self == Self::Symlink
+    }
+}
+
+/// 符号链接条目的目标：`read_link` 原文，以及建索引时目标是否可达。
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkTarget {
+    pub path: PathBuf,
+    pub broken: bool,
+}
+
+impl LinkTarget {
+    /// 目标的绝对路径（相对目标按链接所在目录拼接，只做词法归一，不解析中间链接）。
+    pub fn resolve_from(&self, link: &Path) -> PathBuf {
+        let joined = match link.parent() {
+            Some(parent) if self.path.is_relative() => parent.join(&self.path),
+            _ => self.path.clone(),
+        };
+        let mut out = PathBuf::new();
+        for component in joined.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    out.pop();
+                }
+                other => out.push(other),
+            }
+        }
+        out
+    }
+}
+
+/// 文件的硬链接数（`st_nlink`）；目录的 nlink 含义不同（子目录数 + 2），记为 0（未知）。
+pub fn link_count(meta: &std::fs::Metadata) -> u32 {
+    if meta.is_dir() {
+        return 0;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        u32::try_from(meta.nlink()).unwrap_or(u32::MAX)
+    }
+    #[cfg(not(unix))]
+    {
+        0
+    }
+}
+
 /// 文件元数据
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FileMeta {
@@ -110,12 +184,70 @@ pub struct FileMeta {
     pub path: PathBuf,
     pub size: u64,
     pub mtime: Option<std::time::SystemTime>,
-    /// 文件创建时间（Linux 上为 ctime/inode-change-time；不持久化到快照）
+    /// 文件创建时间（Linux 上为 ctime/inode-change-time）。
+    /// serde 层跳过以兼容旧 bincode 快照；v7 快照经 `FileEntry` 时间列持久化。
     #[serde(default, skip_serializing)]
     pub ctime: Option<std::time::SystemTime>,
-    /// 最近访问时间（不持久化到快照）
+    /// 最近访问时间（持久化方式同 `ctime`）
     #[serde(default, skip_serializing)]
     pub atime: Option<std::time::SystemTime>,
+    /// 条目类型（v7 快照通过 EntriesByKey 的 kind 位持久化）
+    #[serde(default, skip_serializing)]
+    pub kind: FileKind,
+    /// 硬链接数（0 表示未知；v7 快照经 EntriesByKey 的 links 扩展持久化）。
+    /// 同一 inode 的每条路径都是独立条目，共享 `file_key`。
+    #[serde(default, skip_serializing)]
+    pub nlink: u32,
+    /// 符号链接的目标（仅 `kind == Symlink`；v7 快照经 EntriesByKey 的 symlinks 扩展持久化）
+    #[serde(default, skip_serializing)]
+    pub target: Option<LinkTarget>,
+}
+
+impl FileMeta {
+    /// 由 `symlink_metadata` 的结果构造条目：符号链接本身成为 `Symlink` 条目——
+    /// FileKey 取链接自身的 inode，size/时间取目标（目标不可达时取链接自身并记为 broken）。
+    pub fn from_lstat(path: &Path, lmeta: &std::fs::Metadata) -> Option<Self> {
+        if !lmeta.file_type().is_symlink() {
+            return Some(Self {
+                file_key: FileKey::from_path_and_metadata(path, lmeta)?,
+                path: path.to_path_buf(),
+                size: lmeta.len(),
+                mtime: lmeta.modified().ok(),
+                ctime: lmeta.created().ok(),
+                atime: lmeta.accessed().ok(),
+                kind: FileKind::from_file_type(lmeta.file_type()),
+                nlink: link_count(lmeta),
+                target: None,
+            });
+        }
+        let file_key = FileKey {
+            generation: 0,
+            ..FileKey::from_path_and_metadata(path, lmeta)?
+        };
+        let target = std::fs::read_link(path).ok()?;
+        let resolved = std::fs::metadata(path).ok();
+        let m = resolved.as_ref().unwrap_or(lmeta);
+        Some(Self {
+            file_key,
+            path: path.to_path_buf(),
+            size: m.len(),
+            mtime: m.modified().ok(),
+            ctime: m.created().ok(),
+            atime: m.accessed().ok(),
+            kind: FileKind::Symlink,
+            nlink: link_count(lmeta),
+            target: Some(LinkTarget {
+                path: target,
+                broken: resolved.is_none(),
+            }),
+        })
+    }
+
+    /// 按路径读取条目元数据（不跟随末级符号链接）；路径不存在时返回 `None`。
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let lmeta = std::fs::symlink_metadata(path).ok()?;
+        Self::from_lstat(path, &lmeta)
+    }
 }
 
 /// 分区定义（用于构建流水线）
@@ -150,6 +282,7 @@ pub struct FsScanRDD {
     follow_links: bool,
     ignore_enabled: bool,
     exclude_dirs: Vec<String>,
+    path_rules: Arc<PathRules>,
 }
 
 impl FsScanRDD {
@@ -170,6 +303,7 @@ impl FsScanRDD {
             follow_links: false,
             ignore_enabled: true,
             exclude_dirs: Vec::new(),
+            path_rules: Arc::new(PathRules::default()),
         }
     }
 
@@ -203,6 +337,12 @@ impl FsScanRDD {
         self
     }
 
+    /// 索引期 glob 规则（`exclude_globs` / `include_globs`）。
+    pub fn with_path_rules(mut self, path_rules: Arc<PathRules>) -> Self {
+        self.path_rules = path_rules;
+        self
+    }
+
     /// 按指定并行度遍历所有文件元数据（用于冷启动/重建的弹性构建）。
     ///
     /// 注意：这是 FsScanRDD 的专用入口，不改变 `BuildRDD` 的 Iterator 抽象，
@@ -227,6 +367,7 @@ impl FsScanRDD {
                 self.follow_links,
                 self.ignore_enabled,
                 self.exclude_dirs.clone(),
+                self.path_rules.clone(),
                 sink.clone(),
             );
         }
@@ -241,8 +382,6 @@ impl BuildRDD<FileMeta> for FsScanRDD {
     fn compute(&self, part: &Partition) -> Box<dyn Iterator<Item = FileMeta> + Send> {
         use ignore::WalkBuilder;
 
-        let mut visited: std::collections::HashSet<FileKey> = std::collections::HashSet::new();
-
         let mut builder = WalkBuilder::new(&part.root);
         builder
             .max_depth(Some(part.max_depth))
@@ -253,49 +392,45 @@ impl BuildRDD<FileMeta> for FsScanRDD {
             .git_global(self.ignore_enabled)
             .git_exclude(self.ignore_enabled);
         let exclude_dirs = self.exclude_dirs.clone();
-        if !exclude_dirs.is_empty() {
+        let path_rules = self.path_rules.clone();
+        let visits = self.follow_links.then(|| Arc::new(DirVisits::default()));
+        if !exclude_dirs.is_empty() || !path_rules.is_empty() || visits.is_some() {
+            let root = part.root.clone();
+            let visits = visits.clone();
             builder.filter_entry(move |entry| {
                 !path_has_excluded_component(entry.path(), &exclude_dirs)
+                    && !path_rules.excludes_entry(Some(&root), entry.path())
+                    && visits.as_deref().is_none_or(|v| v.first_visit(entry))
             });
         }
         let walker = builder.build();
-
+        let links = LinkEntryFilter {
+            root: part.root.clone(),
+            include_hidden: self.include_hidden,
+            exclude_dirs: self.exclude_dirs.clone(),
+            path_rules: self.path_rules.clone(),
+        };
+        let path_rules = self.path_rules.clone();
+
+        // 硬链接的每条路径都收录；重复到达的目录已在 filter_entry 中剪掉
         let iter = walker
-            .filter_map(|e| match e {
-                Ok(entry) => Some(entry),
-                Err(err) => {
-                    log_walk_error(&err);
-                    None
-                }
+            .filter_map(move |e| match e {
+                Ok(e) => (is_indexable_entry(&e)
+                    && path_rules.includes_file(e.path(), entry_is_dir(&e)))
+                .then(|| entry_meta(&e))
+                .flatten(),
+                Err(err) => links.meta_for_walk_error(&err),
             })
-            .filter(|e| e.file_type().map(|ft| ft.is_file()).unwrap_or(false))
-            .filter_map(move |e| {
-                let meta = match e.metadata() {
-                    Ok(meta) => meta,
-                    Err(err) => {
-                        log_metadata_error(e.path(), &err);
-                        return None;
-                    }
-                };
-                let file_key = FileKey::from_path_and_metadata(e.path(), &meta)?;
-                // ino+dev 去重：同一文件可能通过多条符号链接路径到达
-                if !visited.insert(file_key) {
-                    return None;
-                }
-                Some(FileMeta {
-                    file_key,
-                    path: e.path().to_path_buf(),
-                    size: meta.len(),
-                    mtime: meta.modified().ok(),
-                    ctime: meta.created().ok(),
-                    atime: meta.accessed().ok(),
-                })
-            });
+            .chain(
+                std::iter::from_fn(move || visits.as_ref().and_then(|v| v.pruned.lock().pop()))
+                    .filter_map(|p| FileMeta::from_path(&p)),
+            );
 
         Box::new(iter)
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn scan_partition_parallel(
     part: &Partition,
     parallelism: usize,
@@ -303,12 +438,11 @@ fn scan_partition_parallel(
     follow_links: bool,
     ignore_enabled: bool,
     exclude_dirs: Vec<String>,
+    path_rules: Arc<PathRules>,
     sink: Arc<dyn Fn(FileMeta) + Send + Sync>,
 ) {
     use ignore::{WalkBuilder, WalkState};
 
-    let visited: Arc<dashmap::DashSet<FileKey>> = Arc::new(dashmap::DashSet::new());
-
     let mut builder = WalkBuilder::new(&part.root);
     builder
         .max_depth(Some(part.max_depth))
@@ -319,53 +453,158 @@ fn scan_partition_parallel(
         .git_global(ignore_enabled)
         .git_exclude(ignore_enabled)
         .threads(parallelism);
-    if !exclude_dirs.is_empty() {
-        builder
-            .filter_entry(move |entry| !path_has_excluded_component(entry.path(), &exclude_dirs));
+    let visits = follow_links.then(|| Arc::new(DirVisits::default()));
+    let links = Arc::new(LinkEntryFilter {
+        root: part.root.clone(),
+        include_hidden,
+        exclude_dirs: exclude_dirs.clone(),
+        path_rules: path_rules.clone(),
+    });
+    if !exclude_dirs.is_empty() || !path_rules.is_empty() || visits.is_some() {
+        let root = part.root.clone();
+        let path_rules = path_rules.clone();
+        let visits = visits.clone();
+        builder.filter_entry(move |entry| {
+            !path_has_excluded_component(entry.path(), &exclude_dirs)
+                && !path_rules.excludes_entry(Some(&root), entry.path())
+                && visits.as_deref().is_none_or(|v| v.first_visit(entry))
+        });
     }
     let walker = builder.build_parallel();
 
     walker.run(|| {
         let sink = sink.clone();
-        let visited = visited.clone();
+        let path_rules = path_rules.clone();
+        let links = links.clone();
         Box::new(move |entry| {
             let e = match entry {
                 Ok(e) => e,
                 Err(err) => {
-                    log_walk_error(&err);
+                    if let Some(meta) = links.meta_for_walk_error(&err) {
+                        sink(meta);
+                    }
                     return WalkState::Continue;
                 }
             };
-            if !e.file_type().map(|ft| ft.is_file()).unwrap_or(false) {
+            if !is_indexable_entry(&e) || !path_rules.includes_file(e.path(), entry_is_dir(&e)) {
                 return WalkState::Continue;
             }
-            let meta = match e.metadata() {
-                Ok(meta) => meta,
-                Err(err) => {
-                    log_metadata_error(e.path(), &err);
-                    return WalkState::Continue;
-                }
-            };
-            let Some(file_key) = FileKey::from_path_and_metadata(e.path(), &meta) else {
-                return WalkState::Continue;
-            };
-            // ino+dev 去重：避免符号链接导致同一文件被多次索引
-            if !visited.insert(file_key) {
-                return WalkState::Continue;
+            if let Some(meta) = entry_meta(&e) {
+                sink(meta);
             }
-
-            sink(FileMeta {
-                file_key,
-                path: e.path().to_path_buf(),
-                size: meta.len(),
-                mtime: meta.modified().ok(),
-                ctime: meta.created().ok(),
-                atime: meta.accessed().ok(),
-            });
-
             WalkState::Continue
         })
     });
+
+    if let Some(visits) = visits {
+        for path in visits.pruned.lock().drain(..) {
+            if let Some(meta) = FileMeta::from_path(&path) {
+                sink(meta);
+            }
+        }
+    }
+}
+
+/// 跟随符号链接时的目录访问记录：同一目录（dev+ino）只遍历一次，不论先经链接还是经真实路径到达，
+/// 挡住环路与重复子树（否则其下文件会以两条路径各收录一次，看起来像硬链接）。
+/// 被剪掉的路径本身（链接或真实目录）留到遍历结束后作为独立条目补上。
+#[derive(Default)]
+struct DirVisits {
+    keys: dashmap::DashSet<FileKey>,
+    pruned: parking_lot::Mutex<Vec<PathBuf>>,
+}
+
+impl DirVisits {
+    fn first_visit(&self, entry: &ignore::DirEntry) -> bool {
+        if !entry_is_dir(entry) {
+            return true;
+        }
+        let Ok(meta) = entry.metadata() else {
+            return true;
+        };
+        let Some(key) = FileKey::from_path_and_metadata(entry.path(), &meta) else {
+            return true;
+        };
+        if self.keys.insert(key) {
+            return true;
+        }
+        self.pruned.lock().push(entry.path().to_path_buf());
+        false
+    }
+}
+
+/// 跟随符号链接时，目标不可达或成环的链接由 walker 以错误形式报告；
+/// 这些链接本身仍按 Symlink 条目收录（需通过与正常条目相同的过滤）。
+struct LinkEntryFilter {
+    root: PathBuf,
+    include_hidden: bool,
+    exclude_dirs: Vec<String>,
+    path_rules: Arc<PathRules>,
+}
+
+impl LinkEntryFilter {
+    fn meta_for_walk_error(&self, err: &ignore::Error) -> Option<FileMeta> {
+        let Some(path) = symlink_in_walk_error(err) else {
+            log_walk_error(err);
+            return None;
+        };
+        let hidden = path
+            .file_name()
+            .is_some_and(|n| n.as_encoded_bytes().starts_with(b"."));
+        if (hidden && !self.include_hidden)
+            || path_has_excluded_component(path, &self.exclude_dirs)
+            || !self.path_rules.allows(Some(&self.root), path, || false)
+        {
+            return None;
+        }
+        FileMeta::from_path(path)
+    }
+}
+
+fn symlink_in_walk_error(err: &ignore::Error) -> Option<&Path> {
+    let is_link = |p: &Path| {
+        std::fs::symlink_metadata(p)
+            .map(|m| m.file_type().is_symlink())
+            .unwrap_or(false)
+    };
+    match err {
+        ignore::Error::WithPath { path, err } => {
+            symlink_in_walk_error(err).or_else(|| is_link(path).then_some(path.as_path()))
+        }
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            symlink_in_walk_error(err)
+        }
+        ignore::Error::Loop { child, .. } => is_link(child).then_some(child.as_path()),
+        _ => None,
+    }
+}
+
+/// walker 条目 -> 元数据。符号链接（不跟随时的链接条目，或跟随时经由链接的路径）按链接本身收录。
+fn entry_meta(e: &ignore::DirEntry) -> Option<FileMeta> {
+    if e.path_is_symlink() {
+        return FileMeta::from_path(e.path());
+    }
+    let meta = match e.metadata() {
+        Ok(meta) => meta,
+        Err(err) => {
+            log_metadata_error(e.path(), &err);
+            return None;
+        }
+    };
+    FileMeta::from_lstat(e.path(), &meta)
+}
+
+/// 普通文件、目录与符号链接都入索引；分区根目录本身不作为条目。
+fn entry_is_dir(e: &ignore::DirEntry) -> bool {
+    e.file_type().is_some_and(|ft| ft.is_dir())
+}
+
+fn is_indexable_entry(e: &ignore::DirEntry) -> bool {
+    match e.file_type() {
+        Some(ft) if ft.is_file() => true,
+        Some(ft) if ft.is_dir() || ft.is_symlink() => e.depth() > 0,
+        _ => false,
+    }
 }
 
 fn log_walk_error(err: &ignore::Error) {
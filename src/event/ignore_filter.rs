@@ -2,105 +2,203 @@
 //!
 //! Wraps the `ignore` crate's gitignore matcher so that file-change events
 //! matching .gitignore rules are silently dropped before reaching the index.
+//!
+//! Nested ignore files are loaded lazily per directory and cached; when an
+//! ignore file itself changes, the pipeline calls [`IgnoreFilter::reload_for`]
+//! to drop the cached matcher and reconcile the affected subtree.
 
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use parking_lot::{Mutex, RwLock};
+
+/// Upper bound on cached per-directory matchers; the cache is simply reset when exceeded.
+const MAX_CACHED_DIRS: usize = 65_536;
 
-/// Filters file paths against .gitignore rules loaded from one or more root directories.
+/// Filters file paths against .gitignore rules under one or more root directories.
 ///
-/// Each root gets its own matcher built from:
-/// - `<root>/.gitignore`
-/// - `<root>/.ignore`
-/// - `<root>/.git/info/exclude`
+/// Every directory from a root down to the path's parent may contribute a matcher built from:
+/// - `<dir>/.gitignore`
+/// - `<dir>/.ignore`
+/// - `<dir>/.git/info/exclude`
 ///
-/// In addition, git's global ignore file (`core.excludesFile` / XDG fallback)
+/// Deeper directories take precedence over shallower ones (a nested `!pattern`
+/// can re-include what a parent ignores), and an ignored directory hides its
+/// whole subtree. Git's global ignore file (`core.excludesFile` / XDG fallback)
 /// is loaded once and applied to every path.
+///
+/// Clones share the same roots and matcher cache.
 #[derive(Clone)]
 pub struct IgnoreFilter {
-    matchers: Vec<(PathBuf, Gitignore)>,
+    inner: Arc<IgnoreFilterInner>,
+}
+
+struct IgnoreFilterInner {
+    /// Deepest root first, so the first prefix match is the owning root.
+    roots: RwLock<Vec<PathBuf>>,
+    /// Per-directory matcher; `None` means the directory has no ignore files.
+    dirs: Mutex<HashMap<PathBuf, Option<Arc<Gitignore>>>>,
     global: Gitignore,
 }
 
 impl IgnoreFilter {
-    /// Load ignore rules from each root directory plus git global ignore.
+    /// Track ignore rules under each root directory plus git global ignore.
     pub fn from_roots(roots: &[PathBuf]) -> Self {
-        let mut matchers = Vec::new();
-        for root in roots {
-            let mut builder = GitignoreBuilder::new(root);
-            let mut loaded_any = false;
-            for path in [
-                root.join(".gitignore"),
-                root.join(".ignore"),
-                root.join(".git").join("info").join("exclude"),
-            ] {
-                if !path.exists() {
-                    continue;
-                }
-                loaded_any = true;
-                if let Some(err) = builder.add(&path) {
-                    tracing::warn!(
-                        "Failed to load ignore rules from {}: {}",
-                        path.display(),
-                        err
-                    );
-                }
-            }
-            if !loaded_any {
-                continue;
-            }
-            match builder.build() {
-                Ok(gi) => matchers.push((root.clone(), gi)),
-                Err(e) => {
-                    tracing::warn!(
-                        "Failed to parse ignore rules under {}: {}",
-                        root.display(),
-                        e
-                    );
-                }
-            }
-        }
-        matchers.sort_by_key(|(root, _)| std::cmp::Reverse(root.components().count()));
-
         let (global, global_err) = Gitignore::global();
         if let Some(err) = global_err {
             tracing::warn!("Failed to load global gitignore rules: {}", err);
         }
 
-        IgnoreFilter { matchers, global }
+        let filter = IgnoreFilter {
+            inner: Arc::new(IgnoreFilterInner {
+                roots: RwLock::new(Vec::new()),
+                dirs: Mutex::new(HashMap::new()),
+                global,
+            }),
+        };
+        filter.set_roots(roots);
+        filter
+    }
+
+    /// Replace the root set (runtime root add/remove); cached matchers are dropped.
+    pub fn set_roots(&self, roots: &[PathBuf]) {
+        let mut roots = roots.to_vec();
+        roots.sort_by_key(|root| std::cmp::Reverse(root.components().count()));
+        *self.inner.roots.write() = roots;
+        self.inner.dirs.lock().clear();
     }
 
     /// Returns `true` if the given path should be ignored according to .gitignore rules.
     ///
-    /// The path is matched against the gitignore of the root it falls under.
-    /// If the path doesn't belong to any known root, it is *not* ignored.
+    /// The path is matched against the ignore files between the root it falls under
+    /// and its parent directory. If the path doesn't belong to any known root, it is
+    /// *not* ignored.
     pub fn is_ignored(&self, path: &Path) -> bool {
         let is_dir = path.is_dir();
 
-        if !self.global.is_empty() {
-            let global_root = self.global.path();
+        let global = &self.inner.global;
+        if !global.is_empty() {
+            let global_root = global.path();
             if path.starts_with(global_root) {
                 let rel = path.strip_prefix(global_root).unwrap_or(path);
-                if self
-                    .global
-                    .matched_path_or_any_parents(rel, is_dir)
-                    .is_ignore()
-                {
+                if global.matched_path_or_any_parents(rel, is_dir).is_ignore() {
                     return true;
                 }
             }
         }
 
-        for (root, gi) in &self.matchers {
-            if path.starts_with(root) {
-                let rel = path.strip_prefix(root).unwrap_or(path);
-                if gi.matched_path_or_any_parents(rel, is_dir).is_ignore() {
-                    return true;
+        let Some(root) = self.root_for(path) else {
+            return false;
+        };
+        let Ok(rel) = path.strip_prefix(&root) else {
+            return false;
+        };
+
+        // dirs[i] 是 prefixes[i] 的父目录；每个前缀由最深的有结论的 matcher 裁决。
+        let mut dirs: Vec<Option<Arc<Gitignore>>> = vec![self.matcher_for(&root)];
+        let mut prefix = root;
+        let mut components = rel.components().peekable();
+        while let Some(component) = components.next() {
+            prefix.push(component);
+            let prefix_is_dir = components.peek().is_some() || is_dir;
+            for gi in dirs.iter().rev().flatten() {
+                match gi.matched(&prefix, prefix_is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => break,
+                    Match::None => {}
                 }
             }
+            if components.peek().is_some() {
+                dirs.push(self.matcher_for(&prefix));
+            }
         }
         false
     }
+
+    /// If `path` is an ignore file, drop the cached matcher of the directory it
+    /// governs and return that directory (whose subtree needs reconciling).
+    pub fn reload_for(&self, path: &Path) -> Option<PathBuf> {
+        let dir = ignore_file_dir(path)?;
+        self.root_for(&dir)?;
+        self.inner.dirs.lock().remove(&dir);
+        Some(dir)
+    }
+
+    fn root_for(&self, path: &Path) -> Option<PathBuf> {
+        self.inner
+            .roots
+            .read()
+            .iter()
+            .find(|root| path.starts_with(root))
+            .cloned()
+    }
+
+    fn matcher_for(&self, dir: &Path) -> Option<Arc<Gitignore>> {
+        if let Some(cached) = self.inner.dirs.lock().get(dir) {
+            return cached.clone();
+        }
+        let loaded = load_dir_matcher(dir).map(Arc::new);
+        let mut dirs = self.inner.dirs.lock();
+        if dirs.len() >= MAX_CACHED_DIRS {
+            dirs.clear();
+        }
+        dirs.insert(dir.to_path_buf(), loaded.clone());
+        loaded
+    }
+}
+
+/// The directory whose rules an ignore file defines, if `path` is one.
+fn ignore_file_dir(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?;
+    if name == ".gitignore" || name == ".ignore" {
+        return path.parent().map(Path::to_path_buf);
+    }
+    let info = path.parent()?;
+    let git = info.parent()?;
+    if name == "exclude" && info.file_name()? == "info" && git.file_name()? == ".git" {
+        return git.parent().map(Path::to_path_buf);
+    }
+    None
+}
+
+fn load_dir_matcher(dir: &Path) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new(dir);
+    let mut loaded_any = false;
+    for path in [
+        dir.join(".gitignore"),
+        dir.join(".ignore"),
+        dir.join(".git").join("info").join("exclude"),
+    ] {
+        if !path.is_file() {
+            continue;
+        }
+        loaded_any = true;
+        if let Some(err) = builder.add(&path) {
+            tracing::warn!(
+                "Failed to load ignore rules from {}: {}",
+                path.display(),
+                err
+            );
+        }
+    }
+    if !loaded_any {
+        return None;
+    }
+    match builder.build() {
+        Ok(gi) if !gi.is_empty() => Some(gi),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::warn!(
+                "Failed to parse ignore rules under {}: {}",
+                dir.display(),
+                e
+            );
+            None
+        }
+    }
 }
 
 #[cfg(test)]
@@ -169,4 +267,50 @@ mod tests {
 
         let _ = fs::remove_dir_all(root);
     }
+
+    #[test]
+    fn nested_gitignore_applies_to_its_subtree() {
+        let root = unique_tmp_dir("ignore-nested");
+        let sub = root.join("sub");
+        fs::create_dir_all(sub.join("gen")).unwrap();
+        fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(sub.join(".gitignore"), "gen/\n*.tmp\n!keep.log\n").unwrap();
+
+        let filter = IgnoreFilter::from_roots(std::slice::from_ref(&root));
+        assert!(filter.is_ignored(&sub.join("gen").join("out.rs")));
+        assert!(filter.is_ignored(&sub.join("x.tmp")));
+        assert!(!filter.is_ignored(&root.join("x.tmp")));
+        assert!(filter.is_ignored(&sub.join("app.log")));
+        // A nested negation re-includes what the parent ignores.
+        assert!(!filter.is_ignored(&sub.join("keep.log")));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn reload_for_picks_up_changed_ignore_file() {
+        let root = unique_tmp_dir("ignore-reload");
+        let sub = root.join("sub");
+        fs::create_dir_all(&sub).unwrap();
+
+        let filter = IgnoreFilter::from_roots(std::slice::from_ref(&root));
+        let shared = filter.clone();
+        assert!(!filter.is_ignored(&sub.join("x.tmp")));
+
+        let gitignore = sub.join(".gitignore");
+        fs::write(&gitignore, "*.tmp\n").unwrap();
+        // Cached "no rules" until the change is reported.
+        assert!(!filter.is_ignored(&sub.join("x.tmp")));
+        assert_eq!(shared.reload_for(&gitignore), Some(sub.clone()));
+        assert!(filter.is_ignored(&sub.join("x.tmp")));
+
+        fs::remove_file(&gitignore).unwrap();
+        assert_eq!(filter.reload_for(&gitignore), Some(sub.clone()));
+        assert!(!shared.is_ignored(&sub.join("x.tmp")));
+
+        assert_eq!(filter.reload_for(&sub.join("main.rs")), None);
+        assert_eq!(filter.reload_for(Path::new("/elsewhere/.gitignore")), None);
+
+        let _ = fs::remove_dir_all(root);
+    }
 }
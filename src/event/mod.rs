@@ -2,7 +2,9 @@ pub mod ignore_filter;
 pub mod stream;
 pub mod sync;
 pub mod tiered_watch;
+pub mod verify;
 pub mod watcher;
 
 pub use stream::{EventPipeline, WatchCommand};
 pub use tiered_watch::TieredWatchRuntime;
+pub use verify::ElasticVerifier;
@@ -8,6 +8,7 @@ use std::time::{Duration, Instant};
 use crate::core::{EventRecord, EventType, FileIdentifier};
 use crate::event::ignore_filter::IgnoreFilter;
 use crate::event::tiered_watch::TieredWatchRuntime;
+use crate::event::verify::ElasticVerifier;
 use crate::event::watcher::{check_inotify_limit, watch_roots_enhanced, EventWatcher};
 use crate::index::TieredIndex;
 use crate::stats::EventPipelineStats;
@@ -36,6 +37,8 @@ where
 pub enum WatchCommand {
     Add(PathBuf),
     Remove(PathBuf),
+    /// 运行期新增的 root：只注册递归监听，子树已由 `TieredIndex::add_root` 扫描过。
+    AddRoot(PathBuf),
 }
 
 type WatchCommandRx = Arc<Mutex<Option<tokio::sync::mpsc::Receiver<WatchCommand>>>>;
@@ -63,6 +66,8 @@ pub struct EventPipeline {
     watch_command_tx: tokio::sync::mpsc::Sender<WatchCommand>,
     watch_command_rx: WatchCommandRx,
     tiered_runtime: Option<Arc<TieredWatchRuntime>>,
+    /// 持续校验：比对批次前后的 WAL 日志位置，Rescan（队列溢出）信号转为补扫
+    verifier: Option<Arc<ElasticVerifier>>,
     /// 共享计数器：累计处理事件数
     pub total_events: Arc<AtomicU64>,
     /// 共享计数器：最近批次大小
@@ -99,6 +104,7 @@ impl EventPipeline {
             watch_command_tx,
             watch_command_rx,
             tiered_runtime: None,
+            verifier: None,
             total_events: Arc::new(AtomicU64::new(0)),
             last_batch_size: Arc::new(AtomicU64::new(0)),
             overflow_drops: Arc::new(AtomicU64::new(0)),
@@ -125,6 +131,7 @@ impl EventPipeline {
             watch_command_tx,
             watch_command_rx,
             tiered_runtime: None,
+            verifier: None,
             total_events: Arc::new(AtomicU64::new(0)),
             last_batch_size: Arc::new(AtomicU64::new(0)),
             overflow_drops: Arc::new(AtomicU64::new(0)),
@@ -156,6 +163,7 @@ impl EventPipeline {
             watch_command_tx,
             watch_command_rx,
             tiered_runtime: None,
+            verifier: None,
             total_events: Arc::new(AtomicU64::new(0)),
             last_batch_size: Arc::new(AtomicU64::new(0)),
             overflow_drops: Arc::new(AtomicU64::new(0)),
@@ -190,6 +198,11 @@ impl EventPipeline {
         self
     }
 
+    pub fn with_verifier(mut self, verifier: Option<Arc<ElasticVerifier>>) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
     pub fn watch_command_sender(&self) -> tokio::sync::mpsc::Sender<WatchCommand> {
         self.watch_command_tx.clone()
     }
@@ -221,7 +234,7 @@ impl EventPipeline {
         let roots = self
             .watch_roots
             .clone()
-            .unwrap_or_else(|| self.index.roots.clone());
+            .unwrap_or_else(|| self.index.roots());
         let overflow_drops = self.overflow_drops.clone();
         let rescan_signals = self.rescan_signals.clone();
         let keep_cap = self.channel_size.max(256);
@@ -233,6 +246,7 @@ impl EventPipeline {
                 rescan_signals.clone(),
             )?;
         let tiered_runtime = self.tiered_runtime.clone();
+        let verifier = self.verifier.clone();
         // inotify watch 数兜底检查
         check_inotify_limit(roots.len());
         let failed_roots = watch_roots_enhanced(&mut watcher, &roots);
@@ -358,6 +372,22 @@ impl EventPipeline {
                                     }
                                 }
                             }
+                            Some(WatchCommand::AddRoot(path)) => {
+                                match watcher.watch(path.as_path(), notify::RecursiveMode::Recursive) {
+                                    Ok(()) => {
+                                        if let Some(runtime) = tiered_runtime.as_ref() {
+                                            runtime.confirm_promoted(path.as_path());
+                                        }
+                                    }
+                                    Err(e) => {
+                                        watch_failures.fetch_add(1, Ordering::Relaxed);
+                                        if let Some(runtime) = tiered_runtime.as_ref() {
+                                            runtime.rollback_promote(path.as_path());
+                                        }
+                                        tracing::warn!("watcher add failed for new root {:?}: {}", path, e);
+                                    }
+                                }
+                            }
                             None => {}
                         }
                         continue;
@@ -429,6 +459,24 @@ impl EventPipeline {
                     }
                 }
 
+                // 内核队列溢出：本窗口之前的事件已丢失，交给校验器按时间点补扫。
+                if let Some(verifier) = verifier.as_ref() {
+                    if raw_events.iter().any(|ev| ev.need_rescan()) {
+                        verifier.note_rescan();
+                    }
+                }
+
+                // 忽略文件自身变化：失效该目录的缓存规则，子树对账（补删 / 补回）交给后台线程。
+                // 需在排除过滤前执行，否则 `.git/info/exclude` 的变化会被 `.git` 排除规则吞掉。
+                if let Some(ref gi) = ignore_filter {
+                    let ignore_dirs: Vec<PathBuf> = raw_events
+                        .iter()
+                        .flat_map(|ev| ev.paths.iter())
+                        .filter_map(|p| gi.reload_for(p))
+                        .collect();
+                    index.spawn_ignore_reconcile(ignore_dirs, gi);
+                }
+
                 // 过滤：全局目录排除和索引自身写入路径，必须在动态 watch / fast path 前执行。
                 raw_events.retain(|ev| {
                     !should_ignore_event(ev, &ignore_paths)
@@ -437,6 +485,8 @@ impl EventPipeline {
                             .iter()
                             .any(|p| path_has_excluded_component(p, &exclude_dirs))
                 });
+                // 索引期 glob 规则（exclude_globs / include_globs），与扫描侧同一份。
+                raw_events.retain(|ev| ev.paths.iter().all(|p| index.path_allowed(p)));
                 if let Some(ref gi) = ignore_filter {
                     raw_events.retain(|ev| !ev.paths.iter().any(|p| gi.is_ignored(p)));
                 }
@@ -556,7 +606,11 @@ impl EventPipeline {
                         }
                     }
                     if !fast_records.is_empty() {
+                        let mark = verifier.as_ref().and_then(|v| v.begin_batch(&fast_records));
                         index.apply_events(&fast_records);
+                        if let Some(verifier) = verifier.as_ref() {
+                            verifier.verify_and_repair(mark);
+                        }
                         total_events.fetch_add(fast_records.len() as u64, Ordering::Relaxed);
                         last_batch_size.store(fast_records.len() as u64, Ordering::Relaxed);
                     }
@@ -634,7 +688,13 @@ impl EventPipeline {
                         merged_count,
                         total_events.load(Ordering::Relaxed) + merged_count as u64
                     );
+                    let mark = verifier
+                        .as_ref()
+                        .and_then(|v| v.begin_batch(&merge_scratch.records));
                     index.apply_events_drain(&mut merge_scratch.records);
+                    if let Some(verifier) = verifier.as_ref() {
+                        verifier.verify_and_repair(mark);
+                    }
                     total_events.fetch_add(merged_count as u64, Ordering::Relaxed);
                     last_batch_size.store(merged_count as u64, Ordering::Relaxed);
                 }
@@ -715,6 +775,7 @@ fn cleanup_pending_moves(pending: &mut PendingMoveMap) {
 fn merge_events_in_place(seq: &mut u64, raw: &mut Vec<notify::Event>, scratch: &mut MergeScratch) {
     scratch.merged.clear();
     scratch.records.clear();
+    let seq_base = *seq;
     let now = std::time::SystemTime::now();
 
     for ev in raw.drain(..) {
@@ -787,6 +848,11 @@ fn merge_events_in_place(seq: &mut u64, raw: &mut Vec<notify::Event>, scratch: &
             path_hint: v.path_hint,
         }));
     scratch.records.sort_by_key(|r| r.seq);
+    // 被合并掉的事件不占序号：批次内按顺序重新连续编号，管道序号即已应用的事件数。
+    for (i, record) in scratch.records.iter_mut().enumerate() {
+        record.seq = seq_base + 1 + i as u64;
+    }
+    *seq = seq_base + scratch.records.len() as u64;
 }
 
 #[cfg(test)]
@@ -889,4 +955,31 @@ mod tests {
             _ => panic!("expected rename event type"),
         }
     }
+
+    #[test]
+    fn merge_assigns_contiguous_seqs_across_batches() {
+        let mut seq: u64 = 0;
+        let mut raw: Vec<notify::Event> = Vec::new();
+        let mut scratch = MergeScratch::default();
+        let a = PathBuf::from("/tmp/a.txt");
+        let b = PathBuf::from("/tmp/b.txt");
+
+        for path in [&a, &a, &b, &a] {
+            raw.push(mk_event(
+                notify::EventKind::Modify(notify::event::ModifyKind::Any),
+                vec![path.clone()],
+            ));
+        }
+        merge_events_in_place(&mut seq, &mut raw, &mut scratch);
+        let seqs: Vec<u64> = scratch.records.iter().map(|r| r.seq).collect();
+        assert_eq!(seqs, vec![1, 2]);
+        assert_eq!(seq, 2);
+
+        raw.push(mk_event(
+            notify::EventKind::Modify(notify::event::ModifyKind::Any),
+            vec![b],
+        ));
+        merge_events_in_place(&mut seq, &mut raw, &mut scratch);
+        assert_eq!(scratch.records[0].seq, 3);
+    }
 }
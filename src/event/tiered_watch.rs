@@ -246,6 +246,38 @@ impl TieredWatchRuntime {
         self.try_reserve_promotion(path.as_path())
     }
 
+    /// 移除 root 时丢弃其子树下的全部目录状态并释放 L0 预算；返回需要 unwatch 的 L0 目录。
+    pub fn forget_under(&self, root: &Path) -> Vec<PathBuf> {
+        let mut dirs = self.dirs.write();
+        let forgotten = dirs
+            .keys()
+            .filter(|path| path_is_under_or_equal(path, root))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut watched = Vec::new();
+        for path in forgotten {
+            let Some(state) = dirs.remove(&path) else {
+                continue;
+            };
+            if state.tier() == WatchTier::L0 || state.promotion_pending.load(Ordering::Relaxed) {
+                let cost = state.watch_cost.load(Ordering::Relaxed);
+                let _ = self.current_watch_cost.fetch_update(
+                    Ordering::AcqRel,
+                    Ordering::Relaxed,
+                    |current| Some(current.saturating_sub(cost)),
+                );
+            }
+            if state.tier() == WatchTier::L0 {
+                watched.push(path);
+            }
+        }
+        if !watched.is_empty() {
+            self.last_adjustment_unix_secs
+                .store(unix_secs(), Ordering::Relaxed);
+        }
+        watched
+    }
+
     pub fn record_scan(&self, path: &Path, outcome: ScanOutcome) {
         if let Some(state) = self.state(path) {
             state
@@ -527,4 +559,26 @@ mod tests {
             .iter()
             .any(|note| note.contains("blocked by watch budget")));
     }
+
+    #[test]
+    fn forget_under_drops_subtree_and_releases_budget() {
+        let rt = runtime();
+        let child = PathBuf::from("/tmp/hot/child");
+        assert_eq!(
+            rt.register_dynamic_candidate(child.clone(), 1),
+            PromotionDecision::SendAdd
+        );
+        rt.confirm_promoted(child.as_path());
+        assert_eq!(rt.report().watched_dirs_estimated, 3);
+
+        let mut watched = rt.forget_under(Path::new("/tmp/hot"));
+        watched.sort();
+        assert_eq!(watched, vec![PathBuf::from("/tmp/hot"), child]);
+
+        let report = rt.report();
+        assert_eq!(report.l0_dirs, 0);
+        assert_eq!(report.l1_dirs, 1);
+        assert_eq!(report.watched_dirs_estimated, 0);
+        assert_eq!(rt.l1_batch(10), vec![PathBuf::from("/tmp/warm")]);
+    }
 }
@@ -1,39 +1,80 @@
 use crate::core::EventRecord;
 use crate::event::sync::DirtyScope;
 use crate::index::TieredIndex;
+use parking_lot::Mutex;
 use std::collections::BTreeSet;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// 批次应用前的日志位置：应用后日志序号应至少前进 `events` 条
+/// （symlink / rename 展开只会多写，不会少写）。
+#[derive(Clone, Copy, Debug)]
+pub struct JournalMark {
+    pub journal_seq: u64,
+    pub events: u64,
+}
 
+/// 日志缺口：批次已应用，但 WAL 只前进到 `observed_seq`。
 #[derive(Clone, Debug)]
 pub struct GapRecord {
     pub expected_seq: u64,
     pub observed_seq: u64,
     pub missing_count: u64,
-    pub scope: DirtyScope,
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct VerifyReport {
     pub last_verified_seq: u64,
-    pub repaired_gap: Option<GapRecord>,
     pub dirs_scanned: usize,
     pub upsert_events: usize,
     pub delete_events: usize,
 }
 
+/// 持续校验的累计结果（`/health` 展示）。
+#[derive(Clone, Debug, Default)]
+pub struct AuditReport {
+    /// 已完成的抽查轮数
+    pub audits: u64,
+    /// 最近一轮抽查完成时间（unix 秒，0 = 尚未抽查）
+    pub last_audit_unix_secs: u64,
+    /// 最近一轮抽查的目录数
+    pub last_dirs: usize,
+    /// 最近一轮比对的条目数
+    pub last_checked: usize,
+    /// 最近一轮发现漂移的条目数
+    pub last_drifted: usize,
+    /// 最近一轮的漂移率（drifted / checked）
+    pub drift_ratio: f64,
+    /// 累计漂移条目数
+    pub total_drifted: u64,
+    /// 累计收到的 watcher 队列溢出（Rescan）信号：内核丢弃的事件不会占用序号，只能由溢出信号得知
+    pub overflow_rescans: u64,
+    /// 累计检测到的日志缺口次数（批次已应用但 WAL 追加失败，每次触发一次快照落盘）
+    pub journal_gaps: u64,
+    /// 累计缺失的日志记录数
+    pub journal_missing: u64,
+    /// 累计执行的修复 fast-sync 次数（并发的修复请求合并为一次）
+    pub repairs_triggered: u64,
+}
+
 /// 弹性校验：
 /// - 维护最近一次已验证的事件序号
-/// - 检测批次中的序号缺口
-/// - 将缺口降级为 fast-sync repair，而不是直接放任漂移
+/// - 比对批次应用前后的 WAL 日志位置，日志缺口转为强制快照落盘
+/// - watcher 队列溢出降级为 fast-sync 补扫，而不是直接放任漂移
+/// - 周期性抽查已索引目录与文件系统是否一致，不一致的目录定向 fast-sync
+/// - 修复串行执行：同一时刻至多一个修复线程，期间到达的请求合并进待办 scope
 pub struct ElasticVerifier {
     pub index: Arc<TieredIndex>,
     ignore_prefixes: Vec<PathBuf>,
-    targeted_dir_limit: usize,
     last_verified_seq: AtomicU64,
     last_verified_ts_ns: AtomicU64,
+    /// 构造时间：尚未应用任何批次时的溢出补扫起点
+    started_ns: u64,
+    audit: Mutex<AuditReport>,
+    pending_repair: Mutex<Option<DirtyScope>>,
+    repair_in_flight: AtomicBool,
 }
 
 impl ElasticVerifier {
@@ -41,9 +82,12 @@ impl ElasticVerifier {
         Self {
             index,
             ignore_prefixes: Vec::new(),
-            targeted_dir_limit: 16,
             last_verified_seq: AtomicU64::new(0),
             last_verified_ts_ns: AtomicU64::new(0),
+            started_ns: system_time_to_ns(SystemTime::now()).unwrap_or(0),
+            audit: Mutex::new(AuditReport::default()),
+            pending_repair: Mutex::new(None),
+            repair_in_flight: AtomicBool::new(false),
         }
     }
 
@@ -52,86 +96,101 @@ impl ElasticVerifier {
         self
     }
 
-    pub fn with_targeted_dir_limit(mut self, limit: usize) -> Self {
-        self.targeted_dir_limit = limit.max(1);
-        self
-    }
-
     pub fn last_verified_seq(&self) -> u64 {
         self.last_verified_seq.load(Ordering::Relaxed)
     }
 
-    pub fn verify_gap(&self, events: &[EventRecord]) -> Option<GapRecord> {
+    /// 事件管道在批次入索引前调用：记录批次的序号与时间（溢出补扫的起点），并记下当前日志位置。
+    /// 未挂载 WAL 时返回 None，不做日志缺口校验。
+    pub fn begin_batch(&self, events: &[EventRecord]) -> Option<JournalMark> {
+        self.checkpoint(events);
         if events.is_empty() {
             return None;
         }
-
-        let mut last_seen = self.last_verified_seq();
-        for ev in events {
-            if last_seen == 0 {
-                last_seen = ev.seq;
-                continue;
-            }
-
-            if ev.seq <= last_seen {
-                continue;
-            }
-
-            let expected = last_seen.saturating_add(1);
-            if ev.seq > expected {
-                return Some(GapRecord {
-                    expected_seq: expected,
-                    observed_seq: ev.seq,
-                    missing_count: ev.seq.saturating_sub(expected),
-                    scope: self.scope_from_events(events),
-                });
-            }
-
-            last_seen = ev.seq;
-        }
-
-        None
+        Some(JournalMark {
+            journal_seq: self.index.change_cursor()?,
+            events: events.len() as u64,
+        })
     }
 
-    pub fn verify_and_repair(&self, events: &[EventRecord]) -> VerifyReport {
-        let mut report = VerifyReport::default();
+    /// 比对批次应用后的日志位置：日志前进少于批次事件数，说明有事件进了内存索引却没写进 WAL。
+    /// 并发写入（修复、扫描）只会让日志多前进，因此这里不会误报。
+    pub fn verify_gap(&self, mark: JournalMark) -> Option<GapRecord> {
+        let expected = mark.journal_seq.saturating_add(mark.events);
+        let observed = self.index.change_cursor()?;
+        (observed < expected).then(|| GapRecord {
+            expected_seq: expected,
+            observed_seq: observed,
+            missing_count: expected - observed,
+        })
+    }
 
-        if let Some(gap) = self.verify_gap(events) {
-            tracing::warn!(
-                "ElasticVerifier: sequence gap detected (expected={}, observed={}, missing={}), starting fast-sync repair",
-                gap.expected_seq,
-                gap.observed_seq,
-                gap.missing_count
-            );
-            let sync = self
-                .index
-                .fast_sync(gap.scope.clone(), &self.ignore_prefixes);
-            report.dirs_scanned = sync.dirs_scanned;
-            report.upsert_events = sync.upsert_events;
-            report.delete_events = sync.delete_events;
-            report.repaired_gap = Some(gap);
+    /// 批次应用后调用：日志有缺口时强制落盘快照。内存索引已含这些事件，
+    /// 快照覆盖之后重启回放不再依赖缺失的日志记录。
+    pub fn verify_and_repair(&self, mark: Option<JournalMark>) -> Option<GapRecord> {
+        let gap = self.verify_gap(mark?)?;
+        tracing::warn!(
+            "ElasticVerifier: journal gap detected (expected={}, observed={}, missing={}), requesting snapshot flush",
+            gap.expected_seq,
+            gap.observed_seq,
+            gap.missing_count
+        );
+        {
+            let mut audit = self.audit.lock();
+            audit.journal_gaps += 1;
+            audit.journal_missing += gap.missing_count;
         }
-
-        self.checkpoint(events);
-        report.last_verified_seq = self.last_verified_seq();
-        report
+        self.index.request_flush();
+        Some(gap)
     }
 
     pub fn repair_scope(&self, scope: DirtyScope) -> VerifyReport {
         let sync = self.index.fast_sync(scope.clone(), &self.ignore_prefixes);
         VerifyReport {
             last_verified_seq: self.last_verified_seq(),
-            repaired_gap: None,
             dirs_scanned: sync.dirs_scanned,
             upsert_events: sync.upsert_events,
             delete_events: sync.delete_events,
         }
     }
 
+    /// 后台修复：scope 并入待办；没有修复线程在跑时才启动一个，由它串行处理到待办清空。
     pub fn spawn_repair(self: &Arc<Self>, scope: DirtyScope) {
+        {
+            let mut pending = self.pending_repair.lock();
+            let merged = match pending.take() {
+                Some(prev) => merge_scopes(prev, scope),
+                None => scope,
+            };
+            *pending = Some(merged);
+        }
+        if self.repair_in_flight.swap(true, Ordering::AcqRel) {
+            return;
+        }
         let verifier = self.clone();
-        std::thread::spawn(move || {
-            let report = verifier.repair_scope(scope.clone());
+        std::thread::spawn(move || verifier.drain_repairs());
+    }
+
+    /// 是否有修复线程在运行（含待办）。
+    pub fn repair_in_flight(&self) -> bool {
+        self.repair_in_flight.load(Ordering::Acquire)
+    }
+
+    fn drain_repairs(&self) {
+        loop {
+            let next = self.pending_repair.lock().take();
+            let Some(scope) = next else {
+                self.repair_in_flight.store(false, Ordering::Release);
+                // 清标记前后可能有新请求入队：它若看到的仍是 true 就不会起线程，这里接着处理。
+                if self.pending_repair.lock().is_none()
+                    || self.repair_in_flight.swap(true, Ordering::AcqRel)
+                {
+                    return;
+                }
+                continue;
+            };
+            self.audit.lock().repairs_triggered += 1;
+            let report = self.repair_scope(scope.clone());
             tracing::warn!(
                 "ElasticVerifier repair complete: scope={:?} dirs={} upserts={} deletes={}",
                 scope,
@@ -139,9 +198,105 @@ impl ElasticVerifier {
                 report.upsert_events,
                 report.delete_events
             );
+        }
+    }
+
+    /// 内核事件队列溢出（notify Rescan）：丢失的事件没有路径可言，
+    /// 从最近一次已应用批次的时间点起全量对账（目录 mtime 表会剪掉未变化的子树）。
+    /// 溢出风暴中的多次信号合并为一次对账。
+    pub fn note_rescan(self: &Arc<Self>) {
+        let cutoff_ns = match self.last_verified_ts_ns.load(Ordering::Relaxed) {
+            0 => self.started_ns,
+            ns => ns,
+        };
+        tracing::warn!(
+            "ElasticVerifier: watcher queue overflow, reconciling changes since {}",
+            cutoff_ns
+        );
+        self.audit.lock().overflow_rescans += 1;
+        self.spawn_repair(DirtyScope::All { cutoff_ns });
+    }
+
+    /// 一轮抽查：随机取至多 `sample_dirs` 个已索引目录逐个比对，耗时超过 `budget` 即停；
+    /// 有漂移的目录合并为一次定向 fast-sync（交给修复线程，与其他修复请求合并）。
+    pub fn audit_once(self: &Arc<Self>, sample_dirs: usize, budget: Duration) -> AuditReport {
+        let started = Instant::now();
+        let seed = system_time_to_ns(SystemTime::now()).unwrap_or(1);
+        let mut dirs_done = 0usize;
+        let mut checked = 0usize;
+        let mut drifted = 0usize;
+        let mut drifted_dirs: Vec<PathBuf> = Vec::new();
+        for dir in self.index.sample_indexed_dirs(sample_dirs, seed) {
+            if started.elapsed() >= budget {
+                break;
+            }
+            if self.ignore_prefixes.iter().any(|p| dir.starts_with(p)) {
+                continue;
+            }
+            let result = self.index.audit_dir(&dir);
+            dirs_done += 1;
+            checked += result.checked;
+            drifted += result.drifted;
+            if result.drifted > 0 {
+                drifted_dirs.push(dir);
+            }
+        }
+
+        if !drifted_dirs.is_empty() {
+            tracing::warn!(
+                "ElasticVerifier audit: {} of {} entries drifted in {} dirs, starting targeted fast-sync",
+                drifted,
+                checked,
+                drifted_dirs.len()
+            );
+            self.spawn_repair(DirtyScope::Dirs {
+                cutoff_ns: 0,
+                dirs: drifted_dirs,
+            });
+        }
+
+        let mut report = self.audit.lock();
+        report.audits += 1;
+        report.last_audit_unix_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        report.last_dirs = dirs_done;
+        report.last_checked = checked;
+        report.last_drifted = drifted;
+        report.drift_ratio = if checked == 0 {
+            0.0
+        } else {
+            drifted as f64 / checked as f64
+        };
+        report.total_drifted += drifted as u64;
+        report.clone()
+    }
+
+    /// 后台抽查线程：每 `interval` 跑一轮 `audit_once`（先等一个周期，避开启动期的全量扫描）。
+    pub fn spawn_audit_loop(
+        self: &Arc<Self>,
+        interval: Duration,
+        sample_dirs: usize,
+        budget: Duration,
+    ) {
+        let verifier = self.clone();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            let report = verifier.audit_once(sample_dirs, budget);
+            tracing::debug!(
+                "ElasticVerifier audit: dirs={} checked={} drifted={}",
+                report.last_dirs,
+                report.last_checked,
+                report.last_drifted
+            );
         });
     }
 
+    pub fn report(&self) -> AuditReport {
+        self.audit.lock().clone()
+    }
+
     fn checkpoint(&self, events: &[EventRecord]) {
         let Some(max_seq) = events.iter().map(|ev| ev.seq).max() else {
             return;
@@ -156,29 +311,36 @@ impl ElasticVerifier {
         self.last_verified_ts_ns
             .fetch_max(max_ts_ns, Ordering::Relaxed);
     }
+}
 
-    fn scope_from_events(&self, events: &[EventRecord]) -> DirtyScope {
-        let cutoff_ns = self.last_verified_ts_ns.load(Ordering::Relaxed);
-        let mut dirs: BTreeSet<PathBuf> = BTreeSet::new();
-
-        for ev in events {
-            let Some(parent) = ev.best_path().and_then(|p| p.parent()) else {
-                return DirtyScope::All { cutoff_ns };
-            };
-            dirs.insert(parent.to_path_buf());
-            if dirs.len() > self.targeted_dir_limit {
-                return DirtyScope::All { cutoff_ns };
-            }
-        }
-
-        if dirs.is_empty() {
-            DirtyScope::All { cutoff_ns }
-        } else {
+/// 合并两个待办 scope：取较早的 cutoff；任一侧为 All 则整体为 All，否则合并目录集合。
+fn merge_scopes(a: DirtyScope, b: DirtyScope) -> DirtyScope {
+    match (a, b) {
+        (
+            DirtyScope::Dirs {
+                cutoff_ns: ca,
+                dirs: da,
+            },
             DirtyScope::Dirs {
-                cutoff_ns,
+                cutoff_ns: cb,
+                dirs: db,
+            },
+        ) => {
+            let dirs: BTreeSet<PathBuf> = da.into_iter().chain(db).collect();
+            DirtyScope::Dirs {
+                cutoff_ns: ca.min(cb),
                 dirs: dirs.into_iter().collect(),
             }
         }
+        (a, b) => DirtyScope::All {
+            cutoff_ns: scope_cutoff(&a).min(scope_cutoff(&b)),
+        },
+    }
+}
+
+fn scope_cutoff(scope: &DirtyScope) -> u64 {
+    match scope {
+        DirtyScope::All { cutoff_ns } | DirtyScope::Dirs { cutoff_ns, .. } => *cutoff_ns,
     }
 }
 
@@ -192,6 +354,7 @@ fn system_time_to_ns(ts: SystemTime) -> Option<u64> {
 mod tests {
     use super::*;
     use crate::core::{EventType, FileIdentifier};
+    use crate::storage::snapshot::SnapshotStore;
     use std::fs;
     use std::time::SystemTime;
 
@@ -222,44 +385,168 @@ mod tests {
     }
 
     #[test]
-    fn verifier_detects_sequence_gap() {
-        let root = unique_tmp_dir("gap-detect");
+    fn verifier_detects_journal_gap_and_requests_flush() -> anyhow::Result<()> {
+        let root = unique_tmp_dir("journal-gap");
+        let data = root.join("data");
+        fs::create_dir_all(&data)?;
+        let store = Arc::new(SnapshotStore::new(root.join("index.db")));
+        let index = Arc::new(TieredIndex::empty(vec![data.clone()]));
+        index.attach_wal(&*store)?;
+        let verifier = ElasticVerifier::new(index.clone());
+
+        let alpha = data.join("alpha.txt");
+        let beta = data.join("beta.txt");
+        fs::write(&alpha, b"a")?;
+        fs::write(&beta, b"b")?;
+
+        let batch = vec![ev(1, alpha.clone())];
+        let mark = verifier.begin_batch(&batch);
+        index.apply_events(&batch);
+        assert!(verifier.verify_and_repair(mark).is_none());
+        assert_eq!(verifier.last_verified_seq(), 1);
+        assert!(!index.flush_pending());
+
+        // 两条事件只有一条进了日志：等价于 WAL 追加中途失败。
+        let batch = vec![ev(2, alpha), ev(3, beta)];
+        let mark = verifier.begin_batch(&batch);
+        index.apply_events(&batch[1..]);
+        let gap = verifier.verify_and_repair(mark).expect("journal gap");
+        assert_eq!(gap.expected_seq, 3);
+        assert_eq!(gap.observed_seq, 2);
+        assert_eq!(gap.missing_count, 1);
+        assert_eq!(verifier.last_verified_seq(), 3);
+        assert!(index.flush_pending());
+
+        let report = verifier.report();
+        assert_eq!(report.journal_gaps, 1);
+        assert_eq!(report.journal_missing, 1);
+
+        let _ = fs::remove_dir_all(&root);
+        Ok(())
+    }
+
+    #[test]
+    fn verifier_skips_journal_check_without_wal() {
+        let root = unique_tmp_dir("no-wal");
         let index = Arc::new(TieredIndex::empty(vec![root.clone()]));
         let verifier = ElasticVerifier::new(index);
 
-        let first = root.join("alpha.txt");
-        let second = root.join("beta.txt");
-        let initial = vec![ev(1, first.clone())];
-        let report = verifier.verify_and_repair(&initial);
-        assert_eq!(report.last_verified_seq, 1);
-        assert!(report.repaired_gap.is_none());
+        let mark = verifier.begin_batch(&[ev(7, root.join("a.txt"))]);
+        assert!(mark.is_none());
+        assert!(verifier.verify_and_repair(mark).is_none());
+        assert_eq!(verifier.last_verified_seq(), 7);
+    }
 
-        let gap = verifier.verify_gap(&[ev(3, second)]);
-        assert!(matches!(
-            gap.map(|g| g.scope),
-            Some(DirtyScope::Dirs { .. })
-        ));
+    fn write_aged(path: &std::path::Path, body: &[u8]) -> anyhow::Result<()> {
+        fs::write(path, body)?;
+        // 抽查会跳过刚修改过的条目（事件可能还在途），把 mtime 拨回一小时。
+        fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(SystemTime::now() - Duration::from_secs(3600))?;
+        Ok(())
     }
 
     #[test]
-    fn verifier_repairs_gap_with_fast_sync() -> anyhow::Result<()> {
-        let root = unique_tmp_dir("gap-repair");
-        let alpha = root.join("alpha_match.txt");
-        let beta = root.join("beta_match.txt");
-        fs::write(&alpha, b"a")?;
-        fs::write(&beta, b"b")?;
+    fn audit_detects_offline_drift_and_repairs_dir() -> anyhow::Result<()> {
+        let root = unique_tmp_dir("audit");
+        let sub = root.join("sub");
+        fs::create_dir_all(&sub)?;
+        write_aged(&sub.join("keep_match.txt"), b"k")?;
+        write_aged(&sub.join("gone_match.txt"), b"g")?;
 
         let index = Arc::new(TieredIndex::empty(vec![root.clone()]));
-        let verifier = ElasticVerifier::new(index.clone());
+        index.fast_sync(DirtyScope::All { cutoff_ns: 0 }, &[]);
+        index.refresh_base();
+        let verifier = Arc::new(ElasticVerifier::new(index.clone()));
+
+        let clean = verifier.audit_once(8, Duration::from_secs(5));
+        assert_eq!(clean.audits, 1);
+        assert!(clean.last_dirs >= 1);
+        assert_eq!(clean.last_drifted, 0);
+        assert_eq!(clean.repairs_triggered, 0);
+        assert!(clean.last_audit_unix_secs > 0);
+
+        // 绕过 watcher 的变化：删一个、加一个。
+        fs::remove_file(sub.join("gone_match.txt"))?;
+        write_aged(&sub.join("new_match.txt"), b"n")?;
+
+        let drifted = verifier.audit_once(8, Duration::from_secs(5));
+        assert_eq!(drifted.last_drifted, 2);
+        assert!(drifted.drift_ratio > 0.0);
+        assert_eq!(drifted.total_drifted, 2);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while verifier.repair_in_flight() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!verifier.repair_in_flight());
+        assert_eq!(verifier.report().repairs_triggered, 1);
+        assert_eq!(index.query_limit("new_match", 10).len(), 1);
+        assert!(index.query_limit("gone_match", 10).is_empty());
 
-        verifier.verify_and_repair(&[ev(1, alpha.clone())]);
-        let report = verifier.verify_and_repair(&[ev(3, beta.clone())]);
+        let _ = fs::remove_dir_all(&root);
+        Ok(())
+    }
 
-        assert!(report.repaired_gap.is_some());
-        assert!(report.dirs_scanned >= 1);
+    #[test]
+    fn overflow_storm_coalesces_into_one_repair() {
+        let root = unique_tmp_dir("overflow");
+        let index = Arc::new(TieredIndex::empty(vec![root.clone()]));
+        let verifier = Arc::new(ElasticVerifier::new(index));
+        verifier.begin_batch(&[ev(1, root.join("a.txt"))]);
 
-        let results = index.query_limit("match", 10);
-        assert_eq!(results.len(), 2);
-        Ok(())
+        // 修复线程在跑：后续请求只并入待办，不再起线程。
+        verifier.repair_in_flight.store(true, Ordering::Release);
+        for _ in 0..5 {
+            verifier.note_rescan();
+        }
+        verifier.spawn_repair(DirtyScope::Dirs {
+            cutoff_ns: 0,
+            dirs: vec![root.join("sub")],
+        });
+        assert!(matches!(
+            *verifier.pending_repair.lock(),
+            Some(DirtyScope::All { cutoff_ns: 0 })
+        ));
+
+        verifier.drain_repairs();
+        let report = verifier.report();
+        assert_eq!(report.overflow_rescans, 5);
+        assert_eq!(report.repairs_triggered, 1);
+        assert!(!verifier.repair_in_flight());
+        assert!(verifier.pending_repair.lock().is_none());
+    }
+
+    #[test]
+    fn merge_scopes_unions_dirs_and_keeps_earliest_cutoff() {
+        let a = PathBuf::from("/r/a");
+        let b = PathBuf::from("/r/b");
+        let merged = merge_scopes(
+            DirtyScope::Dirs {
+                cutoff_ns: 20,
+                dirs: vec![a.clone(), b.clone()],
+            },
+            DirtyScope::Dirs {
+                cutoff_ns: 10,
+                dirs: vec![a.clone()],
+            },
+        );
+        match merged {
+            DirtyScope::Dirs { cutoff_ns, dirs } => {
+                assert_eq!(cutoff_ns, 10);
+                assert_eq!(dirs, vec![a, b]);
+            }
+            other => panic!("expected dirs scope, got {:?}", other),
+        }
+        assert!(matches!(
+            merge_scopes(
+                DirtyScope::All { cutoff_ns: 30 },
+                DirtyScope::Dirs {
+                    cutoff_ns: 5,
+                    dirs: Vec::new(),
+                }
+            ),
+            DirtyScope::All { cutoff_ns: 5 }
+        ));
     }
 }
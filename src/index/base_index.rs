@@ -1,15 +1,16 @@
 use arc_swap::ArcSwap;
 use roaring::RoaringBitmap;
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 use std::sync::Arc;
 
-use crate::core::{FileKey, FileMeta};
+use crate::core::{FileKey, FileKind, FileMeta, LinkTarget};
+use crate::index::dir_mtimes::{DirCheck, DirMtimeTable};
 pub use crate::index::file_entry_v2::{FileEntry, FileEntryIndex};
 use crate::index::parent_index::ParentIndex;
 use crate::index::path_table_v2::PathTableV2;
 use crate::index::PathFreshness;
-use crate::query::Matcher;
+use crate::query::{Matcher, TrigramPlan};
 use crate::stats::BaseStats;
 use crate::util::pathbuf_from_encoded_vec;
 
@@ -59,6 +60,82 @@ impl TrigramIndex {
     }
 }
 
+/// ShortGramIndex: 路径组件的 1/2 字节 gram → RoaringBitmap。
+///
+/// trigram 只覆盖 >= 3 字节的 hint；单字母、双字母查询（启动器里最常敲的）
+/// 靠它直接得到候选集，而不必遍历 `entries_by_key`。与 trigram 相同的标准化：
+/// 按 `Component::Normal` 切分、lossy UTF-8 + to_lowercase。
+#[derive(Clone, Debug, Default)]
+pub struct ShortGramIndex {
+    pub unigrams: HashMap<u8, RoaringBitmap>,
+    pub bigrams: HashMap<[u8; 2], RoaringBitmap>,
+}
+
+impl ShortGramIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 以路径的全部组件登记 docid。
+    pub fn add_path(&mut self, docid: u32, path: &Path) {
+        for c in path.components() {
+            let Component::Normal(os) = c else {
+                continue;
+            };
+            let lower = os.to_string_lossy().to_lowercase();
+            let bytes = lower.as_bytes();
+            for &b in bytes {
+                self.unigrams.entry(b).or_default().insert(docid);
+            }
+            for w in bytes.windows(2) {
+                self.bigrams.entry([w[0], w[1]]).or_default().insert(docid);
+            }
+        }
+    }
+
+    /// 按路径表重建（旧快照缺少该段时使用）。
+    pub fn build(path_table: &PathTableV2, entries: &FileEntryIndex) -> Self {
+        let mut idx = Self::new();
+        for (docid, entry) in entries.iter().enumerate() {
+            if let Some(path_bytes) = path_table.resolve(entry.path_idx) {
+                idx.add_path(docid as u32, &pathbuf_from_encoded_vec(path_bytes));
+            }
+        }
+        idx
+    }
+
+    /// 1 或 2 字节（已小写）gram 的 posting；其余长度返回 None。
+    pub fn get(&self, gram: &[u8]) -> Option<&RoaringBitmap> {
+        match gram {
+            [a] => self.unigrams.get(a),
+            [a, b] => self.bigrams.get(&[*a, *b]),
+            _ => None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.unigrams.len() + self.bigrams.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.unigrams.is_empty() && self.bigrams.is_empty()
+    }
+
+    pub fn memory_stats(&self) -> (usize, usize, u64) {
+        use std::mem::size_of;
+
+        let mut postings_total = 0usize;
+        let mut bytes = size_of::<Self>()
+            + self.unigrams.capacity() * (size_of::<(u8, RoaringBitmap)>() + 1)
+            + self.bigrams.capacity() * (size_of::<([u8; 2], RoaringBitmap)>() + 1);
+        for bitmap in self.unigrams.values().chain(self.bigrams.values()) {
+            postings_total += bitmap.len() as usize;
+            bytes += size_of::<RoaringBitmap>() + bitmap.serialized_size();
+        }
+        (self.len(), postings_total, bytes as u64)
+    }
+}
+
 /// BaseIndexData: 只读基础索引的快照数据。
 ///
 /// 所有字段均为只读（ArcSwap 保证读者无锁），后台重建完成后原子切换。
@@ -67,10 +144,20 @@ pub struct BaseIndexData {
     pub path_table: PathTableV2,
     pub entries_by_key: FileEntryIndex,
     pub trigram_index: TrigramIndex,
+    pub short_gram_index: ShortGramIndex,
     pub parent_index: ParentIndex,
+    /// 目录 mtime 表（对账时跳过未变化目录的 `read_dir`）
+    pub dir_mtimes: DirMtimeTable,
     pub tombstones: RoaringBitmap,
 }
 
+/// 一次 anchor 扫描的候选规模：trigram / short-gram 求得的候选数，或退化为全量扫描。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct CandidateScan {
+    pub candidates: usize,
+    pub full_scan: bool,
+}
+
 impl BaseIndexData {
     pub fn file_count(&self) -> usize {
         self.entries_by_key
@@ -83,11 +170,18 @@ impl BaseIndexData {
         let entries_bytes = self.entries_by_key.allocated_bytes() as u64;
         let (trigram_distinct, trigram_postings_total, trigram_bytes) =
             self.trigram_index.memory_stats();
-        let parent_bytes = self.parent_index.allocated_bytes() as u64;
+        let (short_gram_distinct, short_gram_postings_total, short_gram_bytes) =
+            self.short_gram_index.memory_stats();
+        let parent_bytes =
+            (self.parent_index.allocated_bytes() + self.dir_mtimes.allocated_bytes()) as u64;
         let tombstone_bytes =
             std::mem::size_of::<RoaringBitmap>() as u64 + self.tombstones.serialized_size() as u64;
-        let estimated_bytes =
-            path_table_bytes + entries_bytes + trigram_bytes + parent_bytes + tombstone_bytes;
+        let estimated_bytes = path_table_bytes
+            + entries_bytes
+            + trigram_bytes
+            + short_gram_bytes
+            + parent_bytes
+            + tombstone_bytes;
 
         BaseStats {
             file_count: self.file_count(),
@@ -98,6 +192,9 @@ impl BaseIndexData {
             trigram_distinct,
             trigram_postings_total,
             trigram_bytes,
+            short_gram_distinct,
+            short_gram_postings_total,
+            short_gram_bytes,
             parent_file_dirs: self.parent_index.file_dir_count(),
             parent_subdir_dirs: self.parent_index.subdir_dir_count(),
             parent_bytes,
@@ -115,13 +212,29 @@ impl BaseIndexData {
             let Some(path_bytes) = self.path_table.resolve(entry.path_idx) else {
                 continue;
             };
-            f(entry_to_meta(entry, &path_bytes));
+            f(entry_to_meta(
+                &self.entries_by_key,
+                docid as u32,
+                entry,
+                &path_bytes,
+            ));
         }
     }
 
     pub fn query_keys(&self, matcher: &dyn Matcher) -> Vec<FileKey> {
+        self.query_docids_with_scan(matcher)
+            .0
+            .into_iter()
+            .filter_map(|docid| self.entries_by_key.get(docid as usize))
+            .map(FileEntry::file_key)
+            .collect()
+    }
+
+    /// 命中的 DocId（同一 inode 的每条硬链接路径各一个），并返回本次扫描的候选规模（查询剖析用）。
+    pub fn query_docids_with_scan(&self, matcher: &dyn Matcher) -> (Vec<u32>, CandidateScan) {
         let candidates = self.trigram_candidates(matcher);
         let mut out = Vec::new();
+        let scan = self.scan_of(candidates.as_ref());
 
         match candidates {
             Some(bitmap) => {
@@ -140,7 +253,7 @@ impl BaseIndexData {
                         Err(_) => String::from_utf8_lossy(&path_bytes),
                     };
                     if matcher.matches(&path_str) {
-                        out.push(entry.file_key());
+                        out.push(docid);
                     }
                 }
             }
@@ -157,23 +270,50 @@ impl BaseIndexData {
                         Err(_) => String::from_utf8_lossy(&path_bytes),
                     };
                     if matcher.matches(&path_str) {
-                        out.push(entry.file_key());
+                        out.push(docid as u32);
                     }
                 }
             }
         }
 
-        out
+        (out, scan)
     }
 
+    /// 某 FileKey 的首个存活条目（硬链接时取 DocId 最小的路径）。
     pub fn get_meta(&self, key: FileKey) -> Option<FileMeta> {
-        let docid = self.entries_by_key.lookup_docid_by_filekey(key)?;
+        let docid = self
+            .entries_by_key
+            .docids_by_filekey(key)
+            .iter()
+            .copied()
+            .filter(|docid| !self.tombstones.contains(*docid))
+            .min()?;
+        self.meta_at(docid)
+    }
+
+    /// 某 FileKey 的全部存活条目（每条硬链接路径一个）。
+    pub fn metas_for_key(&self, key: FileKey) -> Vec<FileMeta> {
+        let mut docids = self.entries_by_key.docids_by_filekey(key).to_vec();
+        docids.sort_unstable();
+        docids
+            .into_iter()
+            .filter_map(|docid| self.meta_at(docid))
+            .collect()
+    }
+
+    /// DocId 上的条目；tombstone 返回 None。
+    pub fn meta_at(&self, docid: u32) -> Option<FileMeta> {
         if self.tombstones.contains(docid) {
             return None;
         }
         let entry = self.entries_by_key.get(docid as usize)?;
         let path_bytes = self.path_table.resolve(entry.path_idx)?;
-        Some(entry_to_meta(entry, &path_bytes))
+        Some(entry_to_meta(
+            &self.entries_by_key,
+            docid,
+            entry,
+            &path_bytes,
+        ))
     }
 
     pub fn path_freshness(
@@ -183,15 +323,20 @@ impl BaseIndexData {
         size: u64,
         mtime_ns: i64,
     ) -> PathFreshness {
-        let Some(meta) = self.get_meta(file_key) else {
+        let metas = self.metas_for_key(file_key);
+        if metas.is_empty() {
             return PathFreshness::Missing;
+        }
+        // 硬链接：只有这条路径本身已在索引中才可能 Unchanged
+        let Some(meta) = metas.into_iter().find(|m| m.path == path) else {
+            return PathFreshness::Changed;
         };
         let old_mtime_ns = meta
             .mtime
             .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
             .and_then(|d| i64::try_from(d.as_nanos()).ok())
             .unwrap_or(-1);
-        if meta.path == path && meta.size == size && old_mtime_ns == mtime_ns {
+        if meta.size == size && old_mtime_ns == mtime_ns {
             PathFreshness::Unchanged
         } else {
             PathFreshness::Changed
@@ -226,6 +371,15 @@ impl BaseIndexData {
     }
 
     pub fn parent_candidates(&self, parent_path: &str) -> Vec<FileKey> {
+        self.parent_docids(parent_path)
+            .into_iter()
+            .filter_map(|docid| self.entries_by_key.get(docid as usize))
+            .map(FileEntry::file_key)
+            .collect()
+    }
+
+    /// 父目录恰为 `parent_path` 的条目 DocId（ParentIndex 直查）。
+    pub fn parent_docids(&self, parent_path: &str) -> Vec<u32> {
         let parent_bytes = PathBuf::from(parent_path)
             .as_os_str()
             .as_encoded_bytes()
@@ -240,13 +394,83 @@ impl BaseIndexData {
             None => return Vec::new(),
         };
 
-        let mut keys = Vec::with_capacity(bitmap.len());
-        for &doc_id in bitmap {
-            if let Some(entry) = self.entries_by_key.get(doc_id as usize) {
-                keys.push(entry.file_key());
-            }
+        bitmap.to_vec()
+    }
+
+    /// 路径对应的存活条目 DocId：路径表定位后在父目录的直接子项中查找。
+    pub fn docid_for_path(&self, path: &Path) -> Option<u32> {
+        let idx = self
+            .path_table
+            .lookup(path.as_os_str().as_encoded_bytes())?;
+        let parent = self.path_table.parent_idx(idx)?;
+        self.parent_index
+            .files_in_dir(parent)?
+            .iter()
+            .copied()
+            .find(|&docid| {
+                !self.tombstones.contains(docid)
+                    && self
+                        .entries_by_key
+                        .get(docid as usize)
+                        .is_some_and(|entry| entry.path_idx == idx)
+            })
+    }
+
+    /// `dir` 之下（不含 `dir` 本身）全部存活条目的 DocId。
+    ///
+    /// 路径表按字节序排列：`dir/` 前缀区间即子树内的全部目录，再经 ParentIndex 取各自的
+    /// 直接子项，代价与子树规模成正比而非全表。
+    pub fn docids_under(&self, dir: &Path) -> Vec<u32> {
+        let dir_bytes = dir.as_os_str().as_encoded_bytes();
+        let mut prefix = dir_bytes.to_vec();
+        if !prefix.ends_with(b"/") {
+            prefix.push(b'/');
+        }
+        let mut docids: Vec<u32> = self
+            .path_table
+            .lookup(dir_bytes)
+            .into_iter()
+            .chain(self.path_table.idxs_with_prefix(&prefix).iter().copied())
+            .filter_map(|idx| self.parent_index.files_in_dir(idx))
+            .flatten()
+            .copied()
+            .filter(|docid| !self.tombstones.contains(*docid))
+            .collect();
+        docids.sort_unstable();
+        docids.dedup();
+        docids
+    }
+
+    /// `docids_under` 对应的路径。
+    pub fn paths_under(&self, dir: &Path) -> Vec<PathBuf> {
+        self.docids_under(dir)
+            .into_iter()
+            .filter_map(|docid| self.entries_by_key.get(docid as usize))
+            .filter_map(|entry| self.path_table.resolve(entry.path_idx))
+            .map(pathbuf_from_encoded_vec)
+            .collect()
+    }
+
+    /// 对账：目录当前 mtime 与目录 mtime 表一致时给出索引中的直接子目录。
+    pub fn check_dir(&self, dir: &Path, mtime_ns: i64) -> DirCheck {
+        let Some(dir_idx) = self.path_table.lookup(dir.as_os_str().as_encoded_bytes()) else {
+            return DirCheck::Unknown;
+        };
+        let children = self.parent_index.files_in_dir(dir_idx).unwrap_or(&[]);
+        match self.dir_mtimes.matches(dir_idx, mtime_ns, children.len()) {
+            None => DirCheck::Unknown,
+            Some(false) => DirCheck::Changed,
+            Some(true) => DirCheck::Unchanged(
+                children
+                    .iter()
+                    .filter(|docid| !self.tombstones.contains(**docid))
+                    .filter_map(|docid| self.entries_by_key.get(*docid as usize))
+                    .filter(|entry| entry.kind() == FileKind::Dir)
+                    .filter_map(|entry| self.path_table.resolve(entry.path_idx))
+                    .map(pathbuf_from_encoded_vec)
+                    .collect(),
+            ),
         }
-        keys
     }
 
     pub fn build_parent_index(&self) -> ParentIndex {
@@ -256,12 +480,33 @@ impl BaseIndexData {
         self.parent_index.clone()
     }
 
+    /// 只求候选规模、不物化结果（`/explain` 的估算）。
+    pub fn estimate_candidates(&self, matcher: &dyn Matcher) -> CandidateScan {
+        self.scan_of(self.trigram_candidates(matcher).as_ref())
+    }
+
+    fn scan_of(&self, candidates: Option<&RoaringBitmap>) -> CandidateScan {
+        match candidates {
+            Some(bitmap) => CandidateScan {
+                candidates: bitmap.len() as usize,
+                full_scan: false,
+            },
+            None => CandidateScan {
+                candidates: self.file_count(),
+                full_scan: true,
+            },
+        }
+    }
+
     fn trigram_candidates(&self, matcher: &dyn Matcher) -> Option<RoaringBitmap> {
+        if let Some(plan) = matcher.trigram_plan() {
+            return self.plan_candidates(plan);
+        }
         let hint = matcher.literal_hint()?;
         let lower = String::from_utf8_lossy(hint).to_lowercase();
         let bytes = lower.as_bytes();
         if bytes.len() < 3 {
-            return None;
+            return self.short_gram_candidates(bytes);
         }
         let tris: Vec<[u8; 3]> = bytes.windows(3).map(|w| [w[0], w[1], w[2]]).collect();
 
@@ -281,20 +526,87 @@ impl BaseIndexData {
         }
         Some(acc)
     }
+
+    /// 1/2 字节 hint 的候选集；gram 不存在即无人包含。
+    fn short_gram_candidates(&self, gram: &[u8]) -> Option<RoaringBitmap> {
+        if gram.is_empty() || self.short_gram_index.is_empty() {
+            return None;
+        }
+        Some(self.short_gram_index.get(gram).cloned().unwrap_or_default())
+    }
+
+    /// 按 AND/OR 计划求候选集。trigram 索引覆盖全部路径组件，缺失的 trigram 即无人包含；
+    /// 仅在整个 trigram 索引为空（无从判断）时回退全扫。
+    fn plan_candidates(&self, plan: &TrigramPlan) -> Option<RoaringBitmap> {
+        if self.trigram_index.is_empty() || plan.is_unconstrained() {
+            return None;
+        }
+        Some(self.plan_bitmap(plan))
+    }
+
+    fn plan_bitmap(&self, plan: &TrigramPlan) -> RoaringBitmap {
+        match plan {
+            TrigramPlan::Literal(literal) => {
+                let mut postings = Vec::with_capacity(literal.len().saturating_sub(2));
+                for w in literal.windows(3) {
+                    match self.trigram_index.get(&[w[0], w[1], w[2]]) {
+                        Some(b) => postings.push(b),
+                        None => return RoaringBitmap::new(),
+                    }
+                }
+                postings.sort_by_key(|b| b.len());
+                let mut iter = postings.into_iter();
+                let mut acc = iter.next().cloned().unwrap_or_default();
+                for b in iter {
+                    if acc.is_empty() {
+                        break;
+                    }
+                    acc &= b;
+                }
+                acc
+            }
+            TrigramPlan::All(children) => {
+                let mut iter = children.iter();
+                let mut acc = iter.next().map(|c| self.plan_bitmap(c)).unwrap_or_default();
+                for child in iter {
+                    if acc.is_empty() {
+                        break;
+                    }
+                    acc &= self.plan_bitmap(child);
+                }
+                acc
+            }
+            TrigramPlan::Any(children) => {
+                let mut acc = RoaringBitmap::new();
+                for child in children {
+                    acc |= self.plan_bitmap(child);
+                }
+                acc
+            }
+        }
+    }
 }
 
-fn entry_to_meta(entry: &FileEntry, path_bytes: &[u8]) -> FileMeta {
+fn entry_to_meta(
+    entries: &FileEntryIndex,
+    docid: u32,
+    entry: &FileEntry,
+    path_bytes: &[u8],
+) -> FileMeta {
+    let target = entries.link_target(docid);
     FileMeta {
         file_key: entry.file_key(),
         path: pathbuf_from_encoded_vec(path_bytes.to_vec()),
         size: entry.size,
-        mtime: if entry.mtime_ns >= 0 {
-            Some(std::time::UNIX_EPOCH + std::time::Duration::from_nanos(entry.mtime_ns as u64))
-        } else {
-            None
-        },
-        ctime: None,
-        atime: None,
+        mtime: entry.mtime(),
+        ctime: entries.times().ctime(docid as usize),
+        atime: entries.times().atime(docid as usize),
+        kind: entry.kind(),
+        nlink: entry.nlink,
+        target: target.map(|t| LinkTarget {
+            path: pathbuf_from_encoded_vec(t.to_vec()),
+            broken: entry.is_broken(),
+        }),
     }
 }
 
@@ -389,4 +701,106 @@ mod tests {
         let snap = idx.snapshot();
         assert_eq!(snap.entries_by_key.len(), 1);
     }
+
+    #[test]
+    fn regex_query_uses_trigram_plan_candidates() {
+        use crate::core::{FileKind, FileMeta};
+        use crate::index::l2_partition::PersistentIndex;
+        use crate::query::{PathScope, RegexMatcher};
+
+        let l2 = PersistentIndex::new_with_roots(vec![PathBuf::from("/docs")]);
+        let mut names: Vec<String> = (0..500).map(|i| format!("notes_{i}.txt")).collect();
+        names.extend(["report_2023.pdf", "report_2024.pdf", "report_2024.txt"].map(String::from));
+        names.push("REPORT_2099.PDF".to_string());
+        for (i, name) in names.iter().enumerate() {
+            l2.upsert(FileMeta {
+                file_key: FileKey {
+                    dev: 1,
+                    ino: i as u64 + 1,
+                    generation: 0,
+                },
+                path: PathBuf::from("/docs").join(name),
+                size: 1,
+                mtime: None,
+                ctime: None,
+                atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
+            });
+        }
+        let base = l2.to_base_index_data();
+
+        let re = regex::RegexBuilder::new(r"^report_20\d\d\.pdf$")
+            .case_insensitive(true)
+            .build()
+            .unwrap();
+        let matcher = RegexMatcher::new(re, PathScope::Basename, false);
+        let candidates = base.trigram_candidates(&matcher).expect("plan candidates");
+        assert_eq!(candidates.len(), 3);
+
+        let mut got: Vec<u64> = base
+            .query_keys(&matcher)
+            .into_iter()
+            .map(|k| k.ino)
+            .collect();
+        got.sort_unstable();
+        assert_eq!(got, vec![501, 502, 504]);
+
+        // 计划中的 literal 不存在于任何路径：候选集为空而非全扫
+        let re = regex::Regex::new(r"(invoice|receipt)_\d+").unwrap();
+        let matcher = RegexMatcher::new(re, PathScope::Basename, true);
+        assert_eq!(base.trigram_candidates(&matcher).map(|c| c.len()), Some(0));
+    }
+
+    #[test]
+    fn short_queries_use_short_gram_candidates() {
+        use crate::core::{FileKind, FileMeta};
+        use crate::index::l2_partition::PersistentIndex;
+        use crate::query::ExactMatcher;
+
+        let l2 = PersistentIndex::new_with_roots(vec![PathBuf::from("/w")]);
+        let names = ["alpha.md", "Beta.md", "zq", "cargo.toml", "x"];
+        for (i, name) in names.iter().enumerate() {
+            l2.upsert(FileMeta {
+                file_key: FileKey {
+                    dev: 1,
+                    ino: i as u64 + 1,
+                    generation: 0,
+                },
+                path: PathBuf::from("/w").join(name),
+                size: 1,
+                mtime: None,
+                ctime: None,
+                atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
+            });
+        }
+        let base = l2.to_base_index_data();
+        assert!(base.memory_stats().short_gram_bytes > 0);
+
+        let cases: [(&str, &[u64]); 5] = [
+            ("zq", &[3]),
+            ("B", &[2]),
+            ("x", &[5]),
+            ("md", &[1, 2]),
+            ("qz", &[]),
+        ];
+        for (query, expected) in cases {
+            let matcher = ExactMatcher::new(query, false);
+            let candidates = base
+                .trigram_candidates(&matcher)
+                .expect("short gram candidates");
+            assert_eq!(candidates.len(), expected.len() as u64, "{query}");
+            let mut got: Vec<u64> = base
+                .query_keys(&matcher)
+                .into_iter()
+                .map(|k| k.ino)
+                .collect();
+            got.sort_unstable();
+            assert_eq!(got, expected, "{query}");
+        }
+    }
 }
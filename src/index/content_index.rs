@@ -0,0 +1,389 @@
+use roaring::RoaringBitmap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Read;
+use std::path::Path;
+
+use crate::index::content_filter::ContentFilter;
+
+/// 内容索引中的单个文档：路径 + 内容哈希（xxh3，用于跳过未变化的 Modify）。
+#[derive(Clone, Debug)]
+pub struct ContentDoc {
+    pub path: Vec<u8>,
+    pub hash: u64,
+}
+
+/// ContentIndex: 文件内容 trigram 倒排（opt-in，服务 `content:` 查询）。
+///
+/// - trigram 统一按 ASCII 小写切分，大小写敏感由查询阶段的回读校验保证；
+/// - 更新采用 "tombstone 旧 doc + 追加新 doc"，tombstone 过多时整体压实；
+/// - 仅作为候选预过滤：命中候选后仍需回读文件确认（避免 trigram 假阳性）；
+/// - 路径表有序，目录删除 / 改名按前缀区间整体处理子树。
+#[derive(Clone, Debug, Default)]
+pub struct ContentIndex {
+    pub(crate) docs: Vec<Option<ContentDoc>>,
+    pub(crate) path_to_doc: BTreeMap<Vec<u8>, u32>,
+    pub(crate) postings: HashMap<[u8; 3], RoaringBitmap>,
+    pub(crate) tombstones: RoaringBitmap,
+    generation: u64,
+}
+
+/// tombstone 数超过该值且超过存活文档数时触发压实。
+const COMPACT_MIN_TOMBSTONES: u64 = 4096;
+
+impl ContentIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 存活文档数。
+    pub fn len(&self) -> usize {
+        self.path_to_doc.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.path_to_doc.is_empty()
+    }
+
+    /// 压实代数：doc id 重新分配后递增，查询侧据此失效候选缓存。
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn doc_id(&self, path: &[u8]) -> Option<u32> {
+        self.path_to_doc.get(path).copied()
+    }
+
+    /// 存活文档的路径；tombstone 返回 None。
+    pub fn doc_path(&self, doc_id: u32) -> Option<&[u8]> {
+        self.docs
+            .get(doc_id as usize)?
+            .as_ref()
+            .map(|doc| doc.path.as_slice())
+    }
+
+    /// 读取可索引的文件内容：仅普通文件、未超过 ContentFilter 大小上限、非二进制。
+    pub fn read_indexable(path: &Path) -> Option<Vec<u8>> {
+        let meta = std::fs::metadata(path).ok()?;
+        if !meta.is_file() || !ContentFilter::should_index(path, meta.len()) {
+            return None;
+        }
+        let file = std::fs::File::open(path).ok()?;
+        let mut data = Vec::with_capacity(meta.len() as usize);
+        // 读取过程中文件可能继续增长：多读 1 字节用于识别越界。
+        let cap = meta.len().saturating_add(1);
+        file.take(cap).read_to_end(&mut data).ok()?;
+        if !ContentFilter::should_index(path, data.len() as u64) || ContentFilter::is_binary(&data)
+        {
+            return None;
+        }
+        Some(data)
+    }
+
+    /// 写入/更新文档。内容哈希未变化时直接跳过，返回是否发生变更。
+    pub fn upsert(&mut self, path: &[u8], data: &[u8]) -> bool {
+        let hash = ContentFilter::content_hash(data);
+        if let Some(old) = self.path_to_doc.get(path).copied() {
+            if self
+                .docs
+                .get(old as usize)
+                .and_then(|d| d.as_ref())
+                .is_some_and(|d| d.hash == hash)
+            {
+                return false;
+            }
+            self.tombstone_doc(old);
+        }
+
+        let doc_id = self.docs.len() as u32;
+        self.docs.push(Some(ContentDoc {
+            path: path.to_vec(),
+            hash,
+        }));
+        self.path_to_doc.insert(path.to_vec(), doc_id);
+        for tri in content_trigrams(data) {
+            self.postings.entry(tri).or_default().insert(doc_id);
+        }
+        self.maybe_compact();
+        true
+    }
+
+    pub fn remove(&mut self, path: &[u8]) -> bool {
+        let Some(doc_id) = self.path_to_doc.remove(path) else {
+            return false;
+        };
+        if let Some(slot) = self.docs.get_mut(doc_id as usize) {
+            *slot = None;
+        }
+        self.tombstones.insert(doc_id);
+        self.maybe_compact();
+        true
+    }
+
+    /// 移除 `dir` 本身及其下所有文档（目录删除），返回移除条数。
+    pub fn remove_under(&mut self, dir: &[u8]) -> usize {
+        let paths = self.paths_under(dir);
+        for p in &paths {
+            self.remove(p);
+        }
+        paths.len()
+    }
+
+    /// 把 `from` 本身及其下所有文档改挂到 `to` 下（目录改名；内容不变，posting 原样保留），
+    /// 返回迁移条数。目标路径上已有的文档被替换。
+    pub fn move_under(&mut self, from: &[u8], to: &[u8]) -> usize {
+        let paths = self.paths_under(from);
+        for old in &paths {
+            let Some(doc_id) = self.path_to_doc.remove(old) else {
+                continue;
+            };
+            let mut new = to.to_vec();
+            new.extend_from_slice(&old[from.len()..]);
+            if let Some(replaced) = self.path_to_doc.insert(new.clone(), doc_id) {
+                self.tombstone_doc(replaced);
+            }
+            if let Some(Some(doc)) = self.docs.get_mut(doc_id as usize) {
+                doc.path = new;
+            }
+        }
+        self.maybe_compact();
+        paths.len()
+    }
+
+    /// `dir` 本身与 `dir/` 前缀下的已索引路径。
+    fn paths_under(&self, dir: &[u8]) -> Vec<Vec<u8>> {
+        let mut out: Vec<Vec<u8>> = Vec::new();
+        if self.path_to_doc.contains_key(dir) {
+            out.push(dir.to_vec());
+        }
+        let mut prefix = dir.strip_suffix(b"/").unwrap_or(dir).to_vec();
+        prefix.push(b'/');
+        out.extend(
+            self.path_to_doc
+                .range(prefix.clone()..)
+                .take_while(|(p, _)| p.starts_with(&prefix))
+                .map(|(p, _)| p.clone()),
+        );
+        out
+    }
+
+    /// 仅保留 `live` 中仍存在的路径（全量重建后对齐用），返回删除条数。
+    pub fn retain_paths(&mut self, live: &HashSet<&[u8]>) -> usize {
+        let stale: Vec<Vec<u8>> = self
+            .path_to_doc
+            .keys()
+            .filter(|p| !live.contains(p.as_slice()))
+            .cloned()
+            .collect();
+        for p in &stale {
+            self.remove(p);
+        }
+        stale.len()
+    }
+
+    /// needle 的 trigram 交集候选；needle 不足 3 字节时无法预过滤，返回 None。
+    pub fn candidates(&self, needle: &[u8]) -> Option<RoaringBitmap> {
+        let lower = needle.to_ascii_lowercase();
+        if lower.len() < 3 {
+            return None;
+        }
+        let mut tris: Vec<[u8; 3]> = lower.windows(3).map(|w| [w[0], w[1], w[2]]).collect();
+        tris.sort_unstable();
+        tris.dedup();
+
+        let mut bitmaps: Vec<&RoaringBitmap> = Vec::with_capacity(tris.len());
+        for tri in &tris {
+            match self.postings.get(tri) {
+                Some(b) => bitmaps.push(b),
+                None => return Some(RoaringBitmap::new()),
+            }
+        }
+        bitmaps.sort_by_key(|b| b.len());
+
+        let mut iter = bitmaps.into_iter();
+        let mut acc = iter.next().cloned().unwrap_or_default();
+        for b in iter {
+            acc &= b;
+            if acc.is_empty() {
+                break;
+            }
+        }
+        acc -= &self.tombstones;
+        Some(acc)
+    }
+
+    /// 重新分配 doc id，丢弃 tombstone 占用的 posting 位。
+    pub fn compact(&mut self) {
+        if self.tombstones.is_empty() {
+            return;
+        }
+        let mut remap: Vec<Option<u32>> = Vec::with_capacity(self.docs.len());
+        let mut docs = Vec::with_capacity(self.path_to_doc.len());
+        for doc in self.docs.drain(..) {
+            match doc {
+                Some(d) => {
+                    remap.push(Some(docs.len() as u32));
+                    docs.push(Some(d));
+                }
+                None => remap.push(None),
+            }
+        }
+
+        let mut postings = HashMap::with_capacity(self.postings.len());
+        for (tri, bitmap) in self.postings.drain() {
+            let mapped: RoaringBitmap = bitmap
+                .iter()
+                .filter_map(|id| remap.get(id as usize).copied().flatten())
+                .collect();
+            if !mapped.is_empty() {
+                postings.insert(tri, mapped);
+            }
+        }
+
+        self.path_to_doc = docs
+            .iter()
+            .enumerate()
+            .filter_map(|(id, d)| d.as_ref().map(|d| (d.path.clone(), id as u32)))
+            .collect();
+        self.docs = docs;
+        self.postings = postings;
+        self.tombstones = RoaringBitmap::new();
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    pub fn memory_stats(&self) -> (usize, usize, u64) {
+        use std::mem::size_of;
+
+        let mut bytes = size_of::<Self>()
+            + self.docs.capacity() * size_of::<Option<ContentDoc>>()
+            + self.postings.capacity() * (size_of::<([u8; 3], RoaringBitmap)>() + 1)
+            + self.path_to_doc.len() * size_of::<(Vec<u8>, u32)>();
+        for doc in self.docs.iter().flatten() {
+            // docs 与 path_to_doc 各持有一份路径字节。
+            bytes += doc.path.capacity() * 2;
+        }
+        for bitmap in self.postings.values() {
+            bytes += bitmap.serialized_size();
+        }
+        bytes += self.tombstones.serialized_size();
+        (self.len(), self.postings.len(), bytes as u64)
+    }
+
+    fn tombstone_doc(&mut self, doc_id: u32) {
+        if let Some(slot) = self.docs.get_mut(doc_id as usize) {
+            *slot = None;
+        }
+        self.tombstones.insert(doc_id);
+    }
+
+    fn maybe_compact(&mut self) {
+        let dead = self.tombstones.len();
+        if dead >= COMPACT_MIN_TOMBSTONES && dead > self.path_to_doc.len() as u64 {
+            self.compact();
+        }
+    }
+}
+
+/// 按 ASCII 小写切分内容 trigram（去重）。
+pub fn content_trigrams(data: &[u8]) -> HashSet<[u8; 3]> {
+    let mut out = HashSet::new();
+    for w in data.windows(3) {
+        out.insert([
+            w[0].to_ascii_lowercase(),
+            w[1].to_ascii_lowercase(),
+            w[2].to_ascii_lowercase(),
+        ]);
+    }
+    out
+}
+
+/// 回读校验：`haystack` 是否包含 `needle`（非大小写敏感时按 ASCII 折叠）。
+pub fn bytes_contain(haystack: &[u8], needle: &[u8], case_sensitive: bool) -> bool {
+    if needle.is_empty() {
+        return true;
+    }
+    if needle.len() > haystack.len() {
+        return false;
+    }
+    if case_sensitive {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    } else {
+        haystack
+            .windows(needle.len())
+            .any(|w| w.eq_ignore_ascii_case(needle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidates_intersect_trigrams_case_insensitively() {
+        let mut ci = ContentIndex::new();
+        ci.upsert(b"/a.rs", b"// TODO fixme later");
+        ci.upsert(b"/b.rs", b"fn main() {}");
+
+        let hits = ci.candidates(b"todo FIX").unwrap();
+        assert!(hits.contains(ci.doc_id(b"/a.rs").unwrap()));
+        assert!(!hits.contains(ci.doc_id(b"/b.rs").unwrap()));
+        assert!(ci.candidates(b"absent").unwrap().is_empty());
+        assert!(ci.candidates(b"fn").is_none());
+    }
+
+    #[test]
+    fn upsert_same_content_is_noop_and_modify_replaces_postings() {
+        let mut ci = ContentIndex::new();
+        assert!(ci.upsert(b"/a.txt", b"alpha"));
+        assert!(!ci.upsert(b"/a.txt", b"alpha"));
+        assert!(ci.upsert(b"/a.txt", b"omega"));
+
+        assert_eq!(ci.len(), 1);
+        assert!(ci.candidates(b"alpha").unwrap().is_empty());
+        assert!(ci
+            .candidates(b"omega")
+            .unwrap()
+            .contains(ci.doc_id(b"/a.txt").unwrap()));
+    }
+
+    #[test]
+    fn compact_remaps_doc_ids_and_bumps_generation() {
+        let mut ci = ContentIndex::new();
+        ci.upsert(b"/a.txt", b"first body");
+        ci.upsert(b"/b.txt", b"second body");
+        ci.remove(b"/a.txt");
+        let gen = ci.generation();
+
+        ci.compact();
+
+        assert_eq!(ci.generation(), gen + 1);
+        assert!(ci.tombstones.is_empty());
+        assert_eq!(ci.doc_id(b"/b.txt"), Some(0));
+        assert!(ci.candidates(b"second").unwrap().contains(0));
+        assert!(ci.candidates(b"first").unwrap().is_empty());
+    }
+
+    #[test]
+    fn directory_remove_and_move_cover_the_subtree_only() {
+        let mut ci = ContentIndex::new();
+        ci.upsert(b"/src/a.rs", b"alpha body");
+        ci.upsert(b"/src/sub/b.rs", b"beta body");
+        ci.upsert(b"/src2/c.rs", b"gamma body");
+
+        assert_eq!(ci.move_under(b"/src", b"/lib"), 2);
+        assert!(ci.doc_id(b"/src/a.rs").is_none());
+        let moved = ci.doc_id(b"/lib/sub/b.rs").unwrap();
+        assert!(ci.candidates(b"beta").unwrap().contains(moved));
+        assert!(ci.doc_id(b"/src2/c.rs").is_some());
+
+        assert_eq!(ci.remove_under(b"/lib"), 2);
+        assert_eq!(ci.len(), 1);
+        assert!(ci.candidates(b"alpha").unwrap().is_empty());
+    }
+
+    #[test]
+    fn bytes_contain_respects_case_mode() {
+        assert!(bytes_contain(b"Hello World", b"world", false));
+        assert!(!bytes_contain(b"Hello World", b"world", true));
+        assert!(bytes_contain(b"Hello World", b"World", true));
+    }
+}
@@ -0,0 +1,150 @@
+//! 目录 mtime 表：目录 path_idx → (mtime_ns, 已索引的直接子项数)。
+//!
+//! 与 ParentIndex 一起随 v7 快照持久化。对账时目录的 lstat mtime 与表中记录一致，
+//! 说明自上次列举以来没有子项被增删改名，可以直接沿索引里的子目录下行而不必 `read_dir`
+//! （updatedb / plocate 的做法）；每个目录仍需一次 `stat`，但 `read_dir` 只落在变化过的目录上。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use roaring::RoaringBitmap;
+
+use crate::core::FileKind;
+use crate::index::file_entry_v2::FileEntryIndex;
+use crate::index::parent_index::ParentIndex;
+
+/// 单个目录的记录。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DirStamp {
+    /// 列举该目录时的 mtime（`time_to_ns` 口径，未知为 -1）
+    pub mtime_ns: i64,
+    /// 当时索引中的直接子项数（与 ParentIndex 交叉校验）
+    pub children: u32,
+}
+
+/// 对账时单个目录的判定结果。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DirCheck {
+    /// 表中没有该目录（root、新目录或未入索引的目录），按原规则处理
+    Unknown,
+    /// mtime 或子项数与记录不符
+    Changed,
+    /// 与记录一致：附带索引中的直接子目录
+    Unchanged(Vec<PathBuf>),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DirMtimeTable {
+    pub(crate) stamps: HashMap<u32, DirStamp>,
+}
+
+impl DirMtimeTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 由条目与 ParentIndex 推导：每个存活的目录条目取其 mtime 与直接子项数。
+    pub fn build(
+        entries: &FileEntryIndex,
+        parent_index: &ParentIndex,
+        tombstones: &RoaringBitmap,
+    ) -> Self {
+        let mut stamps = HashMap::new();
+        for (docid, entry) in entries.iter().enumerate() {
+            if entry.kind() != FileKind::Dir || tombstones.contains(docid as u32) {
+                continue;
+            }
+            let children = parent_index
+                .files_in_dir(entry.path_idx)
+                .map_or(0, <[u32]>::len);
+            stamps.insert(
+                entry.path_idx,
+                DirStamp {
+                    mtime_ns: entry.mtime_ns,
+                    children: children as u32,
+                },
+            );
+        }
+        stamps.shrink_to_fit();
+        Self { stamps }
+    }
+
+    pub fn len(&self) -> usize {
+        self.stamps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stamps.is_empty()
+    }
+
+    pub fn get(&self, dir_path_idx: u32) -> Option<DirStamp> {
+        self.stamps.get(&dir_path_idx).copied()
+    }
+
+    pub fn allocated_bytes(&self) -> usize {
+        use std::mem::size_of;
+
+        size_of::<Self>() + self.stamps.capacity() * (size_of::<(u32, DirStamp)>() + 1)
+    }
+
+    /// 比对目录当前的 mtime 与索引中的直接子项数：`None` 表示表中没有该目录。
+    pub fn matches(&self, dir_path_idx: u32, mtime_ns: i64, children: usize) -> Option<bool> {
+        let stamp = self.get(dir_path_idx)?;
+        // mtime 未知（-1）无从比较；子项数不符说明表与 ParentIndex 不同源。
+        Some(
+            stamp.mtime_ns >= 0
+                && stamp.mtime_ns == mtime_ns
+                && stamp.children as usize == children,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::FileKey;
+    use crate::index::file_entry_v2::FileEntry;
+
+    fn entry(ino: u64, path_idx: u32, mtime_ns: i64, kind: FileKind) -> FileEntry {
+        FileEntry::from_file_key(
+            FileKey {
+                dev: 1,
+                ino,
+                generation: 0,
+            },
+            path_idx,
+            0,
+            mtime_ns,
+        )
+        .with_kind(kind)
+    }
+
+    #[test]
+    fn build_and_check_dir_stamps() {
+        // path_idx: 0=/r 1=/r/d 2=/r/d/a.txt 3=/r/d/sub
+        let mut entries = FileEntryIndex::new();
+        entries.push(entry(1, 1, 100, FileKind::Dir));
+        entries.push(entry(2, 2, 50, FileKind::File));
+        entries.push(entry(3, 3, 70, FileKind::Dir));
+        let mut parent_index = ParentIndex::new();
+        parent_index.dir_to_files.insert(0, vec![0]);
+        parent_index.dir_to_files.insert(1, vec![1, 2]);
+        let tombstones = RoaringBitmap::new();
+
+        let table = DirMtimeTable::build(&entries, &parent_index, &tombstones);
+        assert_eq!(table.len(), 2);
+        assert_eq!(
+            table.get(1),
+            Some(DirStamp {
+                mtime_ns: 100,
+                children: 2
+            })
+        );
+        assert_eq!(table.get(3).map(|s| s.children), Some(0));
+
+        assert_eq!(table.matches(1, 100, 2), Some(true));
+        assert_eq!(table.matches(1, 99, 2), Some(false));
+        assert_eq!(table.matches(1, 100, 3), Some(false));
+        assert_eq!(table.matches(0, 1, 1), None);
+    }
+}
@@ -1,8 +1,17 @@
-//! FileEntry v2: fixed-size 40-byte struct + file-key lookup index.
+//! FileEntry v2: fixed-size 48-byte struct + file-key lookup index.
 
-use crate::core::FileKey;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-/// Fixed-size file metadata entry (40 bytes).
+use crate::core::{FileKey, FileKind};
+
+/// `FileEntry::flags` bit: the entry is a directory.
+pub const ENTRY_FLAG_DIR: u32 = 1;
+/// `FileEntry::flags` bit: the entry is a symbolic link (target kept in `FileEntryIndex`).
+pub const ENTRY_FLAG_SYMLINK: u32 = 2;
+/// `FileEntry::flags` bit: symbolic link whose target could not be resolved.
+pub const ENTRY_FLAG_BROKEN: u32 = 4;
+
+/// Fixed-size file metadata entry (48 bytes).
 ///
 /// Layout:
 /// - dev:      8 bytes
@@ -11,8 +20,13 @@ use crate::core::FileKey;
 /// - path_idx: 4 bytes
 /// - size:     8 bytes
 /// - mtime_ns: 8 bytes
+/// - flags:    4 bytes (kind bits, see `ENTRY_FLAG_*`)
+/// - nlink:    4 bytes (hard link count, `0` = unknown)
+///
+/// Total: 48 bytes
 ///
-/// Total: 40 bytes
+/// Timestamps are nanoseconds since the Unix epoch; `-1` means unknown.
+/// ctime / atime live in the `EntryTimes` side columns.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct FileEntry {
@@ -22,11 +36,29 @@ pub struct FileEntry {
     pub path_idx: u32,
     pub size: u64,
     pub mtime_ns: i64,
+    pub flags: u32,
+    pub nlink: u32,
 }
 
 // Assert size at compile time.
 #[cfg(target_pointer_width = "64")]
-const _: [(); 1] = [(); (std::mem::size_of::<FileEntry>() == 40) as usize];
+const _: [(); 1] = [(); (std::mem::size_of::<FileEntry>() == 48) as usize];
+
+/// `SystemTime` -> nanoseconds since epoch (`-1` for unknown / pre-epoch).
+pub fn time_to_ns(t: Option<SystemTime>) -> i64 {
+    t.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .and_then(|d| i64::try_from(d.as_nanos()).ok())
+        .unwrap_or(-1)
+}
+
+/// Inverse of `time_to_ns`.
+pub fn time_from_ns(ns: i64) -> Option<SystemTime> {
+    if ns < 0 {
+        None
+    } else {
+        Some(UNIX_EPOCH + Duration::from_nanos(ns as u64))
+    }
+}
 
 impl FileEntry {
     pub fn file_key(&self) -> FileKey {
@@ -45,8 +77,112 @@ impl FileEntry {
             path_idx,
             size,
             mtime_ns,
+            flags: 0,
+            nlink: 0,
+        }
+    }
+
+    pub fn mtime(&self) -> Option<SystemTime> {
+        time_from_ns(self.mtime_ns)
+    }
+
+    pub fn with_nlink(mut self, nlink: u32) -> Self {
+        self.nlink = nlink;
+        self
+    }
+
+    pub fn with_kind(mut self, kind: FileKind) -> Self {
+        self.set_kind(kind);
+        self
+    }
+
+    pub fn set_kind(&mut self, kind: FileKind) {
+        self.flags &= !(ENTRY_FLAG_DIR | ENTRY_FLAG_SYMLINK);
+        match kind {
+            FileKind::Dir => self.flags |= ENTRY_FLAG_DIR,
+            FileKind::Symlink => self.flags |= ENTRY_FLAG_SYMLINK,
+            FileKind::File => {}
+        }
+    }
+
+    pub fn kind(&self) -> FileKind {
+        if self.flags & ENTRY_FLAG_DIR != 0 {
+            FileKind::Dir
+        } else if self.flags & ENTRY_FLAG_SYMLINK != 0 {
+            FileKind::Symlink
+        } else {
+            FileKind::File
         }
     }
+
+    pub fn set_broken(&mut self, broken: bool) {
+        if broken {
+            self.flags |= ENTRY_FLAG_BROKEN;
+        } else {
+            self.flags &= !ENTRY_FLAG_BROKEN;
+        }
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.flags & ENTRY_FLAG_BROKEN != 0
+    }
+}
+
+/// ctime / atime side columns indexed by DocId.
+///
+/// Mirrors the on-disk `TIMES_HAS_CTIME` / `TIMES_HAS_ATIME` columns: a column is only
+/// allocated once some entry has a known value, and grows lazily up to the highest DocId
+/// written. Reads past the end are `-1` (unknown), so `noatime` mounts cost nothing.
+#[derive(Clone, Debug, Default)]
+pub struct EntryTimes {
+    ctime_ns: Vec<i64>,
+    atime_ns: Vec<i64>,
+}
+
+impl EntryTimes {
+    pub fn set(&mut self, docid: usize, ctime_ns: i64, atime_ns: i64) {
+        Self::set_column(&mut self.ctime_ns, docid, ctime_ns);
+        Self::set_column(&mut self.atime_ns, docid, atime_ns);
+    }
+
+    fn set_column(column: &mut Vec<i64>, docid: usize, ns: i64) {
+        if let Some(slot) = column.get_mut(docid) {
+            *slot = ns;
+        } else if ns >= 0 {
+            column.resize(docid, -1);
+            column.push(ns);
+        }
+    }
+
+    pub fn ctime_ns(&self, docid: usize) -> i64 {
+        self.ctime_ns.get(docid).copied().unwrap_or(-1)
+    }
+
+    pub fn atime_ns(&self, docid: usize) -> i64 {
+        self.atime_ns.get(docid).copied().unwrap_or(-1)
+    }
+
+    pub fn ctime(&self, docid: usize) -> Option<SystemTime> {
+        time_from_ns(self.ctime_ns(docid))
+    }
+
+    pub fn atime(&self, docid: usize) -> Option<SystemTime> {
+        time_from_ns(self.atime_ns(docid))
+    }
+
+    /// Whether any entry has a known ctime (the on-disk column is written).
+    pub fn has_ctime(&self) -> bool {
+        self.ctime_ns.iter().any(|&ns| ns >= 0)
+    }
+
+    /// Whether any entry has a known atime (the on-disk column is written).
+    pub fn has_atime(&self) -> bool {
+        self.atime_ns.iter().any(|&ns| ns >= 0)
+    }
+
+    pub fn allocated_bytes(&self) -> usize {
+        (self.ctime_ns.capacity() + self.atime_ns.capacity()) * std::mem::size_of::<i64>()
+    }
 }
 
 /// Index over `FileEntry` providing DocId-order iteration and O(log N) lookup by file key.
@@ -56,6 +192,10 @@ pub struct FileEntryIndex {
     entries: Vec<FileEntry>,
     /// Permutation sorted by `(dev, ino, generation)`.
     by_filekey: Vec<u32>,
+    /// Symlink targets (raw `read_link` bytes), sorted by DocId.
+    link_targets: Vec<(u32, Box<[u8]>)>,
+    /// ctime / atime by DocId.
+    times: EntryTimes,
 }
 
 impl FileEntryIndex {
@@ -63,6 +203,8 @@ impl FileEntryIndex {
         Self {
             entries: Vec::new(),
             by_filekey: Vec::new(),
+            link_targets: Vec::new(),
+            times: EntryTimes::default(),
         }
     }
 
@@ -70,6 +212,8 @@ impl FileEntryIndex {
         Self {
             entries: Vec::with_capacity(cap),
             by_filekey: Vec::with_capacity(cap),
+            link_targets: Vec::new(),
+            times: EntryTimes::default(),
         }
     }
 
@@ -83,6 +227,12 @@ impl FileEntryIndex {
         size_of::<Self>()
             + self.entries.capacity() * size_of::<FileEntry>()
             + self.by_filekey.capacity() * size_of::<u32>()
+            + self
+                .link_targets
+                .iter()
+                .map(|(_, t)| size_of::<(u32, Box<[u8]>)>() + t.len())
+                .sum::<usize>()
+            + self.times.allocated_bytes()
     }
 
     pub fn is_empty(&self) -> bool {
@@ -95,6 +245,43 @@ impl FileEntryIndex {
         self.by_filekey.push(idx);
     }
 
+    /// Push a symlink entry together with its target.
+    pub fn push_link(&mut self, entry: FileEntry, target: &[u8]) {
+        let idx = self.entries.len() as u32;
+        self.push(entry);
+        self.link_targets.push((idx, target.into()));
+    }
+
+    /// Attach a target to an already pushed entry (decoder path); DocIds must be ascending.
+    pub fn set_link_target(&mut self, docid: u32, target: &[u8]) {
+        debug_assert!(self.link_targets.last().is_none_or(|(d, _)| *d < docid));
+        self.link_targets.push((docid, target.into()));
+    }
+
+    /// Symlink target of `docid`, if the entry is a symlink.
+    pub fn link_target(&self, docid: u32) -> Option<&[u8]> {
+        let pos = self
+            .link_targets
+            .binary_search_by_key(&docid, |(d, _)| *d)
+            .ok()?;
+        Some(&self.link_targets[pos].1)
+    }
+
+    /// All symlink targets in DocId order.
+    pub fn link_targets(&self) -> impl Iterator<Item = (u32, &[u8])> {
+        self.link_targets.iter().map(|(d, t)| (*d, &t[..]))
+    }
+
+    /// Record ctime / atime (`-1` = unknown) for an already pushed entry.
+    pub fn set_times(&mut self, docid: u32, ctime_ns: i64, atime_ns: i64) {
+        self.times.set(docid as usize, ctime_ns, atime_ns);
+    }
+
+    /// ctime / atime side columns.
+    pub fn times(&self) -> &EntryTimes {
+        &self.times
+    }
+
     /// Finalize the index: sort the file-key permutation vector.
     pub fn build(mut self) -> Self {
         self.sort_by_key();
@@ -109,36 +296,28 @@ impl FileEntryIndex {
         });
     }
 
-    /// Lookup by `FileKey` using binary search. Returns a slice of matching entries
-    /// (there may be multiple entries with the same key in edge cases).
-    pub fn lookup_by_filekey(&self, key: FileKey) -> Option<&[FileEntry]> {
-        let pos = self
-            .by_filekey
-            .binary_search_by(|&i| {
-                let e = &self.entries[i as usize];
-                (e.dev, e.ino, e.generation).cmp(&(key.dev, key.ino, key.generation))
-            })
-            .ok()?;
-        // Expand to all equal entries.
-        let mut start = pos;
-        while start > 0 {
-            let e = &self.entries[self.by_filekey[start - 1] as usize];
-            if (e.dev, e.ino, e.generation) == (key.dev, key.ino, key.generation) {
-                start -= 1;
-            } else {
-                break;
-            }
-        }
-        let mut end = pos + 1;
-        while end < self.by_filekey.len() {
-            let e = &self.entries[self.by_filekey[end] as usize];
-            if (e.dev, e.ino, e.generation) == (key.dev, key.ino, key.generation) {
-                end += 1;
-            } else {
-                break;
-            }
+    /// Lookup by `FileKey`. Returns every entry sharing the key (one per hard-link path),
+    /// in DocId order.
+    pub fn lookup_by_filekey(&self, key: FileKey) -> Option<Vec<FileEntry>> {
+        let docids = self.docids_by_filekey(key);
+        if docids.is_empty() {
+            return None;
         }
-        Some(&self.entries[self.by_filekey[start] as usize..=self.by_filekey[end - 1] as usize])
+        let mut docids = docids.to_vec();
+        docids.sort_unstable();
+        Some(docids.iter().map(|&d| self.entries[d as usize]).collect())
+    }
+
+    /// DocIds of all entries sharing `key` (empty if none); order within the run is unspecified.
+    pub fn docids_by_filekey(&self, key: FileKey) -> &[u32] {
+        let target = (key.dev, key.ino, key.generation);
+        let key_of = |i: &u32| {
+            let e = &self.entries[*i as usize];
+            (e.dev, e.ino, e.generation)
+        };
+        let start = self.by_filekey.partition_point(|i| key_of(i) < target);
+        let end = start + self.by_filekey[start..].partition_point(|i| key_of(i) == target);
+        &self.by_filekey[start..end]
     }
 
     /// Lookup by `FileKey` and return the docid of the first matching entry.
@@ -198,7 +377,25 @@ mod tests {
 
     #[test]
     fn test_file_entry_size() {
-        assert_eq!(std::mem::size_of::<FileEntry>(), 40);
+        assert_eq!(std::mem::size_of::<FileEntry>(), 48);
+    }
+
+    #[test]
+    fn entry_times_columns_grow_lazily() {
+        let mut times = EntryTimes::default();
+        times.set(3, -1, -1);
+        assert!(!times.has_ctime() && !times.has_atime());
+        assert_eq!(times.allocated_bytes(), 0);
+
+        times.set(2, 7, -1);
+        assert!(times.has_ctime() && !times.has_atime());
+        assert_eq!((times.ctime_ns(0), times.ctime_ns(2)), (-1, 7));
+        assert_eq!(times.ctime_ns(5), -1);
+
+        // 覆盖为未知后列仍在，但不再算作“有值”。
+        times.set(2, -1, -1);
+        assert!(!times.has_ctime());
+        assert!(times.ctime(2).is_none());
     }
 
     #[test]
@@ -231,6 +428,22 @@ mod tests {
         assert!(index.lookup_by_filekey(make_key(99, 99)).is_none());
     }
 
+    #[test]
+    fn lookup_by_filekey_returns_every_hard_link() {
+        let mut index = FileEntryIndex::new();
+        index.push(FileEntry::from_file_key(make_key(1, 7), 0, 10, 1));
+        index.push(FileEntry::from_file_key(make_key(1, 3), 1, 20, 2));
+        index.push(FileEntry::from_file_key(make_key(1, 7), 2, 10, 1));
+        index.push(FileEntry::from_file_key(make_key(1, 9), 3, 30, 3));
+        let index = index.build();
+
+        let links = index.lookup_by_filekey(make_key(1, 7)).unwrap();
+        let path_idxs: Vec<u32> = links.iter().map(|e| e.path_idx).collect();
+        assert_eq!(path_idxs, vec![0, 2]);
+        assert_eq!(index.docids_by_filekey(make_key(1, 3)), &[1]);
+        assert!(index.docids_by_filekey(make_key(2, 7)).is_empty());
+    }
+
     #[test]
     fn file_entry_lookup_matches_compact_meta() {
         // Build entries in a non-sorted order to verify index sorting.
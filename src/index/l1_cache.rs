@@ -284,6 +284,7 @@ impl L1Cache {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::FileKind;
     use crate::query::matcher::create_matcher;
     use std::path::PathBuf;
 
@@ -299,6 +300,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         }
     }
 
@@ -9,8 +9,10 @@ use std::sync::Arc;
 
 #[cfg(feature = "rkyv")]
 use crate::core::FileKeyEntry;
-use crate::core::{EventRecord, EventType, FileIdentifier, FileKey, FileMeta};
-use crate::index::file_entry_v2::FileEntry;
+use crate::core::{
+    EventRecord, EventType, FileIdentifier, FileKey, FileKind, FileMeta, LinkTarget,
+};
+use crate::index::file_entry_v2::{time_to_ns, EntryTimes, FileEntry};
 use crate::index::parent_index::PathTable as PathTableTrait;
 use crate::index::{IndexLayer, PathFreshness};
 use crate::query::matcher::Matcher;
@@ -56,25 +58,50 @@ fn short_component_matches(encoded: u16, hint: &[u8]) -> bool {
     }
 }
 
-#[derive(Clone, Copy)]
-struct ResolvedFsMeta {
-    file_key: FileKey,
+/// 条目上可随事件刷新的元数据（docid/path 之外的部分），整体写回 `FileEntry` 与时间侧列。
+#[derive(Clone, Copy, Debug)]
+struct EntryStat {
     size: u64,
-    mtime: Option<std::time::SystemTime>,
+    mtime_ns: i64,
+    ctime_ns: i64,
+    atime_ns: i64,
+    kind: FileKind,
+    nlink: u32,
+    broken: bool,
 }
 
-pub(crate) fn mtime_to_ns(mtime: Option<std::time::SystemTime>) -> i64 {
-    mtime
-        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-        .and_then(|d| i64::try_from(d.as_nanos()).ok())
-        .unwrap_or(-1)
-}
+impl EntryStat {
+    fn from_meta(meta: &FileMeta) -> Self {
+        Self {
+            size: meta.size,
+            mtime_ns: time_to_ns(meta.mtime),
+            ctime_ns: time_to_ns(meta.ctime),
+            atime_ns: time_to_ns(meta.atime),
+            kind: meta.kind,
+            nlink: meta.nlink,
+            broken: meta.target.as_ref().is_some_and(|t| t.broken),
+        }
+    }
 
-fn mtime_from_ns(ns: i64) -> Option<std::time::SystemTime> {
-    if ns < 0 {
-        None
-    } else {
-        Some(std::time::UNIX_EPOCH + std::time::Duration::from_nanos(ns as u64))
+    fn of_entry(entry: &FileEntry, times: &EntryTimes, docid: DocId) -> Self {
+        Self {
+            size: entry.size,
+            mtime_ns: entry.mtime_ns,
+            ctime_ns: times.ctime_ns(docid as usize),
+            atime_ns: times.atime_ns(docid as usize),
+            kind: entry.kind(),
+            nlink: entry.nlink,
+            broken: entry.is_broken(),
+        }
+    }
+
+    fn apply_to(self, docid: DocId, entry: &mut FileEntry, times: &mut EntryTimes) {
+        entry.size = self.size;
+        entry.mtime_ns = self.mtime_ns;
+        entry.set_kind(self.kind);
+        entry.nlink = self.nlink;
+        entry.set_broken(self.broken);
+        times.set(docid as usize, self.ctime_ns, self.atime_ns);
     }
 }
 
@@ -366,20 +393,24 @@ impl OneOrManyDocId {
 
 /// L2: 持久索引（内存常驻，可直接查询；trigram 倒排加速）
 ///
-/// ## 单路径策略 (Single-Path Policy)
-/// 一个 `FileKey(dev, ino)` 只存储一条路径（最先发现的那个）。
-/// Hardlink 的其他路径视为"不在索引中"。
-/// 理由：简单、可预测、够用。如需多路径支持，需扩展为 `FileKey -> Vec<PathBuf>`。
+/// ## 多路径策略 (Hard Links)
+/// 一个 `FileKey(dev, ino)` 的每条硬链接路径各占一个 DocId，共享 inode 元数据
+/// （size/mtime/nlink 随任一路径的上报一起刷新）。同 key 的新路径只有在旧路径已从磁盘
+/// 消失（或显式 rename）时才视为 rename，否则作为新的硬链接收录。
 pub struct PersistentIndex {
     /// root 列表（root_id -> root Path）。root_id=0 固定为 "/" 作为兜底。
     roots: Vec<PathBuf>,
     roots_bytes: Vec<Vec<u8>>,
     /// DocId -> FileEntry
     entries: RwLock<Vec<FileEntry>>,
+    /// DocId -> ctime / atime（侧列；与 `entries` 同锁序，先 entries 后 times）
+    entry_times: RwLock<EntryTimes>,
     /// DocId -> absolute path bytes
     paths: RwLock<Vec<Vec<u8>>>,
-    /// FileKey -> DocId
-    filekey_to_docid: RwLock<HashMap<FileKey, DocId>>,
+    /// FileKey -> DocId（硬链接时多个）
+    filekey_to_docid: RwLock<HashMap<FileKey, OneOrManyDocId>>,
+    /// DocId -> 符号链接目标（`read_link` 原文 bytes；仅 kind=Symlink 的条目）
+    link_targets: RwLock<HashMap<DocId, Vec<u8>>>,
 
     /// 路径反查：hash(path_bytes) -> DocId（或少量冲突列表）
     path_hash_to_id: RwLock<HashMap<u64, OneOrManyDocId>>,
@@ -476,8 +507,10 @@ impl PersistentIndex {
             roots,
             roots_bytes,
             entries: RwLock::new(Vec::new()),
+            entry_times: RwLock::new(EntryTimes::default()),
             paths: RwLock::new(Vec::new()),
             filekey_to_docid: RwLock::new(HashMap::new()),
+            link_targets: RwLock::new(HashMap::new()),
             path_hash_to_id: RwLock::new(HashMap::new()),
             trigram_index: RwLock::new(HashMap::new()),
             short_component_index: RwLock::new(HashMap::new()),
@@ -553,7 +586,7 @@ impl PersistentIndex {
                 continue;
             };
             let docid = entries.len() as u32;
-            let mtime_ns = mtime_to_ns(m.mtime);
+            let mtime_ns = time_to_ns(m.mtime);
             entries.push(FileEntry::from_file_key(
                 m.file_key, docid, m.size, mtime_ns,
             ));
@@ -611,7 +644,10 @@ impl PersistentIndex {
                 continue;
             }
 
-            filekey_to_docid.insert(entry.file_key(), docid);
+            filekey_to_docid
+                .entry(entry.file_key())
+                .and_modify(|v| v.insert(docid))
+                .or_insert(OneOrManyDocId::One(docid));
 
             let Some(abs_bytes) = paths.get(docid_usize) else {
                 continue;
@@ -639,140 +675,197 @@ impl PersistentIndex {
 
     /// 插入/更新一条文件记录
     ///
-    /// ## 单路径策略 (first-seen wins)
-    /// 如果该 FileKey 已存在且路径不同（hardlink 场景），
-    /// 保留最先发现的路径，仅更新 size/mtime 等元数据。
-    /// 只有显式 rename 事件，或补扫时检测到旧路径已消失的 reconcile 场景，才会更新路径。
+    /// 同 FileKey 已有条目时：同路径只刷新元数据；路径不同则看旧路径——
+    /// 旧路径已从磁盘消失（补扫 reconcile）视为 rename，仍存在则作为新的硬链接收录。
     pub fn upsert(&self, meta: FileMeta) {
         self.upsert_inner(meta, false);
     }
 
-    /// rename 专用：强制更新路径
+    /// rename 专用：强制更新路径（多路径时优先移动已消失的那条）
     pub fn upsert_rename(&self, meta: FileMeta) {
         self.upsert_inner(meta, true);
     }
 
+    /// 按路径精确写入：同 FileKey 同路径只刷新元数据，否则新增条目；不做 rename 推断、
+    /// 不访问文件系统。用于从已对齐的数据集（分区重建 / compaction）装载。
+    pub fn upsert_exact(&self, mut meta: FileMeta) {
+        meta.path = crate::index::tiered::normalize_path(&meta.path);
+        let fkey = meta.file_key;
+        let new_abs_bytes = meta.path.as_os_str().as_encoded_bytes().to_vec();
+        let stat = EntryStat::from_meta(&meta);
+
+        let existing = self.docids_for_key(fkey);
+        let same_path = {
+            let paths = self.paths.read();
+            existing.into_iter().find(|docid| {
+                paths
+                    .get(*docid as usize)
+                    .is_some_and(|old| old.as_slice() == new_abs_bytes.as_slice())
+            })
+        };
+        if let Some(docid) = same_path {
+            self.update_entry_metadata(docid, stat);
+            self.set_link_target(docid, &meta);
+        } else if let Some(docid) = self.alloc_docid(fkey, &new_abs_bytes, stat) {
+            self.set_link_target(docid, &meta);
+            self.insert_trigrams(docid, meta.path.as_path());
+            self.insert_path_hash(docid, meta.path.as_path());
+        }
+        self.dirty.store(true, std::sync::atomic::Ordering::Release);
+    }
+
     fn upsert_inner(&self, mut meta: FileMeta, force_path_update: bool) {
         meta.path = crate::index::tiered::normalize_path(&meta.path);
         let fkey = meta.file_key;
         let new_abs_bytes = meta.path.as_os_str().as_encoded_bytes().to_vec();
-        let new_mtime_ns = mtime_to_ns(meta.mtime);
+        let stat = EntryStat::from_meta(&meta);
 
         // 先查 docid（只持有 mapping 的读锁）
-        let existing_docid = { self.filekey_to_docid.read().get(&fkey).copied() };
+        let existing = self.docids_for_key(fkey);
 
-        if let Some(docid) = existing_docid {
+        if !existing.is_empty() {
             // 读旧路径 bytes（不持有 trigram/path_hash 锁）
-            let old_path_bytes = { self.paths.read().get(docid as usize).cloned() };
-            let same_path = old_path_bytes
-                .as_deref()
-                .map(|old| old == new_abs_bytes.as_slice())
-                .unwrap_or(false);
+            let old_paths: Vec<(DocId, Option<Vec<u8>>)> = {
+                let paths = self.paths.read();
+                existing
+                    .iter()
+                    .map(|&docid| (docid, paths.get(docid as usize).cloned()))
+                    .collect()
+            };
 
-            if same_path {
-                // 同路径重复上报：只更新元数据，避免 posting 重复写入
-                self.update_entry_metadata(docid, meta.size, new_mtime_ns);
-                self.dirty.store(true, std::sync::atomic::Ordering::Release);
-                return;
+            // 硬链接共享 inode 元数据：任一路径的上报都刷新全部路径
+            for (docid, _) in &old_paths {
+                self.update_entry_metadata(*docid, stat);
+                self.set_link_target(*docid, &meta);
             }
+            self.dirty.store(true, std::sync::atomic::Ordering::Release);
 
-            let old_path_missing = if force_path_update {
-                false
-            } else {
-                old_path_bytes
-                    .as_ref()
-                    .map(|old| pathbuf_from_encoded_vec(old.clone()))
-                    .map(|old_path| match std::fs::symlink_metadata(&old_path) {
-                        Ok(_) => false,
-                        Err(err) if err.kind() == std::io::ErrorKind::NotFound => true,
-                        Err(_) => false,
-                    })
-                    .unwrap_or(false)
-            };
-
-            // 路径不同：hardlink、rename，或旧路径已消失后的 reconcile
-            if !force_path_update && !old_path_missing {
-                // hardlink/重复发现：保留旧路径，仅更新元数据
-                self.update_entry_metadata(docid, meta.size, new_mtime_ns);
-                self.dirty.store(true, std::sync::atomic::Ordering::Release);
+            if old_paths
+                .iter()
+                .any(|(_, old)| old.as_deref() == Some(new_abs_bytes.as_slice()))
+            {
+                // 同路径重复上报：只更新元数据，避免 posting 重复写入
                 return;
             }
 
-            // rename：先移除旧路径关联
-            if let Some(old_path_bytes) = old_path_bytes {
-                let old_path = pathbuf_from_encoded_vec(old_path_bytes);
-                self.remove_trigrams(docid, &old_path);
-                self.remove_path_hash(docid, &old_path);
-            };
+            // 路径不同：rename、旧路径已消失后的 reconcile，或新的硬链接
+            let moved = old_paths
+                .iter()
+                .find(|(_, old)| old.as_deref().is_some_and(path_bytes_missing))
+                .or_else(|| force_path_update.then(|| old_paths.first()).flatten())
+                .cloned();
+
+            if let Some((docid, old_path_bytes)) = moved {
+                // rename：先移除旧路径关联
+                if let Some(old_path_bytes) = old_path_bytes {
+                    let old_path = pathbuf_from_encoded_vec(old_path_bytes);
+                    self.remove_trigrams(docid, &old_path);
+                    self.remove_path_hash(docid, &old_path);
+                };
 
-            // posting/path_hash 先写（与 query 锁顺序一致：trigram -> entries/paths）
-            self.insert_trigrams(docid, meta.path.as_path());
-            self.insert_path_hash(docid, meta.path.as_path());
+                // posting/path_hash 先写（与 query 锁顺序一致：trigram -> entries/paths）
+                self.insert_trigrams(docid, meta.path.as_path());
+                self.insert_path_hash(docid, meta.path.as_path());
 
-            if !self.update_entry_path(docid, &new_abs_bytes, meta.size, new_mtime_ns) {
-                // 极端情况：docid 槽位不存在，降级为 append
-                if let Some(docid_new) =
-                    self.alloc_docid(fkey, &new_abs_bytes, meta.size, new_mtime_ns)
-                {
-                    self.insert_trigrams(docid_new, meta.path.as_path());
-                    self.insert_path_hash(docid_new, meta.path.as_path());
+                if !self.update_entry_path(docid, &new_abs_bytes, stat) {
+                    // 极端情况：docid 槽位不存在，降级为 append
+                    if let Some(docid_new) = self.alloc_docid(fkey, &new_abs_bytes, stat) {
+                        self.set_link_target(docid_new, &meta);
+                        self.insert_trigrams(docid_new, meta.path.as_path());
+                        self.insert_path_hash(docid_new, meta.path.as_path());
+                    }
                 }
-            }
 
-            // rename 视为“存在且活跃”
-            self.tombstones.write().remove(docid);
-            self.dirty.store(true, std::sync::atomic::Ordering::Release);
-            return;
+                // rename 视为“存在且活跃”
+                self.tombstones.write().remove(docid);
+                return;
+            }
+            // 旧路径都还在：新的硬链接，落到下面按新条目写入
         }
 
-        // 新文件：分配 docid 并写入
-        let Some(docid) = self.alloc_docid(fkey, &new_abs_bytes, meta.size, new_mtime_ns) else {
+        // 新文件 / 新硬链接：分配 docid 并写入
+        let Some(docid) = self.alloc_docid(fkey, &new_abs_bytes, stat) else {
             return;
         };
+        self.set_link_target(docid, &meta);
         self.insert_trigrams(docid, meta.path.as_path());
         self.insert_path_hash(docid, meta.path.as_path());
         self.dirty.store(true, std::sync::atomic::Ordering::Release);
     }
 
+    /// 按 meta 记录（或清除）docid 上的符号链接目标。
+    fn set_link_target(&self, docid: DocId, meta: &FileMeta) {
+        match &meta.target {
+            Some(target) => {
+                let bytes = target.path.as_os_str().as_encoded_bytes().to_vec();
+                self.link_targets.write().insert(docid, bytes);
+            }
+            None => {
+                if !self.link_targets.read().is_empty() {
+                    self.link_targets.write().remove(&docid);
+                }
+            }
+        }
+    }
+
+    /// 该 FileKey 当前关联的全部 docid（每条硬链接路径一个，tombstone 不在其中）。
+    fn docids_for_key(&self, key: FileKey) -> Vec<DocId> {
+        self.filekey_to_docid
+            .read()
+            .get(&key)
+            .map(|v| v.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// 只按 FileKey 定位时（FID 事件）选哪条路径：单路径即它，多路径优先已从磁盘消失的那条。
+    fn docid_for_key_event(&self, key: FileKey) -> Option<DocId> {
+        let docids = self.docids_for_key(key);
+        if docids.len() <= 1 {
+            return docids.first().copied();
+        }
+        let paths = self.paths.read();
+        docids
+            .iter()
+            .copied()
+            .find(|docid| {
+                paths
+                    .get(*docid as usize)
+                    .is_some_and(|p| path_bytes_missing(p))
+            })
+            .or(docids.first().copied())
+    }
+
     fn alloc_docid(
         &self,
         file_key: FileKey,
         abs_path_bytes: &[u8],
-        size: u64,
-        mtime_ns: i64,
+        stat: EntryStat,
     ) -> Option<DocId> {
         let mut entries = self.entries.write();
         let docid: DocId = entries.len() as DocId;
         let path_idx: u32 = docid.try_into().ok()?;
-        entries.push(FileEntry::from_file_key(file_key, path_idx, size, mtime_ns));
+        let mut entry = FileEntry::from_file_key(file_key, path_idx, 0, -1);
+        stat.apply_to(docid, &mut entry, &mut self.entry_times.write());
+        entries.push(entry);
         self.paths.write().push(abs_path_bytes.to_vec());
 
-        self.filekey_to_docid.write().insert(file_key, docid);
+        self.filekey_to_docid
+            .write()
+            .entry(file_key)
+            .and_modify(|v| v.insert(docid))
+            .or_insert(OneOrManyDocId::One(docid));
         self.tombstones.write().remove(docid);
         Some(docid)
     }
 
-    /// 标记删除（tombstone）
+    /// 标记删除（tombstone）：该 FileKey 的全部路径
     pub fn mark_deleted(&self, file_key: FileKey) {
-        let docid = { self.filekey_to_docid.read().get(&file_key).copied() };
-        let Some(docid) = docid else {
-            return;
-        };
-
-        let path = { self.path_buf_for_docid(docid) };
-
-        // Atomicity: mark tombstone first so queries see deleted before trigrams are removed.
-        self.filekey_to_docid.write().remove(&file_key);
-        self.tombstones.write().insert(docid);
-        self.dirty.store(true, std::sync::atomic::Ordering::Release);
-
-        if let Some(p) = path {
-            self.remove_trigrams(docid, &p);
-            self.remove_path_hash(docid, &p);
+        for docid in self.docids_for_key(file_key) {
+            self.tombstone_docid(file_key, docid);
         }
     }
 
-    /// 按路径删除
+    /// 按路径删除（只删这一条；同 inode 的其他硬链接保留）
     pub fn mark_deleted_by_path(&self, path: &Path) {
         if let Some(docid) = self.lookup_docid_by_path(path) {
             let file_key = {
@@ -780,11 +873,33 @@ impl PersistentIndex {
                 entries.get(docid as usize).map(|e| e.file_key())
             };
             if let Some(k) = file_key {
-                self.mark_deleted(k);
+                self.tombstone_docid(k, docid);
             }
         }
     }
 
+    fn tombstone_docid(&self, file_key: FileKey, docid: DocId) {
+        let path = { self.path_buf_for_docid(docid) };
+
+        // Atomicity: mark tombstone first so queries see deleted before trigrams are removed.
+        {
+            let mut map = self.filekey_to_docid.write();
+            if let Some(docids) = map.get_mut(&file_key) {
+                if docids.remove(docid) {
+                    map.remove(&file_key);
+                }
+            }
+        }
+        self.tombstones.write().insert(docid);
+        self.link_targets.write().remove(&docid);
+        self.dirty.store(true, std::sync::atomic::Ordering::Release);
+
+        if let Some(p) = path {
+            self.remove_trigrams(docid, &p);
+            self.remove_path_hash(docid, &p);
+        }
+    }
+
     pub fn path_freshness(&self, path: &Path, size: u64, mtime_ns: i64) -> PathFreshness {
         let Some(docid) = self.lookup_docid_by_path(path) else {
             return PathFreshness::Missing;
@@ -792,10 +907,10 @@ impl PersistentIndex {
         if self.tombstones.read().contains(docid) {
             return PathFreshness::Missing;
         }
-        let Some((old_size, old_mtime_ns)) = self.entry_size_mtime(docid) else {
+        let Some(old) = self.entry_stat(docid) else {
             return PathFreshness::Changed;
         };
-        if old_size == size && old_mtime_ns == mtime_ns {
+        if old.size == size && old.mtime_ns == mtime_ns {
             PathFreshness::Unchanged
         } else {
             PathFreshness::Changed
@@ -814,6 +929,9 @@ impl PersistentIndex {
         let entries = self.entries.read();
         let paths = self.paths.read();
         let tombstones = self.tombstones.read();
+        let link_targets = self.link_targets.read();
+        let target_of = |docid: DocId| link_targets.get(&docid).map(Vec::as_slice);
+        let times = self.entry_times.read();
 
         match candidates {
             Some(bitmap) => bitmap
@@ -822,16 +940,22 @@ impl PersistentIndex {
                 .filter_map(|docid| {
                     let entry = entries.get(docid as usize)?;
                     let path_bytes = paths.get(docid as usize)?;
-                    Some((entry, path_bytes))
+                    Some((docid, entry, path_bytes))
                 })
-                .filter(|(_, path_bytes)| {
+                .filter(|(_, _, path_bytes)| {
                     let s = std::str::from_utf8(path_bytes)
                         .map(std::borrow::Cow::Borrowed)
                         .unwrap_or_else(|_| String::from_utf8_lossy(path_bytes));
                     matcher.matches(&s)
                 })
-                .map(|(entry, path_bytes)| {
-                    Self::meta_from_entry_and_path(entry, path_bytes.as_slice())
+                .map(|(docid, entry, path_bytes)| {
+                    Self::meta_from_entry_and_path(
+                        docid,
+                        entry,
+                        &times,
+                        path_bytes.as_slice(),
+                        target_of(docid),
+                    )
                 })
                 .take(limit)
                 .collect(),
@@ -850,7 +974,13 @@ impl PersistentIndex {
                             .map(std::borrow::Cow::Borrowed)
                             .unwrap_or_else(|_| String::from_utf8_lossy(path_bytes));
                         if matcher.matches(&s) {
-                            Some(Self::meta_from_entry_and_path(entry, path_bytes))
+                            Some(Self::meta_from_entry_and_path(
+                                docid,
+                                entry,
+                                &times,
+                                path_bytes,
+                                target_of(docid),
+                            ))
                         } else {
                             None
                         }
@@ -866,6 +996,8 @@ impl PersistentIndex {
         let entries = self.entries.read();
         let paths = self.paths.read();
         let tombstones = self.tombstones.read();
+        let link_targets = self.link_targets.read();
+        let times = self.entry_times.read();
 
         for (i, entry) in entries.iter().enumerate() {
             let docid: DocId = i as DocId;
@@ -875,8 +1007,46 @@ impl PersistentIndex {
             let Some(path_bytes) = paths.get(i) else {
                 continue;
             };
-            f(Self::meta_from_entry_and_path(entry, path_bytes));
+            f(Self::meta_from_entry_and_path(
+                docid,
+                entry,
+                &times,
+                path_bytes,
+                link_targets.get(&docid).map(Vec::as_slice),
+            ));
+        }
+    }
+
+    /// 存活的目录条目路径：只看条目类型，不物化元数据。
+    pub fn live_dir_paths(&self) -> Vec<PathBuf> {
+        let entries = self.entries.read();
+        let paths = self.paths.read();
+        let tombstones = self.tombstones.read();
+        entries
+            .iter()
+            .zip(paths.iter())
+            .enumerate()
+            .filter(|(i, (entry, _))| {
+                entry.kind() == FileKind::Dir && !tombstones.contains(*i as DocId)
+            })
+            .map(|(_, (_, p))| pathbuf_from_encoded_vec(p.clone()))
+            .collect()
+    }
+
+    /// `dir` 之下（不含 `dir` 本身）的存活路径：只比较路径字节，不物化元数据。
+    pub fn live_paths_under(&self, dir: &Path) -> Vec<PathBuf> {
+        let mut prefix = dir.as_os_str().as_encoded_bytes().to_vec();
+        if !prefix.ends_with(b"/") {
+            prefix.push(b'/');
         }
+        let paths = self.paths.read();
+        let tombstones = self.tombstones.read();
+        paths
+            .iter()
+            .enumerate()
+            .filter(|(i, p)| p.starts_with(&prefix) && !tombstones.contains(*i as DocId))
+            .map(|(_, p)| pathbuf_from_encoded_vec(p.clone()))
+            .collect()
     }
 
     /// 构建/重建 ParentIndex
@@ -999,17 +1169,12 @@ impl PersistentIndex {
         }
     }
 
-    fn resolve_path_meta(path: &Path) -> Option<ResolvedFsMeta> {
-        let meta = std::fs::metadata(path).ok()?;
-        Some(ResolvedFsMeta {
-            file_key: FileKey::from_path_and_metadata(path, &meta)?,
-            size: meta.len(),
-            mtime: meta.modified().ok(),
-        })
+    fn resolve_path_meta(path: &Path) -> Option<FileMeta> {
+        FileMeta::from_path(path)
     }
 
     fn existing_path_for_file_key(&self, fk: FileKey) -> Option<PathBuf> {
-        let docid = { self.filekey_to_docid.read().get(&fk).copied()? };
+        let docid = self.docids_for_key(fk).first().copied()?;
         self.path_buf_for_docid(docid)
     }
 
@@ -1102,14 +1267,7 @@ impl PersistentIndex {
             let Some(meta) = Self::resolve_path_meta(path.as_ref()) else {
                 return;
             };
-            self.upsert(FileMeta {
-                file_key: meta.file_key,
-                path: path.into_owned(),
-                size: meta.size,
-                mtime: meta.mtime,
-                ctime: None,
-                atime: None,
-            });
+            self.upsert(meta);
         }
 
         let Some(fk) = fid else {
@@ -1122,22 +1280,43 @@ impl PersistentIndex {
             return;
         };
         if meta.file_key == fk {
-            self.upsert(FileMeta {
-                file_key: fk,
-                path,
-                size: meta.size,
-                mtime: meta.mtime,
-                ctime: None,
-                atime: None,
-            });
+            self.upsert(meta);
         }
     }
 
     fn handle_delete(&self, path: Option<&Path>, fid: Option<FileKey>) {
-        if let Some(fk) = fid {
+        let Some(fk) = fid else {
+            if let Some(path) = path {
+                self.mark_deleted_by_path(path);
+            }
+            return;
+        };
+        let docids = self.docids_for_key(fk);
+        if docids.len() <= 1 {
             self.mark_deleted(fk);
-        } else if let Some(path) = path {
-            self.mark_deleted_by_path(path);
+            return;
+        }
+        // 多路径（硬链接）：只删事件指向的那条；没有路径时删掉已从磁盘消失的路径
+        if let Some(docid) = path
+            .and_then(|p| self.lookup_docid_by_path(p))
+            .filter(|docid| docids.contains(docid))
+        {
+            self.tombstone_docid(fk, docid);
+            return;
+        }
+        let gone: Vec<DocId> = {
+            let paths = self.paths.read();
+            docids
+                .into_iter()
+                .filter(|docid| {
+                    paths
+                        .get(*docid as usize)
+                        .is_some_and(|p| path_bytes_missing(p))
+                })
+                .collect()
+        };
+        for docid in gone {
+            self.tombstone_docid(fk, docid);
         }
     }
 
@@ -1156,13 +1335,11 @@ impl PersistentIndex {
             None
         };
 
-        let docid_opt = if let Some(fk) = from_fid {
-            self.filekey_to_docid.read().get(&fk).copied()
-        } else {
-            from_best_path
-                .as_deref()
-                .and_then(|p| self.lookup_docid_by_path(p))
-        };
+        // 路径比 FID 精确（FID 可能对应多条硬链接路径）
+        let docid_opt = from_best_path
+            .as_deref()
+            .and_then(|p| self.lookup_docid_by_path(p))
+            .or_else(|| from_fid.and_then(|fk| self.docid_for_key_event(fk)));
 
         if let Some(docid) = docid_opt {
             if let Some(old_path) = self.path_buf_for_docid(docid) {
@@ -1175,17 +1352,22 @@ impl PersistentIndex {
 
             if let Some(ref to_path) = to_path {
                 let to_path_owned = to_path.clone();
-                let (size, mtime_ns) = if let Some(meta) = to_meta {
-                    (meta.size, mtime_to_ns(meta.mtime))
-                } else {
-                    self.entry_size_mtime(docid).unwrap_or((0, -1))
+                let stat = match &to_meta {
+                    Some(meta) => {
+                        self.set_link_target(docid, meta);
+                        Some(EntryStat::from_meta(meta))
+                    }
+                    None => self.entry_stat(docid),
                 };
                 self.insert_trigrams(docid, &to_path_owned);
                 self.insert_path_hash(docid, &to_path_owned);
                 let abs_path_bytes = to_path_owned.as_os_str().as_encoded_bytes().to_vec();
-                self.update_entry_path(docid, &abs_path_bytes, size, mtime_ns);
+                if let Some(stat) = stat {
+                    self.update_entry_path(docid, &abs_path_bytes, stat);
+                }
             } else if let Some(meta) = fallback_meta {
-                self.update_entry_metadata(docid, meta.size, mtime_to_ns(meta.mtime));
+                self.update_entry_metadata(docid, EntryStat::from_meta(&meta));
+                self.set_link_target(docid, &meta);
                 if let Some(old_path) = self.path_buf_for_docid(docid) {
                     self.insert_trigrams(docid, &old_path);
                     self.insert_path_hash(docid, &old_path);
@@ -1206,15 +1388,8 @@ impl PersistentIndex {
         }
 
         self.handle_delete(from_best_path.as_deref(), from_fid);
-        if let (Some(to_path), Some(meta)) = (to_path, to_meta) {
-            self.upsert(FileMeta {
-                file_key: meta.file_key,
-                path: to_path.into_owned(),
-                size: meta.size,
-                mtime: meta.mtime,
-                ctime: None,
-                atime: None,
-            });
+        if let (Some(_), Some(meta)) = (to_path, to_meta) {
+            self.upsert(meta);
         }
     }
 
@@ -1242,7 +1417,7 @@ impl PersistentIndex {
     /// 用途：段合并/replace-base 时做“真·Tombstone GC”，让段文件尺寸随真实文件系统状态收敛。
     pub fn export_segments_v6_compacted(&self) -> V6Segments {
         let compact = PersistentIndex::new_with_roots(self.roots.clone());
-        self.for_each_live_meta(|m| compact.upsert_rename(m));
+        self.for_each_live_meta(|m| compact.upsert_exact(m));
         compact.export_segments_v6()
     }
 
@@ -1357,7 +1532,9 @@ impl PersistentIndex {
         // 注意：来源为 filekey_to_docid（天然排除 tombstone）。
         let mut pairs: Vec<(FileKey, DocId)> = {
             let m = self.filekey_to_docid.read();
-            m.iter().map(|(k, v)| (*k, *v)).collect()
+            m.iter()
+                .flat_map(|(k, v)| v.iter().map(move |docid| (*k, *docid)))
+                .collect()
         };
         pairs.sort_unstable_by_key(|(k, _)| (k.dev, k.ino, k.generation));
         const FKM_MAGIC: [u8; 4] = *b"FKM\0";
@@ -1505,7 +1682,9 @@ impl PersistentIndex {
         // FileKeyMap 段
         let mut pairs: Vec<(FileKey, DocId)> = {
             let m = self.filekey_to_docid.read();
-            m.iter().map(|(k, v)| (*k, *v)).collect()
+            m.iter()
+                .flat_map(|(k, v)| v.iter().map(move |docid| (*k, *docid)))
+                .collect()
         };
         pairs.sort_unstable_by_key(|(k, _)| (k.dev, k.ino, k.generation));
         const FKM_MAGIC: [u8; 4] = *b"FKM\0";
@@ -1552,7 +1731,7 @@ impl PersistentIndex {
         writer: &mut impl std::io::Write,
     ) -> std::io::Result<()> {
         let compact = PersistentIndex::new_with_roots(self.roots.clone());
-        self.for_each_live_meta(|m| compact.upsert_rename(m));
+        self.for_each_live_meta(|m| compact.upsert_exact(m));
         compact.export_segments_v6_to_writer(writer)
     }
 
@@ -1599,14 +1778,23 @@ impl PersistentIndex {
         // - 这是“近似占用”，不包含 allocator 产生的碎片/空闲块（RSS 高水位常驻的主要来源）。
         // - HashMap 的真实 bucket/ctrl 布局由 hashbrown 决定，这里按“entry + 1B ctrl”做近似。
 
-        // entries: Vec<FileEntry>
+        // entries: Vec<FileEntry> + ctime/atime 侧列
         let metas_bytes = entries.capacity() as u64 * size_of::<FileEntry>() as u64
-            + size_of::<Vec<FileEntry>>() as u64;
-
-        // mapping: HashMap<FileKey, DocId>
-        let map_entry_bytes = size_of::<(FileKey, DocId)>() as u64;
+            + size_of::<Vec<FileEntry>>() as u64
+            + self.entry_times.read().allocated_bytes() as u64;
+
+        // mapping: HashMap<FileKey, OneOrManyDocId>（硬链接的 Many 另计堆上 Vec）
+        let map_entry_bytes = size_of::<(FileKey, OneOrManyDocId)>() as u64;
+        let link_bytes: u64 = filekey_to_docid
+            .values()
+            .map(|v| match v {
+                OneOrManyDocId::One(_) => 0,
+                OneOrManyDocId::Many(ids) => (ids.capacity() * size_of::<DocId>()) as u64,
+            })
+            .sum();
         let filekey_to_docid_bytes = filekey_to_docid.len() as u64 * (map_entry_bytes + 1)
-            + size_of::<HashMap<FileKey, DocId>>() as u64;
+            + link_bytes
+            + size_of::<HashMap<FileKey, OneOrManyDocId>>() as u64;
 
         // paths: Vec<Vec<u8>>
         let mut arena_bytes = paths.capacity() as u64 * size_of::<Vec<u8>>() as u64
@@ -1699,6 +1887,7 @@ impl PersistentIndex {
         self.filekey_to_docid.write().clear();
         self.tombstones.write().clear();
         self.entries.write().clear();
+        *self.entry_times.write() = EntryTimes::default();
         self.paths.write().clear();
         self.dirty.store(true, std::sync::atomic::Ordering::Release);
     }
@@ -1748,14 +1937,26 @@ impl PersistentIndex {
         (root_id, rel_bytes)
     }
 
-    fn meta_from_entry_and_path(entry: &FileEntry, path_bytes: &[u8]) -> FileMeta {
+    fn meta_from_entry_and_path(
+        docid: DocId,
+        entry: &FileEntry,
+        times: &EntryTimes,
+        path_bytes: &[u8],
+        target: Option<&[u8]>,
+    ) -> FileMeta {
         FileMeta {
             file_key: entry.file_key(),
             path: pathbuf_from_encoded_vec(path_bytes.to_vec()),
             size: entry.size,
-            mtime: mtime_from_ns(entry.mtime_ns),
-            ctime: None,
-            atime: None,
+            mtime: entry.mtime(),
+            ctime: times.ctime(docid as usize),
+            atime: times.atime(docid as usize),
+            kind: entry.kind(),
+            nlink: entry.nlink,
+            target: target.map(|t| LinkTarget {
+                path: pathbuf_from_encoded_vec(t.to_vec()),
+                broken: entry.is_broken(),
+            }),
         }
     }
 
@@ -1766,29 +1967,24 @@ impl PersistentIndex {
             .map(|bytes| pathbuf_from_encoded_vec(bytes.clone()))
     }
 
-    fn entry_size_mtime(&self, docid: DocId) -> Option<(u64, i64)> {
+    fn entry_stat(&self, docid: DocId) -> Option<EntryStat> {
         let entries = self.entries.read();
-        let entry = entries.get(docid as usize)?;
-        Some((entry.size, entry.mtime_ns))
+        let times = self.entry_times.read();
+        entries
+            .get(docid as usize)
+            .map(|entry| EntryStat::of_entry(entry, &times, docid))
     }
 
-    fn update_entry_metadata(&self, docid: DocId, size: u64, mtime_ns: i64) -> bool {
+    fn update_entry_metadata(&self, docid: DocId, stat: EntryStat) -> bool {
         let mut entries = self.entries.write();
         let Some(entry) = entries.get_mut(docid as usize) else {
             return false;
         };
-        entry.size = size;
-        entry.mtime_ns = mtime_ns;
+        stat.apply_to(docid, entry, &mut self.entry_times.write());
         true
     }
 
-    fn update_entry_path(
-        &self,
-        docid: DocId,
-        abs_path_bytes: &[u8],
-        size: u64,
-        mtime_ns: i64,
-    ) -> bool {
+    fn update_entry_path(&self, docid: DocId, abs_path_bytes: &[u8], stat: EntryStat) -> bool {
         {
             let mut entries = self.entries.write();
             let Some(entry) = entries.get_mut(docid as usize) else {
@@ -1798,8 +1994,7 @@ impl PersistentIndex {
                 Ok(path_idx) => path_idx,
                 Err(_) => return false,
             };
-            entry.size = size;
-            entry.mtime_ns = mtime_ns;
+            stat.apply_to(docid, entry, &mut self.entry_times.write());
         }
         let mut paths = self.paths.write();
         let Some(path) = paths.get_mut(docid as usize) else {
@@ -1970,6 +2165,8 @@ impl PersistentIndex {
         let paths_v2 = self.paths.read();
         let tombstones = self.tombstones.read();
         let trigram_index = self.trigram_index.read();
+        let link_targets = self.link_targets.read();
+        let entry_times = self.entry_times.read();
 
         let mut rebuild_path_table = RebuildPathTable::new();
         for root in &self.roots_bytes {
@@ -1979,6 +2176,7 @@ impl PersistentIndex {
         }
         let mut entry_path_idxs: Vec<u32> = Vec::with_capacity(entries_v2.len());
         let mut parent_entries: Vec<(u32, u64)> = Vec::with_capacity(entries_v2.len());
+        let mut short_grams = crate::index::base_index::ShortGramIndex::new();
 
         for (docid_usize, abs_bytes) in paths_v2.iter().enumerate() {
             intern_parent_dirs(&mut rebuild_path_table, abs_bytes);
@@ -1987,6 +2185,7 @@ impl PersistentIndex {
             let docid = docid_usize as DocId;
             if !tombstones.contains(docid) {
                 parent_entries.push((path_idx, docid as u64));
+                short_grams.add_path(docid as u32, &pathbuf_from_encoded_vec(abs_bytes.clone()));
             }
         }
 
@@ -2003,13 +2202,25 @@ impl PersistentIndex {
             let Some(&path_idx) = entry_path_idxs.get(docid_usize) else {
                 continue;
             };
-            let new_entry = crate::index::file_entry_v2::FileEntry::from_file_key(
+            let mut new_entry = crate::index::file_entry_v2::FileEntry::from_file_key(
                 entry.file_key(),
                 path_idx,
                 entry.size,
                 entry.mtime_ns,
+            )
+            .with_kind(entry.kind())
+            .with_nlink(entry.nlink);
+            new_entry.set_broken(entry.is_broken());
+            let new_docid = entry_index.len() as u32;
+            match link_targets.get(&(docid_usize as DocId)) {
+                Some(target) => entry_index.push_link(new_entry, target),
+                None => entry_index.push(new_entry),
+            }
+            entry_index.set_times(
+                new_docid,
+                entry_times.ctime_ns(docid_usize),
+                entry_times.atime_ns(docid_usize),
             );
-            entry_index.push(new_entry);
         }
 
         let path_table = path_table_builder.build();
@@ -2027,12 +2238,19 @@ impl PersistentIndex {
 
         let tombstones_bitmap: roaring::RoaringBitmap =
             tombstones.iter().map(|v| v as u32).collect();
+        let dir_mtimes = crate::index::dir_mtimes::DirMtimeTable::build(
+            &entries_by_key,
+            &parent_index,
+            &tombstones_bitmap,
+        );
 
         crate::index::base_index::BaseIndexData {
             path_table,
             entries_by_key,
             trigram_index: tri,
+            short_gram_index: short_grams,
             parent_index,
+            dir_mtimes,
             tombstones: tombstones_bitmap,
         }
     }
@@ -2119,7 +2337,7 @@ impl IndexLayer for PersistentIndex {
     }
 
     fn get_meta(&self, key: FileKey) -> Option<FileMeta> {
-        let docid = { self.filekey_to_docid.read().get(&key).copied()? };
+        let docid = self.docids_for_key(key).first().copied()?;
         if self.tombstones.read().contains(docid) {
             return None;
         }
@@ -2127,10 +2345,26 @@ impl IndexLayer for PersistentIndex {
         let paths = self.paths.read();
         let entry = entries.get(docid as usize)?;
         let path_bytes = paths.get(docid as usize)?;
-        Some(PersistentIndex::meta_from_entry_and_path(entry, path_bytes))
+        let link_targets = self.link_targets.read();
+        Some(PersistentIndex::meta_from_entry_and_path(
+            docid,
+            entry,
+            &self.entry_times.read(),
+            path_bytes,
+            link_targets.get(&docid).map(Vec::as_slice),
+        ))
     }
 }
 
+/// 旧路径是否已从磁盘消失（只认 NotFound；其余错误按“仍存在”保守处理）。
+fn path_bytes_missing(path_bytes: &[u8]) -> bool {
+    let path = pathbuf_from_encoded_vec(path_bytes.to_vec());
+    matches!(
+        std::fs::symlink_metadata(&path),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound
+    )
+}
+
 fn path_hash_bytes(bytes: &[u8]) -> u64 {
     let mut hasher = std::collections::hash_map::DefaultHasher::new();
     bytes.hash(&mut hasher);
@@ -2180,6 +2414,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
         idx.upsert(FileMeta {
             file_key: FileKey {
@@ -2192,6 +2429,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         let m = create_matcher("alpha", true);
@@ -2214,6 +2454,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
         idx.upsert(FileMeta {
             file_key: FileKey {
@@ -2226,6 +2469,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         let m = create_matcher("ab", true);
@@ -2252,6 +2498,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         assert_eq!(idx.file_count(), 1);
@@ -2275,6 +2524,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         let long_path = PathBuf::from(format!("/tmp/{}", "b".repeat(u16::MAX as usize + 1)));
@@ -2289,6 +2541,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         assert_eq!(idx.file_count(), 1);
@@ -2330,6 +2585,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         let new_project = root.join("new_project");
@@ -2343,6 +2601,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         let meta = idx.get_meta(file_key).expect("file should remain indexed");
@@ -2376,6 +2637,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         let m = create_matcher("中文", true);
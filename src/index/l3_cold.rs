@@ -1,16 +1,20 @@
-use crate::core::{BuildRDD, ExecutionStrategy, FileMeta, FsScanRDD};
-use crate::index::l2_partition::PersistentIndex;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-
-/// L3: IndexBuilder — 仅用于启动全扫/补扫/重建，不进入查询链路
+use crate::core::path_rules::PathRules;
+use crate::core::{BuildRDD, ExecutionStrategy, FileMeta, FsScanRDD};
+use crate::index::l2_partition::PersistentIndex;
+use arc_swap::ArcSwap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// L3: IndexBuilder — 仅用于启动全扫/补扫/重建，不进入查询链路
 pub struct IndexBuilder {
     pub roots: Vec<PathBuf>,
     pub include_hidden: bool,
     pub ignore_enabled: bool,
     pub follow_symlinks: bool,
     pub exclude_dirs: Vec<String>,
+    /// 索引期 glob 规则；运行期可整体替换，扫描时取当前版本。
+    path_rules: ArcSwap<PathRules>,
 }
 
 impl IndexBuilder {
@@ -58,53 +62,74 @@ impl IndexBuilder {
             ignore_enabled,
             follow_symlinks,
             exclude_dirs,
+            path_rules: ArcSwap::from_pointee(PathRules::default()),
         }
     }
-
+
+    pub fn path_rules(&self) -> Arc<PathRules> {
+        self.path_rules.load_full()
+    }
+
+    pub fn set_path_rules(&self, path_rules: Arc<PathRules>) {
+        self.path_rules.store(path_rules);
+    }
+
     /// 全量构建：扫描所有 roots，流式灌入 PersistentIndex
     pub fn full_build(&self, index: &PersistentIndex) {
         let rdd = FsScanRDD::from_roots(self.roots.clone())
             .with_hidden(self.include_hidden)
             .with_ignore_rules(self.ignore_enabled)
             .with_follow_links(self.follow_symlinks)
-            .with_exclude_dirs(self.exclude_dirs.clone());
+            .with_exclude_dirs(self.exclude_dirs.clone())
+            .with_path_rules(self.path_rules());
         let mut count = 0usize;
-
-        rdd.for_each(|meta: FileMeta| {
-            index.upsert(meta);
-            count += 1;
+
+        rdd.for_each(|meta: FileMeta| {
+            index.upsert(meta);
+            count += 1;
             if count.is_multiple_of(10000) {
-                tracing::info!("IndexBuilder: scanned {} files...", count);
-            }
-        });
-
-        tracing::info!("IndexBuilder: full build complete, {} files indexed", count);
-    }
-
-    /// 全量构建（带执行策略）：用于 rebuild/full_build 的“弹性计算”。
-    pub fn full_build_with_strategy(
-        &self,
-        index: &Arc<PersistentIndex>,
-        strategy: ExecutionStrategy,
-    ) {
-        let mut parallelism = match strategy {
-            ExecutionStrategy::Serial => 1,
-            ExecutionStrategy::Parallel { shards, .. } => shards.max(1),
-        };
-        // 兜底：避免过高并发导致系统抖动；scheduler 可能会放大 shards。
-        let max_threads = num_cpus::get().saturating_mul(2).max(1);
-        parallelism = parallelism.clamp(1, max_threads);
-
-        let rdd = FsScanRDD::from_roots(self.roots.clone())
+                tracing::info!("IndexBuilder: scanned {} files...", count);
+            }
+        });
+
+        tracing::info!("IndexBuilder: full build complete, {} files indexed", count);
+    }
+
+    /// 全量构建（带执行策略）：用于 rebuild/full_build 的“弹性计算”。
+    pub fn full_build_with_strategy(
+        &self,
+        index: &Arc<PersistentIndex>,
+        strategy: ExecutionStrategy,
+    ) {
+        self.full_build_roots_with_strategy(self.roots.clone(), index, strategy);
+    }
+
+    /// 同 `full_build_with_strategy`，但扫描调用方给出的 roots（运行期增删 root 后以索引当前的 roots 为准）。
+    pub fn full_build_roots_with_strategy(
+        &self,
+        roots: Vec<PathBuf>,
+        index: &Arc<PersistentIndex>,
+        strategy: ExecutionStrategy,
+    ) {
+        let mut parallelism = match strategy {
+            ExecutionStrategy::Serial => 1,
+            ExecutionStrategy::Parallel { shards, .. } => shards.max(1),
+        };
+        // 兜底：避免过高并发导致系统抖动；scheduler 可能会放大 shards。
+        let max_threads = num_cpus::get().saturating_mul(2).max(1);
+        parallelism = parallelism.clamp(1, max_threads);
+
+        let rdd = FsScanRDD::from_roots(roots)
             .with_hidden(self.include_hidden)
             .with_ignore_rules(self.ignore_enabled)
             .with_follow_links(self.follow_symlinks)
             .with_exclude_dirs(self.exclude_dirs.clone())
+            .with_path_rules(self.path_rules())
             .with_parallelism(parallelism);
-        let count = Arc::new(AtomicUsize::new(0));
-        let idx = index.clone();
-        let c = count.clone();
-
+        let count = Arc::new(AtomicUsize::new(0));
+        let idx = index.clone();
+        let c = count.clone();
+
         rdd.for_each_meta(move |meta: FileMeta| {
             idx.upsert(meta);
             let n = c.fetch_add(1, Ordering::Relaxed) + 1;
@@ -115,33 +140,34 @@ impl IndexBuilder {
                 std::thread::sleep(std::time::Duration::from_millis(1));
             }
         });
-
-        let total = count.load(Ordering::Relaxed);
-        tracing::info!(
-            "IndexBuilder: full build complete, {} files indexed (parallelism={}, strategy={:?})",
-            total,
-            parallelism,
-            strategy
-        );
-    }
-
+
+        let total = count.load(Ordering::Relaxed);
+        tracing::info!(
+            "IndexBuilder: full build complete, {} files indexed (parallelism={}, strategy={:?})",
+            total,
+            parallelism,
+            strategy
+        );
+    }
+
     /// 增量补扫：扫描指定目录，补充缺失条目
     pub fn incremental_scan(&self, index: &PersistentIndex, dirs: Vec<PathBuf>) {
         let rdd = FsScanRDD::from_roots(dirs)
             .with_hidden(self.include_hidden)
             .with_ignore_rules(self.ignore_enabled)
             .with_follow_links(self.follow_symlinks)
-            .with_exclude_dirs(self.exclude_dirs.clone());
+            .with_exclude_dirs(self.exclude_dirs.clone())
+            .with_path_rules(self.path_rules());
         let mut count = 0usize;
-
-        rdd.for_each(|meta: FileMeta| {
-            index.upsert(meta);
-            count += 1;
-        });
-
-        tracing::info!(
-            "IndexBuilder: incremental scan complete, {} files updated",
-            count
-        );
-    }
-}
+
+        rdd.for_each(|meta: FileMeta| {
+            index.upsert(meta);
+            count += 1;
+        });
+
+        tracing::info!(
+            "IndexBuilder: incremental scan complete, {} files updated",
+            count
+        );
+    }
+}
@@ -7,7 +7,7 @@ use roaring::RoaringTreemap;
 
 #[cfg(feature = "rkyv")]
 use crate::core::FileKeyEntry;
-use crate::core::{FileKey, FileMeta};
+use crate::core::{FileKey, FileKind, FileMeta};
 use crate::index::IndexLayer;
 use crate::query::matcher::Matcher;
 use crate::storage::snapshot::MmapSnapshotV6;
@@ -650,6 +650,9 @@ impl MmapIndex {
             mtime,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         })
     }
 
@@ -697,6 +700,9 @@ impl MmapIndex {
                     mtime,
                     ctime: None,
                     atime: None,
+                    kind: FileKind::File,
+                    nlink: 0,
+                    target: None,
                 });
             }
             return out;
@@ -728,6 +734,9 @@ impl MmapIndex {
                 mtime,
                 ctime: None,
                 atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
             });
         }
 
@@ -759,6 +768,9 @@ impl MmapIndex {
                 mtime,
                 ctime: None,
                 atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
             });
         }
     }
@@ -866,6 +878,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         let store = SnapshotStore::new(root.join("index.db"));
@@ -961,6 +976,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
         idx.upsert(FileMeta {
             file_key: FileKey {
@@ -973,6 +991,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         // 模拟“旧段”：仅 basename 建 trigram，且无哨兵 key。
@@ -1028,6 +1049,9 @@ mod tests {
                 mtime: None,
                 ctime: None,
                 atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
             });
         }
 
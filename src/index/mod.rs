@@ -1,12 +1,15 @@
 pub mod base_index;
 pub mod content_filter;
+pub mod content_index;
 pub mod delta_buffer;
+pub mod dir_mtimes;
 pub mod file_entry_v2;
 pub mod l1_cache;
 pub mod l2_partition;
 pub mod l3_cold;
 pub mod mmap_index;
 pub mod parent_index;
+pub mod partitioned_base;
 pub mod path_table_v2;
 pub mod pathtable;
 pub mod tiered;
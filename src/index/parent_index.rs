@@ -53,8 +53,8 @@ impl ParentIndex {
 
     /// Build a ParentIndex from a slice of (path_idx, doc_id) entries.
     ///
-    /// For each file entry (where `path_table.is_dir(path_idx) == false`):
-    /// - The file's doc_id is added to its immediate parent directory.
+    /// For each entry (files and indexed directories alike):
+    /// - The entry's doc_id is added to its immediate parent directory.
     ///
     /// The index intentionally stores only direct children; recursive directory expansion is
     /// handled by higher-level scans when needed.
@@ -62,11 +62,10 @@ impl ParentIndex {
         let mut dir_to_files: HashMap<u32, Vec<u32>> = HashMap::new();
 
         for &(path_idx, doc_id) in entries {
-            // Add file to its immediate parent directory
-            if !path_table.is_dir(path_idx) {
-                if let Some(parent) = path_table.parent_idx(path_idx) {
-                    dir_to_files.entry(parent).or_default().push(doc_id as u32);
-                }
+            // Add entry to its immediate parent directory. Directory entries are real docs
+            // too, so they are listed under their parent just like files.
+            if let Some(parent) = path_table.parent_idx(path_idx) {
+                dir_to_files.entry(parent).or_default().push(doc_id as u32);
             }
         }
 
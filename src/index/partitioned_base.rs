@@ -0,0 +1,318 @@
+//! PartitionedBase：按 root 分区的只读基础索引。
+//!
+//! 每个分区持有独立的 `BaseIndexData`（路径表、trigram/short-gram posting、ParentIndex），
+//! 可单独重建、丢弃或从快照段重新加载而不触碰其他分区。查询按分区依次扇出，
+//! 由调用方用全局 `seen` / `blocked_paths` 合并去重。
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::core::partition::{route_path, Partition, FALLBACK_PARTITION_ROOT};
+use crate::core::{FileKey, FileMeta};
+use crate::index::base_index::{BaseIndexData, CandidateScan};
+use crate::index::dir_mtimes::DirCheck;
+use crate::index::l2_partition::PersistentIndex;
+use crate::index::PathFreshness;
+use crate::query::Matcher;
+use crate::stats::BaseStats;
+
+/// 单个分区：root + 该 root 下的只读基础索引。
+#[derive(Clone, Debug)]
+pub struct BasePartition {
+    pub partition: Partition,
+    pub data: Arc<BaseIndexData>,
+    /// 每次替换分区数据时递增；快照只重写 generation 变化过的分区段。
+    pub generation: u64,
+}
+
+impl BasePartition {
+    pub fn root(&self) -> &Path {
+        &self.partition.root
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PartitionedBase {
+    parts: Vec<BasePartition>,
+}
+
+impl PartitionedBase {
+    pub fn new(parts: Vec<BasePartition>) -> Self {
+        let mut out = Self { parts: Vec::new() };
+        for part in parts {
+            out.upsert(part);
+        }
+        out
+    }
+
+    /// 旧的单体 base（legacy v7 快照 / 全量重建结果）按 roots 拆成分区。
+    pub fn split(data: BaseIndexData, roots: &[PathBuf], generation: u64) -> Self {
+        let mut metas: Vec<FileMeta> = Vec::with_capacity(data.file_count());
+        data.for_each_live_meta(|meta| metas.push(meta));
+        drop(data);
+        Self::from_metas(roots, metas, generation)
+    }
+
+    /// 按最长 root 前缀把元数据分桶，逐分区构建；root 外的路径进入 `/` 兜底分区。
+    /// 配置的 root 即使为空也保留分区，兜底分区只在有数据时出现。
+    pub fn from_metas(roots: &[PathBuf], metas: Vec<FileMeta>, generation: u64) -> Self {
+        let mut buckets: Vec<(PathBuf, Vec<FileMeta>)> =
+            roots.iter().map(|r| (r.clone(), Vec::new())).collect();
+        for meta in metas {
+            let slot = match route_path(buckets.iter().map(|(r, _)| r.as_path()), &meta.path) {
+                Some(i) => i,
+                None => {
+                    buckets.push((PathBuf::from(FALLBACK_PARTITION_ROOT), Vec::new()));
+                    buckets.len() - 1
+                }
+            };
+            buckets[slot].1.push(meta);
+        }
+        Self::new(
+            buckets
+                .into_iter()
+                .enumerate()
+                .map(|(id, (root, metas))| BasePartition {
+                    data: Arc::new(build_partition_data(&root, metas)),
+                    partition: Partition::for_root(id, root),
+                    generation,
+                })
+                .collect(),
+        )
+    }
+
+    pub fn parts(&self) -> &[BasePartition] {
+        &self.parts
+    }
+
+    pub fn partition(&self, root: &Path) -> Option<&BasePartition> {
+        self.parts.iter().find(|p| p.root() == root)
+    }
+
+    pub fn roots(&self) -> Vec<PathBuf> {
+        self.parts.iter().map(|p| p.root().to_path_buf()).collect()
+    }
+
+    /// 路径归属的分区下标（最长前缀匹配）。
+    pub fn route(&self, path: &Path) -> Option<usize> {
+        route_path(self.parts.iter().map(|p| p.root()), path)
+    }
+
+    /// 新增或替换同 root 的分区，返回新的分区集合（原集合不变，供 ArcSwap 整体切换）。
+    pub fn with_partition(&self, part: BasePartition) -> Self {
+        let mut out = self.clone();
+        out.upsert(part);
+        out
+    }
+
+    pub fn without_partition(&self, root: &Path) -> Self {
+        let mut out = self.clone();
+        out.parts.retain(|p| p.root() != root);
+        out.renumber();
+        out
+    }
+
+    fn upsert(&mut self, part: BasePartition) {
+        match self.parts.iter_mut().find(|p| p.root() == part.root()) {
+            Some(slot) => *slot = part,
+            None => self.parts.push(part),
+        }
+        self.renumber();
+    }
+
+    fn renumber(&mut self) {
+        for (id, part) in self.parts.iter_mut().enumerate() {
+            part.partition.id = id;
+        }
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.parts.iter().map(|p| p.data.file_count()).sum()
+    }
+
+    pub fn memory_stats(&self) -> BaseStats {
+        let mut total = BaseStats::default();
+        for part in &self.parts {
+            let s = part.data.memory_stats();
+            total.file_count += s.file_count;
+            total.path_table_entries += s.path_table_entries;
+            total.path_table_bytes += s.path_table_bytes;
+            total.entries_count += s.entries_count;
+            total.entries_bytes += s.entries_bytes;
+            total.trigram_distinct += s.trigram_distinct;
+            total.trigram_postings_total += s.trigram_postings_total;
+            total.trigram_bytes += s.trigram_bytes;
+            total.short_gram_distinct += s.short_gram_distinct;
+            total.short_gram_postings_total += s.short_gram_postings_total;
+            total.short_gram_bytes += s.short_gram_bytes;
+            total.parent_file_dirs += s.parent_file_dirs;
+            total.parent_subdir_dirs += s.parent_subdir_dirs;
+            total.parent_bytes += s.parent_bytes;
+            total.tombstone_count += s.tombstone_count;
+            total.tombstone_bytes += s.tombstone_bytes;
+            total.estimated_bytes += s.estimated_bytes;
+        }
+        total
+    }
+
+    pub fn for_each_live_meta(&self, mut f: impl FnMut(FileMeta)) {
+        for part in &self.parts {
+            part.data.for_each_live_meta(&mut f);
+        }
+    }
+
+    /// `dir` 之下的存活路径：只访问与 `dir` 有重叠的分区（见 `BaseIndexData::paths_under`）。
+    pub fn paths_under(&self, dir: &Path) -> Vec<PathBuf> {
+        self.parts
+            .iter()
+            .filter(|p| p.root().starts_with(dir) || dir.starts_with(p.root()))
+            .flat_map(|p| p.data.paths_under(dir))
+            .collect()
+    }
+
+    pub fn get_meta(&self, key: FileKey) -> Option<FileMeta> {
+        self.parts.iter().find_map(|p| p.data.get_meta(key))
+    }
+
+    pub fn estimate_candidates(&self, matcher: &dyn Matcher) -> CandidateScan {
+        let mut total = CandidateScan::default();
+        for part in &self.parts {
+            let scan = part.data.estimate_candidates(matcher);
+            total.candidates += scan.candidates;
+            total.full_scan |= scan.full_scan;
+        }
+        total
+    }
+
+    /// 按 file_key 查找：任一分区认定该路径 Unchanged 即 Unchanged
+    /// （跨 root 的硬链接会让多个分区认识同一个 key）。
+    pub fn path_freshness(
+        &self,
+        path: &Path,
+        file_key: FileKey,
+        size: u64,
+        mtime_ns: i64,
+    ) -> PathFreshness {
+        let mut freshness = PathFreshness::Missing;
+        for part in &self.parts {
+            match part.data.path_freshness(path, file_key, size, mtime_ns) {
+                PathFreshness::Unchanged => return PathFreshness::Unchanged,
+                PathFreshness::Changed => freshness = PathFreshness::Changed,
+                PathFreshness::Missing => {}
+            }
+        }
+        freshness
+    }
+
+    pub fn parent_candidates(&self, parent_path: &str) -> Vec<FileKey> {
+        self.parts
+            .iter()
+            .flat_map(|p| p.data.parent_candidates(parent_path))
+            .collect()
+    }
+
+    /// 目录归属分区的目录 mtime 表判定（见 `BaseIndexData::check_dir`）。
+    pub fn check_dir(&self, dir: &Path, mtime_ns: i64) -> DirCheck {
+        match self.route(dir) {
+            Some(i) => self.parts[i].data.check_dir(dir, mtime_ns),
+            None => DirCheck::Unknown,
+        }
+    }
+
+    pub fn delete_alignment_with_parent_index(
+        &self,
+        dirty_dirs: &HashSet<PathBuf>,
+    ) -> Vec<(u64, PathBuf)> {
+        self.parts
+            .iter()
+            .flat_map(|p| p.data.delete_alignment_with_parent_index(dirty_dirs))
+            .collect()
+    }
+}
+
+/// 以单个 root 构建分区数据。
+pub fn build_partition_data(root: &Path, metas: Vec<FileMeta>) -> BaseIndexData {
+    let l2 = PersistentIndex::new_with_roots(vec![root.to_path_buf()]);
+    for meta in metas {
+        l2.upsert_exact(meta);
+    }
+    l2.to_base_index_data()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::FileKind;
+
+    fn meta(ino: u64, path: &str) -> FileMeta {
+        FileMeta {
+            file_key: FileKey {
+                dev: 1,
+                ino,
+                generation: 0,
+            },
+            path: PathBuf::from(path),
+            size: ino,
+            mtime: None,
+            ctime: None,
+            atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
+        }
+    }
+
+    #[test]
+    fn metas_route_to_longest_root_and_fallback() {
+        let roots = vec![PathBuf::from("/data"), PathBuf::from("/data/nfs")];
+        let base = PartitionedBase::from_metas(
+            &roots,
+            vec![
+                meta(1, "/data/a.txt"),
+                meta(2, "/data/nfs/b.txt"),
+                meta(3, "/data/nfs/c.txt"),
+                meta(4, "/elsewhere/d.txt"),
+            ],
+            1,
+        );
+
+        assert_eq!(
+            base.roots(),
+            vec![
+                PathBuf::from("/data"),
+                PathBuf::from("/data/nfs"),
+                PathBuf::from("/"),
+            ]
+        );
+        assert_eq!(
+            base.partition(Path::new("/data"))
+                .unwrap()
+                .data
+                .file_count(),
+            1
+        );
+        assert_eq!(
+            base.partition(Path::new("/data/nfs"))
+                .unwrap()
+                .data
+                .file_count(),
+            2
+        );
+        assert_eq!(base.file_count(), 4);
+        assert_eq!(
+            base.get_meta(meta(4, "").file_key).unwrap().path,
+            PathBuf::from("/elsewhere/d.txt")
+        );
+
+        // 丢弃一个分区不影响其他分区的数据（同一 Arc）。
+        let before = base.partition(Path::new("/data")).unwrap().data.clone();
+        let dropped = base.without_partition(Path::new("/data/nfs"));
+        assert_eq!(dropped.file_count(), 2);
+        assert!(Arc::ptr_eq(
+            &before,
+            &dropped.partition(Path::new("/data")).unwrap().data
+        ));
+        assert_eq!(dropped.route(Path::new("/data/nfs/b.txt")), Some(0));
+    }
+}
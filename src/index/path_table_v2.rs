@@ -325,6 +325,14 @@ impl PathTableV2 {
         None
     }
 
+    /// Original `PathIdx`es of all entries whose paths start with `prefix`.
+    pub fn idxs_with_prefix(&self, prefix: &[u8]) -> &[PathIdx] {
+        match self.find_prefix_range(prefix) {
+            Some((start, end)) => &self.sorted_to_idx[start..end],
+            None => &[],
+        }
+    }
+
     /// Find the range of entries whose paths start with `prefix`.
     /// Returns `(start_sorted, end_sorted)` where `end_sorted` is exclusive.
     pub fn find_prefix_range(&self, prefix: &[u8]) -> Option<(usize, usize)> {
@@ -465,6 +473,11 @@ mod tests {
         assert_eq!(end - start, 2);
 
         assert!(table.find_prefix_range(b"/nonexistent/").is_none());
+
+        let mut idxs = table.idxs_with_prefix(b"/var/log/").to_vec();
+        idxs.sort_unstable();
+        assert_eq!(idxs, vec![3, 4]);
+        assert!(table.idxs_with_prefix(b"/nonexistent/").is_empty());
     }
 
     #[test]
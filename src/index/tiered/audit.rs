@@ -0,0 +1,184 @@
+//! 一致性抽查：随机挑选已索引的目录，与文件系统逐项比对（不写索引）。
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::core::{FileKind, FileMeta};
+use crate::index::file_entry_v2::time_to_ns;
+use crate::index::PathFreshness;
+
+use super::TieredIndex;
+
+/// mtime 落在这个窗口内的条目跳过比对：事件可能还在 debounce / 管道里。
+const IN_FLIGHT_GRACE: Duration = Duration::from_secs(2);
+
+/// 单个目录的比对结果。
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DirAudit {
+    /// 参与比对的条目数（磁盘上的直接子项 + 索引中已不存在于磁盘的子项）
+    pub checked: usize,
+    /// 索引缺失、元数据过期或已从磁盘消失的条目数
+    pub drifted: usize,
+}
+
+impl TieredIndex {
+    /// 从基础索引中随机抽取至多 `n` 个存活的目录条目（`seed` 决定抽样序列）。
+    pub fn sample_indexed_dirs(&self, n: usize, seed: u64) -> Vec<PathBuf> {
+        let base = self.base.load_full();
+        let parts = base.parts();
+        let total: usize = parts.iter().map(|p| p.data.entries_by_key.len()).sum();
+        if n == 0 || total == 0 {
+            return Vec::new();
+        }
+
+        let mut rng = XorShift64::new(seed);
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+        let mut out = Vec::with_capacity(n);
+        // 目录在条目中的占比未知：按尝试次数封顶，避免纯文件分区里空转。
+        for _ in 0..n.saturating_mul(32) {
+            if out.len() >= n {
+                break;
+            }
+            let mut pick = (rng.next() % total as u64) as usize;
+            let Some(part) = parts.iter().find(|p| {
+                let len = p.data.entries_by_key.len();
+                if pick < len {
+                    true
+                } else {
+                    pick -= len;
+                    false
+                }
+            }) else {
+                continue;
+            };
+            let data = &part.data;
+            if data.tombstones.contains(pick as u32) {
+                continue;
+            }
+            let Some(entry) = data.entries_by_key.get(pick) else {
+                continue;
+            };
+            if entry.kind() != FileKind::Dir {
+                continue;
+            }
+            let Some(path_bytes) = data.path_table.resolve(entry.path_idx) else {
+                continue;
+            };
+            if self.delta_buffer.lock().is_deleted(&path_bytes) {
+                continue;
+            }
+            let path = crate::util::pathbuf_from_encoded_vec(path_bytes);
+            if seen.insert(path.clone()) {
+                out.push(path);
+            }
+        }
+        out
+    }
+
+    /// 按扫描同一套规则列出 `dir` 的直接子项，与索引比对：
+    /// 普通文件比对 size + mtime，目录与符号链接只比对是否存在
+    /// （目录 mtime 随子项变化，事件路径不会刷新它）。
+    pub fn audit_dir(&self, dir: &Path) -> DirAudit {
+        let mut audit = DirAudit::default();
+        let dir = super::normalize_path(dir);
+        if !std::fs::symlink_metadata(&dir).is_ok_and(|m| m.is_dir()) {
+            return audit;
+        }
+        let roots = self.roots();
+        let path_rules = self.path_rules();
+        let fresh_after = SystemTime::now() - IN_FLIGHT_GRACE;
+
+        let mut builder = ignore::WalkBuilder::new(&dir);
+        builder
+            .max_depth(Some(1))
+            .hidden(!self.include_hidden)
+            .follow_links(false)
+            .ignore(self.ignore_enabled)
+            .git_ignore(self.ignore_enabled)
+            .git_global(self.ignore_enabled)
+            .git_exclude(self.ignore_enabled);
+        self.apply_walk_filters(&mut builder, &roots, &path_rules);
+        for ent in builder.build() {
+            let Ok(ent) = ent else {
+                continue;
+            };
+            let Some(ft) = ent.file_type() else {
+                continue;
+            };
+            if ent.depth() == 0 || !path_rules.includes_file(ent.path(), ft.is_dir()) {
+                continue;
+            }
+            let path = super::normalize_path(ent.path());
+            let Some(meta) = ent
+                .metadata()
+                .ok()
+                .and_then(|m| FileMeta::from_lstat(&path, &m))
+            else {
+                continue;
+            };
+            if meta.mtime.is_some_and(|t| t > fresh_after) {
+                continue;
+            }
+            audit.checked += 1;
+            let freshness =
+                self.path_freshness(&path, meta.file_key, meta.size, time_to_ns(meta.mtime));
+            let drifted = if meta.kind == FileKind::File && meta.target.is_none() {
+                freshness != PathFreshness::Unchanged
+            } else {
+                freshness == PathFreshness::Missing
+            };
+            if drifted {
+                tracing::debug!(
+                    "audit drift: {} is {:?} in index",
+                    path.display(),
+                    freshness
+                );
+                audit.drifted += 1;
+            }
+        }
+
+        // 索引里有、磁盘上已没有的子项（运行期已删除的由 overlay 记录，不算漂移）。
+        let dirty: HashSet<PathBuf> = std::iter::once(dir).collect();
+        let stale = self
+            .base
+            .load_full()
+            .delete_alignment_with_parent_index(&dirty);
+        for (_, path) in stale {
+            if self
+                .delta_buffer
+                .lock()
+                .is_deleted(path.as_os_str().as_encoded_bytes())
+            {
+                continue;
+            }
+            if matches!(
+                std::fs::symlink_metadata(&path),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound
+            ) {
+                tracing::debug!("audit drift: {} vanished from disk", path.display());
+                audit.checked += 1;
+                audit.drifted += 1;
+            }
+        }
+        audit
+    }
+}
+
+/// 抽样用的 xorshift64（不需要密码学强度，避免为此引入 rand）。
+struct XorShift64(u64);
+
+impl XorShift64 {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+}
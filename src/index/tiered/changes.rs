@@ -0,0 +1,192 @@
+//! 变更日志：以 WAL 日志序号为游标，读取某个游标之后的创建/删除/修改/重命名。
+//!
+//! 数据只来自仍保留的 WAL 文件：snapshot 边界 seal 并被 cleanup 的部分不可再读，
+//! 游标落在保留范围之外时返回 `ChangesError::Expired`，调用方需全量重新同步。
+
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::core::{EventRecord, EventType, FileKey, FileKind, FileMeta};
+use crate::query::dsl::{compile_query, CompiledQuery, ContentLookup, QueryCompileError};
+use crate::storage::wal::WalTail;
+
+use super::TieredIndex;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeKind {
+    Created,
+    Deleted,
+    Modified,
+    Renamed,
+}
+
+impl ChangeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Created => "created",
+            ChangeKind::Deleted => "deleted",
+            ChangeKind::Modified => "modified",
+            ChangeKind::Renamed => "renamed",
+        }
+    }
+}
+
+/// 变更日志中的一条记录。
+#[derive(Clone, Debug)]
+pub struct ChangeEntry {
+    pub seq: u64,
+    pub kind: ChangeKind,
+    pub path: PathBuf,
+    /// 仅 Renamed：原路径
+    pub from: Option<PathBuf>,
+    pub timestamp: SystemTime,
+    /// 读取时的当前元数据（文件已不存在时为 None）
+    pub meta: Option<FileMeta>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ChangeFeed {
+    pub changes: Vec<ChangeEntry>,
+    /// 下一次请求使用的游标
+    pub next: u64,
+    /// 读取时的最新日志序号；`next < latest` 表示还有未读完的变更
+    pub latest: u64,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ChangesError {
+    #[error(transparent)]
+    Query(#[from] QueryCompileError),
+    #[error("cursor {since} too old, resync required (oldest retained cursor {oldest})")]
+    Expired {
+        since: u64,
+        oldest: u64,
+        latest: u64,
+    },
+    #[error("change journal unavailable: no WAL attached")]
+    Unavailable,
+    #[error("change journal read failed: {0}")]
+    Read(anyhow::Error),
+}
+
+impl TieredIndex {
+    /// 当前最新的变更游标；未挂载 WAL 时为 None。
+    pub fn change_cursor(&self) -> Option<u64> {
+        self.wal.lock().as_ref().map(|w| w.last_seq())
+    }
+
+    /// 读取游标 `since` 之后的变更：至多扫描 `limit` 条日志记录，
+    /// 再按 `query`（DSL，空串表示不过滤）筛选。
+    ///
+    /// 过滤对象是读取时的文件元数据；文件已不存在时只按路径匹配，
+    /// 重命名只要新旧路径之一命中即保留。
+    pub fn changes_since(
+        &self,
+        since: u64,
+        query: &str,
+        limit: usize,
+    ) -> Result<ChangeFeed, ChangesError> {
+        let compiled = if query.trim().is_empty() {
+            None
+        } else {
+            Some(compile_query(query)?)
+        };
+        let wal = self.wal.lock().clone().ok_or(ChangesError::Unavailable)?;
+        let limit = limit.max(1);
+        let (events, latest) = match wal.read_since(since, limit).map_err(ChangesError::Read)? {
+            WalTail::Events { events, latest } => (events, latest),
+            WalTail::Expired { oldest, latest } => {
+                return Err(ChangesError::Expired {
+                    since,
+                    oldest,
+                    latest,
+                })
+            }
+        };
+
+        let next = if events.len() < limit {
+            latest
+        } else {
+            events.last().map(|ev| ev.seq).unwrap_or(latest)
+        };
+        let probe = compiled
+            .as_ref()
+            .and_then(|compiled| self.content_probe_for(compiled));
+        let content = probe.as_ref().map(|c| c as &dyn ContentLookup);
+
+        let changes = events
+            .iter()
+            .filter_map(|ev| self.change_entry(ev))
+            .filter(|change| match &compiled {
+                Some(compiled) => change_matches(change, compiled, content),
+                None => true,
+            })
+            .collect();
+        Ok(ChangeFeed {
+            changes,
+            next,
+            latest,
+        })
+    }
+
+    fn change_entry(&self, ev: &EventRecord) -> Option<ChangeEntry> {
+        let path = ev.best_path()?.to_path_buf();
+        let (kind, from) = match &ev.event_type {
+            EventType::Create => (ChangeKind::Created, None),
+            EventType::Delete => (ChangeKind::Deleted, None),
+            EventType::Modify => (ChangeKind::Modified, None),
+            EventType::Rename {
+                from,
+                from_path_hint,
+            } => (
+                ChangeKind::Renamed,
+                from_path_hint
+                    .clone()
+                    .or_else(|| from.as_path().map(|p| p.to_path_buf())),
+            ),
+        };
+        let meta = match kind {
+            ChangeKind::Deleted => None,
+            _ => self.overlay_meta_for_event(ev),
+        };
+        Some(ChangeEntry {
+            seq: ev.seq,
+            kind,
+            path,
+            from,
+            timestamp: ev.timestamp,
+            meta,
+        })
+    }
+}
+
+fn change_matches(
+    change: &ChangeEntry,
+    compiled: &CompiledQuery,
+    content: Option<&dyn ContentLookup>,
+) -> bool {
+    let current = match &change.meta {
+        Some(meta) => compiled.matches_with_content(meta, content),
+        None => compiled.matches_with_content(&path_only_meta(&change.path), content),
+    };
+    current
+        || change
+            .from
+            .as_ref()
+            .is_some_and(|from| compiled.matches_with_content(&path_only_meta(from), content))
+}
+
+/// 已不存在的路径：只有路径可供匹配。
+fn path_only_meta(path: &std::path::Path) -> FileMeta {
+    FileMeta {
+        file_key: FileKey::default(),
+        path: path.to_path_buf(),
+        size: 0,
+        mtime: None,
+        ctime: None,
+        atime: None,
+        kind: FileKind::File,
+        nlink: 0,
+        target: None,
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use parking_lot::{Mutex, RwLock};
+use roaring::RoaringBitmap;
+
+use crate::core::{EventRecord, EventType, FileMeta};
+use crate::index::content_index::{bytes_contain, ContentIndex};
+use crate::query::dsl::{CompiledQuery, ContentLookup};
+
+use super::TieredIndex;
+
+impl TieredIndex {
+    pub fn content_index_enabled(&self) -> bool {
+        self.content_index_enabled.load(Ordering::Relaxed)
+    }
+
+    /// 开关内容索引（opt-in）。
+    ///
+    /// - 开启：若当前内容索引为空而文件索引非空（例如快照来自未开启时），后台补建；
+    /// - 关闭：释放内容索引内存，下次快照写入空段。
+    pub fn set_content_index_enabled(self: &Arc<Self>, enabled: bool) {
+        let was = self.content_index_enabled.swap(enabled, Ordering::AcqRel);
+        if !enabled {
+            if was {
+                self.content_queue.reset();
+                *self.content_index.write() = ContentIndex::new();
+            }
+            return;
+        }
+        if self.content_index.read().is_empty() && self.file_count() > 0 {
+            self.spawn_content_index_sync();
+        }
+    }
+
+    /// 内容索引统计：(文档数, trigram 数, 估算字节)。
+    pub fn content_index_stats(&self) -> (usize, usize, u64) {
+        self.content_index.read().memory_stats()
+    }
+
+    /// 快照加载时恢复内容索引；非空即视为此前已开启。
+    pub(super) fn restore_content_index(&self, content: ContentIndex) {
+        if content.is_empty() {
+            return;
+        }
+        *self.content_index.write() = content;
+        self.content_index_enabled.store(true, Ordering::Release);
+    }
+
+    /// 事件驱动的增量维护：只把路径登记进 `ContentQueue`，回读与 posting 更新在后台线程完成。
+    ///
+    /// Delete 移除该路径及其子树，Rename 把旧子树整体改挂到新路径下，
+    /// Create/Modify（以及 Rename 的目标）按磁盘现状重新回读。
+    pub(super) fn update_content_index_for_events(&self, events: &[EventRecord]) {
+        if !self.content_index_enabled() {
+            return;
+        }
+        let mut pending = self.content_queue.pending.lock();
+        for ev in events {
+            let Some(path) = ev.best_path().map(super::normalize_path) else {
+                continue;
+            };
+            match &ev.event_type {
+                EventType::Delete => pending.remove_subtree(path),
+                EventType::Rename {
+                    from,
+                    from_path_hint,
+                } => {
+                    if let Some(from) = from_path_hint.as_deref().or_else(|| from.as_path()) {
+                        pending.move_subtree(super::normalize_path(from), path.clone());
+                    }
+                    pending.reindex.insert(path);
+                }
+                EventType::Create | EventType::Modify => {
+                    pending.reindex.insert(path);
+                }
+            }
+        }
+        let idle = pending.is_empty();
+        drop(pending);
+        if !idle {
+            self.content_queue.spawn_worker(self.content_index.clone());
+        }
+    }
+
+    /// 在当前线程处理完内容队列中已登记的全部变更（快照前与测试用）。
+    pub fn drain_content_queue(&self) {
+        self.content_queue.drain(&self.content_index);
+    }
+
+    /// 全量对齐：以当前可见文件集为准补建/清理内容索引（重建完成或开启时调用）。
+    ///
+    /// 每个文件的回读与写入都持有 `ContentQueue` 的 apply 锁：期间到达的事件排在其后重新回读，
+    /// 不会被这里读到的旧内容覆盖。
+    pub(crate) fn sync_content_index(&self) -> usize {
+        for meta in self.collect_all_live_metas() {
+            if !self.content_index_enabled() {
+                return 0;
+            }
+            let _apply = self.content_queue.apply.lock();
+            let key = meta.path.as_os_str().as_encoded_bytes();
+            match ContentIndex::read_indexable(&meta.path) {
+                Some(data) => {
+                    self.content_index.write().upsert(key, &data);
+                }
+                None => {
+                    self.content_index.write().remove(key);
+                }
+            }
+        }
+        // 清理以持锁时的可见文件集为准：对齐期间新建并已由工作线程入索引的文件不能被清掉。
+        let _apply = self.content_queue.apply.lock();
+        let metas = self.collect_all_live_metas();
+        let live: HashSet<&[u8]> = metas
+            .iter()
+            .map(|meta| meta.path.as_os_str().as_encoded_bytes())
+            .collect();
+        let mut ci = self.content_index.write();
+        ci.retain_paths(&live);
+        ci.len()
+    }
+
+    /// 后台执行 `sync_content_index`；运行中再次请求会合并为下一轮。
+    pub(crate) fn spawn_content_index_sync(self: &Arc<Self>) {
+        if !self.content_index_enabled() {
+            return;
+        }
+        self.content_sync_requested.store(true, Ordering::Release);
+        if self.content_sync_running.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let idx = self.clone();
+        std::thread::spawn(move || {
+            while idx.content_sync_requested.swap(false, Ordering::AcqRel) {
+                let docs = idx.sync_content_index();
+                tracing::info!("Content index sync complete: {} documents", docs);
+            }
+            idx.content_sync_running.store(false, Ordering::Release);
+        });
+    }
+
+    pub(super) fn content_probe_for(&self, compiled: &CompiledQuery) -> Option<ContentProbe> {
+        if !self.content_index_enabled() || !compiled.has_content_filter() {
+            return None;
+        }
+        Some(ContentProbe {
+            index: self.content_index.clone(),
+            candidates: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+/// 内容索引的后台维护队列：apply 路径只登记，工作线程负责回读文件与更新 posting。
+#[derive(Default)]
+pub(super) struct ContentQueue {
+    pending: Mutex<PendingContent>,
+    /// 同一时刻只有一个批次在写内容索引（工作线程、`drain` 或全量对齐）
+    apply: Mutex<()>,
+    running: AtomicBool,
+}
+
+#[derive(Default)]
+struct PendingContent {
+    /// 子树级操作，按到达顺序执行
+    subtree_ops: Vec<SubtreeOp>,
+    /// 待回读的路径（去重），在子树操作之后按磁盘现状处理
+    reindex: HashSet<PathBuf>,
+    /// 关闭内容索引时递增：已取出的旧批次不得写入新的空索引
+    epoch: u64,
+}
+
+enum SubtreeOp {
+    Remove(PathBuf),
+    Move { from: PathBuf, to: PathBuf },
+}
+
+impl PendingContent {
+    fn is_empty(&self) -> bool {
+        self.subtree_ops.is_empty() && self.reindex.is_empty()
+    }
+
+    fn remove_subtree(&mut self, path: PathBuf) {
+        self.subtree_ops.push(SubtreeOp::Remove(path));
+    }
+
+    /// 目录改名：排队中的子路径一并改写到新位置，否则它们回读时旧路径已不存在。
+    fn move_subtree(&mut self, from: PathBuf, to: PathBuf) {
+        let moved: Vec<PathBuf> = self
+            .reindex
+            .iter()
+            .filter(|p| p.starts_with(&from))
+            .cloned()
+            .collect();
+        for old in moved {
+            self.reindex.remove(&old);
+            if let Ok(rest) = old.strip_prefix(&from) {
+                self.reindex.insert(to.join(rest));
+            }
+        }
+        self.subtree_ops.push(SubtreeOp::Move { from, to });
+    }
+}
+
+impl ContentQueue {
+    /// 关闭内容索引：丢弃排队的变更，并让已取出的批次失效。
+    fn reset(&self) {
+        let mut pending = self.pending.lock();
+        pending.epoch = pending.epoch.wrapping_add(1);
+        pending.subtree_ops.clear();
+        pending.reindex.clear();
+    }
+
+    /// 工作线程已在运行时只登记，由它在下一轮取走。
+    fn spawn_worker(self: &Arc<Self>, index: Arc<RwLock<ContentIndex>>) {
+        if self.running.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let queue = self.clone();
+        std::thread::spawn(move || loop {
+            queue.drain(&index);
+            queue.running.store(false, Ordering::Release);
+            // 清标志与新登记之间的竞态：有新变更且抢回标志则继续。
+            if queue.pending.lock().is_empty() || queue.running.swap(true, Ordering::AcqRel) {
+                break;
+            }
+        });
+    }
+
+    fn drain(&self, index: &RwLock<ContentIndex>) {
+        let _apply = self.apply.lock();
+        while self.apply_batch(index) {}
+    }
+
+    /// 取出并执行一批变更；队列为空时返回 false。
+    fn apply_batch(&self, index: &RwLock<ContentIndex>) -> bool {
+        let (subtree_ops, reindex, epoch) = {
+            let mut pending = self.pending.lock();
+            if pending.is_empty() {
+                return false;
+            }
+            (
+                std::mem::take(&mut pending.subtree_ops),
+                std::mem::take(&mut pending.reindex),
+                pending.epoch,
+            )
+        };
+        let current = || self.pending.lock().epoch == epoch;
+
+        if !subtree_ops.is_empty() {
+            let mut ci = index.write();
+            if !current() {
+                return true;
+            }
+            for op in subtree_ops {
+                match op {
+                    SubtreeOp::Remove(path) => {
+                        ci.remove_under(path.as_os_str().as_encoded_bytes());
+                    }
+                    SubtreeOp::Move { from, to } => {
+                        ci.move_under(
+                            from.as_os_str().as_encoded_bytes(),
+                            to.as_os_str().as_encoded_bytes(),
+                        );
+                    }
+                }
+            }
+        }
+
+        for path in reindex {
+            // IO 在锁外完成，写锁只覆盖 posting 更新。
+            let data = ContentIndex::read_indexable(&path);
+            let key = path.as_os_str().as_encoded_bytes();
+            let mut ci = index.write();
+            if !current() {
+                return true;
+            }
+            match data {
+                Some(data) => ci.upsert(key, &data),
+                None => ci.remove(key),
+            };
+        }
+        true
+    }
+}
+
+/// needle（ASCII 小写）→ (压实代数, trigram 候选)。
+type CandidateCache = HashMap<Vec<u8>, (u64, Option<RoaringBitmap>)>;
+
+/// 单次查询内的 `content:` 求值器：trigram 候选按 needle 缓存，命中后回读文件确认。
+pub(super) struct ContentProbe {
+    index: Arc<RwLock<ContentIndex>>,
+    candidates: Mutex<CandidateCache>,
+}
+
+impl ContentProbe {
+    /// `content:` 作为唯一有区分度的条件时的候选路径：trigram 交集命中的文档（仍需逐条回读确认）。
+    /// needle 不足 3 字节无法预过滤，返回 None。
+    pub(super) fn candidate_paths(&self, needle: &str) -> Option<Vec<PathBuf>> {
+        let ci = self.index.read();
+        let docs = ci.candidates(needle.as_bytes())?;
+        Some(
+            docs.iter()
+                .filter_map(|doc_id| ci.doc_path(doc_id))
+                .map(|p| PathBuf::from(OsStr::from_bytes(p)))
+                .collect(),
+        )
+    }
+}
+
+impl ContentLookup for ContentProbe {
+    fn contains(&self, meta: &FileMeta, needle: &str, case_sensitive: bool) -> bool {
+        let path_bytes = meta.path.as_os_str().as_encoded_bytes();
+        {
+            let ci = self.index.read();
+            let Some(doc_id) = ci.doc_id(path_bytes) else {
+                return false;
+            };
+            let mut cache = self.candidates.lock();
+            let key = needle.as_bytes().to_ascii_lowercase();
+            let entry = cache
+                .entry(key)
+                .or_insert_with(|| (ci.generation(), ci.candidates(needle.as_bytes())));
+            if entry.0 != ci.generation() {
+                *entry = (ci.generation(), ci.candidates(needle.as_bytes()));
+            }
+            if entry.1.as_ref().is_some_and(|bm| !bm.contains(doc_id)) {
+                return false;
+            }
+        }
+
+        let Some(data) = ContentIndex::read_indexable(&meta.path) else {
+            return false;
+        };
+        bytes_contain(&data, needle.as_bytes(), case_sensitive)
+    }
+}
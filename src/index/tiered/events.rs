@@ -1,7 +1,8 @@
+use std::path::{Path, PathBuf};
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use crate::core::{EventRecord, EventType, FileIdentifier, FileMeta};
+use crate::core::{EventRecord, EventType, FileIdentifier, FileKey, FileMeta};
 use crate::index::l2_partition::PersistentIndex;
 
 use super::TieredIndex;
@@ -19,6 +20,8 @@ impl TieredIndex {
         for ev in &mut normalized {
             Self::normalize_event_paths(ev);
         }
+        self.expand_symlink_events(&mut normalized);
+        self.expand_moved_link_events(&mut normalized);
         self.apply_events_inner(&normalized, true);
     }
 
@@ -31,6 +34,8 @@ impl TieredIndex {
         for ev in events.iter_mut() {
             Self::normalize_event_paths(ev);
         }
+        self.expand_symlink_events(events);
+        self.expand_moved_link_events(events);
         self.apply_events_inner_drain(events, true);
     }
 
@@ -109,6 +114,18 @@ impl TieredIndex {
         }
     }
 
+    /// 请求一次快照落盘（合并触发：只有 false->true 才唤醒 flush 循环）。
+    pub fn request_flush(&self) {
+        if !self.flush_requested.swap(true, Ordering::AcqRel) {
+            self.flush_notify.notify_one();
+        }
+    }
+
+    /// 是否有尚未执行的落盘请求。
+    pub fn flush_pending(&self) -> bool {
+        self.flush_requested.load(Ordering::Acquire)
+    }
+
     pub(super) fn append_events_to_wal(&self, events: &[EventRecord], log_to_wal: bool) {
         if !log_to_wal {
             return;
@@ -186,6 +203,8 @@ impl TieredIndex {
         self.maybe_request_flush(overlay_paths, overlay_arena_bytes);
         self.note_pending_flush_batch(events);
         self.invalidate_l1_for_events(events);
+        self.update_content_index_for_events(events);
+        self.notify_subscribers(events);
 
         Some(ApplyBatchState {
             l2,
@@ -243,6 +262,10 @@ impl TieredIndex {
         metas: &mut Vec<FileMeta>,
         log_to_wal: bool,
     ) {
+        let mut link_events = self.symlink_events_for_metas(metas);
+        link_events.extend(
+            self.moved_link_deletes(metas.iter().map(|m| (m.path.as_path(), m.file_key)), 0),
+        );
         let Some(batch) = self.begin_apply_batch(events, log_to_wal) else {
             metas.clear();
             return;
@@ -256,6 +279,80 @@ impl TieredIndex {
         self.event_seq
             .fetch_add(batch.event_count as u64, Ordering::Relaxed);
         self.stats.record_events_applied(batch.event_count as u64);
+        self.apply_events_inner(&link_events, log_to_wal);
+    }
+}
+
+impl TieredIndex {
+    /// 为 Create / Rename 事件追加旧路径的 Delete：见 `moved_link_deletes`。
+    fn expand_moved_link_events(&self, events: &mut Vec<EventRecord>) {
+        let keyed: Vec<(PathBuf, FileKey)> = events
+            .iter()
+            .filter(|ev| matches!(ev.event_type, EventType::Create | EventType::Rename { .. }))
+            .filter_map(|ev| {
+                let path = ev.best_path()?;
+                let m = std::fs::symlink_metadata(path).ok()?;
+                Some((
+                    path.to_path_buf(),
+                    FileKey::from_path_and_metadata(path, &m)?,
+                ))
+            })
+            .collect();
+        if keyed.is_empty() {
+            return;
+        }
+        let seq = events.iter().map(|ev| ev.seq).max().unwrap_or(0);
+        let deletes = self.moved_link_deletes(keyed.iter().map(|(p, k)| (p.as_path(), *k)), seq);
+        events.extend(deletes);
+    }
+
+    /// 新路径的 FileKey 已在 base 中挂在别的路径上：要么是 rename（旧路径已不存在），
+    /// 要么是硬链接（旧路径仍在）。在应用事件时 lstat 一次旧路径区分两者，
+    /// rename 的旧路径以 Delete 记入 overlay，查询路径上不再做任何 stat。
+    pub(super) fn moved_link_deletes<'a>(
+        &self,
+        keyed: impl Iterator<Item = (&'a Path, FileKey)>,
+        mut seq: u64,
+    ) -> Vec<EventRecord> {
+        let base = self.base.load_full();
+        let mut stale: Vec<PathBuf> = Vec::new();
+        for (path, key) in keyed {
+            for part in base.parts() {
+                for meta in part.data.metas_for_key(key) {
+                    if meta.path == path || stale.contains(&meta.path) {
+                        continue;
+                    }
+                    if matches!(
+                        std::fs::symlink_metadata(&meta.path),
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound
+                    ) {
+                        stale.push(meta.path);
+                    }
+                }
+            }
+        }
+        if stale.is_empty() {
+            return Vec::new();
+        }
+        let db = self.delta_buffer.lock();
+        stale.retain(|p| {
+            let bytes = p.as_os_str().as_encoded_bytes();
+            !db.is_deleted(bytes) && !db.is_live(bytes)
+        });
+        drop(db);
+        stale
+            .into_iter()
+            .map(|path| {
+                seq = seq.wrapping_add(1);
+                EventRecord {
+                    seq,
+                    timestamp: std::time::SystemTime::now(),
+                    event_type: EventType::Delete,
+                    id: FileIdentifier::Path(path),
+                    path_hint: None,
+                }
+            })
+            .collect()
     }
 }
 
@@ -0,0 +1,121 @@
+//! 查询计划说明：DSL 编译结构 + base 层候选估算 + 可选的实际执行计数。
+//!
+//! 估算只在 posting list 上求候选集大小，不物化结果；`execute` 时才真正跑一遍计划，
+//! 按 overlay（DeltaBuffer）/ base 拆分候选与命中，便于定位"为什么没结果 / 为什么慢"。
+
+use std::time::Instant;
+
+use crate::query::dsl::{explain_query, AnchorPrefilter, QueryExplain};
+use crate::stats::query_profile::QueryProfile;
+
+use super::TieredIndex;
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct IndexExplain {
+    pub query: String,
+    /// DSL 编译失败原因；此时整串按 legacy matcher 匹配
+    pub compile_error: Option<String>,
+    pub plan: Option<QueryExplain>,
+    /// 实际参与扫描的 anchor 及其在 base 层的候选估算
+    pub anchors: Vec<AnchorEstimate>,
+    pub parent_shortcut: Option<ParentShortcut>,
+    pub base_files: usize,
+    /// DeltaBuffer 中尚未并入 base 的存活记录数（overlay 逐条过 anchor）
+    pub overlay_entries: usize,
+    pub estimated_base_candidates: usize,
+    /// 是否有 anchor 退化为 base 全量扫描
+    pub full_scan: bool,
+    pub actual: Option<ExplainActual>,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct AnchorEstimate {
+    pub matcher: String,
+    pub prefilter: AnchorPrefilter,
+    pub estimated_candidates: usize,
+    pub full_scan: bool,
+}
+
+/// `parent:` 走 ParentIndex 直接取目录下的候选（与 anchor 扫描并行生效）。
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ParentShortcut {
+    pub path: String,
+    pub candidates: usize,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct ExplainActual {
+    pub overlay_candidates: usize,
+    pub overlay_matches: usize,
+    pub base_candidates: usize,
+    pub base_matches: usize,
+    pub full_scan: bool,
+    pub results: usize,
+    /// 命中达到 limit 后提前停止，计数只覆盖已扫描部分
+    pub truncated: bool,
+    pub elapsed_us: u64,
+}
+
+impl TieredIndex {
+    /// 说明查询计划；`execute_limit` 为 Some 时按该 limit 实际执行并返回分层计数。
+    pub fn explain(&self, keyword: &str, execute_limit: Option<usize>) -> IndexExplain {
+        let (plan_explain, compile_error) = match explain_query(keyword) {
+            Ok(ex) => (Some(ex), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let plan = self.plan_query(keyword);
+        let base = self.base.load_full();
+
+        let anchors: Vec<AnchorEstimate> = plan
+            .anchors()
+            .iter()
+            .map(|a| {
+                let scan = base.estimate_candidates(a.as_ref());
+                AnchorEstimate {
+                    matcher: a.describe(),
+                    prefilter: AnchorPrefilter::of(a.as_ref()),
+                    estimated_candidates: scan.candidates,
+                    full_scan: scan.full_scan,
+                }
+            })
+            .collect();
+        let parent_shortcut = plan.parent_filter().map(|path| ParentShortcut {
+            candidates: base.parent_candidates(&path).len(),
+            path,
+        });
+        let estimated_base_candidates = anchors
+            .iter()
+            .map(|a| a.estimated_candidates)
+            .chain(parent_shortcut.as_ref().map(|p| p.candidates))
+            .sum();
+
+        let actual = execute_limit.map(|limit| {
+            let started = Instant::now();
+            let mut profile = QueryProfile::default();
+            let results = self.execute_plan_limit(&plan, limit, &mut profile);
+            ExplainActual {
+                overlay_candidates: profile.overlay_candidates,
+                overlay_matches: profile.overlay_matches,
+                base_candidates: profile.base_candidates,
+                base_matches: results.len().saturating_sub(profile.overlay_matches),
+                full_scan: profile.full_scan,
+                results: results.len(),
+                truncated: results.len() >= limit,
+                elapsed_us: started.elapsed().as_micros() as u64,
+            }
+        });
+
+        IndexExplain {
+            query: keyword.to_string(),
+            compile_error,
+            plan: plan_explain,
+            full_scan: anchors.iter().any(|a| a.full_scan),
+            anchors,
+            parent_shortcut,
+            base_files: base.file_count(),
+            overlay_entries: self.delta_buffer.lock().live_records().count(),
+            estimated_base_candidates,
+            actual,
+        }
+    }
+}
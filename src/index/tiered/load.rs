@@ -1,17 +1,31 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use crate::index::base_index::BaseIndexData;
 use crate::index::l1_cache::L1Cache;
 use crate::index::l2_partition::PersistentIndex;
 use crate::index::l3_cold::IndexBuilder;
+use crate::index::partitioned_base::PartitionedBase;
+use crate::storage::partitions::{partition_manifest_path_for, read_partition_manifest};
 use crate::storage::snapshot::{
-    read_recovery_runtime_state, stable_prev_v7_path_for, stable_v7_path_for,
+    read_recovery_runtime_state, stable_prev_v7_path_for, stable_v7_path_for, RecoveryRuntimeState,
+    SnapshotStore,
 };
 use crate::storage::traits::StorageBackend;
 use crate::util::maybe_trim_rss;
 
-use super::{StartupRecoveryReport, TieredIndex};
+use super::{OfflineLoadReport, StartupRecoveryReport, TieredIndex};
+
+/// 构造时（含拆分单体快照）分区使用的 generation；分区段加载的分区为 0（即已落盘）。
+const INITIAL_PARTITION_GENERATION: u64 = 1;
+
+/// WAL 回放起点：只有从最新快照加载时，seal_id ≤ `last_wal_seal_id` 的 sealed WAL 才已包含在快照里
+/// （清理失败时会残留）；回退到 prev / legacy 快照时必须全部回放。
+fn wal_checkpoint_for(source: &str, runtime_state: &RecoveryRuntimeState) -> u64 {
+    match source {
+        "partitions" | "stable" => runtime_state.last_wal_seal_id,
+        _ => 0,
+    }
+}
 
 impl TieredIndex {
     #[allow(dead_code, clippy::too_many_arguments)]
@@ -70,7 +84,7 @@ impl TieredIndex {
         ignore_enabled: bool,
         follow_symlinks: bool,
         exclude_dirs: Vec<String>,
-        base_data: Option<BaseIndexData>,
+        base_data: Option<PartitionedBase>,
     ) -> Self {
         use arc_swap::ArcSwap;
         use parking_lot::Mutex;
@@ -80,7 +94,12 @@ impl TieredIndex {
         use super::rebuild::RebuildState;
         use crate::core::AdaptiveScheduler;
 
-        let base_data = base_data.unwrap_or_else(|| l2.to_base_index_data());
+        let base_data = base_data.unwrap_or_else(|| {
+            let mut metas = Vec::with_capacity(l2.file_count());
+            l2.for_each_live_meta(|meta| metas.push(meta));
+            PartitionedBase::from_metas(&roots, metas, INITIAL_PARTITION_GENERATION)
+        });
+        let symlinks = super::symlinks::SymlinkTargets::from_base(&base_data);
         let base = ArcSwap::from(Arc::new(base_data));
 
         Self {
@@ -95,6 +114,9 @@ impl TieredIndex {
                 262_144,
             )),
             base,
+            partition_generation: AtomicU64::new(INITIAL_PARTITION_GENERATION),
+            persisted_partitions: Mutex::new(Default::default()),
+            partition_swap: Mutex::new(()),
             flush_requested: AtomicBool::new(false),
             flush_notify: Notify::new(),
             auto_flush_overlay_paths: AtomicU64::new(250_000),
@@ -107,7 +129,9 @@ impl TieredIndex {
             pending_flush_events: AtomicU64::new(0),
             pending_flush_bytes: AtomicU64::new(0),
             last_snapshot_time: AtomicU64::new(0),
-            roots,
+            roots: parking_lot::RwLock::new(roots),
+            roots_update: Mutex::new(()),
+            root_change_hook: Mutex::new(None),
             include_hidden,
             ignore_enabled,
             follow_symlinks,
@@ -116,6 +140,17 @@ impl TieredIndex {
             recovery_status: Mutex::new(super::RecoveryStatus::default()),
             stable_snapshot_enabled: AtomicBool::new(true),
             stats: Arc::new(crate::stats::StatsCollector::new()),
+            content_index: Arc::new(parking_lot::RwLock::new(
+                crate::index::content_index::ContentIndex::new(),
+            )),
+            content_index_enabled: AtomicBool::new(false),
+            content_sync_running: AtomicBool::new(false),
+            content_sync_requested: AtomicBool::new(false),
+            content_queue: Arc::new(Default::default()),
+            subscriptions: Arc::new(Default::default()),
+            ignore_reconcile: Default::default(),
+            symlinks: Mutex::new(symlinks),
+            repair_run: Mutex::new(None),
         }
     }
 
@@ -330,7 +365,50 @@ impl TieredIndex {
             Default::default()
         });
 
-        // 优先加载 stable.v7 / stable.prev.v7，再回退到 legacy v7 单文件快照。
+        // 优先按 MANIFEST 加载各 root 的分区段；缺段的 root 由上层单独扫描。
+        if let Some(loaded) = Self::load_partitions(store.path(), &roots) {
+            tracing::info!(
+                "partition snapshot loaded: {} partitions, {} entries, {} missing",
+                loaded.base.parts().len(),
+                loaded.base.file_count(),
+                loaded.missing.len()
+            );
+            let l2 = Arc::new(PersistentIndex::new_with_roots(roots.clone()));
+            let persisted = loaded.base.clone();
+            let idx = Self::new_with_base(
+                l1,
+                l2,
+                l3,
+                roots,
+                include_hidden,
+                ignore_enabled,
+                follow_symlinks,
+                exclude_dirs,
+                Some(loaded.base),
+            );
+            idx.mark_partitions_persisted(&persisted);
+            idx.restore_content_index(loaded.content);
+            idx.attach_wal(store)?;
+            let source = if loaded.used_prev {
+                "partitions-prev"
+            } else {
+                "partitions"
+            };
+            let replay = idx.replay_wal_if_any(wal_checkpoint_for(source, &runtime_state));
+            idx.set_startup_recovery_report(StartupRecoveryReport {
+                snapshot_source: source.to_string(),
+                wal_events_replayed: replay.events_replayed,
+                wal_truncated_tail_records: replay.truncated_tail_records,
+                requires_repair: !runtime_state.last_clean_shutdown
+                    || replay.truncated_tail_records > 0,
+                previous_clean_shutdown: runtime_state.last_clean_shutdown,
+                missing_partitions: loaded.missing,
+            });
+            maybe_trim_rss();
+            return Ok(idx);
+        }
+
+        // 旧布局：stable.v7 / stable.prev.v7，再回退到 legacy v7 单文件快照，按 roots 拆成分区。
         let stable_path = stable_v7_path_for(store.path());
         let stable_prev_path = stable_prev_v7_path_for(store.path());
         let legacy_v7_path = store.path().with_extension("v7");
@@ -341,8 +419,8 @@ impl TieredIndex {
         ];
 
         for (source, path) in snapshot_candidates {
-            match crate::storage::snapshot_v7::try_load_v7(path) {
-                Ok(Some(v7_data)) => {
+            match crate::storage::snapshot_v7::try_load_v7_with_content(path) {
+                Ok(Some((v7_data, content))) => {
                     tracing::info!(
                         "{} snapshot loaded directly into base: {} entries, {} trigrams",
                         source,
@@ -350,6 +428,8 @@ impl TieredIndex {
                         v7_data.trigram_index.len()
                     );
                     let l2 = Arc::new(PersistentIndex::new_with_roots(roots.clone()));
+                    let base =
+                        PartitionedBase::split(v7_data, &roots, INITIAL_PARTITION_GENERATION);
                     let idx = Self::new_with_base(
                         l1,
                         l2,
@@ -359,10 +439,11 @@ impl TieredIndex {
                         ignore_enabled,
                         follow_symlinks,
                         exclude_dirs,
-                        Some(v7_data),
+                        Some(base),
                     );
+                    idx.restore_content_index(content);
                     idx.attach_wal(store)?;
-                    let replay = idx.replay_wal_if_any(0);
+                    let replay = idx.replay_wal_if_any(wal_checkpoint_for(source, &runtime_state));
                     idx.set_startup_recovery_report(StartupRecoveryReport {
                         snapshot_source: source.to_string(),
                         wal_events_replayed: replay.events_replayed,
@@ -370,6 +451,7 @@ impl TieredIndex {
                         requires_repair: !runtime_state.last_clean_shutdown
                             || replay.truncated_tail_records > 0,
                         previous_clean_shutdown: runtime_state.last_clean_shutdown,
+                        missing_partitions: Vec::new(),
                     });
                     maybe_trim_rss();
                     return Ok(idx);
@@ -399,10 +481,119 @@ impl TieredIndex {
             wal_truncated_tail_records: replay.truncated_tail_records,
             requires_repair: true,
             previous_clean_shutdown: runtime_state.last_clean_shutdown,
+            missing_partitions: Vec::new(),
         });
         Ok(idx)
     }
 
+    /// 只读离线加载：按分区段 / stable / stable-prev / legacy v7 顺序读取快照并回放 WAL，
+    /// 不挂载 WAL、不写 runtime state，也不创建或改动任何索引文件。
+    ///
+    /// 供 daemon 未运行时在进程内直接查询（`fd-rdd-query --offline`）；结果只反映
+    /// 最后一次落盘的快照与 WAL，之后的文件系统变化不可见。没有可用快照时返回 `Ok(None)`。
+    /// 分区段按 MANIFEST 全部加载（不要求与 `roots` 一致）。
+    pub fn load_read_only(
+        snapshot_path: &Path,
+        roots: Vec<PathBuf>,
+    ) -> anyhow::Result<Option<(Self, OfflineLoadReport)>> {
+        let manifest_roots: Vec<PathBuf> = read_partition_manifest(snapshot_path)
+            .ok()
+            .flatten()
+            .map(|m| m.partitions.into_iter().map(|e| e.root).collect())
+            .unwrap_or_default();
+        if let Some(loaded) = Self::load_partitions(snapshot_path, &manifest_roots) {
+            let source = if loaded.used_prev {
+                "partitions-prev"
+            } else {
+                "partitions"
+            };
+            let path = partition_manifest_path_for(snapshot_path);
+            let idx = Self::read_only_with_base(roots, loaded.base, loaded.content);
+            return Ok(Some(idx.finish_read_only_load(snapshot_path, source, path)));
+        }
+
+        let stable_path = stable_v7_path_for(snapshot_path);
+        let stable_prev_path = stable_prev_v7_path_for(snapshot_path);
+        let legacy_v7_path = snapshot_path.with_extension("v7");
+        let snapshot_candidates = [
+            ("stable", stable_path),
+            ("stable-prev", stable_prev_path),
+            ("legacy-v7", legacy_v7_path),
+        ];
+
+        for (source, path) in snapshot_candidates {
+            let (v7_data, content) =
+                match crate::storage::snapshot_v7::try_load_v7_with_content(&path) {
+                    Ok(Some(loaded)) => loaded,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        tracing::warn!("{} load failed: {}", source, e);
+                        continue;
+                    }
+                };
+            let base = PartitionedBase::split(v7_data, &roots, INITIAL_PARTITION_GENERATION);
+            let idx = Self::read_only_with_base(roots, base, content);
+            return Ok(Some(idx.finish_read_only_load(snapshot_path, source, path)));
+        }
+        Ok(None)
+    }
+
+    fn read_only_with_base(
+        roots: Vec<PathBuf>,
+        base: PartitionedBase,
+        content: crate::index::content_index::ContentIndex,
+    ) -> Self {
+        let idx = Self::new_with_base(
+            L1Cache::with_capacity(1000),
+            Arc::new(PersistentIndex::new_with_roots(roots.clone())),
+            IndexBuilder::new(roots.clone()),
+            roots,
+            false,
+            true,
+            false,
+            Vec::new(),
+            Some(base),
+        );
+        idx.restore_content_index(content);
+        idx
+    }
+
+    fn finish_read_only_load(
+        self,
+        snapshot_path: &Path,
+        source: &str,
+        path: PathBuf,
+    ) -> (Self, OfflineLoadReport) {
+        let wal_dir = SnapshotStore::new(snapshot_path.to_path_buf()).derived_lsm_dir_path();
+        let runtime_state = read_recovery_runtime_state(snapshot_path).unwrap_or_default();
+        let checkpoint = wal_checkpoint_for(source, &runtime_state);
+        let (wal_events_replayed, wal_truncated_tail_records) =
+            match crate::storage::wal::replay_read_only(&wal_dir, checkpoint) {
+                Ok(r) => {
+                    if !r.events.is_empty() {
+                        self.apply_events_inner(&r.events, false);
+                    }
+                    (r.events.len(), r.truncated_tail_records)
+                }
+                Err(e) => {
+                    tracing::warn!("read-only WAL replay failed, ignoring: {}", e);
+                    (0, 1)
+                }
+            };
+
+        let snapshot_mtime = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        (
+            self,
+            OfflineLoadReport {
+                snapshot_source: source.to_string(),
+                snapshot_path: path,
+                snapshot_mtime,
+                wal_events_replayed,
+                wal_truncated_tail_records,
+            },
+        )
+    }
+
     pub fn attach_wal<S: StorageBackend + ?Sized>(&self, store: &S) -> anyhow::Result<()> {
         let mut g = self.wal.lock();
         if g.is_some() {
@@ -11,7 +11,8 @@ impl TieredIndex {
     /// 手动刷新 base 索引（当 l2 被外部直接修改后需要调用）。
     pub fn refresh_base(&self) {
         let l2 = self.l2.load_full();
-        let new_base = Arc::new(l2.to_base_index_data());
+        let _swap = self.partition_swap.lock();
+        let new_base = Arc::new(self.partitions_from_l2(&l2));
         self.base.store(new_base);
         self.delta_buffer.lock().clear();
     }
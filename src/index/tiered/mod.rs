@@ -1,33 +1,57 @@
 pub(crate) mod arena;
+mod audit;
+mod changes;
+mod content;
 pub(crate) mod events;
+mod explain;
 pub(crate) mod load;
 mod memory;
+mod partitions;
+mod path_rules;
 mod query;
 mod query_plan;
 pub(crate) mod rebuild;
+mod repair;
+mod roots;
 mod snapshot;
+mod subscribe;
+mod symlinks;
 pub(crate) mod sync;
 
 #[cfg(test)]
 mod tests;
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, AtomicU64};
 use std::sync::Arc;
 use std::time::Duration;
 
 use arc_swap::ArcSwap;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use tokio::sync::Notify;
 
 use crate::core::AdaptiveScheduler;
+use crate::index::content_index::ContentIndex;
 use crate::index::l1_cache::L1Cache;
 use crate::index::l2_partition::PersistentIndex;
 use crate::index::l3_cold::IndexBuilder;
+use crate::stats::query_profile::{QueryProfile, SlowQuery};
 use crate::stats::{StatsCollector, StatsReport};
 use crate::storage::traits::WriteAheadLog;
 
 use self::rebuild::RebuildState;
+use self::subscribe::SubscriptionHub;
+
+pub use self::audit::DirAudit;
+pub use self::changes::{ChangeEntry, ChangeFeed, ChangeKind, ChangesError};
+pub use self::explain::{AnchorEstimate, ExplainActual, IndexExplain, ParentShortcut};
+pub use self::partitions::{PartitionRebuild, PartitionStatus};
+pub use self::path_rules::PathRulesChange;
+pub use self::roots::{RootChange, RootChangeHook, RootChangeKind, RootsError};
+pub use self::subscribe::{
+    SubscribeError, Subscription, SubscriptionUpdate, DEFAULT_MAX_SUBSCRIBERS,
+};
 
 const REBUILD_COOLDOWN: Duration = Duration::from_secs(60);
 
@@ -45,6 +69,19 @@ pub struct StartupRecoveryReport {
     pub wal_truncated_tail_records: usize,
     pub requires_repair: bool,
     pub previous_clean_shutdown: bool,
+    /// 分区快照中缺失的 root（新增 root 或段损坏），需单独扫描补齐
+    pub missing_partitions: Vec<PathBuf>,
+}
+
+/// 只读离线加载（`TieredIndex::load_read_only`）的数据来源，用于提示结果可能过时。
+#[derive(Clone, Debug)]
+pub struct OfflineLoadReport {
+    pub snapshot_source: String,
+    pub snapshot_path: PathBuf,
+    /// 快照文件的修改时间（即最后一次落盘时间）
+    pub snapshot_mtime: Option<std::time::SystemTime>,
+    pub wal_events_replayed: usize,
+    pub wal_truncated_tail_records: usize,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -54,6 +91,12 @@ pub struct StartupRepairStats {
     pub scanned: usize,
     pub changed: usize,
     pub elapsed_ms: u64,
+    /// 前台预算已用尽，剩余目录在后台继续
+    pub in_progress: bool,
+    /// 从上次未完成的续传游标继续
+    pub resumed: bool,
+    pub dirs_done: usize,
+    pub dirs_pending: usize,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -84,7 +127,13 @@ pub struct TieredIndex {
     pub event_seq: AtomicU64,
     pub(self) rebuild_state: Mutex<RebuildState>,
     pub(self) delta_buffer: Mutex<crate::index::delta_buffer::DeltaBuffer>,
-    pub base: ArcSwap<crate::index::base_index::BaseIndexData>,
+    /// 按 root 分区的只读基础索引；分区可独立重建 / 丢弃 / 落盘。
+    pub base: ArcSwap<crate::index::partitioned_base::PartitionedBase>,
+    pub(self) partition_generation: AtomicU64,
+    /// 已落盘的分区 generation（root → generation），快照只重写不一致的分区段
+    pub(self) persisted_partitions: Mutex<HashMap<PathBuf, u64>>,
+    /// 串行化"物化快照"与"单分区重建"的分区切换
+    pub(self) partition_swap: Mutex<()>,
     pub(self) flush_requested: AtomicBool,
     pub(self) flush_notify: Notify,
     pub(self) auto_flush_overlay_paths: AtomicU64,
@@ -94,7 +143,10 @@ pub struct TieredIndex {
     pub(self) pending_flush_events: AtomicU64,
     pub(self) pending_flush_bytes: AtomicU64,
     pub(self) last_snapshot_time: AtomicU64,
-    pub roots: Vec<PathBuf>,
+    /// 运行期可增删（`add_root` / `remove_root`），读取请用 `roots()` 取快照。
+    pub(self) roots: RwLock<Vec<PathBuf>>,
+    pub(self) roots_update: Mutex<()>,
+    pub(self) root_change_hook: Mutex<Option<RootChangeHook>>,
     pub include_hidden: bool,
     pub ignore_enabled: bool,
     pub follow_symlinks: bool,
@@ -103,6 +155,19 @@ pub struct TieredIndex {
     pub(self) recovery_status: Mutex<RecoveryStatus>,
     pub(self) stable_snapshot_enabled: AtomicBool,
     pub(self) stats: Arc<StatsCollector>,
+    pub(self) content_index: Arc<RwLock<ContentIndex>>,
+    pub(self) content_index_enabled: AtomicBool,
+    pub(self) content_sync_running: AtomicBool,
+    pub(self) content_sync_requested: AtomicBool,
+    /// 事件驱动的内容索引变更，由后台线程回读文件后写入
+    pub(self) content_queue: Arc<content::ContentQueue>,
+    pub(self) subscriptions: Arc<SubscriptionHub>,
+    /// 忽略文件变化后待对账的目录，由 `spawn_ignore_reconcile` 的后台线程处理
+    pub(self) ignore_reconcile: sync::IgnoreReconcileQueue,
+    /// 符号链接反向表（目标 → 链接），目标变化时据此刷新链接条目
+    pub(self) symlinks: Mutex<symlinks::SymlinkTargets>,
+    /// 前台预算内未完成的启动修复，由 `spawn_startup_repair` 接到后台
+    pub(self) repair_run: Mutex<Option<repair::RepairRun>>,
 }
 
 impl TieredIndex {
@@ -134,6 +199,27 @@ impl TieredIndex {
     pub fn stats_report(&self) -> StatsReport {
         self.stats.report()
     }
+
+    /// 记录一次查询的分阶段剖析（直方图 + 慢查询日志）。
+    pub fn record_query_profile(&self, query: &str, mode: &str, profile: &QueryProfile) {
+        self.stats.record_query_profile(query, mode, profile);
+    }
+
+    /// 慢查询日志（最新的在前）。
+    pub fn slow_queries(&self) -> Vec<SlowQuery> {
+        self.stats.slow_queries()
+    }
+
+    /// 慢查询阈值（总耗时）与环形缓冲区容量；阈值 0 记录全部，容量 0 关闭。
+    pub fn set_slow_query_log(&self, threshold: std::time::Duration, capacity: usize) {
+        self.stats
+            .slow_query_log()
+            .configure(threshold.as_micros() as u64, capacity);
+    }
+
+    pub fn slow_query_threshold(&self) -> std::time::Duration {
+        std::time::Duration::from_micros(self.stats.slow_query_log().threshold_us())
+    }
 }
 
 // Re-exports
@@ -0,0 +1,290 @@
+//! 分区维护：单分区重建 / 丢弃、分区段落盘与加载。
+//!
+//! 分区切换都经 `partition_swap` 串行化：快照物化与单分区重建不会互相覆盖。
+//! 单分区重建期间到达的事件照常进入 DeltaBuffer（overlay 优先于 base），切换后依然可见。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::core::partition::{segment_key_for, Partition, FALLBACK_PARTITION_ROOT};
+use crate::core::{FileMeta, Task};
+use crate::index::content_index::ContentIndex;
+use crate::index::l2_partition::PersistentIndex;
+use crate::index::partitioned_base::{BasePartition, PartitionedBase};
+use crate::storage::partitions::{
+    load_partition_content, load_partition_segment, read_partition_manifest,
+    write_partition_content, write_partition_manifest, write_partition_segment, PartitionManifest,
+    PartitionManifestEntry, PARTITION_MANIFEST_VERSION,
+};
+
+use super::{RootsError, TieredIndex};
+
+/// `/status` 中的分区概览。
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PartitionStatus {
+    pub root: String,
+    pub files: usize,
+    pub generation: u64,
+    /// 当前 generation 是否已写入快照段
+    pub persisted: bool,
+}
+
+/// 一次单分区重建的结果。
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct PartitionRebuild {
+    pub root: String,
+    pub files: usize,
+    pub elapsed_ms: u64,
+}
+
+/// 从分区快照段加载的结果。
+pub(super) struct LoadedPartitions {
+    pub base: PartitionedBase,
+    pub content: ContentIndex,
+    pub missing: Vec<PathBuf>,
+    pub used_prev: bool,
+}
+
+impl TieredIndex {
+    pub(super) fn next_partition_generation(&self) -> u64 {
+        self.partition_generation.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// 把（全量重建得到的）L2 按当前 roots 拆成分区。
+    pub(super) fn partitions_from_l2(&self, l2: &PersistentIndex) -> PartitionedBase {
+        let mut metas: Vec<FileMeta> = Vec::with_capacity(l2.file_count());
+        l2.for_each_live_meta(|meta| metas.push(meta));
+        PartitionedBase::from_metas(&self.roots(), metas, self.next_partition_generation())
+    }
+
+    pub fn partition_status(&self) -> Vec<PartitionStatus> {
+        let persisted = self.persisted_partitions.lock();
+        self.base
+            .load()
+            .parts()
+            .iter()
+            .map(|p| PartitionStatus {
+                root: p.root().to_string_lossy().into_owned(),
+                files: p.data.file_count(),
+                generation: p.generation,
+                persisted: persisted.get(p.root()) == Some(&p.generation),
+            })
+            .collect()
+    }
+
+    pub(super) fn has_unpersisted_partitions(&self) -> bool {
+        let persisted = self.persisted_partitions.lock();
+        let base = self.base.load();
+        base.parts().len() != persisted.len()
+            || base
+                .parts()
+                .iter()
+                .any(|p| persisted.get(p.root()) != Some(&p.generation))
+    }
+
+    pub(super) fn mark_partitions_persisted(&self, base: &PartitionedBase) {
+        *self.persisted_partitions.lock() = base
+            .parts()
+            .iter()
+            .map(|p| (p.root().to_path_buf(), p.generation))
+            .collect();
+    }
+
+    /// 只重扫一个 root，替换其分区；其他分区与 DeltaBuffer 不受影响。
+    ///
+    /// 同步执行，调用方应放到阻塞线程池。全量重建进行中时拒绝。
+    pub fn rebuild_partition(&self, root: &Path) -> Result<PartitionRebuild, RootsError> {
+        let root = super::normalize_path(root);
+        if !self.roots().contains(&root) {
+            return Err(RootsError::NotFound(root));
+        }
+        if self.rebuild_in_progress() {
+            return Err(RootsError::RebuildInProgress);
+        }
+
+        let _swap = self.partition_swap.lock();
+        let started = Instant::now();
+        let strategy = {
+            let mut sched = self.scheduler.lock();
+            sched.adjust_parallelism();
+            sched.select_strategy(&Task::ColdBuild { total_dirs: 1 })
+        };
+        let l2 = Arc::new(PersistentIndex::new_with_roots(vec![root.clone()]));
+        self.l3
+            .full_build_roots_with_strategy(vec![root.clone()], &l2, strategy);
+        let data = Arc::new(l2.to_base_index_data());
+        drop(l2);
+        let files = data.file_count();
+
+        let part = BasePartition {
+            partition: Partition::for_root(0, root.clone()),
+            data,
+            generation: self.next_partition_generation(),
+        };
+        let next = self.base.load().with_partition(part);
+        self.base.store(Arc::new(next));
+        self.reindex_symlinks();
+        self.l1.clear();
+        if !self.flush_requested.swap(true, Ordering::AcqRel) {
+            self.flush_notify.notify_one();
+        }
+
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        tracing::info!(
+            "partition rebuilt: {} ({} files in {} ms)",
+            root.display(),
+            files,
+            elapsed_ms
+        );
+        Ok(PartitionRebuild {
+            root: root.to_string_lossy().into_owned(),
+            files,
+            elapsed_ms,
+        })
+    }
+
+    /// 后台依次重建若干分区（启动时快照段缺失的 root）。
+    pub fn spawn_partition_builds(self: &Arc<Self>, roots: Vec<PathBuf>) {
+        if roots.is_empty() {
+            return;
+        }
+        let idx = self.clone();
+        std::thread::spawn(move || {
+            for root in roots {
+                if let Err(e) = idx.rebuild_partition(&root) {
+                    tracing::warn!("partition build for {} skipped: {}", root.display(), e);
+                }
+            }
+        });
+    }
+
+    /// 丢弃一个分区（root 被移除）；下一次快照会把它从 MANIFEST 中删掉。
+    pub(super) fn drop_partition(&self, root: &Path) {
+        let _swap = self.partition_swap.lock();
+        let next = self.base.load().without_partition(root);
+        self.base.store(Arc::new(next));
+        self.reindex_symlinks();
+        self.l1.clear();
+        if !self.flush_requested.swap(true, Ordering::AcqRel) {
+            self.flush_notify.notify_one();
+        }
+    }
+
+    /// 写出 generation 变化过的分区段与 MANIFEST；未变化的分区段保持原样。
+    pub(super) fn persist_partitions(
+        &self,
+        snapshot_path: &Path,
+        base: &PartitionedBase,
+        content: Option<&ContentIndex>,
+        keep_prev: bool,
+    ) -> anyhow::Result<usize> {
+        let previous = read_partition_manifest(snapshot_path).ok().flatten();
+        let persisted = self.persisted_partitions.lock().clone();
+        let now = super::snapshot::unix_secs();
+        let mut written = 0usize;
+        let mut entries: Vec<PartitionManifestEntry> = Vec::with_capacity(base.parts().len());
+        let mut generations: HashMap<PathBuf, u64> = HashMap::with_capacity(base.parts().len());
+
+        for part in base.parts() {
+            let segment = part.partition.segment_key();
+            let unchanged = persisted.get(part.root()) == Some(&part.generation);
+            let previous_entry = previous
+                .as_ref()
+                .and_then(|m| m.entry(part.root()))
+                .filter(|e| unchanged && e.segment == segment)
+                .cloned();
+            let entry = match previous_entry {
+                Some(entry) => entry,
+                None => {
+                    write_partition_segment(snapshot_path, &segment, &part.data, keep_prev)?;
+                    written += 1;
+                    PartitionManifestEntry {
+                        root: part.root().to_path_buf(),
+                        segment,
+                        files: part.data.file_count(),
+                        written_unix_secs: now,
+                    }
+                }
+            };
+            entries.push(entry);
+            generations.insert(part.root().to_path_buf(), part.generation);
+        }
+
+        if let Some(content) = content {
+            write_partition_content(snapshot_path, content)?;
+        }
+        write_partition_manifest(
+            snapshot_path,
+            &PartitionManifest {
+                version: PARTITION_MANIFEST_VERSION,
+                partitions: entries,
+                content: content.is_some(),
+            },
+        )?;
+        *self.persisted_partitions.lock() = generations;
+        Ok(written)
+    }
+
+    /// 按 MANIFEST 加载各 root 的分区段；没有任何可用分区时返回 None（回退单体快照）。
+    ///
+    /// 只加载当前 roots（及 `/` 兜底分区）的段：root 集合变化不会让其他分区失效，
+    /// 缺段的 root 记入 `missing`，由上层单独扫描。
+    pub(super) fn load_partitions(
+        snapshot_path: &Path,
+        roots: &[PathBuf],
+    ) -> Option<LoadedPartitions> {
+        let manifest = match read_partition_manifest(snapshot_path) {
+            Ok(Some(m)) => m,
+            Ok(None) => return None,
+            Err(e) => {
+                tracing::warn!("partition manifest unreadable, ignoring: {}", e);
+                return None;
+            }
+        };
+
+        let fallback = PathBuf::from(FALLBACK_PARTITION_ROOT);
+        let mut wanted: Vec<PathBuf> = roots.to_vec();
+        if manifest.entry(&fallback).is_some() && !wanted.contains(&fallback) {
+            wanted.push(fallback);
+        }
+
+        let mut parts: Vec<BasePartition> = Vec::with_capacity(wanted.len());
+        let mut missing: Vec<PathBuf> = Vec::new();
+        let mut used_prev = false;
+        for root in wanted {
+            let loaded = manifest
+                .entry(&root)
+                .filter(|e| e.segment == segment_key_for(&root))
+                .and_then(|e| load_partition_segment(snapshot_path, &e.segment));
+            match loaded {
+                Some((data, is_prev)) => {
+                    used_prev |= is_prev;
+                    parts.push(BasePartition {
+                        partition: Partition::for_root(parts.len(), root),
+                        data: Arc::new(data),
+                        generation: 0,
+                    });
+                }
+                None => missing.push(root),
+            }
+        }
+        if parts.is_empty() {
+            return None;
+        }
+
+        let content = if manifest.content {
+            load_partition_content(snapshot_path).unwrap_or_default()
+        } else {
+            ContentIndex::new()
+        };
+        Some(LoadedPartitions {
+            base: PartitionedBase::new(parts),
+            content,
+            missing,
+            used_prev,
+        })
+    }
+}
@@ -0,0 +1,136 @@
+//! 索引期 glob 规则的运行期状态：查询当前规则、单路径判定，以及规则变化后的定向重过滤。
+//!
+//! 规则变化不触发全量重建：现在被排除的条目直接 tombstone，只有被放宽的范围
+//! （删掉的 exclude 或放宽的 include 目录）才重新扫描补回。
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::core::partition::route_path;
+use crate::core::path_rules::{PathRules, PathRulesConfig};
+
+use super::TieredIndex;
+
+/// 一次规则替换的结果。
+#[derive(Clone, Debug, Default)]
+pub struct PathRulesChange {
+    /// 因新规则被 tombstone 的条目数
+    pub removed: usize,
+    /// 重新扫描的范围（被放宽的目录或 root）
+    pub rescanned: Vec<PathBuf>,
+    /// 重新扫描中新增或变化的条目数
+    pub restored: usize,
+    pub elapsed_ms: u64,
+}
+
+impl TieredIndex {
+    pub fn path_rules(&self) -> Arc<PathRules> {
+        self.l3.path_rules()
+    }
+
+    /// 单条路径是否应进入索引（事件管道用）。路径已不存在时只看 exclude：
+    /// 删除事件不能因为 include 判定不了文件类型而被丢掉。
+    pub fn path_allowed(&self, path: &Path) -> bool {
+        let rules = self.path_rules();
+        rules.is_empty() || path_allowed_by(&rules, &self.roots(), path)
+    }
+
+    /// 换用新规则，并按 `previous`（上一次生效的规则）定向对齐已有条目。
+    ///
+    /// 同步执行；规则与 `previous` 相同时只替换规则对象，不扫描。
+    pub fn apply_path_rules(
+        &self,
+        rules: Arc<PathRules>,
+        previous: &PathRulesConfig,
+    ) -> PathRulesChange {
+        let start = Instant::now();
+        let loosened = previous.loosened_scopes(rules.config());
+        let changed = previous != rules.config();
+        self.l3.set_path_rules(rules.clone());
+        if !changed {
+            return PathRulesChange::default();
+        }
+        self.refilter_path_rules(&rules, loosened, start)
+    }
+
+    /// 只换用规则对象：之后的扫描与事件管道按新规则过滤，已有条目留待
+    /// `spawn_path_rules_refilter` 对齐。
+    pub fn set_path_rules(&self, rules: Arc<PathRules>) {
+        self.l3.set_path_rules(rules);
+    }
+
+    /// 按 `previous`（上一次生效的规则）在后台对齐已有条目，与启动修复、分区构建一样
+    /// 不占用启动路径；结束后调用 `on_done`（例如落盘本次生效的规则，中途退出时
+    /// 下次启动仍会重过滤）。
+    pub fn spawn_path_rules_refilter(
+        self: &Arc<Self>,
+        previous: &PathRulesConfig,
+        on_done: impl FnOnce(&PathRulesChange) + Send + 'static,
+    ) {
+        let start = Instant::now();
+        let rules = self.path_rules();
+        if previous == rules.config() {
+            on_done(&PathRulesChange::default());
+            return;
+        }
+        let loosened = previous.loosened_scopes(rules.config());
+        let idx = self.clone();
+        std::thread::spawn(move || {
+            let change = idx.refilter_path_rules(&rules, loosened, start);
+            on_done(&change);
+        });
+    }
+
+    /// 放宽的范围重新扫描补回，随后把新规则拒绝的条目 tombstone。
+    fn refilter_path_rules(
+        &self,
+        rules: &PathRules,
+        loosened: Option<Vec<PathBuf>>,
+        start: Instant,
+    ) -> PathRulesChange {
+        let mut change = PathRulesChange::default();
+        let roots = self.roots();
+        if let Some(scopes) = loosened {
+            change.rescanned = if scopes.is_empty() {
+                roots.clone()
+            } else {
+                scopes
+                    .into_iter()
+                    .filter(|dir| roots.iter().any(|r| dir.starts_with(r)) && dir.is_dir())
+                    .collect()
+            };
+            let dirs: Vec<&PathBuf> = change.rescanned.iter().collect();
+            if !dirs.is_empty() {
+                change.restored = self.scan_dirs_with_depth(&dirs, None, usize::MAX).changed;
+            }
+        }
+
+        let rejected: Vec<PathBuf> = self
+            .live_paths_under(Path::new("/"))
+            .into_iter()
+            .filter(|p| !path_allowed_by(rules, &roots, p))
+            .collect();
+        change.removed = self.tombstone_paths(rejected);
+        self.l1.clear();
+
+        change.elapsed_ms = start.elapsed().as_millis() as u64;
+        tracing::info!(
+            "path rules changed: removed={} rescanned={:?} restored={} elapsed_ms={}",
+            change.removed,
+            change.rescanned,
+            change.restored,
+            change.elapsed_ms
+        );
+        change
+    }
+}
+
+fn path_allowed_by(rules: &PathRules, roots: &[PathBuf], path: &Path) -> bool {
+    let root = route_path(roots.iter().map(PathBuf::as_path), path).map(|i| roots[i].as_path());
+    rules.allows(root, path, || {
+        std::fs::symlink_metadata(path)
+            .map(|m| m.is_dir())
+            .unwrap_or(true)
+    })
+}
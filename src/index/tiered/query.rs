@@ -1,16 +1,82 @@
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
-use crate::core::{EventRecord, FileKey, FileMeta};
+use crate::core::partition::{Partition, FALLBACK_PARTITION_ROOT};
+use crate::core::{EventRecord, FileMeta};
 use crate::index::base_index::BaseIndexData;
 use crate::index::l2_partition::PersistentIndex;
+use crate::index::partitioned_base::{build_partition_data, BasePartition, PartitionedBase};
 use crate::index::IndexLayer;
 use crate::query::dsl::compile_query;
+use crate::query::fzf::{SortColumn, SortOrder};
 use crate::query::matcher::create_matcher;
+use crate::query::top_k::TopK;
+use crate::stats::query_profile::QueryProfile;
 
 use super::arena::{path_deleted_by_any, PathArenaSet};
 use super::query_plan::QueryPlan;
 use super::TieredIndex;
 
+/// base 候选每扫过这么多条检查一次 `ResultSink::is_full`（含取消标志）。
+const SINK_CHECK_CHUNK: usize = 1024;
+
+/// 查询结果收集器：按 limit 先到先得截断，或按排序保留 top-k。
+trait ResultSink {
+    /// 收下一条命中；返回 false 表示已收满，调用方应停止扫描。
+    fn push(&mut self, meta: FileMeta) -> bool;
+    fn is_full(&self) -> bool;
+}
+
+struct FirstN {
+    results: Vec<FileMeta>,
+    limit: usize,
+}
+
+impl ResultSink for FirstN {
+    fn push(&mut self, meta: FileMeta) -> bool {
+        self.results.push(meta);
+        !self.is_full()
+    }
+
+    fn is_full(&self) -> bool {
+        self.results.len() >= self.limit
+    }
+}
+
+impl ResultSink for TopK {
+    fn push(&mut self, meta: FileMeta) -> bool {
+        TopK::push(self, meta);
+        true
+    }
+
+    fn is_full(&self) -> bool {
+        false
+    }
+}
+
+/// 带取消标志的收集器：标志置位后视为已收满，扫描在下一个候选块处停下。
+struct Cancellable<'a> {
+    inner: &'a mut dyn ResultSink,
+    cancel: &'a AtomicBool,
+}
+
+impl ResultSink for Cancellable<'_> {
+    fn push(&mut self, meta: FileMeta) -> bool {
+        if self.cancel.load(Ordering::Relaxed) {
+            return false;
+        }
+        self.inner.push(meta)
+    }
+
+    fn is_full(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed) || self.inner.is_full()
+    }
+}
+
 impl TieredIndex {
     /// 查询入口：L1 → L2 → DiskSegments（mmap），不扫真实文件系统
     pub fn query(&self, keyword: &str) -> Vec<FileMeta> {
@@ -18,11 +84,104 @@ impl TieredIndex {
     }
 
     /// 查询入口（带 limit）：用于 IPC/HTTP 等"结果集可能很大"的场景，避免一次性聚合造成内存峰值。
+    ///
+    /// 命中按索引顺序先到先得截断；需要"最相关的 N 条"时用 `query_top_k`。
     pub fn query_limit(&self, keyword: &str, limit: usize) -> Vec<FileMeta> {
+        self.query_limit_profiled(keyword, limit).0
+    }
+
+    /// 同 `query_limit`，附带分阶段耗时与候选规模。
+    pub fn query_limit_profiled(
+        &self,
+        keyword: &str,
+        limit: usize,
+    ) -> (Vec<FileMeta>, QueryProfile) {
+        let mut profile = QueryProfile::default();
         if limit == 0 {
-            return Vec::new();
+            return (Vec::new(), profile);
         }
 
+        let started = Instant::now();
+        let plan = self.plan_query(keyword);
+        profile.plan_us = started.elapsed().as_micros() as u64;
+        if let Some(matcher) = plan.legacy_matcher() {
+            if let Some(results) = self.l1.query(matcher) {
+                tracing::debug!("L1 hit: {} results", results.len());
+                let results: Vec<FileMeta> = results.into_iter().take(limit).collect();
+                profile.results = results.len();
+                profile.total_us = started.elapsed().as_micros() as u64;
+                return (results, profile);
+            }
+        }
+
+        let mut sink = FirstN {
+            results: Vec::with_capacity(limit.min(128)),
+            limit,
+        };
+        self.execute_query_plan(&plan, &mut sink, &mut profile);
+        let results = self.finish_query(sink.results);
+        profile.results = results.len();
+        profile.total_us = started.elapsed().as_micros() as u64;
+        (results, profile)
+    }
+
+    /// 排序感知查询：遍历全部命中，用有界堆保留按 `sort`/`order`
+    /// （Score 列使用 `ScoreConfig`）排名最优的 `limit` 条，返回结果已排好序。
+    pub fn query_top_k(
+        &self,
+        keyword: &str,
+        limit: usize,
+        sort: SortColumn,
+        order: SortOrder,
+    ) -> Vec<FileMeta> {
+        self.query_top_k_profiled(keyword, limit, sort, order).0
+    }
+
+    /// 同 `query_top_k`，附带分阶段耗时与候选规模。
+    pub fn query_top_k_profiled(
+        &self,
+        keyword: &str,
+        limit: usize,
+        sort: SortColumn,
+        order: SortOrder,
+    ) -> (Vec<FileMeta>, QueryProfile) {
+        self.query_top_k_cancellable(keyword, limit, sort, order, &AtomicBool::new(false))
+    }
+
+    /// 同 `query_top_k_profiled`；`cancel` 置位后在下一个候选块处停止扫描，
+    /// 返回已收集的部分结果（调用方通常直接丢弃）。
+    pub fn query_top_k_cancellable(
+        &self,
+        keyword: &str,
+        limit: usize,
+        sort: SortColumn,
+        order: SortOrder,
+        cancel: &AtomicBool,
+    ) -> (Vec<FileMeta>, QueryProfile) {
+        let mut profile = QueryProfile::default();
+        if limit == 0 {
+            return (Vec::new(), profile);
+        }
+
+        let started = Instant::now();
+        let plan = self.plan_query(keyword);
+        profile.plan_us = started.elapsed().as_micros() as u64;
+        let mut top = TopK::new(limit, keyword, sort, order);
+        let mut sink = Cancellable {
+            inner: &mut top,
+            cancel,
+        };
+        self.execute_query_plan(&plan, &mut sink, &mut profile);
+        let sort_started = Instant::now();
+        let sorted = top.into_sorted_vec();
+        profile.sort_us = sort_started.elapsed().as_micros() as u64;
+        let results = self.finish_query(sorted);
+        profile.results = results.len();
+        profile.total_us = started.elapsed().as_micros() as u64;
+        (results, profile)
+    }
+
+    pub(super) fn plan_query(&self, keyword: &str) -> QueryPlan {
         if self.base.load().file_count() == 0
             && !self.rebuild_in_progress()
             && self.l2.load().file_count() > 0
@@ -30,8 +189,11 @@ impl TieredIndex {
             self.refresh_base();
         }
 
-        let plan = match compile_query(keyword) {
-            Ok(compiled) => QueryPlan::compiled(compiled),
+        match compile_query(keyword) {
+            Ok(compiled) => {
+                let content = self.content_probe_for(&compiled);
+                QueryPlan::compiled(compiled).with_content(content)
+            }
             Err(e) => {
                 tracing::warn!(
                     "query dsl compile failed, fallback to legacy matcher: {}",
@@ -39,18 +201,29 @@ impl TieredIndex {
                 );
                 let case_sensitive =
                     keyword.contains("case:") || keyword.chars().any(|c| c.is_uppercase());
-                let matcher = create_matcher(keyword, case_sensitive);
-
-                if let Some(results) = self.l1.query(matcher.as_ref()) {
-                    tracing::debug!("L1 hit: {} results", results.len());
-                    return results.into_iter().take(limit).collect();
-                }
-
-                QueryPlan::legacy(matcher)
+                QueryPlan::legacy(create_matcher(keyword, case_sensitive))
             }
+        }
+    }
+
+    /// 按既定计划取前 `limit` 条；不写 L1、不触发修复（`/explain` 的实际计数用）。
+    pub(super) fn execute_plan_limit(
+        &self,
+        plan: &QueryPlan,
+        limit: usize,
+        profile: &mut QueryProfile,
+    ) -> Vec<FileMeta> {
+        let mut sink = FirstN {
+            results: Vec::new(),
+            limit,
         };
+        if limit > 0 {
+            self.execute_query_plan(plan, &mut sink, profile);
+        }
+        sink.results
+    }
 
-        let results = self.execute_query_plan(&plan, limit);
+    fn finish_query(&self, results: Vec<FileMeta>) -> Vec<FileMeta> {
         if !results.is_empty() {
             tracing::debug!("Query hit: {} results", results.len());
             for meta in results.iter().take(10) {
@@ -75,8 +248,6 @@ impl TieredIndex {
         let overlay_deleted = Arc::new(del);
         let mut blocked_paths = PathArenaSet::default();
         let deleted_sources: Vec<Arc<PathArenaSet>> = vec![overlay_deleted];
-        let mut seen: std::collections::HashSet<FileKey> =
-            std::collections::HashSet::with_capacity(base.file_count().saturating_add(256));
         let mut results: Vec<FileMeta> = Vec::with_capacity(base.file_count().saturating_add(256));
 
         for ev in &live_events {
@@ -89,9 +260,6 @@ impl TieredIndex {
             {
                 continue;
             }
-            if !seen.insert(meta.file_key) {
-                continue;
-            }
             let _ = blocked_paths.insert(path_bytes);
             results.push(meta);
         }
@@ -99,9 +267,7 @@ impl TieredIndex {
         base.for_each_live_meta(|meta| {
             collect_live_meta(
                 meta,
-                None,
                 deleted_sources.as_slice(),
-                &mut seen,
                 &mut blocked_paths,
                 &mut results,
             );
@@ -110,22 +276,35 @@ impl TieredIndex {
         results
     }
 
-    pub(crate) fn materialize_snapshot_base(&self) -> Arc<BaseIndexData> {
+    /// 把 DeltaBuffer 折叠进 base：只重建被 overlay 触及的分区，其余分区原样复用。
+    pub(crate) fn materialize_snapshot_base(&self) -> Arc<PartitionedBase> {
+        let _swap = self.partition_swap.lock();
         let mut db = self.delta_buffer.lock();
         let mut del = PathArenaSet::default();
+        let mut deleted_paths: Vec<PathBuf> = Vec::new();
         for p in db.deleted_paths() {
             let _ = del.insert(p);
+            deleted_paths.push(PathBuf::from(OsStr::from_bytes(p)));
         }
         let live_events: Vec<EventRecord> = db.live_records().cloned().collect();
         db.clear();
 
-        let base = self.base.load_full();
+        // 每个当前 root 都应有分区（新增 root 的空分区随快照一起落盘）。
+        let mut base = (*self.base.load_full()).clone();
+        for root in self.roots() {
+            if base.partition(&root).is_none() {
+                base = base.with_partition(BasePartition {
+                    partition: Partition::for_root(0, root),
+                    data: Arc::new(BaseIndexData::default()),
+                    generation: self.next_partition_generation(),
+                });
+            }
+        }
+
         let overlay_deleted = Arc::new(del);
         let mut blocked_paths = PathArenaSet::default();
         let deleted_sources: Vec<Arc<PathArenaSet>> = vec![overlay_deleted];
-        let mut seen: std::collections::HashSet<FileKey> =
-            std::collections::HashSet::with_capacity(base.file_count().saturating_add(256));
-        let mut metas: Vec<FileMeta> = Vec::with_capacity(base.file_count().saturating_add(256));
+        let mut overlay_metas: Vec<FileMeta> = Vec::with_capacity(live_events.len());
 
         for ev in &live_events {
             let Some(meta) = self.overlay_meta_for_event(ev) else {
@@ -137,37 +316,76 @@ impl TieredIndex {
             {
                 continue;
             }
-            if !seen.insert(meta.file_key) {
-                continue;
-            }
             let _ = blocked_paths.insert(path_bytes);
-            metas.push(meta);
+            overlay_metas.push(meta);
         }
 
-        base.for_each_live_meta(|meta| {
-            collect_live_meta(
-                meta,
-                None,
-                deleted_sources.as_slice(),
-                &mut seen,
-                &mut blocked_paths,
-                &mut metas,
-            );
-        });
+        // 受影响的分区：overlay 路径落入的分区、旧 file_key 所在分区（跨分区 rename）、
+        // 以及删除路径所在分区。
+        if overlay_metas.iter().any(|m| base.route(&m.path).is_none()) {
+            base = base.with_partition(BasePartition {
+                partition: Partition::for_root(0, PathBuf::from(FALLBACK_PARTITION_ROOT)),
+                data: Arc::new(BaseIndexData::default()),
+                generation: self.next_partition_generation(),
+            });
+        }
+        let mut touched = vec![false; base.parts().len()];
+        let mut routed: Vec<Vec<FileMeta>> = vec![Vec::new(); base.parts().len()];
+        for meta in overlay_metas {
+            for (i, part) in base.parts().iter().enumerate() {
+                if !touched[i] && part.data.get_meta(meta.file_key).is_some() {
+                    touched[i] = true;
+                }
+            }
+            if let Some(i) = base.route(&meta.path) {
+                touched[i] = true;
+                routed[i].push(meta);
+            }
+        }
+        for path in &deleted_paths {
+            if let Some(i) = base.route(path) {
+                touched[i] = true;
+            }
+        }
 
-        let compact = PersistentIndex::new_with_roots(self.roots.clone());
-        for meta in metas {
-            compact.upsert_rename(meta);
+        let mut rebuilt: Vec<BasePartition> = Vec::new();
+        for (i, part) in base.parts().iter().enumerate() {
+            if !touched[i] {
+                continue;
+            }
+            let mut metas = std::mem::take(&mut routed[i]);
+            part.data.for_each_live_meta(|meta| {
+                collect_live_meta(
+                    meta,
+                    deleted_sources.as_slice(),
+                    &mut blocked_paths,
+                    &mut metas,
+                );
+            });
+            rebuilt.push(BasePartition {
+                partition: part.partition.clone(),
+                data: Arc::new(build_partition_data(part.root(), metas)),
+                generation: self.next_partition_generation(),
+            });
+        }
+        for part in rebuilt {
+            base = base.with_partition(part);
         }
-        let new_base = Arc::new(compact.to_base_index_data());
+
+        let new_base = Arc::new(base);
         self.base.store(new_base.clone());
-        self.l2.store(Arc::new(PersistentIndex::new_with_roots(
-            self.roots.clone(),
-        )));
+        self.l2
+            .store(Arc::new(PersistentIndex::new_with_roots(self.roots())));
         new_base
     }
 
-    fn execute_query_plan(&self, plan: &QueryPlan, limit: usize) -> Vec<FileMeta> {
+    fn execute_query_plan(
+        &self,
+        plan: &QueryPlan,
+        sink: &mut dyn ResultSink,
+        profile: &mut QueryProfile,
+    ) {
+        let overlay_started = Instant::now();
         let base = self.base.load_full();
         let db = self.delta_buffer.lock();
         let mut del = PathArenaSet::default();
@@ -179,8 +397,6 @@ impl TieredIndex {
         let overlay_deleted = Arc::new(del);
         let mut blocked_paths = PathArenaSet::default();
         let deleted_sources: Vec<Arc<PathArenaSet>> = vec![overlay_deleted];
-        let mut overlay_live_keys: std::collections::HashSet<FileKey> =
-            std::collections::HashSet::with_capacity(live_events.len());
         let mut overlay_live_metas: Vec<FileMeta> = Vec::with_capacity(live_events.len());
         for ev in &live_events {
             let Some(meta) = self.overlay_meta_for_event(ev) else {
@@ -192,18 +408,16 @@ impl TieredIndex {
             {
                 continue;
             }
-            let _ = overlay_live_keys.insert(meta.file_key);
+            // overlay 的路径整体遮住 base 中的同一路径（不论本次是否命中）
+            let _ = blocked_paths.insert(path_bytes);
             overlay_live_metas.push(meta);
         }
-        let mut seen: std::collections::HashSet<FileKey> =
-            std::collections::HashSet::with_capacity(base.file_count().saturating_add(256));
-        let mut results: Vec<FileMeta> = Vec::with_capacity(limit.min(128));
 
         // Overlay upserts take precedence over the immutable base. This keeps
         // delete+recreate and rename windows correct while base is only
         // materialized at snapshot/rebuild boundaries.
         for meta in &overlay_live_metas {
-            if results.len() >= limit {
+            if sink.is_full() {
                 break;
             }
             let path_str = meta.path.to_string_lossy();
@@ -211,84 +425,106 @@ impl TieredIndex {
             if !matches_anchor {
                 continue;
             }
-            let path_bytes = meta.path.as_os_str().as_encoded_bytes();
-            if blocked_paths.contains(path_bytes)
-                || path_deleted_by_any(path_bytes, deleted_sources.as_slice())
-            {
-                continue;
-            }
-            if !seen.insert(meta.file_key) {
-                continue;
-            }
-            let _ = blocked_paths.insert(path_bytes);
             if plan.matches(meta) {
-                results.push(meta.clone());
+                profile.overlay_matches += 1;
+                sink.push(meta.clone());
             }
         }
+        profile.overlay_candidates = overlay_live_metas.len();
+        profile.overlay_us = overlay_started.elapsed().as_micros() as u64;
 
-        if results.len() >= limit {
-            return results;
+        if sink.is_full() {
+            return;
         }
 
-        // Even if an overlay rename target does not match this query, its
-        // file_key must shadow the immutable base entry. Otherwise q=old_dir
-        // can still return the stale pre-rename path until the next snapshot.
-        seen.extend(overlay_live_keys);
+        // Renames whose old path the event did not carry are resolved when the
+        // event is applied (see `moved_link_deletes`): the old path is already
+        // in the overlay's deleted set, so base entries only need path checks.
+        let base_started = Instant::now();
+        self.scan_base(
+            plan,
+            &base,
+            deleted_sources.as_slice(),
+            &mut blocked_paths,
+            sink,
+            profile,
+        );
+        profile.base_scan_us = base_started.elapsed().as_micros() as u64;
+    }
 
-        // ParentIndex fast path: if query has a parent filter, get exact candidates from base
-        if let Some(ref parent_path) = plan.parent_filter() {
-            let candidates = base.parent_candidates(parent_path);
-            for key in candidates {
-                if !seen.insert(key) {
-                    continue;
+    /// 按分区依次扇出；全局 `blocked_paths` 负责跨分区按路径合并去重。
+    #[allow(clippy::too_many_arguments)]
+    fn scan_base(
+        &self,
+        plan: &QueryPlan,
+        base: &PartitionedBase,
+        deleted_sources: &[Arc<PathArenaSet>],
+        blocked_paths: &mut PathArenaSet,
+        sink: &mut dyn ResultSink,
+        profile: &mut QueryProfile,
+    ) {
+        // 只有 `content:` 有区分度：候选直接来自内容索引的 trigram posting，不再逐分区全表回读。
+        if let Some(paths) = plan.content_candidates() {
+            profile.base_candidates += paths.len();
+            for (i, path) in paths.iter().enumerate() {
+                if i % SINK_CHECK_CHUNK == 0 && sink.is_full() {
+                    return;
                 }
-                let Some(meta) = base.get_meta(key) else {
+                let Some(part) = base.route(path).map(|i| &base.parts()[i]) else {
+                    continue;
+                };
+                let Some(meta) = part
+                    .data
+                    .docid_for_path(path)
+                    .and_then(|docid| part.data.meta_at(docid))
+                else {
                     continue;
                 };
-                let path_bytes = meta.path.as_os_str().as_encoded_bytes();
-                if blocked_paths.contains(path_bytes)
-                    || path_deleted_by_any(path_bytes, deleted_sources.as_slice())
-                {
+                if !admit_base_meta(&meta, deleted_sources, blocked_paths) {
                     continue;
                 }
-                let _ = blocked_paths.insert(path_bytes);
-                if plan.matches(&meta) {
-                    results.push(meta);
-                    if results.len() >= limit {
-                        return results;
-                    }
+                if plan.matches(&meta) && !sink.push(meta) {
+                    return;
                 }
             }
+            return;
         }
 
-        if self.query_layer(
-            plan,
-            base.as_ref(),
-            None,
-            deleted_sources.as_slice(),
-            &mut seen,
-            &mut blocked_paths,
-            &mut results,
-            limit,
-        ) {
-            return results;
-        }
+        for part in base.parts() {
+            if sink.is_full() {
+                return;
+            }
+            let data = part.data.as_ref();
+            // ParentIndex fast path: if query has a parent filter, get exact candidates from base
+            if let Some(ref parent_path) = plan.parent_filter() {
+                let candidates = data.parent_docids(parent_path);
+                profile.base_candidates += candidates.len();
+                for (i, docid) in candidates.into_iter().enumerate() {
+                    if i % SINK_CHECK_CHUNK == 0 && sink.is_full() {
+                        return;
+                    }
+                    let Some(meta) = data.meta_at(docid) else {
+                        continue;
+                    };
+                    if !admit_base_meta(&meta, deleted_sources, blocked_paths) {
+                        continue;
+                    }
+                    if plan.matches(&meta) && !sink.push(meta) {
+                        return;
+                    }
+                }
+            }
 
-        results
+            if self.query_layer(plan, data, deleted_sources, blocked_paths, sink, profile) {
+                return;
+            }
+        }
     }
 
-    fn overlay_meta_for_event(&self, ev: &EventRecord) -> Option<FileMeta> {
+    pub(super) fn overlay_meta_for_event(&self, ev: &EventRecord) -> Option<FileMeta> {
         let path = ev.best_path().map(super::normalize_path)?;
-        if let Ok(m) = std::fs::metadata(&path) {
-            let file_key = FileKey::from_path_and_metadata(&path, &m)?;
-            return Some(FileMeta {
-                file_key,
-                path,
-                size: m.len(),
-                mtime: m.modified().ok(),
-                ctime: m.created().ok(),
-                atime: m.accessed().ok(),
-            });
+        if let Some(meta) = FileMeta::from_path(&path) {
+            return Some(meta);
         }
 
         let fk = ev.id.as_file_key()?;
@@ -299,37 +535,28 @@ impl TieredIndex {
     fn query_layer(
         &self,
         plan: &QueryPlan,
-        layer: &dyn IndexLayer,
-        layer_deleted: Option<&PathArenaSet>,
+        layer: &BaseIndexData,
         deleted_sources: &[Arc<PathArenaSet>],
-        seen: &mut std::collections::HashSet<FileKey>,
         blocked_paths: &mut PathArenaSet,
-        results: &mut Vec<FileMeta>,
-        limit: usize,
+        sink: &mut dyn ResultSink,
+        profile: &mut QueryProfile,
     ) -> bool {
         for anchor in plan.anchors() {
-            for key in layer.query_keys(anchor.as_ref()) {
-                if !seen.insert(key) {
-                    continue;
+            let (docids, scan) = layer.query_docids_with_scan(anchor.as_ref());
+            profile.base_candidates += scan.candidates;
+            profile.full_scan |= scan.full_scan;
+            for (i, docid) in docids.into_iter().enumerate() {
+                if i % SINK_CHECK_CHUNK == 0 && sink.is_full() {
+                    return true;
                 }
-
-                let Some(meta) = layer.get_meta(key) else {
+                let Some(meta) = layer.meta_at(docid) else {
                     continue;
                 };
-                let path_bytes = meta.path.as_os_str().as_encoded_bytes();
-                if blocked_paths.contains(path_bytes)
-                    || layer_deleted.is_some_and(|paths| paths.contains(path_bytes))
-                    || path_deleted_by_any(path_bytes, deleted_sources)
-                {
+                if !admit_base_meta(&meta, deleted_sources, blocked_paths) {
                     continue;
                 }
-
-                let _ = blocked_paths.insert(path_bytes);
-                if plan.matches(&meta) {
-                    results.push(meta);
-                    if results.len() >= limit {
-                        return true;
-                    }
+                if plan.matches(&meta) && !sink.push(meta) {
+                    return true;
                 }
             }
         }
@@ -340,24 +567,26 @@ impl TieredIndex {
 
 fn collect_live_meta(
     meta: FileMeta,
-    layer_deleted: Option<&PathArenaSet>,
     deleted_sources: &[Arc<PathArenaSet>],
-    seen: &mut std::collections::HashSet<FileKey>,
     blocked_paths: &mut PathArenaSet,
     results: &mut Vec<FileMeta>,
 ) {
-    if !seen.insert(meta.file_key) {
-        return;
+    if admit_base_meta(&meta, deleted_sources, blocked_paths) {
+        results.push(meta);
     }
+}
 
+/// base 条目能否进入结果：按路径去重（同一 inode 的多条硬链接路径各自保留），
+/// 并排除被 overlay 删除或遮住的路径。通过时登记到 `blocked_paths`。
+fn admit_base_meta(
+    meta: &FileMeta,
+    deleted_sources: &[Arc<PathArenaSet>],
+    blocked_paths: &mut PathArenaSet,
+) -> bool {
     let path_bytes = meta.path.as_os_str().as_encoded_bytes();
-    if blocked_paths.contains(path_bytes)
-        || layer_deleted.is_some_and(|paths| paths.contains(path_bytes))
-        || path_deleted_by_any(path_bytes, deleted_sources)
-    {
-        return;
+    if blocked_paths.contains(path_bytes) || path_deleted_by_any(path_bytes, deleted_sources) {
+        return false;
     }
-
     let _ = blocked_paths.insert(path_bytes);
-    results.push(meta);
+    true
 }
@@ -1,9 +1,12 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use crate::core::FileMeta;
-use crate::query::dsl::CompiledQuery;
+use crate::query::dsl::{CompiledQuery, ContentLookup};
 use crate::query::matcher::Matcher;
 
+use super::content::ContentProbe;
+
 pub(super) enum QueryEvaluator {
     Legacy(Arc<dyn Matcher>),
     Compiled(CompiledQuery),
@@ -12,6 +15,7 @@ pub(super) enum QueryEvaluator {
 pub(super) struct QueryPlan {
     anchors: Vec<Arc<dyn Matcher>>,
     evaluator: QueryEvaluator,
+    content: Option<ContentProbe>,
 }
 
 impl QueryPlan {
@@ -19,13 +23,28 @@ impl QueryPlan {
         Self {
             anchors: compiled.anchors().to_vec(),
             evaluator: QueryEvaluator::Compiled(compiled),
+            content: None,
         }
     }
 
+    pub(super) fn with_content(mut self, content: Option<ContentProbe>) -> Self {
+        self.content = content;
+        self
+    }
+
     pub(super) fn legacy(matcher: Arc<dyn Matcher>) -> Self {
         Self {
             anchors: vec![matcher.clone()],
             evaluator: QueryEvaluator::Legacy(matcher),
+            content: None,
+        }
+    }
+
+    /// DSL 编译失败时的降级匹配器（可走 L1 热缓存）。
+    pub(super) fn legacy_matcher(&self) -> Option<&dyn Matcher> {
+        match &self.evaluator {
+            QueryEvaluator::Legacy(matcher) => Some(matcher.as_ref()),
+            QueryEvaluator::Compiled(_) => None,
         }
     }
 
@@ -36,10 +55,20 @@ impl QueryPlan {
     pub(super) fn matches(&self, meta: &FileMeta) -> bool {
         match &self.evaluator {
             QueryEvaluator::Legacy(matcher) => matcher.matches(&meta.path.to_string_lossy()),
-            QueryEvaluator::Compiled(compiled) => compiled.matches(meta),
+            QueryEvaluator::Compiled(compiled) => compiled
+                .matches_with_content(meta, self.content.as_ref().map(|c| c as &dyn ContentLookup)),
         }
     }
 
+    /// 路径 anchor 退化为 MatchAll 且 `content:` 为必要条件时，由内容索引给出的候选路径。
+    pub(super) fn content_candidates(&self) -> Option<Vec<PathBuf>> {
+        let QueryEvaluator::Compiled(compiled) = &self.evaluator else {
+            return None;
+        };
+        let (needle, _) = compiled.content_anchor()?;
+        self.content.as_ref()?.candidate_paths(needle)
+    }
+
     pub(super) fn parent_filter(&self) -> Option<String> {
         match &self.evaluator {
             QueryEvaluator::Compiled(compiled) => compiled.extract_parent_filter(),
@@ -0,0 +1,479 @@
+//! 启动修复：按优先级逐目录浅扫描（tiered 热目录 → roots → 索引中最近修改的目录 → 其余目录），
+//! 前台只花 `budget_ms`，剩余目录转到后台继续；进度写入续传游标，进程中途退出后下次启动接着跑。
+//!
+//! 待扫目录不预先展开：优先队列只有少量目录，其余按各分区的 DocId 顺序惰性遍历目录条目，
+//! 游标只记录优先队列与各分区的遍历位置。
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::core::FileKind;
+use crate::index::base_index::BaseIndexData;
+use crate::index::file_entry_v2::FileEntry;
+use crate::storage::snapshot::{
+    clear_repair_cursor, read_repair_cursor, write_repair_cursor, RepairCursor, RepairPosition,
+};
+use crate::util::pathbuf_from_encoded_vec;
+
+use super::{StartupRepairStats, TieredIndex};
+
+/// 游标落盘的最小间隔：待扫目录多时每批都写会放大 IO。
+const CURSOR_SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 优先队列中"最近修改的目录"的上限；其余目录留给分区遍历。
+const RECENT_DIRS: usize = 1024;
+
+/// 一次启动修复的进度：优先队列、各分区的遍历位置与累计统计。
+pub(super) struct RepairRun {
+    /// 优先目录；扫描中发现的新子目录插到队首
+    hot: VecDeque<PathBuf>,
+    /// 进入过优先队列的目录：分区遍历时跳过，新子目录只入队一次
+    seen: HashSet<PathBuf>,
+    walks: Vec<PartitionWalk>,
+    /// 每批扫描的目录数（批间检查预算、更新进度）
+    batch_dirs: usize,
+    snapshot_path: Option<PathBuf>,
+    stats: StartupRepairStats,
+    started_unix_secs: u64,
+    started: Instant,
+    last_saved: Instant,
+}
+
+/// 单个分区的目录遍历：持有规划时的分区数据，位置即该数据中的 DocId。
+///
+/// 修复期间分区被快照物化替换时，旧数据保留到遍历结束，避免 DocId 错位漏扫目录。
+struct PartitionWalk {
+    root: PathBuf,
+    data: Arc<BaseIndexData>,
+    next: usize,
+    /// 位置之后尚未遍历的目录条目数
+    remaining: usize,
+}
+
+impl PartitionWalk {
+    fn new(root: PathBuf, data: Arc<BaseIndexData>, next: usize) -> Self {
+        let remaining = data
+            .entries_by_key
+            .iter()
+            .enumerate()
+            .skip(next)
+            .filter(|(docid, entry)| is_live_dir(&data, *docid, entry))
+            .count();
+        Self {
+            root,
+            data,
+            next,
+            remaining,
+        }
+    }
+
+    fn next_dir(&mut self) -> Option<PathBuf> {
+        while let Some(entry) = self.data.entries_by_key.get(self.next) {
+            let docid = self.next;
+            self.next += 1;
+            if !is_live_dir(&self.data, docid, entry) {
+                continue;
+            }
+            self.remaining = self.remaining.saturating_sub(1);
+            if let Some(bytes) = self.data.path_table.resolve(entry.path_idx) {
+                return Some(pathbuf_from_encoded_vec(bytes));
+            }
+        }
+        None
+    }
+
+    /// 该目录已在分区数据中：遍历会（或已经）覆盖它。
+    fn covers(&self, dir: &Path) -> bool {
+        dir.starts_with(&self.root)
+            && self
+                .data
+                .path_table
+                .lookup(dir.as_os_str().as_encoded_bytes())
+                .is_some()
+    }
+
+    fn position(&self) -> RepairPosition {
+        RepairPosition {
+            root: self.root.clone(),
+            next_docid: self.next,
+            entries: self.data.entries_by_key.len(),
+        }
+    }
+}
+
+fn is_live_dir(data: &BaseIndexData, docid: usize, entry: &FileEntry) -> bool {
+    entry.kind() == FileKind::Dir && !data.tombstones.contains(docid as u32)
+}
+
+impl RepairRun {
+    fn new(
+        hot: Vec<PathBuf>,
+        walks: Vec<PartitionWalk>,
+        batch_dirs: usize,
+        snapshot_path: Option<&Path>,
+        stats: StartupRepairStats,
+        started_unix_secs: u64,
+    ) -> Self {
+        let now = Instant::now();
+        let mut seen = HashSet::with_capacity(hot.len());
+        let hot = hot.into_iter().filter(|d| seen.insert(d.clone())).collect();
+        Self {
+            hot,
+            seen,
+            walks,
+            batch_dirs: batch_dirs.max(1),
+            snapshot_path: snapshot_path.map(Path::to_path_buf),
+            stats,
+            started_unix_secs,
+            started: now,
+            last_saved: now,
+        }
+    }
+
+    fn resume(
+        cursor: RepairCursor,
+        walks: Vec<PartitionWalk>,
+        batch_dirs: usize,
+        snapshot_path: &Path,
+    ) -> Self {
+        let stats = StartupRepairStats {
+            ran: true,
+            resumed: true,
+            scanned: cursor.scanned,
+            changed: cursor.changed,
+            dirs_done: cursor.dirs_done,
+            ..StartupRepairStats::default()
+        };
+        Self::new(
+            cursor.hot,
+            walks,
+            batch_dirs,
+            Some(snapshot_path),
+            stats,
+            cursor.started_unix_secs,
+        )
+    }
+
+    /// 下一个待扫目录：先取优先队列，再沿分区遍历（跳过已在优先队列中出现过的）。
+    fn next_dir(&mut self) -> Option<PathBuf> {
+        if let Some(dir) = self.hot.pop_front() {
+            return Some(dir);
+        }
+        for walk in &mut self.walks {
+            while let Some(dir) = walk.next_dir() {
+                if !self.seen.contains(&dir) {
+                    return Some(dir);
+                }
+            }
+        }
+        None
+    }
+
+    /// 扫描中遇到的子目录：索引里没有的（停机期间新建）插到队首，紧接着扫描。
+    fn push_found(&mut self, found: Vec<PathBuf>) {
+        for dir in found.into_iter().rev() {
+            if !self.walks.iter().any(|w| w.covers(&dir)) && self.seen.insert(dir.clone()) {
+                self.hot.push_front(dir);
+            }
+        }
+    }
+
+    fn pending(&self) -> usize {
+        self.hot.len() + self.walks.iter().map(|w| w.remaining).sum::<usize>()
+    }
+
+    fn cursor(&self) -> RepairCursor {
+        RepairCursor {
+            hot: self.hot.iter().cloned().collect(),
+            positions: self.walks.iter().map(PartitionWalk::position).collect(),
+            dirs_done: self.stats.dirs_done,
+            scanned: self.stats.scanned,
+            changed: self.stats.changed,
+            started_unix_secs: self.started_unix_secs,
+        }
+    }
+
+    fn save_cursor(&mut self) {
+        self.last_saved = Instant::now();
+        let Some(path) = &self.snapshot_path else {
+            return;
+        };
+        if let Err(e) = write_repair_cursor(path, &self.cursor()) {
+            tracing::warn!("startup repair cursor write failed: {}", e);
+        }
+    }
+
+    fn clear_cursor(&self) {
+        if let Some(path) = &self.snapshot_path {
+            if let Err(e) = clear_repair_cursor(path) {
+                tracing::warn!("startup repair cursor cleanup failed: {}", e);
+            }
+        }
+    }
+}
+
+impl TieredIndex {
+    pub fn startup_repair_if_needed(
+        &self,
+        enabled: bool,
+        mode: &str,
+        max_dirs: usize,
+        budget_ms: u64,
+        force_rebuild_ratio: f32,
+    ) -> StartupRepairStats {
+        self.startup_repair_with_cursor(
+            enabled,
+            mode,
+            max_dirs,
+            budget_ms,
+            force_rebuild_ratio,
+            &[],
+            None,
+        )
+    }
+
+    /// 预算内的启动修复。
+    ///
+    /// - `max_dirs`：每批扫描的目录数（批间检查预算并更新 `/health` 进度）
+    /// - `budget_ms`：前台耗时上限，用尽即返回；剩余目录需调用 `spawn_startup_repair` 在后台继续
+    /// - `hot_dirs`：最先扫描的目录（tiered watch 的热目录）
+    /// - `snapshot_path`：给出时在快照目录旁持久化续传游标，存在未完成游标时无论 mode 都接着跑
+    #[allow(clippy::too_many_arguments)]
+    pub fn startup_repair_with_cursor(
+        &self,
+        enabled: bool,
+        mode: &str,
+        max_dirs: usize,
+        budget_ms: u64,
+        force_rebuild_ratio: f32,
+        hot_dirs: &[PathBuf],
+        snapshot_path: Option<&Path>,
+    ) -> StartupRepairStats {
+        let started = Instant::now();
+        let report = self.recovery_status().report;
+        let allowed = enabled && mode != "never";
+        let cursor = snapshot_path
+            .filter(|_| allowed)
+            .and_then(|p| match read_repair_cursor(p) {
+                Ok(cursor) => cursor,
+                Err(e) => {
+                    tracing::warn!("startup repair cursor unreadable, starting over: {}", e);
+                    None
+                }
+            });
+        let should_run = cursor.is_some()
+            || enabled
+                && match mode {
+                    "never" => false,
+                    "always" => true,
+                    "dirty-only" => report.requires_repair,
+                    other => {
+                        tracing::warn!("unknown startup_repair_mode={}, using dirty-only", other);
+                        report.requires_repair
+                    }
+                };
+
+        if !should_run {
+            if let (false, Some(p)) = (allowed, snapshot_path) {
+                let _ = clear_repair_cursor(p);
+            }
+            let stats = StartupRepairStats::default();
+            self.set_startup_repair_stats(stats.clone());
+            return stats;
+        }
+
+        // 规划（含遍历目录条目）同样计入前台预算。
+        let deadline = started + Duration::from_millis(budget_ms);
+        let mut run = match (cursor, snapshot_path) {
+            (Some(cursor), Some(path)) => {
+                tracing::info!(
+                    "resuming startup repair: dirs_done={} hot_dirs={}",
+                    cursor.dirs_done,
+                    cursor.hot.len()
+                );
+                let walks = self.repair_walks(&cursor.positions);
+                RepairRun::resume(cursor, walks, max_dirs, path)
+            }
+            _ => {
+                let stats = StartupRepairStats {
+                    ran: true,
+                    ..StartupRepairStats::default()
+                };
+                let walks = self.repair_walks(&[]);
+                RepairRun::new(
+                    self.plan_hot_dirs(hot_dirs, &walks, deadline),
+                    walks,
+                    max_dirs,
+                    snapshot_path,
+                    stats,
+                    unix_secs(),
+                )
+            }
+        };
+        let finished = self.run_repair(&mut run, Some(deadline));
+
+        // 升级判断只看前台样本：优先级最高的目录变化比例已能说明快照的可信度。
+        let changed_ratio = if run.stats.scanned == 0 {
+            0.0
+        } else {
+            run.stats.changed as f32 / run.stats.scanned as f32
+        };
+        run.stats.escalated = self.file_count() == 0 || changed_ratio > force_rebuild_ratio;
+        if !finished && run.stats.escalated {
+            // 全量重建会覆盖剩余目录，游标随之作废。
+            run.clear_cursor();
+            run.stats.in_progress = false;
+        }
+        let stats = run.stats.clone();
+        self.set_startup_repair_stats(stats.clone());
+        if run.stats.in_progress {
+            *self.repair_run.lock() = Some(run);
+        }
+        stats
+    }
+
+    /// 把前台预算内未完成的启动修复接到后台线程跑完。
+    pub fn spawn_startup_repair(self: &Arc<Self>) {
+        let Some(mut run) = self.repair_run.lock().take() else {
+            return;
+        };
+        let idx = self.clone();
+        std::thread::spawn(move || {
+            idx.run_repair(&mut run, None);
+            tracing::info!(
+                "startup repair finished in background: dirs={} scanned={} changed={}",
+                run.stats.dirs_done,
+                run.stats.scanned,
+                run.stats.changed
+            );
+        });
+    }
+
+    /// roots 之下各分区的目录遍历；`positions` 中条目数与当前分区一致的从记录位置继续，
+    /// 其余（分区已重建或新增）从头开始。
+    fn repair_walks(&self, positions: &[RepairPosition]) -> Vec<PartitionWalk> {
+        let roots = self.roots();
+        let base = self.base.load_full();
+        base.parts()
+            .iter()
+            .filter(|p| roots.iter().any(|r| p.root().starts_with(r)))
+            .map(|p| {
+                let next = positions
+                    .iter()
+                    .find(|pos| pos.root == p.root() && pos.entries == p.data.entries_by_key.len())
+                    .map_or(0, |pos| pos.next_docid);
+                PartitionWalk::new(p.root().to_path_buf(), p.data.clone(), next)
+            })
+            .collect()
+    }
+
+    /// 优先队列：热目录 → roots → L2 中的目录 → 索引中最近修改的 `RECENT_DIRS` 个目录，
+    /// 只保留 roots 之下的。按条目类型与 mtime 列筛选，只解析入选目录的路径；
+    /// 到达 `deadline` 后不再挑选，剩余分区交给遍历。
+    fn plan_hot_dirs(
+        &self,
+        hot_dirs: &[PathBuf],
+        walks: &[PartitionWalk],
+        deadline: Instant,
+    ) -> Vec<PathBuf> {
+        let roots = self.roots();
+        let mut recent: BinaryHeap<Reverse<(i64, usize, usize)>> =
+            BinaryHeap::with_capacity(RECENT_DIRS + 1);
+        for (w, walk) in walks.iter().enumerate() {
+            if Instant::now() >= deadline {
+                break;
+            }
+            for (docid, entry) in walk.data.entries_by_key.iter().enumerate() {
+                if !is_live_dir(&walk.data, docid, entry) {
+                    continue;
+                }
+                recent.push(Reverse((entry.mtime_ns, w, docid)));
+                if recent.len() > RECENT_DIRS {
+                    recent.pop();
+                }
+            }
+        }
+        let recent = recent
+            .into_sorted_vec()
+            .into_iter()
+            .filter_map(|Reverse((_, w, docid))| {
+                let data = &walks[w].data;
+                let entry = data.entries_by_key.get(docid)?;
+                data.path_table
+                    .resolve(entry.path_idx)
+                    .map(pathbuf_from_encoded_vec)
+            });
+
+        hot_dirs
+            .iter()
+            .map(|d| super::normalize_path(d))
+            .chain(roots.iter().cloned())
+            .chain(self.l2.load_full().live_dir_paths())
+            .chain(recent)
+            .filter(|d| roots.iter().any(|r| d.starts_with(r)))
+            .collect()
+    }
+
+    /// 逐批扫描直到队列清空或到达 `deadline`；返回是否已全部完成。
+    fn run_repair(&self, run: &mut RepairRun, deadline: Option<Instant>) -> bool {
+        let roots = self.roots();
+        loop {
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                break;
+            }
+            let batch_dirs = run.batch_dirs;
+            let batch: Vec<PathBuf> = std::iter::from_fn(|| run.next_dir())
+                .take(batch_dirs)
+                .collect();
+            if batch.is_empty() {
+                break;
+            }
+            // 已删除或已移出 roots 的目录直接跳过（游标可能来自上一次运行）。
+            let dirs: Vec<&PathBuf> = batch
+                .iter()
+                .filter(|d| roots.iter().any(|r| d.starts_with(r)))
+                .filter(|d| {
+                    std::fs::symlink_metadata(d)
+                        .map(|m| m.is_dir())
+                        .unwrap_or(false)
+                })
+                .collect();
+            let mut found: Vec<PathBuf> = Vec::new();
+            let outcome = self.scan_dirs_collect(&dirs, Some(1), usize::MAX, Some(&mut found));
+            run.push_found(found);
+
+            run.stats.dirs_done += batch.len();
+            run.stats.scanned += outcome.scanned;
+            run.stats.changed += outcome.changed;
+            run.stats.dirs_pending = run.pending();
+            run.stats.in_progress = run.stats.dirs_pending > 0;
+            run.stats.elapsed_ms = run.started.elapsed().as_millis() as u64;
+            self.set_startup_repair_stats(run.stats.clone());
+            if run.last_saved.elapsed() >= CURSOR_SAVE_INTERVAL {
+                run.save_cursor();
+            }
+        }
+
+        run.stats.dirs_pending = run.pending();
+        let finished = run.stats.dirs_pending == 0;
+        run.stats.in_progress = !finished;
+        run.stats.elapsed_ms = run.started.elapsed().as_millis() as u64;
+        if finished {
+            run.clear_cursor();
+        } else {
+            run.save_cursor();
+        }
+        self.set_startup_repair_stats(run.stats.clone());
+        finished
+    }
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,206 @@
+//! 运行期 root 管理：不重启 daemon 增删索引根目录。
+//!
+//! - 新增 root：只扫描新子树，结果以 upsert 事件写入 WAL + DeltaBuffer，随下一次 flush 进入 base；
+//! - 移除 root：为该子树下所有可见条目生成 Delete 事件（tombstone），同样经 WAL 持久化，
+//!   并直接丢弃该 root 的 base 分区；
+//! - watcher 计划与配置文件的更新由调用方通过 `set_root_change_hook` 注册的回调完成。
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::core::{EventRecord, EventType, FileIdentifier};
+
+use super::{normalize_path, pathbuf_from_bytes, TieredIndex};
+
+/// root 增删完成后的回调（更新 watcher 计划、持久化配置等）。
+pub type RootChangeHook = Arc<dyn Fn(&RootChange) + Send + Sync>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RootChangeKind {
+    Added,
+    Removed,
+}
+
+impl RootChangeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RootChangeKind::Added => "added",
+            RootChangeKind::Removed => "removed",
+        }
+    }
+}
+
+/// 一次 root 增删的结果。
+#[derive(Clone, Debug)]
+pub struct RootChange {
+    pub kind: RootChangeKind,
+    pub root: PathBuf,
+    /// 变更后的完整 root 列表
+    pub roots: Vec<PathBuf>,
+    /// Added：扫描到的条目数；Removed：被 tombstone 的条目数
+    pub entries: usize,
+    pub elapsed_ms: u64,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum RootsError {
+    #[error("root must be an absolute path: {0}")]
+    NotAbsolute(PathBuf),
+    #[error("root is not a directory: {0}")]
+    NotADirectory(PathBuf),
+    #[error("{path} overlaps existing root {existing}")]
+    Overlaps { path: PathBuf, existing: PathBuf },
+    #[error("not a configured root: {0}")]
+    NotFound(PathBuf),
+    #[error("cannot remove the last root")]
+    LastRoot,
+    #[error("a full rebuild is in progress")]
+    RebuildInProgress,
+}
+
+impl TieredIndex {
+    /// 当前 root 列表快照。
+    pub fn roots(&self) -> Vec<PathBuf> {
+        self.roots.read().clone()
+    }
+
+    /// 注册 root 增删回调；回调在 `add_root` / `remove_root` 的调用线程上同步执行。
+    pub fn set_root_change_hook(&self, hook: RootChangeHook) {
+        *self.root_change_hook.lock() = Some(hook);
+    }
+
+    /// 运行期新增 root：不能与现有 root 重叠（相同、祖先或后代），随后递归扫描该子树。
+    ///
+    /// 同步执行且扫描不设上限，调用方应放到阻塞线程池。
+    pub fn add_root(&self, root: &Path) -> Result<RootChange, RootsError> {
+        let _update = self.roots_update.lock();
+        let start = Instant::now();
+        if !root.is_absolute() {
+            return Err(RootsError::NotAbsolute(root.to_path_buf()));
+        }
+        let root = normalize_path(root);
+        if !std::fs::metadata(&root).is_ok_and(|m| m.is_dir()) {
+            return Err(RootsError::NotADirectory(root));
+        }
+
+        let roots = {
+            let mut roots = self.roots.write();
+            if let Some(existing) = roots
+                .iter()
+                .find(|r| root.starts_with(r) || r.starts_with(&root))
+            {
+                return Err(RootsError::Overlaps {
+                    path: root,
+                    existing: existing.clone(),
+                });
+            }
+            roots.push(root.clone());
+            roots.clone()
+        };
+
+        let outcome = self.scan_dirs_with_depth(&[&root], None, usize::MAX);
+        tracing::info!(
+            "root added: {} ({} entries in {} ms)",
+            root.display(),
+            outcome.scanned,
+            outcome.elapsed_ms
+        );
+        let change = RootChange {
+            kind: RootChangeKind::Added,
+            root,
+            roots,
+            entries: outcome.scanned,
+            elapsed_ms: start.elapsed().as_millis() as u64,
+        };
+        self.notify_root_change(&change);
+        Ok(change)
+    }
+
+    /// 运行期移除 root：把该子树下所有可见条目（base / L2 / DeltaBuffer）标记为删除。
+    pub fn remove_root(&self, root: &Path) -> Result<RootChange, RootsError> {
+        let _update = self.roots_update.lock();
+        let start = Instant::now();
+        let root = normalize_path(root);
+
+        let roots = {
+            let mut roots = self.roots.write();
+            let Some(pos) = roots.iter().position(|r| r == &root) else {
+                return Err(RootsError::NotFound(root));
+            };
+            if roots.len() == 1 {
+                return Err(RootsError::LastRoot);
+            }
+            roots.remove(pos);
+            roots.clone()
+        };
+        self.l1.clear();
+
+        let removed = self.tombstone_paths(self.live_paths_under(&root));
+        // tombstone 事件照常写入 WAL/变更流；该 root 的分区整体丢弃，不必等下一次物化。
+        self.drop_partition(&root);
+
+        tracing::info!("root removed: {} ({} entries)", root.display(), removed);
+        let change = RootChange {
+            kind: RootChangeKind::Removed,
+            root,
+            roots,
+            entries: removed,
+            elapsed_ms: start.elapsed().as_millis() as u64,
+        };
+        self.notify_root_change(&change);
+        Ok(change)
+    }
+
+    /// 为给定路径生成 Delete 事件（分批经 WAL + DeltaBuffer），返回条目数。
+    pub(super) fn tombstone_paths(&self, paths: Vec<PathBuf>) -> usize {
+        let mut events: Vec<EventRecord> = Vec::with_capacity(paths.len().min(2048));
+        let mut removed = 0usize;
+        for (seq, path) in paths.into_iter().enumerate() {
+            events.push(EventRecord {
+                seq: seq as u64 + 1,
+                timestamp: std::time::SystemTime::now(),
+                event_type: EventType::Delete,
+                id: FileIdentifier::Path(path),
+                path_hint: None,
+            });
+            if events.len() >= 2048 {
+                removed += events.len();
+                self.apply_events_inner_drain(&mut events, true);
+            }
+        }
+        removed += events.len();
+        self.apply_events_inner_drain(&mut events, true);
+        removed
+    }
+
+    /// root 之下（不含 root 本身）所有可见条目的路径，按字典序排列。
+    ///
+    /// base 走路径表前缀区间 + ParentIndex，L2 / DeltaBuffer 只比较路径字节，均不做全量元数据遍历。
+    pub(super) fn live_paths_under(&self, root: &Path) -> Vec<PathBuf> {
+        let mut paths: HashSet<PathBuf> = HashSet::new();
+        paths.extend(self.base.load_full().paths_under(root));
+        paths.extend(self.l2.load_full().live_paths_under(root));
+        {
+            let db = self.delta_buffer.lock();
+            paths.retain(|p| !db.is_deleted(p.as_os_str().as_encoded_bytes()));
+            for path in db.upserted_paths() {
+                let path = pathbuf_from_bytes(path);
+                if path.starts_with(root) && path.as_path() != root {
+                    paths.insert(path);
+                }
+            }
+        }
+        let mut paths: Vec<PathBuf> = paths.into_iter().collect();
+        paths.sort();
+        paths
+    }
+
+    fn notify_root_change(&self, change: &RootChange) {
+        let hook = self.root_change_hook.lock().clone();
+        if let Some(hook) = hook {
+            hook(change);
+        }
+    }
+}
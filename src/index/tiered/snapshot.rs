@@ -2,10 +2,7 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::{Duration, UNIX_EPOCH};
 
-use crate::storage::snapshot::{
-    write_recovery_runtime_state, write_stable_v7_atomic, RecoveryRuntimeState,
-};
-use crate::storage::snapshot_v7::write_v7_snapshot_atomic;
+use crate::storage::snapshot::{write_recovery_runtime_state, RecoveryRuntimeState};
 use crate::storage::traits::StorageBackend;
 use crate::util::maybe_trim_rss;
 
@@ -20,7 +17,6 @@ impl TieredIndex {
         S: StorageBackend + 'static,
     {
         let idx = self.clone();
-        let store_for_sync = store.clone();
         let result = tokio::task::spawn_blocking(move || {
             let delta = idx.l2.load_full();
             let delta_dirty = delta.is_dirty();
@@ -31,8 +27,9 @@ impl TieredIndex {
             };
             let pending_flush_dirty = idx.pending_flush_events.load(Ordering::Relaxed) > 0
                 || idx.pending_flush_bytes.load(Ordering::Relaxed) > 0;
-            let unsnapshotted_base = idx.last_snapshot_time.load(Ordering::Relaxed) == 0
-                && idx.base.load().file_count() > 0;
+            let unsnapshotted_base = (idx.last_snapshot_time.load(Ordering::Relaxed) == 0
+                && idx.base.load().file_count() > 0)
+                || idx.has_unpersisted_partitions();
             if !delta_dirty && !overlay_dirty && !pending_flush_dirty && !unsnapshotted_base {
                 tracing::debug!("No delta/overlay changes, skipping flush");
                 idx.flush_requested.store(false, Ordering::Release);
@@ -52,22 +49,26 @@ impl TieredIndex {
                 None => 0,
             };
 
+            // 已登记但未回读的内容变更先落进内容索引，随本次快照一起持久化。
+            if idx.content_index_enabled() {
+                idx.drain_content_queue();
+            }
+
             // Snapshot is the materialization boundary: ordinary event batches
-            // update the delta path only, so the full visible BaseIndex is
-            // rebuilt on this cold path and then written as v7.
+            // update the delta path only, so partitions touched by the overlay
+            // are rebuilt on this cold path and then written as v7 segments.
             let base = idx.materialize_snapshot_base();
-            let v7_path = store_for_sync.path().with_extension("v7");
 
             // delta_buffer has been cleared by materialize_snapshot_base after
             // its content was folded into base.
             idx.flush_requested.store(false, Ordering::Release);
 
-            Some((base, v7_path, wal_seal_id))
+            Some((base, wal_seal_id))
         })
         .await
         .map_err(|e| anyhow::anyhow!("snapshot sync phase panicked: {}", e))?;
 
-        let (base, v7_path, wal_seal_id) = match result {
+        let (base, wal_seal_id) = match result {
             Some(v) => v,
             None => {
                 tokio::time::sleep(std::time::Duration::from_millis(100)).await;
@@ -75,36 +76,53 @@ impl TieredIndex {
             }
         };
 
-        // 写入 v7 快照（原子写：tmp + rename）
-        if let Err(e) = write_v7_snapshot_atomic(&v7_path, &base) {
-            tracing::warn!("v7 snapshot write failed: {}", e);
-        } else {
-            tracing::info!("v7 snapshot written to {:?}", v7_path);
-        }
-
-        if self.stable_snapshot_enabled.load(Ordering::Relaxed) {
-            if let Err(e) = write_stable_v7_atomic(store.path(), &base) {
-                tracing::warn!("stable v7 snapshot write failed: {}", e);
-            } else {
-                let state = RecoveryRuntimeState {
-                    last_clean_shutdown: false,
-                    last_snapshot_unix_secs: unix_secs(),
-                    last_wal_seal_id: wal_seal_id,
-                    last_startup_source: self.recovery_status().report.snapshot_source,
-                    last_recovery_mode: "snapshot".to_string(),
-                };
-                if let Err(e) = write_recovery_runtime_state(store.path(), &state) {
-                    tracing::warn!("recovery runtime state write failed: {}", e);
+        // 内容索引随快照落盘：压实后克隆一份再写，段写入与 fsync 期间不持锁。
+        let content_snapshot = self.content_index_enabled().then(|| {
+            let mut ci = self.content_index.write();
+            ci.compact();
+            ci.clone()
+        });
+        let content = content_snapshot.as_ref();
+
+        // 只重写变化过的分区段（原子写：tmp + rename），最后替换 MANIFEST。
+        let keep_prev = self.stable_snapshot_enabled.load(Ordering::Relaxed);
+        let persisted = match self.persist_partitions(store.path(), &base, content, keep_prev) {
+            Err(e) => {
+                // 段未落盘：保留 WAL，下一轮快照按 generation 重试。
+                tracing::warn!("partition snapshot write failed: {}", e);
+                false
+            }
+            Ok(written) => {
+                tracing::info!(
+                    "partition snapshot written: {}/{} segments rewritten",
+                    written,
+                    base.parts().len()
+                );
+                if keep_prev {
+                    let state = RecoveryRuntimeState {
+                        last_clean_shutdown: false,
+                        last_snapshot_unix_secs: unix_secs(),
+                        last_wal_seal_id: wal_seal_id,
+                        last_startup_source: self.recovery_status().report.snapshot_source,
+                        last_recovery_mode: "snapshot".to_string(),
+                    };
+                    if let Err(e) = write_recovery_runtime_state(store.path(), &state) {
+                        tracing::warn!("recovery runtime state write failed: {}", e);
+                    }
                 }
-                tracing::info!("stable v7 snapshot written for recovery");
+                true
             }
-        }
+        };
+
+        drop(content_snapshot);
 
         self.l1.clear();
-        if let Some(w) = self.wal.lock().clone() {
-            let _ = w.cleanup_sealed_up_to(wal_seal_id);
+        if persisted {
+            if let Some(w) = self.wal.lock().clone() {
+                let _ = w.cleanup_sealed_up_to(wal_seal_id);
+            }
+            self.record_snapshot_success();
         }
-        self.record_snapshot_success();
         self.reset_pending_flush_batch();
 
         // snapshot/flush 是临时分配大户；完成后尝试回吐。
@@ -198,7 +216,7 @@ impl TieredIndex {
     }
 }
 
-fn unix_secs() -> u64 {
+pub(super) fn unix_secs() -> u64 {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map(|d| d.as_secs())
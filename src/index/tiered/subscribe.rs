@@ -0,0 +1,395 @@
+//! 实时查询订阅：事件批次应用后交给订阅工作线程，对各订阅的 `CompiledQuery` 增量求值并推送差异。
+//!
+//! 事件管线侧只把事件登记进 hub 的待处理队列；stat、`content:` 回读与匹配都在工作线程完成。
+//! 推送只做 `try_send`：订阅者消费跟不上（或待处理事件积压过多）时标记 lagged 并丢弃后续差异，
+//! 消费侧随即收到 `SubscriptionUpdate::Lagged`（已排队的旧差异一并丢弃），据此重新查询、
+//! 下发全量，因此慢客户端永远不会阻塞索引写入。
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+
+use parking_lot::{Mutex, RwLock};
+use tokio::sync::{mpsc, Notify};
+
+use crate::core::{EventRecord, EventType, FileMeta};
+use crate::query::dsl::{compile_query, CompiledQuery, ContentLookup, QueryCompileError};
+
+use super::TieredIndex;
+
+/// 默认最大并发订阅数。
+pub const DEFAULT_MAX_SUBSCRIBERS: usize = 64;
+
+/// 单个订阅的待发送差异上限；超出即进入 lagged。
+const SUBSCRIPTION_QUEUE_CAPACITY: usize = 1024;
+
+/// 工作线程待处理事件上限；超出时全部订阅进入 lagged，由客户端重新查询。
+const PENDING_EVENTS_CAPACITY: usize = 64 * 1024;
+
+/// 推送给订阅者的一条差异。
+///
+/// 订阅建立与初始查询之间存在竞态窗口：消费者应把 `Added` 视为 upsert、
+/// 对未知路径的 `Removed` 忽略即可。
+#[derive(Clone, Debug)]
+pub enum SubscriptionUpdate {
+    Added(FileMeta),
+    Updated(FileMeta),
+    Removed(PathBuf),
+    /// 积压导致差异被丢弃：此前排队的差异已清空，调用方应重新查询并 `reset_visible`。
+    Lagged,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum SubscribeError {
+    #[error(transparent)]
+    Query(#[from] QueryCompileError),
+    #[error("too many subscribers (limit {0})")]
+    TooManySubscribers(usize),
+}
+
+struct Subscriber {
+    query: CompiledQuery,
+    tx: mpsc::Sender<SubscriptionUpdate>,
+    /// 客户端当前视图中的路径：决定命中是 add 还是 update、删除是否需要推送
+    /// 有序集合：目录消失时按前缀区间移除其下的可见路径
+    visible: Mutex<BTreeSet<PathBuf>>,
+    lagged: AtomicBool,
+    /// 进入 lagged 时唤醒消费侧：差异通道可能已满或为空，不能靠它送达
+    lag_notify: Notify,
+}
+
+/// 一个事件在订阅视角下的变化：旧路径消失 / 新路径（带最新元数据）出现。
+struct Change {
+    gone: Option<PathBuf>,
+    present: Option<FileMeta>,
+}
+
+impl Subscriber {
+    fn mark_lagged(&self) {
+        if !self.lagged.swap(true, Ordering::AcqRel) {
+            self.lag_notify.notify_one();
+        }
+    }
+
+    fn apply(&self, changes: &[Change], content: Option<&dyn ContentLookup>) -> bool {
+        if self.lagged.load(Ordering::Acquire) {
+            return true;
+        }
+        let mut out = Vec::new();
+        {
+            let mut visible = self.visible.lock();
+            for change in changes {
+                if let Some(path) = &change.gone {
+                    if visible.remove(path) {
+                        out.push(SubscriptionUpdate::Removed(path.clone()));
+                    }
+                    // 目录删除 / 移出 roots：其下可见的后代一并移除（路径有序，后代紧随其后）。
+                    let descendants: Vec<PathBuf> = visible
+                        .range::<PathBuf, _>((
+                            std::ops::Bound::Excluded(path.clone()),
+                            std::ops::Bound::Unbounded,
+                        ))
+                        .take_while(|p| p.starts_with(path))
+                        .cloned()
+                        .collect();
+                    for child in descendants {
+                        visible.remove(&child);
+                        out.push(SubscriptionUpdate::Removed(child));
+                    }
+                }
+                let Some(meta) = &change.present else {
+                    continue;
+                };
+                if self.query.matches_with_content(meta, content) {
+                    if visible.insert(meta.path.clone()) {
+                        out.push(SubscriptionUpdate::Added(meta.clone()));
+                    } else {
+                        out.push(SubscriptionUpdate::Updated(meta.clone()));
+                    }
+                } else if visible.remove(&meta.path) {
+                    out.push(SubscriptionUpdate::Removed(meta.path.clone()));
+                }
+            }
+        }
+        for update in out {
+            match self.tx.try_send(update) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    self.mark_lagged();
+                    break;
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => return false,
+            }
+        }
+        true
+    }
+}
+
+pub(super) struct SubscriptionHub {
+    subscribers: RwLock<HashMap<u64, Arc<Subscriber>>>,
+    next_id: AtomicU64,
+    max_subscribers: AtomicUsize,
+    /// 所属索引：工作线程经此 stat / 查子树，不让 hub 延长索引的生命周期
+    index: Mutex<Weak<TieredIndex>>,
+    /// apply 路径登记、工作线程取走的事件
+    pending: Mutex<Vec<EventRecord>>,
+    /// 同一时刻只有一个批次在求值（工作线程或 `drain`），保证差异按事件顺序送达
+    apply: Mutex<()>,
+    running: AtomicBool,
+}
+
+impl Default for SubscriptionHub {
+    fn default() -> Self {
+        Self {
+            subscribers: RwLock::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            max_subscribers: AtomicUsize::new(DEFAULT_MAX_SUBSCRIBERS),
+            index: Mutex::new(Weak::new()),
+            pending: Mutex::new(Vec::new()),
+            apply: Mutex::new(()),
+            running: AtomicBool::new(false),
+        }
+    }
+}
+
+impl SubscriptionHub {
+    fn remove(&self, id: u64) {
+        self.subscribers.write().remove(&id);
+    }
+
+    fn snapshot(&self) -> Vec<(u64, Arc<Subscriber>)> {
+        self.subscribers
+            .read()
+            .iter()
+            .map(|(id, s)| (*id, s.clone()))
+            .collect()
+    }
+
+    /// 工作线程已在运行时只登记，由它在下一轮取走。
+    fn spawn_worker(self: &Arc<Self>) {
+        if self.running.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let hub = self.clone();
+        std::thread::spawn(move || loop {
+            hub.drain();
+            hub.running.store(false, Ordering::Release);
+            // 清标志与新登记之间的竞态：有新事件且抢回标志则继续。
+            if hub.pending.lock().is_empty() || hub.running.swap(true, Ordering::AcqRel) {
+                break;
+            }
+        });
+    }
+
+    fn drain(&self) {
+        let _apply = self.apply.lock();
+        loop {
+            let events = std::mem::take(&mut *self.pending.lock());
+            if events.is_empty() {
+                return;
+            }
+            let Some(index) = self.index.lock().upgrade() else {
+                return;
+            };
+            index.evaluate_subscriptions(&events);
+        }
+    }
+}
+
+/// 订阅句柄：drop 即退订。
+pub struct Subscription {
+    id: u64,
+    hub: Arc<SubscriptionHub>,
+    subscriber: Arc<Subscriber>,
+    rx: mpsc::Receiver<SubscriptionUpdate>,
+}
+
+impl Subscription {
+    /// 下一条差异；订阅被移除时返回 None。
+    ///
+    /// 进入 lagged 后（无论差异通道是满是空）下一次调用必定返回 `Lagged`，
+    /// 并丢弃此前排队的旧差异。
+    pub async fn recv(&mut self) -> Option<SubscriptionUpdate> {
+        loop {
+            if let Some(lagged) = self.take_lagged() {
+                return Some(lagged);
+            }
+            tokio::select! {
+                biased;
+                _ = self.subscriber.lag_notify.notified() => continue,
+                update = self.rx.recv() => return update,
+            }
+        }
+    }
+
+    pub fn try_recv(&mut self) -> Option<SubscriptionUpdate> {
+        self.take_lagged().or_else(|| self.rx.try_recv().ok())
+    }
+
+    /// 清除 lagged 并丢弃排队的差异：它们早于即将执行的全量查询，转发出去只会覆盖新结果。
+    fn take_lagged(&mut self) -> Option<SubscriptionUpdate> {
+        if !self.subscriber.lagged.swap(false, Ordering::AcqRel) {
+            return None;
+        }
+        while self.rx.try_recv().is_ok() {}
+        Some(SubscriptionUpdate::Lagged)
+    }
+
+    /// 以一次完整查询的结果重置客户端视图（初始结果集 / lagged 之后）。
+    pub fn reset_visible(&self, paths: impl IntoIterator<Item = PathBuf>) {
+        *self.subscriber.visible.lock() = paths.into_iter().collect();
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.hub.remove(self.id);
+    }
+}
+
+impl TieredIndex {
+    /// 注册一个实时订阅。返回后即开始累积差异；调用方随后执行初始查询并 `reset_visible`。
+    pub fn subscribe(self: &Arc<Self>, query: &str) -> Result<Subscription, SubscribeError> {
+        let compiled = compile_query(query)?;
+        let hub = &self.subscriptions;
+        let max = hub.max_subscribers.load(Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel(SUBSCRIPTION_QUEUE_CAPACITY);
+        let subscriber = Arc::new(Subscriber {
+            query: compiled,
+            tx,
+            visible: Mutex::new(BTreeSet::new()),
+            lagged: AtomicBool::new(false),
+            lag_notify: Notify::new(),
+        });
+
+        let id = hub.next_id.fetch_add(1, Ordering::Relaxed);
+        {
+            let mut subs = hub.subscribers.write();
+            if subs.len() >= max {
+                return Err(SubscribeError::TooManySubscribers(max));
+            }
+            subs.insert(id, subscriber.clone());
+        }
+        *hub.index.lock() = Arc::downgrade(self);
+        Ok(Subscription {
+            id,
+            hub: hub.clone(),
+            subscriber,
+            rx,
+        })
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.subscriptions.subscribers.read().len()
+    }
+
+    pub fn set_max_subscribers(&self, max: usize) {
+        self.subscriptions
+            .max_subscribers
+            .store(max, Ordering::Relaxed);
+    }
+
+    /// 在当前线程处理完已登记的订阅事件（测试用）。
+    pub fn drain_subscriptions(&self) {
+        self.subscriptions.drain();
+    }
+
+    /// apply 路径：有订阅时只登记事件，求值交给工作线程。
+    pub(super) fn notify_subscribers(&self, events: &[EventRecord]) {
+        let hub = &self.subscriptions;
+        if hub.subscribers.read().is_empty() {
+            return;
+        }
+        {
+            let mut pending = hub.pending.lock();
+            if pending.len() + events.len() > PENDING_EVENTS_CAPACITY {
+                pending.clear();
+                drop(pending);
+                for (_, sub) in hub.snapshot() {
+                    sub.mark_lagged();
+                }
+                return;
+            }
+            pending.extend_from_slice(events);
+        }
+        hub.spawn_worker();
+    }
+
+    /// 事件批次的订阅求值：每个事件只 stat 一次，所有订阅共享结果。
+    fn evaluate_subscriptions(&self, events: &[EventRecord]) {
+        let subs = self.subscriptions.snapshot();
+        if subs.is_empty() {
+            return;
+        }
+
+        let mut changes = Vec::with_capacity(events.len());
+        for ev in events {
+            let (gone, current) = match &ev.event_type {
+                EventType::Delete => (ev.best_path().map(|p| p.to_path_buf()), None),
+                EventType::Rename {
+                    from,
+                    from_path_hint,
+                } => (
+                    from_path_hint
+                        .clone()
+                        .or_else(|| from.as_path().map(|p| p.to_path_buf())),
+                    ev.best_path(),
+                ),
+                EventType::Create | EventType::Modify => (None, ev.best_path()),
+            };
+            let present = current.and_then(|_| self.overlay_meta_for_event(ev));
+            // Create/Modify 时文件已不存在：按删除处理
+            let gone = match (&gone, &present, current) {
+                (None, None, Some(p)) => Some(p.to_path_buf()),
+                _ => gone,
+            };
+            let moved_dir = match (&ev.event_type, &gone, &present) {
+                (EventType::Rename { .. }, Some(from), Some(meta)) if meta.kind.is_dir() => {
+                    Some((from.clone(), meta.path.clone()))
+                }
+                _ => None,
+            };
+            changes.push(Change { gone, present });
+            if let Some((from, to)) = moved_dir {
+                self.push_moved_children(&from, &to, &mut changes);
+            }
+        }
+
+        let mut closed = Vec::new();
+        for (id, sub) in subs {
+            let probe = self.content_probe_for(&sub.query);
+            let content = probe.as_ref().map(|c| c as &dyn ContentLookup);
+            if !sub.apply(&changes, content) {
+                closed.push(id);
+            }
+        }
+        for id in closed {
+            self.subscriptions.remove(id);
+        }
+    }
+
+    /// 目录改名不会为子项产生事件：按索引中旧/新目录下的子路径展开成逐条差异。
+    fn push_moved_children(&self, from: &Path, to: &Path, changes: &mut Vec<Change>) {
+        let mut rests: Vec<PathBuf> = Vec::new();
+        for (dir, paths) in [
+            (from, self.live_paths_under(from)),
+            (to, self.live_paths_under(to)),
+        ] {
+            rests.extend(
+                paths
+                    .iter()
+                    .filter_map(|p| p.strip_prefix(dir).ok())
+                    .map(Path::to_path_buf),
+            );
+        }
+        rests.sort();
+        rests.dedup();
+        for rest in rests {
+            changes.push(Change {
+                gone: Some(from.join(&rest)),
+                present: FileMeta::from_path(&to.join(&rest)),
+            });
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! 符号链接反向表：目标路径 → 指向它的链接。
+//!
+//! 链接条目的 size / 时间 / broken 取自目标，目标被删除、替换或改名时链接自身不会产生事件；
+//! 事件批次进入索引前按此表为受影响的链接补一条 Modify，让它们随目标一起刷新。
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::core::{EventRecord, EventType, FileIdentifier, FileKind, FileMeta, LinkTarget};
+use crate::index::l2_partition::PersistentIndex;
+use crate::index::partitioned_base::PartitionedBase;
+use crate::util::pathbuf_from_encoded_vec;
+
+use super::TieredIndex;
+
+/// 链式链接（a → b → c）逐级传播的最大轮数，与内核 MAXSYMLINKS 同量级。
+const MAX_LINK_HOPS: usize = 8;
+
+#[derive(Debug, Default)]
+pub(super) struct SymlinkTargets {
+    /// 解析后的目标绝对路径 → 链接路径
+    by_target: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    /// 链接路径 → 解析后的目标绝对路径
+    by_link: BTreeMap<PathBuf, PathBuf>,
+}
+
+impl SymlinkTargets {
+    pub(super) fn from_base(base: &PartitionedBase) -> Self {
+        let mut map = Self::default();
+        for part in base.parts() {
+            let data = &part.data;
+            // 只走链接目标侧列（DocId 升序，仅含符号链接），不为普通条目解析路径。
+            for (docid, target) in data.entries_by_key.link_targets() {
+                if data.tombstones.contains(docid) {
+                    continue;
+                }
+                let Some(entry) = data.entries_by_key.get(docid as usize) else {
+                    continue;
+                };
+                if entry.kind() != FileKind::Symlink {
+                    continue;
+                }
+                let Some(path_bytes) = data.path_table.resolve(entry.path_idx) else {
+                    continue;
+                };
+                let link = pathbuf_from_encoded_vec(path_bytes);
+                let target = LinkTarget {
+                    path: pathbuf_from_encoded_vec(target.to_vec()),
+                    broken: entry.is_broken(),
+                };
+                let resolved = target.resolve_from(&link);
+                map.insert(link, resolved);
+            }
+        }
+        map
+    }
+
+    /// 尚未物化进基础索引的 L2 条目（事件期间新建的链接）。
+    fn note_l2(&mut self, l2: &PersistentIndex) {
+        l2.for_each_live_meta(|meta| {
+            if meta.target.is_some() {
+                self.note_meta(&meta);
+            }
+        });
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.by_link.is_empty()
+    }
+
+    /// 按条目元数据登记（符号链接）或注销（其他类型）该路径。
+    pub(super) fn note_meta(&mut self, meta: &FileMeta) {
+        match &meta.target {
+            Some(target) => self.insert(meta.path.clone(), target.resolve_from(&meta.path)),
+            None => self.remove(&meta.path),
+        }
+    }
+
+    fn insert(&mut self, link: PathBuf, target: PathBuf) {
+        if self.by_link.get(&link) == Some(&target) {
+            return;
+        }
+        self.remove(&link);
+        self.by_target
+            .entry(target.clone())
+            .or_default()
+            .insert(link.clone());
+        self.by_link.insert(link, target);
+    }
+
+    fn remove(&mut self, link: &Path) {
+        let Some(target) = self.by_link.remove(link) else {
+            return;
+        };
+        if let Some(links) = self.by_target.get_mut(&target) {
+            links.remove(link);
+            if links.is_empty() {
+                self.by_target.remove(&target);
+            }
+        }
+    }
+
+    /// 注销位于 `dir`（含自身）之下的全部链接。
+    fn remove_under(&mut self, dir: &Path) {
+        let links: Vec<PathBuf> = self
+            .by_link
+            .range(dir.to_path_buf()..)
+            .take_while(|(link, _)| link.starts_with(dir))
+            .map(|(link, _)| link.clone())
+            .collect();
+        for link in links {
+            self.remove(&link);
+        }
+    }
+
+    /// 目标等于 `path` 或位于其下的链接（目录被替换时其中的目标一并失效）。
+    fn dependents(&self, path: &Path, out: &mut Vec<PathBuf>) {
+        for (_, links) in self
+            .by_target
+            .range(path.to_path_buf()..)
+            .take_while(|(target, _)| target.starts_with(path))
+        {
+            out.extend(links.iter().cloned());
+        }
+    }
+}
+
+fn read_link_target(path: &Path) -> Option<LinkTarget> {
+    let meta = std::fs::symlink_metadata(path).ok()?;
+    if !meta.file_type().is_symlink() {
+        return None;
+    }
+    Some(LinkTarget {
+        path: std::fs::read_link(path).ok()?,
+        broken: false,
+    })
+}
+
+impl TieredIndex {
+    /// 替换 / 丢弃分区后，按新的分区与 L2 重建反向表。
+    pub(super) fn reindex_symlinks(&self) {
+        let mut map = SymlinkTargets::from_base(&self.base.load());
+        map.note_l2(&self.l2.load());
+        *self.symlinks.lock() = map;
+    }
+
+    /// 先按事件路径更新反向表，再为目标受影响的链接追加 Modify 事件。
+    pub(super) fn expand_symlink_events(&self, events: &mut Vec<EventRecord>) {
+        let mut map = self.symlinks.lock();
+        let mut touched: Vec<PathBuf> = Vec::new();
+        for ev in events.iter() {
+            if let EventType::Rename {
+                from,
+                from_path_hint,
+            } = &ev.event_type
+            {
+                if let Some(from) = from_path_hint.as_deref().or_else(|| from.as_path()) {
+                    map.remove_under(from);
+                    touched.push(from.to_path_buf());
+                }
+            }
+            let Some(path) = ev.best_path() else {
+                continue;
+            };
+            // 只有 Create / Rename 会让路径变成（或不再是）链接：Delete 直接注销，
+            // Modify 不改变条目类型，都无需 lstat。
+            match ev.event_type {
+                EventType::Delete => map.remove_under(path),
+                EventType::Modify => {}
+                EventType::Create | EventType::Rename { .. } => match read_link_target(path) {
+                    Some(target) => {
+                        let resolved = target.resolve_from(path);
+                        map.insert(path.to_path_buf(), resolved);
+                    }
+                    None => map.remove(path),
+                },
+            }
+            touched.push(path.to_path_buf());
+        }
+        if map.is_empty() {
+            return;
+        }
+
+        let mut queued: BTreeSet<PathBuf> = events
+            .iter()
+            .filter_map(|ev| ev.best_path().map(Path::to_path_buf))
+            .collect();
+        let mut seq = events.iter().map(|ev| ev.seq).max().unwrap_or(0);
+        for _ in 0..MAX_LINK_HOPS {
+            let mut links = Vec::new();
+            for path in &touched {
+                map.dependents(path, &mut links);
+            }
+            touched.clear();
+            for link in links {
+                if !queued.insert(link.clone()) {
+                    continue;
+                }
+                seq = seq.wrapping_add(1);
+                events.push(EventRecord {
+                    seq,
+                    timestamp: std::time::SystemTime::now(),
+                    event_type: EventType::Modify,
+                    id: FileIdentifier::Path(link.clone()),
+                    path_hint: None,
+                });
+                touched.push(link);
+            }
+            if touched.is_empty() {
+                break;
+            }
+        }
+    }
+
+    /// 扫描路径（fast-sync / scan_dirs_immediate）已带 lstat 元数据：登记后返回需随之刷新的链接事件。
+    pub(super) fn symlink_events_for_metas(&self, metas: &[FileMeta]) -> Vec<EventRecord> {
+        let mut links = Vec::new();
+        {
+            let mut map = self.symlinks.lock();
+            for meta in metas {
+                map.note_meta(meta);
+            }
+            if map.is_empty() {
+                return Vec::new();
+            }
+            for meta in metas {
+                map.dependents(&meta.path, &mut links);
+            }
+        }
+        let scanned: BTreeSet<&Path> = metas.iter().map(|m| m.path.as_path()).collect();
+        links.sort();
+        links.dedup();
+        let mut events: Vec<EventRecord> = links
+            .into_iter()
+            .filter(|link| !scanned.contains(link.as_path()))
+            .map(|link| EventRecord {
+                seq: 0,
+                timestamp: std::time::SystemTime::now(),
+                event_type: EventType::Modify,
+                id: FileIdentifier::Path(link),
+                path_hint: None,
+            })
+            .collect();
+        if !events.is_empty() {
+            self.expand_symlink_events(&mut events);
+        }
+        events
+    }
+}
@@ -1,20 +1,59 @@
-use std::path::PathBuf;
-use std::sync::atomic::Ordering;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Instant, UNIX_EPOCH};
 
+use crate::core::partition::route_path;
+use crate::core::path_rules::PathRules;
 use crate::core::{EventRecord, EventType, FileIdentifier, FileKey, FileMeta, Task};
+use crate::event::ignore_filter::IgnoreFilter;
 use crate::event::sync::DirtyScope;
-use crate::index::l2_partition::{mtime_to_ns, PersistentIndex};
+use crate::index::dir_mtimes::DirCheck;
+use crate::index::file_entry_v2::time_to_ns;
+use crate::index::l2_partition::PersistentIndex;
+use crate::index::partitioned_base::PartitionedBase;
 use crate::index::PathFreshness;
 use crate::util::{maybe_trim_rss, path_has_excluded_component};
 
-use super::{pathbuf_from_bytes, ScanOutcome, StartupRepairStats, TieredIndex, REBUILD_COOLDOWN};
+use super::{pathbuf_from_bytes, ScanOutcome, TieredIndex, REBUILD_COOLDOWN};
+
+/// 忽略规则变化后待对账的目录：事件循环只登记，后台线程合并后逐个对账。
+#[derive(Default)]
+pub(super) struct IgnoreReconcileQueue {
+    dirs: parking_lot::Mutex<BTreeSet<PathBuf>>,
+    /// 最近一次登记时的规则（同一份 matcher 缓存），对账按它判定
+    filter: parking_lot::Mutex<Option<IgnoreFilter>>,
+    /// 同一时刻只有一轮对账（后台线程或 `drain_ignore_reconcile`）
+    apply: parking_lot::Mutex<()>,
+    running: AtomicBool,
+}
 
+impl IgnoreReconcileQueue {
+    /// 取出全部待对账目录；祖先已在队列中的子目录并入祖先的那一次对账。
+    fn take_coalesced(&self) -> Vec<PathBuf> {
+        let dirs = std::mem::take(&mut *self.dirs.lock());
+        let mut covered: Vec<PathBuf> = Vec::with_capacity(dirs.len());
+        for dir in dirs {
+            if !covered.iter().any(|c| dir.starts_with(c)) {
+                covered.push(dir);
+            }
+        }
+        covered
+    }
+}
+
+/// 自 roots 向下遍历目录，按 mtime 是否晚于 `cutoff_ns` 判定变化。
+///
+/// 给出 `known` 时按基础索引的目录 mtime 表剪枝：mtime 与记录一致的目录直接沿索引中的
+/// 子目录下行、不做 `read_dir`；与记录不一致（包括 mtime 回退）的目录一律视为变化。
+#[allow(clippy::too_many_arguments)]
 fn visit_dirs_since(
     roots: &[PathBuf],
     ignore_prefixes: &[PathBuf],
     exclude_dirs: &[String],
+    path_rules: &PathRules,
+    known: Option<&PartitionedBase>,
     cutoff_ns: u64,
     log_prefix: &str,
     mut on_dir: impl FnMut(&std::path::Path, bool) -> bool,
@@ -41,6 +80,12 @@ fn visit_dirs_since(
         if path_has_excluded_component(&dir, exclude_dirs) {
             continue;
         }
+        let root = route_path(roots.iter().map(PathBuf::as_path), &dir).map(|i| &roots[i]);
+        if root.is_some_and(|r| r != &dir)
+            && path_rules.excludes_entry(root.map(PathBuf::as_path), &dir)
+        {
+            continue;
+        }
 
         let md = match std::fs::symlink_metadata(&dir) {
             Ok(m) => m,
@@ -50,14 +95,26 @@ fn visit_dirs_since(
             continue;
         }
 
-        let changed = if let Ok(modified) = md.modified() {
+        let mut changed = if let Ok(modified) = md.modified() {
             cutoff_ns == 0 || modified > cutoff
         } else {
             true // 保守地认为已变化（部分文件系统不支持 mtime）
         };
+        let mut indexed_subdirs = None;
+        if let (Some(base), Ok(modified), false) = (known, md.modified(), cutoff_ns == 0) {
+            match base.check_dir(&dir, time_to_ns(Some(modified))) {
+                DirCheck::Changed => changed = true,
+                DirCheck::Unchanged(subdirs) if !changed => indexed_subdirs = Some(subdirs),
+                _ => {}
+            }
+        }
         if on_dir(&dir, changed) {
             return true;
         }
+        if let Some(subdirs) = indexed_subdirs {
+            stack.extend(subdirs);
+            continue;
+        }
 
         let rd = match std::fs::read_dir(&dir) {
             Ok(rd) => rd,
@@ -93,6 +150,8 @@ fn collect_dirs_changed_since(
     roots: &[PathBuf],
     ignore_prefixes: &[PathBuf],
     exclude_dirs: &[String],
+    path_rules: &PathRules,
+    known: Option<&PartitionedBase>,
     cutoff_ns: u64,
 ) -> Vec<PathBuf> {
     let mut out: Vec<PathBuf> = Vec::new();
@@ -100,6 +159,8 @@ fn collect_dirs_changed_since(
         roots,
         ignore_prefixes,
         exclude_dirs,
+        path_rules,
+        known,
         cutoff_ns,
         "fast-sync",
         |dir, changed| {
@@ -186,17 +247,18 @@ impl TieredIndex {
     pub(super) fn finish_rebuild(self: &Arc<Self>, new_l2: Arc<PersistentIndex>) -> bool {
         loop {
             let batch = {
+                let _swap = self.partition_swap.lock();
                 let mut st = self.rebuild_state.lock();
                 let mut db = self.delta_buffer.lock();
                 if db.is_empty() {
                     // 切换点：持锁判空 -> 原子切换，避免丢事件窗口。
                     self.l1.clear();
-                    let new_base = Arc::new(new_l2.to_base_index_data());
+                    let new_base = Arc::new(self.partitions_from_l2(new_l2.as_ref()));
                     self.base.store(new_base);
                     self.note_pending_flush_rebuild(new_l2.as_ref());
-                    self.l2.store(Arc::new(PersistentIndex::new_with_roots(
-                        self.roots.clone(),
-                    )));
+                    self.l2
+                        .store(Arc::new(PersistentIndex::new_with_roots(self.roots())));
+                    self.reindex_symlinks();
                     if !self.flush_requested.swap(true, Ordering::AcqRel) {
                         self.flush_notify.notify_one();
                     }
@@ -235,7 +297,7 @@ impl TieredIndex {
                 let mut sched = idx.scheduler.lock();
                 sched.adjust_parallelism();
                 sched.select_strategy(&Task::ColdBuild {
-                    total_dirs: idx.roots.len(),
+                    total_dirs: idx.roots().len(),
                 })
             };
 
@@ -244,9 +306,12 @@ impl TieredIndex {
                 reason,
                 strategy
             );
-            let new_l2 = Arc::new(PersistentIndex::new_with_roots(idx.roots.clone()));
-            idx.l3.full_build_with_strategy(&new_l2, strategy);
+            let roots = idx.roots();
+            let new_l2 = Arc::new(PersistentIndex::new_with_roots(roots.clone()));
+            idx.l3
+                .full_build_roots_with_strategy(roots, &new_l2, strategy);
             let again = idx.finish_rebuild(new_l2.clone());
+            idx.spawn_content_index_sync();
             tracing::warn!("Rebuild complete, triggering manual RSS trim...");
             maybe_trim_rss();
             tracing::warn!(
@@ -272,7 +337,7 @@ impl TieredIndex {
                 let mut sched = idx.scheduler.lock();
                 sched.adjust_parallelism();
                 sched.select_strategy(&Task::ColdBuild {
-                    total_dirs: idx.roots.len(),
+                    total_dirs: idx.roots().len(),
                 })
             };
 
@@ -280,9 +345,12 @@ impl TieredIndex {
                 "Starting background full build (strategy={:?})...",
                 strategy
             );
-            let new_l2 = Arc::new(PersistentIndex::new_with_roots(idx.roots.clone()));
-            idx.l3.full_build_with_strategy(&new_l2, strategy);
+            let roots = idx.roots();
+            let new_l2 = Arc::new(PersistentIndex::new_with_roots(roots.clone()));
+            idx.l3
+                .full_build_roots_with_strategy(roots, &new_l2, strategy);
             let again = idx.finish_rebuild(new_l2.clone());
+            idx.spawn_content_index_sync();
             tracing::warn!("Full build complete, triggering manual RSS trim...");
             maybe_trim_rss();
             tracing::info!(
@@ -334,17 +402,22 @@ impl TieredIndex {
         use std::collections::HashSet;
 
         let mut report = FastSyncReport::default();
+        let roots = self.roots();
+        let path_rules = self.path_rules();
+        let known = self.base.load_full();
 
         // 1) 计算需要对齐的目录集合
         let mut dirs: Vec<PathBuf> = match scope {
             DirtyScope::All { cutoff_ns } => collect_dirs_changed_since(
-                &self.roots,
+                &roots,
                 ignore_prefixes,
                 &self.exclude_dirs,
+                &path_rules,
+                Some(&known),
                 cutoff_ns,
             ),
             DirtyScope::Dirs { dirs, cutoff_ns } => {
-                let root_set: HashSet<_> = self.roots.iter().cloned().collect();
+                let root_set: HashSet<_> = roots.iter().cloned().collect();
                 let (root_dirs, leaf_dirs): (Vec<_>, Vec<_>) =
                     dirs.into_iter().partition(|d| root_set.contains(d));
 
@@ -354,6 +427,8 @@ impl TieredIndex {
                         &root_dirs,
                         ignore_prefixes,
                         &self.exclude_dirs,
+                        &path_rules,
+                        Some(&known),
                         effective_cutoff_ns,
                     )
                 } else {
@@ -372,6 +447,7 @@ impl TieredIndex {
                 .iter()
                 .any(|ig| !ig.as_os_str().is_empty() && d.starts_with(ig))
                 || path_has_excluded_component(d, &self.exclude_dirs)
+                || !self.path_allowed(d)
             {
                 return false;
             }
@@ -406,12 +482,7 @@ impl TieredIndex {
                 .git_ignore(self.ignore_enabled)
                 .git_global(self.ignore_enabled)
                 .git_exclude(self.ignore_enabled);
-            let exclude_dirs = self.exclude_dirs.clone();
-            if !exclude_dirs.is_empty() {
-                builder.filter_entry(move |entry| {
-                    !path_has_excluded_component(entry.path(), &exclude_dirs)
-                });
-            }
+            self.apply_walk_filters(&mut builder, &roots, &path_rules);
 
             for ent in builder.build() {
                 let ent = match ent {
@@ -428,7 +499,11 @@ impl TieredIndex {
                 let Some(ft) = ent.file_type() else {
                     continue;
                 };
-                if ft.is_dir() {
+                if !path_rules.includes_file(ent.path(), ft.is_dir()) {
+                    continue;
+                }
+                // 目录同样作为条目入索引；只有 root 本身不入索引。
+                if ent.depth() == 0 && roots.iter().any(|r| r == ent.path()) {
                     continue;
                 }
 
@@ -440,18 +515,11 @@ impl TieredIndex {
                         continue;
                     }
                 };
-                let Some(file_key) = FileKey::from_path_and_metadata(&path, &meta) else {
+                let Some(file_meta) = FileMeta::from_lstat(&path, &meta) else {
                     continue;
                 };
                 seq = seq.wrapping_add(1);
-                upsert_metas.push(FileMeta {
-                    file_key,
-                    path: path.clone(),
-                    size: meta.len(),
-                    mtime: meta.modified().ok(),
-                    ctime: meta.created().ok(),
-                    atime: meta.accessed().ok(),
-                });
+                upsert_metas.push(file_meta);
                 upsert_events.push(EventRecord {
                     seq,
                     timestamp: std::time::SystemTime::now(),
@@ -508,11 +576,47 @@ impl TieredIndex {
         report
     }
 
-    fn scan_dirs_with_depth(
+    /// walker 剪枝：`exclude_dirs` 目录名与 `exclude_globs`（相对路径按所属 root 计算）。
+    pub(super) fn apply_walk_filters(
+        &self,
+        builder: &mut ignore::WalkBuilder,
+        roots: &[PathBuf],
+        path_rules: &Arc<PathRules>,
+    ) {
+        let exclude_dirs = self.exclude_dirs.clone();
+        if exclude_dirs.is_empty() && path_rules.is_empty() {
+            return;
+        }
+        let roots = roots.to_vec();
+        let path_rules = path_rules.clone();
+        builder.filter_entry(move |entry| {
+            let path = entry.path();
+            if path_has_excluded_component(path, &exclude_dirs) {
+                return false;
+            }
+            let root = route_path(roots.iter().map(PathBuf::as_path), path).map(|i| &roots[i]);
+            root.is_some_and(|r| r == path)
+                || !path_rules.excludes_entry(root.map(PathBuf::as_path), path)
+        });
+    }
+
+    pub(super) fn scan_dirs_with_depth(
         &self,
         dirs: &[&PathBuf],
         max_depth: Option<usize>,
         max_entries_per_dir: usize,
+    ) -> ScanOutcome {
+        self.scan_dirs_collect(dirs, max_depth, max_entries_per_dir, None)
+    }
+
+    /// 同 `scan_dirs_with_depth`；给出 `subdirs` 时不收录各扫描目录自身，
+    /// 并把遇到的子目录交给调用方（启动修复据此逐层推进）。
+    pub(super) fn scan_dirs_collect(
+        &self,
+        dirs: &[&PathBuf],
+        max_depth: Option<usize>,
+        max_entries_per_dir: usize,
+        mut subdirs: Option<&mut Vec<PathBuf>>,
     ) -> ScanOutcome {
         let start = Instant::now();
 
@@ -521,6 +625,8 @@ impl TieredIndex {
         let mut scanned: usize = 0;
         let mut changed: usize = 0;
         let mut seq: u64 = 0;
+        let roots = self.roots();
+        let path_rules = self.path_rules();
 
         for dir in dirs {
             let mut dir_count = 0;
@@ -535,12 +641,7 @@ impl TieredIndex {
                 .git_ignore(self.ignore_enabled)
                 .git_global(self.ignore_enabled)
                 .git_exclude(self.ignore_enabled);
-            let exclude_dirs = self.exclude_dirs.clone();
-            if !exclude_dirs.is_empty() {
-                builder.filter_entry(move |entry| {
-                    !path_has_excluded_component(entry.path(), &exclude_dirs)
-                });
-            }
+            self.apply_walk_filters(&mut builder, &roots, &path_rules);
             for ent in builder.build() {
                 let ent = match ent {
                     Ok(e) => e,
@@ -556,7 +657,11 @@ impl TieredIndex {
                 let Some(ft) = ent.file_type() else {
                     continue;
                 };
-                if ft.is_dir() {
+                if !path_rules.includes_file(ent.path(), ft.is_dir()) {
+                    continue;
+                }
+                if ent.depth() == 0 && (subdirs.is_some() || roots.iter().any(|r| r == ent.path()))
+                {
                     continue;
                 }
                 if dir_count >= max_entries_per_dir {
@@ -576,25 +681,22 @@ impl TieredIndex {
                         continue;
                     }
                 };
-                let Some(file_key) = FileKey::from_path_and_metadata(&path, &meta) else {
+                let Some(file_meta) = FileMeta::from_lstat(&path, &meta) else {
                     continue;
                 };
-                let mtime = meta.modified().ok();
-                let mtime_ns = mtime_to_ns(mtime);
-                if self.path_freshness(&path, file_key, meta.len(), mtime_ns)
+                if let Some(out) = subdirs.as_deref_mut() {
+                    if file_meta.kind.is_dir() {
+                        out.push(path.clone());
+                    }
+                }
+                let mtime_ns = time_to_ns(file_meta.mtime);
+                if self.path_freshness(&path, file_meta.file_key, file_meta.size, mtime_ns)
                     != PathFreshness::Unchanged
                 {
                     changed += 1;
                 }
                 seq = seq.wrapping_add(1);
-                upsert_metas.push(FileMeta {
-                    file_key,
-                    path: path.clone(),
-                    size: meta.len(),
-                    mtime,
-                    ctime: meta.created().ok(),
-                    atime: meta.accessed().ok(),
-                });
+                upsert_metas.push(file_meta);
                 upsert_events.push(EventRecord {
                     seq,
                     timestamp: std::time::SystemTime::now(),
@@ -659,52 +761,82 @@ impl TieredIndex {
         (outcome.scanned, outcome.elapsed_ms)
     }
 
-    pub fn startup_repair_if_needed(
-        &self,
-        enabled: bool,
-        mode: &str,
-        max_dirs: usize,
-        _budget_ms: u64,
-        force_rebuild_ratio: f32,
-    ) -> StartupRepairStats {
-        let report = self.recovery_status().report;
-        let should_run = enabled
-            && match mode {
-                "never" => false,
-                "always" => true,
-                "dirty-only" => report.requires_repair,
-                other => {
-                    tracing::warn!("unknown startup_repair_mode={}, using dirty-only", other);
-                    report.requires_repair
-                }
-            };
+    /// 忽略文件变化后在后台对账这些目录（见 `reconcile_ignore_change`）。
+    ///
+    /// 对账期间再次登记的目录并入下一轮；同一子树的多次变化只对账一次。
+    pub fn spawn_ignore_reconcile(
+        self: &Arc<Self>,
+        dirs: impl IntoIterator<Item = PathBuf>,
+        filter: &IgnoreFilter,
+    ) {
+        let queue = &self.ignore_reconcile;
+        {
+            let mut pending = queue.dirs.lock();
+            pending.extend(dirs);
+            if pending.is_empty() {
+                return;
+            }
+        }
+        *queue.filter.lock() = Some(filter.clone());
+        if queue.running.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let idx = self.clone();
+        std::thread::spawn(move || loop {
+            idx.drain_ignore_reconcile();
+            let queue = &idx.ignore_reconcile;
+            queue.running.store(false, Ordering::Release);
+            // 清标志与新登记之间的竞态：有新目录且抢回标志则继续。
+            if queue.dirs.lock().is_empty() || queue.running.swap(true, Ordering::AcqRel) {
+                break;
+            }
+        });
+    }
 
-        if !should_run {
-            let stats = StartupRepairStats::default();
-            self.set_startup_repair_stats(stats.clone());
-            return stats;
+    /// 在当前线程对账已登记的全部目录（后台线程与测试用）。
+    pub fn drain_ignore_reconcile(&self) {
+        let _apply = self.ignore_reconcile.apply.lock();
+        loop {
+            let dirs = self.ignore_reconcile.take_coalesced();
+            if dirs.is_empty() {
+                return;
+            }
+            let Some(filter) = self.ignore_reconcile.filter.lock().clone() else {
+                return;
+            };
+            for dir in dirs {
+                self.reconcile_ignore_change(&dir, |p| filter.is_ignored(p));
+            }
         }
+    }
 
-        let roots = self
-            .roots
-            .iter()
-            .take(max_dirs.max(1))
-            .cloned()
-            .collect::<Vec<_>>();
-        let outcome = self.scan_dirs_immediate_outcome(&roots);
-        let changed_ratio = if outcome.scanned == 0 {
-            0.0
-        } else {
-            outcome.changed as f32 / outcome.scanned as f32
-        };
-        let stats = StartupRepairStats {
-            ran: true,
-            escalated: self.file_count() == 0 || changed_ratio > force_rebuild_ratio,
-            scanned: outcome.scanned,
-            changed: outcome.changed,
-            elapsed_ms: outcome.elapsed_ms,
-        };
-        self.set_startup_repair_stats(stats.clone());
-        stats
+    /// 某目录的忽略规则变化后对其子树对账：现在被忽略的条目标记删除，
+    /// 不再被忽略的条目经深度扫描补回。返回 (removed, restored)。
+    pub fn reconcile_ignore_change(
+        &self,
+        dir: &Path,
+        is_ignored: impl Fn(&Path) -> bool,
+    ) -> (usize, usize) {
+        let dir = super::normalize_path(dir);
+        if !dir.is_dir() {
+            return (0, 0);
+        }
+        let restored = self.scan_dirs_with_depth(&[&dir], None, usize::MAX).changed;
+        let ignored: Vec<PathBuf> = self
+            .live_paths_under(&dir)
+            .into_iter()
+            .filter(|p| is_ignored(p))
+            .collect();
+        let removed = self.tombstone_paths(ignored);
+        if removed > 0 || restored > 0 {
+            self.l1.clear();
+            tracing::info!(
+                "ignore rules changed under {}: removed={} restored={}",
+                dir.display(),
+                removed,
+                restored
+            );
+        }
+        (removed, restored)
     }
 }
@@ -4,7 +4,7 @@ use crate::event::sync::DirtyScope;
 use crate::index::tiered::events::event_record_estimated_bytes;
 use crate::stats::EventPipelineStats;
 use crate::storage::snapshot::SnapshotStore;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 fn mk_event(seq: u64, event_type: EventType, path: PathBuf) -> EventRecord {
@@ -201,6 +201,44 @@ fn rebuild_pending_rename_applied_after_switch() {
     assert!(!idx.query("new_bbb").is_empty());
 }
 
+#[test]
+fn fast_sync_all_uses_dir_mtime_table() {
+    let root = unique_tmp_dir("dir-mtimes");
+    let keep = root.join("keep");
+    let moved = root.join("moved");
+    std::fs::create_dir_all(&keep).unwrap();
+    std::fs::create_dir_all(&moved).unwrap();
+    std::fs::write(keep.join("k_match.txt"), b"k").unwrap();
+    std::fs::write(moved.join("a_match.txt"), b"a").unwrap();
+
+    let idx = TieredIndex::empty(vec![root.clone()]);
+    idx.fast_sync(DirtyScope::All { cutoff_ns: 0 }, &[]);
+    idx.refresh_base();
+    assert!(!idx.query("a_match").is_empty());
+
+    // 离线新增文件后把目录 mtime 拨回到 cutoff 之前：单看 cutoff 会漏掉，
+    // 但与目录 mtime 表记录的值不一致。
+    std::fs::write(moved.join("b_match.txt"), b"b").unwrap();
+    let earlier = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+    std::fs::File::open(&moved)
+        .unwrap()
+        .set_modified(earlier)
+        .unwrap();
+    let cutoff_ns = crate::index::file_entry_v2::time_to_ns(Some(std::time::SystemTime::now()));
+
+    let r = idx.fast_sync(
+        DirtyScope::All {
+            cutoff_ns: cutoff_ns as u64,
+        },
+        &[],
+    );
+    assert_eq!(r.dirs_scanned, 1);
+    assert!(!idx.query("b_match").is_empty());
+    assert!(!idx.query("k_match").is_empty());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
 #[test]
 fn fast_sync_reconciles_add_and_delete() {
     let root = unique_tmp_dir("fast-sync");
@@ -340,7 +378,7 @@ async fn auto_flush_overlay_wakes_snapshot_loop() {
     std::fs::write(&p, b"a").unwrap();
     idx.apply_events(&[mk_event(1, EventType::Create, p.clone())]);
 
-    let v7_path = store.path().with_extension("v7");
+    let v7_path = crate::storage::partitions::partition_manifest_path_for(store.path());
     let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(5);
     while !v7_path.exists() {
         if tokio::time::Instant::now() >= deadline {
@@ -437,7 +475,7 @@ async fn periodic_flush_batch_threshold_skips_then_flushes() {
 
     let h = tokio::spawn(idx.clone().snapshot_loop(store.clone(), 1));
 
-    let v7_path = store.path().with_extension("v7");
+    let v7_path = crate::storage::partitions::partition_manifest_path_for(store.path());
 
     let p1 = root.join("a.txt");
     std::fs::write(&p1, b"a").unwrap();
@@ -477,7 +515,7 @@ async fn periodic_flush_batch_byte_threshold_skips_then_flushes() {
 
     let h = tokio::spawn(idx.clone().snapshot_loop(store.clone(), 1));
 
-    let v7_path = store.path().with_extension("v7");
+    let v7_path = crate::storage::partitions::partition_manifest_path_for(store.path());
 
     std::fs::write(&p1, b"a").unwrap();
     idx.apply_events(&[ev1]);
@@ -550,7 +588,7 @@ fn base_delta_delete_then_recreate_prefers_newest() {
 
 #[test]
 fn query_same_path_different_filekey_prefers_delta_overlay() {
-    use crate::core::{FileKey, FileMeta};
+    use crate::core::{FileKey, FileKind, FileMeta};
 
     let root = unique_tmp_dir("q-samepath-newest");
     std::fs::create_dir_all(&root).unwrap();
@@ -571,6 +609,9 @@ fn query_same_path_different_filekey_prefers_delta_overlay() {
         mtime: None,
         ctime: None,
         atime: None,
+        kind: FileKind::File,
+        nlink: 0,
+        target: None,
     });
     idx.refresh_base();
     idx.apply_events(&[mk_event(2, EventType::Create, a.clone())]);
@@ -647,7 +688,7 @@ fn query_same_filekey_multiple_paths_only_returns_newest_path() {
 
 #[tokio::test]
 async fn lsm_offline_dir_mtime_change_skips_disk_segments() {
-    use crate::core::{FileKey, FileMeta};
+    use crate::core::{FileKey, FileKind, FileMeta};
     use crate::index::PersistentIndex;
 
     let base = unique_tmp_dir("lsm-offline-mtime");
@@ -678,6 +719,9 @@ async fn lsm_offline_dir_mtime_change_skips_disk_segments() {
         mtime: None,
         ctime: None,
         atime: None,
+        kind: FileKind::File,
+        nlink: 0,
+        target: None,
     });
     store
         .lsm_replace_base_v6(
@@ -700,3 +744,913 @@ async fn lsm_offline_dir_mtime_change_skips_disk_segments() {
         .unwrap();
     assert_eq!(idx.file_count(), 0);
 }
+
+#[tokio::test]
+async fn content_query_uses_content_index_and_survives_snapshot() -> anyhow::Result<()> {
+    let root = unique_tmp_dir("content-index");
+    let content_root = root.join("content");
+    let state_root = root.join("state");
+    std::fs::create_dir_all(&content_root)?;
+    std::fs::create_dir_all(&state_root)?;
+
+    let store = Arc::new(SnapshotStore::new(state_root.join("index.db")));
+    let idx = Arc::new(TieredIndex::empty(vec![content_root.clone()]));
+    assert!(idx.query("content:fixme").is_empty());
+    idx.set_content_index_enabled(true);
+
+    let hit = content_root.join("hit.rs");
+    let other_ext = content_root.join("hit.txt");
+    let miss = content_root.join("miss.rs");
+    std::fs::write(&hit, b"fn a() {}\n// TODO fixme: later\n")?;
+    std::fs::write(&other_ext, b"TODO fixme in a text file")?;
+    std::fs::write(&miss, b"fn b() {}\n")?;
+    idx.apply_events(&[
+        mk_event(1, EventType::Create, hit.clone()),
+        mk_event(2, EventType::Create, other_ext.clone()),
+        mk_event(3, EventType::Create, miss.clone()),
+    ]);
+    idx.drain_content_queue();
+
+    let r = idx.query("content:\"TODO fixme\" ext:rs");
+    assert_eq!(r.len(), 1);
+    assert_eq!(r[0].path, hit);
+    // Smart-Case：大写 needle 区分大小写。
+    assert!(idx.query("content:\"todo FIXME\" ext:rs").is_empty());
+    assert_eq!(idx.query("content:fixme").len(), 2);
+
+    std::fs::write(&miss, b"fn b() {}\n// fixme too\n")?;
+    idx.apply_events(&[mk_event(4, EventType::Modify, miss.clone())]);
+    idx.drain_content_queue();
+    assert_eq!(idx.query("content:fixme ext:rs").len(), 2);
+
+    idx.snapshot_now(store.clone()).await?;
+    let loaded = Arc::new(TieredIndex::load_or_empty(&*store, vec![content_root.clone()]).await?);
+    assert!(loaded.content_index_enabled());
+    assert_eq!(loaded.query("content:fixme ext:rs").len(), 2);
+
+    std::fs::remove_file(&hit)?;
+    loaded.apply_events(&[mk_event(5, EventType::Delete, hit.clone())]);
+    loaded.drain_content_queue();
+    let r = loaded.query("content:fixme ext:rs");
+    assert_eq!(r.len(), 1);
+    assert_eq!(r[0].path, miss);
+
+    // 目录改名 / 删除：子树里的内容文档随之迁移 / 移除。
+    let before = loaded.content_index_stats().0;
+    let old_dir = content_root.join("old_dir");
+    let new_dir = content_root.join("new_dir");
+    std::fs::create_dir_all(&old_dir)?;
+    let nested = old_dir.join("nested.rs");
+    std::fs::write(&nested, b"// fixme nested\n")?;
+    loaded.apply_events(&[
+        mk_event(6, EventType::Create, old_dir.clone()),
+        mk_event(7, EventType::Create, nested.clone()),
+    ]);
+    std::fs::rename(&old_dir, &new_dir)?;
+    loaded.apply_events(&[EventRecord {
+        seq: 8,
+        timestamp: std::time::SystemTime::now(),
+        event_type: EventType::Rename {
+            from: FileIdentifier::Path(old_dir.clone()),
+            from_path_hint: Some(old_dir.clone()),
+        },
+        id: FileIdentifier::Path(new_dir.clone()),
+        path_hint: Some(new_dir.clone()),
+    }]);
+    loaded.drain_content_queue();
+    assert_eq!(loaded.content_index_stats().0, before + 1);
+    assert!(loaded
+        .content_index
+        .read()
+        .doc_id(new_dir.join("nested.rs").as_os_str().as_encoded_bytes())
+        .is_some());
+
+    std::fs::remove_dir_all(&new_dir)?;
+    loaded.apply_events(&[mk_event(9, EventType::Delete, new_dir.clone())]);
+    loaded.drain_content_queue();
+    assert_eq!(loaded.content_index_stats().0, before);
+
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn content_only_query_takes_candidates_from_content_postings() {
+    let root = unique_tmp_dir("content-candidates");
+    std::fs::create_dir_all(&root).unwrap();
+    for i in 0..32 {
+        std::fs::write(root.join(format!("file_{i:02}.txt")), b"nothing to see").unwrap();
+    }
+    let hit = root.join("file_hit.txt");
+    std::fs::write(&hit, b"// fixme: later").unwrap();
+
+    let idx = Arc::new(TieredIndex::empty(vec![root.clone()]));
+    idx.fast_sync(DirtyScope::All { cutoff_ns: 0 }, &[]);
+    idx.refresh_base();
+    idx.content_index_enabled
+        .store(true, std::sync::atomic::Ordering::Release);
+    assert_eq!(idx.sync_content_index(), 33);
+
+    let (r, profile) = idx.query_limit_profiled("content:fixme", 10);
+    assert_eq!(r.len(), 1);
+    assert_eq!(r[0].path, hit);
+    assert_eq!(profile.base_candidates, 1);
+    assert!(!profile.full_scan);
+
+    // 还有别的条件时照常过滤。
+    assert!(idx.query("content:fixme size:>1mb").is_empty());
+    assert!(idx.query("content:absent").is_empty());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn directories_are_indexed_with_kind_and_survive_snapshot() -> anyhow::Result<()> {
+    let root = unique_tmp_dir("dir-entries");
+    let data_root = root.join("data");
+    let state_root = root.join("state");
+    let src_dir = data_root.join("proj_alpha/src");
+    std::fs::create_dir_all(&src_dir)?;
+    std::fs::create_dir_all(&state_root)?;
+    std::fs::write(src_dir.join("proj_main.rs"), b"fn main() {}")?;
+
+    let store = Arc::new(SnapshotStore::new(state_root.join("index.db")));
+    let idx = Arc::new(TieredIndex::empty(vec![data_root.clone()]));
+    idx.scan_dirs_immediate_deep(std::slice::from_ref(&data_root));
+
+    // 关键词按完整路径匹配：proj_alpha 与 proj_alpha/src 都命中，文件被 type:folder 排除。
+    let folders = idx.query("type:folder proj");
+    assert_eq!(folders.len(), 2, "{folders:?}");
+    assert!(folders.iter().all(|m| m.kind.is_dir()));
+    assert!(folders
+        .iter()
+        .any(|m| m.path == data_root.join("proj_alpha")));
+
+    let src = idx.query("wfn:src type:dir");
+    assert_eq!(src.len(), 1, "{src:?}");
+    assert_eq!(src[0].path, src_dir);
+
+    let files = idx.query("proj type:file");
+    assert_eq!(files.len(), 1, "{files:?}");
+    assert_eq!(files[0].path, src_dir.join("proj_main.rs"));
+
+    // 事件路径：新建目录经 overlay 立即可见。
+    let docs = data_root.join("proj_docs");
+    std::fs::create_dir(&docs)?;
+    idx.apply_events(&[mk_event(1, EventType::Create, docs.clone())]);
+    assert_eq!(idx.query("type:folder proj").len(), 3);
+
+    idx.snapshot_now(store.clone()).await?;
+    let loaded = TieredIndex::load_or_empty(&*store, vec![data_root.clone()]).await?;
+    let folders = loaded.query("type:folder proj");
+    assert_eq!(folders.len(), 3, "{folders:?}");
+    assert!(folders.iter().all(|m| m.kind.is_dir()));
+    assert_eq!(loaded.query("wfn:src type:dir").len(), 1);
+
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[tokio::test]
+async fn ctime_atime_survive_snapshot() -> anyhow::Result<()> {
+    let root = unique_tmp_dir("entry-times");
+    let data_root = root.join("data");
+    let state_root = root.join("state");
+    std::fs::create_dir_all(&data_root)?;
+    std::fs::create_dir_all(&state_root)?;
+    std::fs::write(data_root.join("stamp.txt"), b"x")?;
+
+    let store = Arc::new(SnapshotStore::new(state_root.join("index.db")));
+    let idx = Arc::new(TieredIndex::empty(vec![data_root.clone()]));
+    idx.scan_dirs_immediate_deep(std::slice::from_ref(&data_root));
+
+    let before = idx.query("wfn:stamp.txt");
+    assert_eq!(before.len(), 1);
+    assert!(before[0].atime.is_some());
+    assert_eq!(idx.query("da:today wfn:stamp.txt").len(), 1);
+
+    idx.snapshot_now(store.clone()).await?;
+    let loaded = TieredIndex::load_or_empty(&*store, vec![data_root.clone()]).await?;
+    let after = loaded.query("wfn:stamp.txt");
+    assert_eq!(after.len(), 1);
+    assert_eq!(after[0].ctime, before[0].ctime);
+    assert_eq!(after[0].atime, before[0].atime);
+    assert_eq!(loaded.query("da:today wfn:stamp.txt").len(), 1);
+
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn subscription_pushes_diffs_for_matching_events() {
+    let root = unique_tmp_dir("subscribe-diffs");
+    std::fs::create_dir_all(&root).unwrap();
+    let idx = Arc::new(TieredIndex::empty(vec![root.clone()]));
+
+    let mut sub = idx.subscribe("ext:log").unwrap();
+    sub.reset_visible(Vec::new());
+
+    let a = root.join("a.log");
+    std::fs::write(&a, b"1").unwrap();
+    idx.apply_events(&[mk_event(1, EventType::Create, a.clone())]);
+    idx.drain_subscriptions();
+    assert!(matches!(sub.try_recv(), Some(SubscriptionUpdate::Added(m)) if m.path == a));
+
+    std::fs::write(&a, b"12").unwrap();
+    idx.apply_events(&[mk_event(2, EventType::Modify, a.clone())]);
+    idx.drain_subscriptions();
+    assert!(matches!(sub.try_recv(), Some(SubscriptionUpdate::Updated(m)) if m.size == 2));
+
+    // 不匹配的事件不产生差异；rename 出查询范围视为 remove。
+    let txt = root.join("a.txt");
+    std::fs::write(root.join("other.txt"), b"x").unwrap();
+    idx.apply_events(&[mk_event(3, EventType::Create, root.join("other.txt"))]);
+    std::fs::rename(&a, &txt).unwrap();
+    idx.apply_events(&[mk_event(
+        4,
+        EventType::Rename {
+            from: FileIdentifier::Path(a.clone()),
+            from_path_hint: Some(a.clone()),
+        },
+        txt.clone(),
+    )]);
+    idx.drain_subscriptions();
+    assert!(matches!(sub.try_recv(), Some(SubscriptionUpdate::Removed(p)) if p == a));
+    assert!(sub.try_recv().is_none());
+
+    // 未在视图中的路径被删除：不推送。
+    idx.apply_events(&[mk_event(5, EventType::Delete, root.join("never.log"))]);
+    idx.drain_subscriptions();
+    assert!(sub.try_recv().is_none());
+
+    // 目录改名：索引中的子项展开为逐条差异。
+    let old_dir = root.join("logs");
+    let new_dir = root.join("archive");
+    std::fs::create_dir_all(&old_dir).unwrap();
+    std::fs::write(old_dir.join("x.log"), b"x").unwrap();
+    idx.apply_events(&[
+        mk_event(6, EventType::Create, old_dir.clone()),
+        mk_event(7, EventType::Create, old_dir.join("x.log")),
+    ]);
+    idx.drain_subscriptions();
+    assert!(
+        matches!(sub.try_recv(), Some(SubscriptionUpdate::Added(m)) if m.path == old_dir.join("x.log"))
+    );
+    std::fs::rename(&old_dir, &new_dir).unwrap();
+    idx.apply_events(&[mk_event(
+        8,
+        EventType::Rename {
+            from: FileIdentifier::Path(old_dir.clone()),
+            from_path_hint: Some(old_dir.clone()),
+        },
+        new_dir.clone(),
+    )]);
+    idx.drain_subscriptions();
+    assert!(
+        matches!(sub.try_recv(), Some(SubscriptionUpdate::Removed(p)) if p == old_dir.join("x.log"))
+    );
+    assert!(
+        matches!(sub.try_recv(), Some(SubscriptionUpdate::Added(m)) if m.path == new_dir.join("x.log"))
+    );
+    assert!(sub.try_recv().is_none());
+
+    // 目录删除：视图中的后代一并移除。
+    std::fs::remove_dir_all(&new_dir).unwrap();
+    idx.apply_events(&[mk_event(9, EventType::Delete, new_dir.clone())]);
+    idx.drain_subscriptions();
+    assert!(
+        matches!(sub.try_recv(), Some(SubscriptionUpdate::Removed(p)) if p == new_dir.join("x.log"))
+    );
+    assert!(sub.try_recv().is_none());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn subscription_bounds_subscribers_and_flags_lag() {
+    let root = unique_tmp_dir("subscribe-bounds");
+    std::fs::create_dir_all(&root).unwrap();
+    let idx = Arc::new(TieredIndex::empty(vec![root.clone()]));
+    idx.set_max_subscribers(1);
+
+    let mut sub = idx.subscribe("ext:log").unwrap();
+    assert!(matches!(
+        idx.subscribe("ext:log"),
+        Err(SubscribeError::TooManySubscribers(1))
+    ));
+    assert!(matches!(
+        idx.subscribe("foo (bar"),
+        Err(SubscribeError::Query(_))
+    ));
+
+    // 消费者不读：事件管线不阻塞，超出队列后标记 lagged。
+    let events: Vec<_> = (0..1100u64)
+        .map(|i| {
+            let p = root.join(format!("f{i}.log"));
+            std::fs::write(&p, b"x").unwrap();
+            mk_event(i, EventType::Create, p)
+        })
+        .collect();
+    idx.apply_events(&events);
+    idx.drain_subscriptions();
+    // 先送达 Lagged，排队的旧差异随之丢弃。
+    assert!(matches!(sub.try_recv(), Some(SubscriptionUpdate::Lagged)));
+    assert!(sub.try_recv().is_none());
+
+    drop(sub);
+    assert_eq!(idx.subscriber_count(), 0);
+    assert!(idx.subscribe("ext:log").is_ok());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn subscription_wakes_idle_subscriber_when_pending_events_overflow() {
+    let root = unique_tmp_dir("subscribe-overflow");
+    std::fs::create_dir_all(&root).unwrap();
+    let idx = Arc::new(TieredIndex::empty(vec![root.clone()]));
+    let mut sub = idx.subscribe("ext:log").unwrap();
+    sub.reset_visible(Vec::new());
+
+    // 差异通道为空的订阅者也必须收到 Lagged，否则永远等不到重置。
+    let waiter = tokio::spawn(async move {
+        let update = sub.recv().await;
+        (sub, update)
+    });
+    let events: Vec<_> = (0..=64 * 1024u64)
+        .map(|i| mk_event(i, EventType::Delete, root.join(format!("gone{i}.log"))))
+        .collect();
+    idx.notify_subscribers(&events);
+    let (mut sub, update) = tokio::time::timeout(std::time::Duration::from_secs(10), waiter)
+        .await
+        .expect("idle subscriber woken")
+        .unwrap();
+    assert!(matches!(update, Some(SubscriptionUpdate::Lagged)));
+    assert!(sub.try_recv().is_none());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn changes_feed_reads_wal_and_expires_after_cleanup() -> anyhow::Result<()> {
+    let root = unique_tmp_dir("changes-feed");
+    let data_root = root.join("data");
+    let state_root = root.join("state");
+    std::fs::create_dir_all(&data_root)?;
+    std::fs::create_dir_all(&state_root)?;
+
+    let store = Arc::new(SnapshotStore::new(state_root.join("index.db")));
+    let idx = Arc::new(TieredIndex::empty(vec![data_root.clone()]));
+    idx.attach_wal(&*store)?;
+    assert_eq!(idx.change_cursor(), Some(0));
+
+    let a = data_root.join("a.log");
+    let b = data_root.join("b.txt");
+    let c = data_root.join("c.txt");
+    std::fs::write(&a, b"1")?;
+    std::fs::write(&b, b"1")?;
+    idx.apply_events(&[
+        mk_event(1, EventType::Create, a.clone()),
+        mk_event(2, EventType::Create, b.clone()),
+    ]);
+    std::fs::write(&b, b"12")?;
+    idx.apply_events(&[mk_event(3, EventType::Modify, b.clone())]);
+    std::fs::rename(&a, &c)?;
+    idx.apply_events(&[mk_event(
+        4,
+        EventType::Rename {
+            from: FileIdentifier::Path(a.clone()),
+            from_path_hint: Some(a.clone()),
+        },
+        c.clone(),
+    )]);
+    std::fs::remove_file(&b)?;
+    idx.apply_events(&[mk_event(5, EventType::Delete, b.clone())]);
+
+    let feed = idx.changes_since(0, "", 100)?;
+    let kinds: Vec<_> = feed.changes.iter().map(|c| (c.seq, c.kind)).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (1, ChangeKind::Created),
+            (2, ChangeKind::Created),
+            (3, ChangeKind::Modified),
+            (4, ChangeKind::Renamed),
+            (5, ChangeKind::Deleted),
+        ]
+    );
+    assert_eq!((feed.next, feed.latest), (5, 5));
+    // 元数据取读取时的当前状态：b 已被删除，c 仍存在。
+    assert!(feed.changes[2].meta.is_none());
+    assert_eq!(feed.changes[3].meta.as_ref().map(|m| m.size), Some(1));
+    assert_eq!(feed.changes[3].from.as_deref(), Some(a.as_path()));
+
+    // 分页：next 指向最后一条已读记录。
+    let page = idx.changes_since(1, "", 2)?;
+    assert_eq!(page.changes.len(), 2);
+    assert_eq!((page.next, page.latest), (3, 5));
+
+    // 过滤：重命名只要旧路径命中即保留；已删除文件按路径匹配。
+    let logs = idx.changes_since(0, "ext:log", 100)?;
+    let seqs: Vec<_> = logs.changes.iter().map(|c| c.seq).collect();
+    assert_eq!(seqs, vec![1, 4]);
+    let deleted = idx.changes_since(0, "b.txt", 100)?;
+    assert_eq!(deleted.changes.len(), 3);
+
+    // snapshot 会 seal 并清理 WAL：旧游标失效，最新游标继续可用。
+    idx.snapshot_now(store.clone()).await?;
+    assert!(matches!(
+        idx.changes_since(0, "", 100),
+        Err(ChangesError::Expired { oldest: 5, .. })
+    ));
+    assert!(matches!(
+        idx.changes_since(6, "", 100),
+        Err(ChangesError::Expired { latest: 5, .. })
+    ));
+    assert!(idx.changes_since(5, "", 100)?.changes.is_empty());
+
+    // 游标跨重启单调。
+    let loaded = TieredIndex::load_or_empty(&*store, vec![data_root.clone()]).await?;
+    assert_eq!(loaded.change_cursor(), Some(5));
+    std::fs::write(&b, b"again")?;
+    loaded.apply_events(&[mk_event(6, EventType::Create, b.clone())]);
+    let feed = loaded.changes_since(5, "", 100)?;
+    assert_eq!(feed.changes.len(), 1);
+    assert_eq!(feed.changes[0].seq, 6);
+
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn query_profile_reports_phases_and_feeds_slow_log() {
+    use crate::query::{execute_query, QueryMode, SortColumn, SortOrder};
+
+    let root = unique_tmp_dir("query-profile");
+    std::fs::create_dir_all(&root).unwrap();
+    let in_base = root.join("alpha_base.txt");
+    let other = root.join("beta_base.txt");
+    let in_overlay = root.join("alpha_overlay.txt");
+    for p in [&in_base, &other, &in_overlay] {
+        std::fs::write(p, b"x").unwrap();
+    }
+
+    let idx = TieredIndex::empty(vec![root.clone()]);
+    idx.apply_events(&[
+        mk_event(1, EventType::Create, in_base.clone()),
+        mk_event(2, EventType::Create, other.clone()),
+    ]);
+    idx.materialize_snapshot_base();
+    idx.apply_events(&[mk_event(3, EventType::Create, in_overlay.clone())]);
+
+    let (results, profile) =
+        idx.query_top_k_profiled("alpha", 10, SortColumn::Name, SortOrder::Asc);
+    assert_eq!(results.len(), 2);
+    assert_eq!(profile.results, 2);
+    assert_eq!(profile.overlay_candidates, 1);
+    assert_eq!(profile.base_candidates, 1);
+    assert!(!profile.full_scan);
+    assert!(profile.total_us >= profile.base_scan_us);
+
+    let (_, profile) = idx.query_top_k_profiled("regex:.*", 10, SortColumn::Name, SortOrder::Asc);
+    assert!(profile.full_scan);
+    assert_eq!(profile.base_candidates, 2);
+
+    // 已取消的查询不再扫描 base，也不产出结果。
+    let cancel = std::sync::atomic::AtomicBool::new(true);
+    let (results, profile) =
+        idx.query_top_k_cancellable("alpha", 10, SortColumn::Name, SortOrder::Asc, &cancel);
+    assert!(results.is_empty());
+    assert_eq!(profile.base_candidates, 0);
+
+    idx.set_slow_query_log(std::time::Duration::ZERO, 4);
+    execute_query(
+        &idx,
+        "alpha",
+        10,
+        QueryMode::Fuzzy,
+        SortColumn::Score,
+        SortOrder::Asc,
+    );
+    let slow = idx.slow_queries();
+    assert_eq!(slow.len(), 1);
+    assert_eq!(slow[0].query, "alpha");
+    assert_eq!(slow[0].mode, "fuzzy");
+    assert_eq!(slow[0].profile.results, 2);
+    let total = idx
+        .stats_report()
+        .query_phase_latency
+        .into_iter()
+        .find(|h| h.phase == "total")
+        .unwrap();
+    assert_eq!(total.count, 1);
+}
+
+#[test]
+fn explain_estimates_base_candidates_and_splits_actual_counts() {
+    let root = unique_tmp_dir("query-explain");
+    std::fs::create_dir_all(root.join("docs")).unwrap();
+    let in_base = root.join("docs").join("alpha_base.txt");
+    let other = root.join("beta_base.txt");
+    let in_overlay = root.join("alpha_overlay.txt");
+    for p in [&in_base, &other, &in_overlay] {
+        std::fs::write(p, b"x").unwrap();
+    }
+
+    let idx = TieredIndex::empty(vec![root.clone()]);
+    idx.apply_events(&[
+        mk_event(1, EventType::Create, in_base.clone()),
+        mk_event(2, EventType::Create, other.clone()),
+    ]);
+    idx.materialize_snapshot_base();
+    idx.apply_events(&[mk_event(3, EventType::Create, in_overlay.clone())]);
+
+    let ex = idx.explain("alpha", None);
+    assert!(ex.compile_error.is_none());
+    assert_eq!(ex.base_files, 2);
+    assert_eq!(ex.overlay_entries, 1);
+    assert_eq!(ex.anchors.len(), 1);
+    assert_eq!(ex.anchors[0].estimated_candidates, 1);
+    assert!(!ex.full_scan);
+    assert!(ex.actual.is_none());
+
+    let ex = idx.explain("alpha", Some(10));
+    let actual = ex.actual.unwrap();
+    assert_eq!(actual.overlay_matches, 1);
+    assert_eq!(actual.base_matches, 1);
+    assert_eq!(actual.results, 2);
+    assert!(!actual.truncated);
+
+    let docs = root.join("docs");
+    let ex = idx.explain(&format!("parent:{} txt", docs.display()), None);
+    let shortcut = ex.parent_shortcut.unwrap();
+    assert_eq!(shortcut.path, docs.to_string_lossy());
+    assert_eq!(shortcut.candidates, 1);
+
+    let ex = idx.explain("regex:.*", None);
+    assert!(ex.full_scan);
+    assert_eq!(ex.estimated_base_candidates, 2);
+}
+
+#[tokio::test]
+async fn runtime_roots_scan_new_subtree_and_tombstone_removed_one() -> anyhow::Result<()> {
+    let root = unique_tmp_dir("runtime-roots");
+    let home = root.join("home");
+    let proj = root.join("proj");
+    let state_root = root.join("state");
+    std::fs::create_dir_all(&home)?;
+    std::fs::create_dir_all(proj.join("src"))?;
+    std::fs::create_dir_all(&state_root)?;
+    std::fs::write(home.join("notes_root.txt"), b"x")?;
+    std::fs::write(proj.join("src/lib_root.rs"), b"x")?;
+
+    let store = Arc::new(SnapshotStore::new(state_root.join("index.db")));
+    let idx = Arc::new(TieredIndex::empty(vec![home.clone()]));
+    idx.scan_dirs_immediate_deep(std::slice::from_ref(&home));
+
+    let changes = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let seen = changes.clone();
+    idx.set_root_change_hook(Arc::new(move |change: &RootChange| {
+        seen.lock().push((change.kind, change.roots.clone()));
+    }));
+
+    assert!(matches!(
+        idx.add_root(&home.join("sub")),
+        Err(RootsError::NotADirectory(_))
+    ));
+    assert!(matches!(
+        idx.add_root(&home),
+        Err(RootsError::Overlaps { .. })
+    ));
+
+    assert!(changes.lock().is_empty());
+    let added = idx.add_root(&proj)?;
+    // proj/src 目录 + lib_root.rs；root 本身不入索引
+    assert_eq!(added.entries, 2);
+    assert_eq!(idx.roots(), vec![home.clone(), proj.clone()]);
+    assert_eq!(idx.query("lib_root").len(), 1);
+    assert_eq!(
+        *changes.lock(),
+        vec![(RootChangeKind::Added, vec![home.clone(), proj.clone()])]
+    );
+
+    // 新 root 的条目经 WAL / flush 进入快照
+    idx.snapshot_now(store.clone()).await?;
+    let loaded = TieredIndex::load_or_empty(&*store, idx.roots()).await?;
+    assert_eq!(loaded.query("lib_root").len(), 1);
+
+    let removed = idx.remove_root(&home)?;
+    assert_eq!(removed.entries, 1);
+    assert_eq!(idx.roots(), vec![proj.clone()]);
+    assert!(idx.query("notes_root").is_empty());
+    assert_eq!(idx.query("lib_root").len(), 1);
+    assert!(matches!(
+        idx.remove_root(&home),
+        Err(RootsError::NotFound(_))
+    ));
+    assert!(matches!(idx.remove_root(&proj), Err(RootsError::LastRoot)));
+
+    idx.snapshot_now(store.clone()).await?;
+    let loaded = TieredIndex::load_or_empty(&*store, idx.roots()).await?;
+    assert!(loaded.query("notes_root").is_empty());
+    assert_eq!(loaded.query("lib_root").len(), 1);
+
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[tokio::test]
+async fn partition_snapshot_rewrites_only_touched_root_and_reloads_independently(
+) -> anyhow::Result<()> {
+    use crate::core::partition::segment_key_for;
+    use crate::storage::partitions::partition_segment_path_for;
+
+    let root = unique_tmp_dir("partitions");
+    let alpha = root.join("alpha");
+    let beta = root.join("beta");
+    let gamma = root.join("gamma");
+    let state_root = root.join("state");
+    for dir in [&alpha, &beta, &gamma, &state_root] {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(alpha.join("alpha_one.txt"), b"x")?;
+    std::fs::write(beta.join("beta_one.txt"), b"x")?;
+    std::fs::write(gamma.join("gamma_one.txt"), b"x")?;
+
+    let store = Arc::new(SnapshotStore::new(state_root.join("index.db")));
+    let idx = Arc::new(TieredIndex::empty(vec![alpha.clone(), beta.clone()]));
+    idx.scan_dirs_immediate_deep(&[alpha.clone(), beta.clone()]);
+    idx.snapshot_now(store.clone()).await?;
+    assert!(idx.partition_status().iter().all(|p| p.persisted));
+
+    let alpha_segment = partition_segment_path_for(store.path(), &segment_key_for(&alpha));
+    let alpha_mtime = std::fs::metadata(&alpha_segment)?.modified()?;
+    let generation_of = |idx: &TieredIndex, root: &Path| {
+        idx.partition_status()
+            .into_iter()
+            .find(|p| Path::new(&p.root) == root)
+            .map(|p| p.generation)
+    };
+    let alpha_generation = generation_of(&idx, &alpha);
+    let beta_generation = generation_of(&idx, &beta);
+
+    // 只改动 beta：快照只重写 beta 段，alpha 段原样保留。
+    let p = beta.join("beta_two.txt");
+    std::fs::write(&p, b"x")?;
+    idx.apply_events(&[mk_event(1, EventType::Create, p)]);
+    idx.snapshot_now(store.clone()).await?;
+    assert_eq!(generation_of(&idx, &alpha), alpha_generation);
+    assert_ne!(generation_of(&idx, &beta), beta_generation);
+    assert_eq!(std::fs::metadata(&alpha_segment)?.modified()?, alpha_mtime);
+    assert_eq!(idx.query("beta_").len(), 2);
+
+    // root 集合变化不会让整个快照失效：alpha 分区照常加载，新 root 记为缺段。
+    let loaded = TieredIndex::load_or_empty(&*store, vec![alpha.clone(), gamma.clone()]).await?;
+    let recovery = loaded.recovery_status();
+    assert_eq!(recovery.report.snapshot_source, "partitions");
+    assert_eq!(recovery.report.missing_partitions, vec![gamma.clone()]);
+    assert_eq!(loaded.query("alpha_one").len(), 1);
+    assert!(loaded.query("beta_").is_empty());
+    assert!(loaded.query("gamma_one").is_empty());
+
+    let rebuilt = loaded.rebuild_partition(&gamma)?;
+    assert_eq!(rebuilt.files, 1);
+    assert_eq!(loaded.query("gamma_one").len(), 1);
+    assert_eq!(loaded.query("alpha_one").len(), 1);
+    assert!(matches!(
+        loaded.rebuild_partition(&beta),
+        Err(RootsError::NotFound(_))
+    ));
+
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn ignore_rule_change_tombstones_and_restores_subtree() -> anyhow::Result<()> {
+    use crate::event::ignore_filter::IgnoreFilter;
+
+    let root = unique_tmp_dir("ignore-reconcile");
+    let sub = root.join("sub");
+    std::fs::create_dir_all(&sub)?;
+    std::fs::write(sub.join("build_out.tmp"), b"x")?;
+    std::fs::write(sub.join("kept_src.rs"), b"x")?;
+
+    let idx = Arc::new(TieredIndex::empty(vec![root.clone()]));
+    idx.scan_dirs_immediate_deep(std::slice::from_ref(&root));
+    let filter = IgnoreFilter::from_roots(std::slice::from_ref(&root));
+    assert_eq!(idx.query("build_out").len(), 1);
+
+    let gitignore = sub.join(".gitignore");
+    std::fs::write(&gitignore, b"*.tmp\n")?;
+    let dir = filter
+        .reload_for(&gitignore)
+        .expect("ignore file under root");
+    let (removed, _) = idx.reconcile_ignore_change(&dir, |p| filter.is_ignored(p));
+    assert_eq!(removed, 1);
+    assert!(idx.query("build_out").is_empty());
+    assert_eq!(idx.query("kept_src").len(), 1);
+
+    std::fs::remove_file(&gitignore)?;
+    let dir = filter
+        .reload_for(&gitignore)
+        .expect("ignore file under root");
+    let (removed, restored) = idx.reconcile_ignore_change(&dir, |p| filter.is_ignored(p));
+    assert_eq!(removed, 0);
+    assert!(restored >= 1);
+    assert_eq!(idx.query("build_out").len(), 1);
+
+    // 事件循环侧走后台对账：子目录并入已登记的祖先目录。
+    std::fs::write(&gitignore, b"*.tmp\n")?;
+    let dir = filter
+        .reload_for(&gitignore)
+        .expect("ignore file under root");
+    idx.spawn_ignore_reconcile([dir.clone(), sub.join("nested")], &filter);
+    idx.drain_ignore_reconcile();
+    assert!(idx.query("build_out").is_empty());
+    assert_eq!(idx.query("kept_src").len(), 1);
+
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn path_rules_change_refilters_without_full_rebuild() -> anyhow::Result<()> {
+    use crate::core::path_rules::{PathRules, PathRulesConfig};
+
+    let root = unique_tmp_dir("path-rules");
+    let media = root.join("media");
+    std::fs::create_dir_all(&media)?;
+    std::fs::write(root.join("main_obj.o"), b"x")?;
+    std::fs::write(root.join("main_src.rs"), b"x")?;
+    std::fs::write(media.join("trip_photo.jpg"), b"x")?;
+    std::fs::write(media.join("trip_notes.txt"), b"x")?;
+
+    let idx = Arc::new(TieredIndex::empty(vec![root.clone()]));
+    let strict = PathRulesConfig {
+        exclude_globs: vec!["*.o".to_string()],
+        include_globs: [(media.clone(), vec!["*.jpg".to_string()])]
+            .into_iter()
+            .collect(),
+    };
+    idx.apply_path_rules(Arc::new(PathRules::new(strict.clone())?), &strict);
+    idx.scan_dirs_immediate_deep(std::slice::from_ref(&root));
+    assert!(idx.query("main_obj").is_empty());
+    assert_eq!(idx.query("main_src").len(), 1);
+    assert_eq!(idx.query("trip_photo").len(), 1);
+    assert!(idx.query("trip_notes").is_empty());
+    std::fs::write(media.join("later.txt"), b"x")?;
+    assert!(!idx.path_allowed(&media.join("later.txt")));
+    // 已删除的路径只看 exclude，删除事件不会被 include 吞掉。
+    assert!(idx.path_allowed(&media.join("gone.txt")));
+
+    // 放宽：删掉 exclude、取消 include —— 只重扫受影响范围补回。
+    let change = idx.apply_path_rules(
+        Arc::new(PathRules::new(PathRulesConfig::default())?),
+        &strict,
+    );
+    assert_eq!(change.removed, 0);
+    assert_eq!(change.rescanned, vec![root.clone()]);
+    assert_eq!(idx.query("main_obj").len(), 1);
+    assert_eq!(idx.query("trip_notes").len(), 1);
+    assert_eq!(idx.query("later").len(), 1);
+
+    // 收紧（启动路径：规则先生效，重过滤在后台）：已有条目直接 tombstone，不需要扫描。
+    let tighter = PathRulesConfig {
+        exclude_globs: vec!["media/**".to_string()],
+        ..Default::default()
+    };
+    idx.set_path_rules(Arc::new(PathRules::new(tighter)?));
+    assert!(!idx.path_allowed(&media.join("trip_photo.jpg")));
+    let (tx, rx) = std::sync::mpsc::channel();
+    idx.spawn_path_rules_refilter(&PathRulesConfig::default(), move |change| {
+        let _ = tx.send(change.clone());
+    });
+    let change = rx.recv_timeout(std::time::Duration::from_secs(10))?;
+    assert!(change.rescanned.is_empty());
+    assert_eq!(change.removed, 3);
+    assert!(idx.query("trip_").is_empty());
+    assert_eq!(idx.query("main_").len(), 2);
+
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[tokio::test]
+async fn hard_links_index_every_path_and_survive_snapshot() -> anyhow::Result<()> {
+    let root = unique_tmp_dir("hard-links");
+    let state_root = root.join("state");
+    let data = root.join("data");
+    std::fs::create_dir_all(data.join("b"))?;
+    std::fs::create_dir_all(&state_root)?;
+    let a = data.join("linked_report.txt");
+    let b = data.join("b").join("linked_copy.txt");
+    std::fs::write(&a, b"x")?;
+    std::fs::hard_link(&a, &b)?;
+    std::fs::write(data.join("linked_single.txt"), b"x")?;
+
+    let store = Arc::new(SnapshotStore::new(state_root.join("index.db")));
+    let idx = Arc::new(TieredIndex::empty(vec![data.clone()]));
+    idx.scan_dirs_immediate_deep(std::slice::from_ref(&data));
+    assert_eq!(idx.query("linked_report").len(), 1);
+    assert_eq!(idx.query("linked_copy").len(), 1);
+    assert_eq!(idx.query("linked_ links:>1").len(), 2);
+    assert_eq!(idx.query("linked_ links:1").len(), 1);
+
+    idx.snapshot_now(store.clone()).await?;
+    let loaded = TieredIndex::load_or_empty(&*store, vec![data.clone()]).await?;
+    assert_eq!(loaded.query("linked_ links:>1").len(), 2);
+
+    // 删除其中一个名字只影响该路径，另一路径仍可查询。
+    std::fs::remove_file(&b)?;
+    loaded.apply_events(&[mk_event(1, EventType::Delete, b.clone())]);
+    assert!(loaded.query("linked_copy").is_empty());
+    assert_eq!(loaded.query("linked_report").len(), 1);
+
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn create_for_moved_inode_hides_old_path_but_keeps_hard_links() {
+    let root = unique_tmp_dir("moved-inode");
+    std::fs::create_dir_all(&root).unwrap();
+    let old = root.join("moved_before.txt");
+    let kept = root.join("linked_before.txt");
+    std::fs::write(&old, b"x").unwrap();
+    std::fs::write(&kept, b"x").unwrap();
+
+    let idx = TieredIndex::empty(vec![root.clone()]);
+    idx.scan_dirs_immediate_deep(std::slice::from_ref(&root));
+
+    // rename 只报了目标路径的 Create：旧路径在应用事件时即记为删除。
+    let moved = root.join("moved_after.txt");
+    std::fs::rename(&old, &moved).unwrap();
+    // 新增硬链接：旧路径仍在磁盘上，两条路径都保留。
+    let linked = root.join("linked_after.txt");
+    std::fs::hard_link(&kept, &linked).unwrap();
+    idx.apply_events(&[
+        mk_event(1, EventType::Create, moved.clone()),
+        mk_event(2, EventType::Create, linked.clone()),
+    ]);
+
+    assert!(idx.query("moved_before").is_empty());
+    assert_eq!(idx.query("moved_after").len(), 1);
+    assert_eq!(idx.query("linked_").len(), 2);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn symlinks_index_target_and_follow_target_events() -> anyhow::Result<()> {
+    use std::os::unix::fs::symlink;
+
+    let root = unique_tmp_dir("symlinks");
+    let state_root = root.join("state");
+    let data = root.join("data");
+    std::fs::create_dir_all(data.join("sub"))?;
+    std::fs::create_dir_all(&state_root)?;
+    let real = data.join("real.txt");
+    std::fs::write(&real, b"12345")?;
+    let lnk_file = data.join("lnk_file");
+    symlink("real.txt", &lnk_file)?;
+    symlink("sub", data.join("lnk_dir"))?;
+    symlink("missing.txt", data.join("lnk_gone"))?;
+
+    let store = Arc::new(SnapshotStore::new(state_root.join("index.db")));
+    let idx = Arc::new(TieredIndex::empty(vec![data.clone()]));
+    idx.scan_dirs_immediate_deep(std::slice::from_ref(&data));
+    assert_eq!(idx.query("lnk_ type:symlink").len(), 3);
+    assert!(idx.query("lnk_ type:file").is_empty());
+    let gone = idx.query("lnk_ broken:");
+    assert_eq!(gone.len(), 1);
+    assert!(gone[0].path.ends_with("lnk_gone"));
+    let hit = idx.query("lnk_ target:real");
+    assert_eq!(hit.len(), 1);
+    assert_eq!(hit[0].size, 5);
+
+    idx.snapshot_now(store.clone()).await?;
+    let loaded = TieredIndex::load_or_empty(&*store, vec![data.clone()]).await?;
+    assert_eq!(loaded.query("lnk_ type:symlink").len(), 3);
+    let hit = loaded.query("lnk_ target:real.txt");
+    assert_eq!(hit.len(), 1);
+    assert_eq!(
+        hit[0].target.as_ref().map(|t| t.path.clone()),
+        Some(PathBuf::from("real.txt"))
+    );
+
+    // 目标被删除 / 重建：链接自身无事件，也要随之标记失效、恢复。
+    std::fs::remove_file(&real)?;
+    loaded.apply_events(&[mk_event(1, EventType::Delete, real.clone())]);
+    assert_eq!(loaded.query("lnk_ broken:").len(), 2);
+
+    std::fs::write(&real, b"123456789")?;
+    loaded.apply_events(&[mk_event(2, EventType::Create, real.clone())]);
+    let live = loaded.query("lnk_file broken:no");
+    assert_eq!(live.len(), 1);
+    assert_eq!(live[0].size, 9);
+
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
@@ -1,13 +1,17 @@
 use clap::Parser;
-use fd_rdd::config::{default_snapshot_path, default_socket_path, Config, WatchMode};
+use fd_rdd::config::{default_snapshot_path, default_socket_path, Config, HttpAuthMode, WatchMode};
+use fd_rdd::core::path_rules::PathRules;
 use fd_rdd::event::ignore_filter::IgnoreFilter;
 use fd_rdd::event::sync::DirtyScope;
-use fd_rdd::event::{EventPipeline, TieredWatchRuntime, WatchCommand};
+use fd_rdd::event::{ElasticVerifier, EventPipeline, TieredWatchRuntime, WatchCommand};
+use fd_rdd::index::tiered::{RootChange, RootChangeKind};
 use fd_rdd::index::TieredIndex;
 use fd_rdd::query::SocketServer;
-use fd_rdd::query::{HealthTelemetry, QueryServer};
+use fd_rdd::query::{HealthTelemetry, HttpAuthPolicy, HttpBind, QueryServer};
 use fd_rdd::stats::{EventPipelineStats, WatchStateReport};
+use fd_rdd::storage::partitions::partition_manifest_path_for;
 use fd_rdd::storage::snapshot::{
+    read_path_rules_state, read_recovery_runtime_state, write_path_rules_state,
     write_recovery_runtime_state, RecoveryRuntimeState, SnapshotStore,
 };
 use fd_rdd::util::normalize_exclude_dirs;
@@ -42,6 +46,18 @@ struct Args {
     #[arg(long)]
     http_port: Option<u16>,
 
+    /// HTTP 监听地址：`host:port`（TCP）或 `unix:<path>`（Unix socket）；设置后忽略 --http-port
+    #[arg(long, value_name = "ADDR")]
+    http_bind: Option<String>,
+
+    /// HTTP 鉴权：none（不鉴权）、token（配置文件中的 bearer token）、cookie（启动时生成随机 token 写入 cookie 文件）
+    #[arg(long, value_parser = ["none", "token", "cookie"])]
+    http_auth: Option<String>,
+
+    /// cookie 鉴权的 token 文件路径（默认: 快照同目录下的 http.cookie）
+    #[arg(long, value_name = "PATH")]
+    http_cookie_path: Option<PathBuf>,
+
     /// Unix domain socket 查询地址（可选）：用于流式输出（避免 HTTP/JSON 聚合带来的峰值）
     #[arg(long, value_name = "PATH")]
     uds_socket: Option<PathBuf>,
@@ -89,6 +105,14 @@ struct Args {
     /// watcher 模式：recursive（现有递归监听）、tiered（预算受控热点监听）、off（关闭）。
     #[arg(long, value_parser = ["recursive", "tiered", "off"])]
     watch_mode: Option<String>,
+
+    /// 慢查询阈值（毫秒）：总耗时超过该值的查询进入 `/slow-queries` 日志；0 表示记录全部
+    #[arg(long, value_name = "MS")]
+    slow_query_ms: Option<u64>,
+
+    /// 启用内容 trigram 索引（支持 `content:` 全文查询；会回读 10MB 以内的文本文件）。
+    #[arg(long)]
+    content_index: bool,
 }
 
 #[tokio::main]
@@ -128,6 +152,7 @@ async fn main() -> anyhow::Result<()> {
                 WatchMode::Recursive
             }),
             ignore_enabled: !args.no_ignore,
+            content_index: args.content_index,
             ..Config::default()
         };
         if let Some(socket) = &args.uds_socket {
@@ -168,12 +193,24 @@ async fn main() -> anyhow::Result<()> {
     let ignore_enabled = !args.no_ignore && cfg.ignore_enabled;
     let include_hidden = args.include_hidden || cfg.include_hidden;
     let follow_symlinks = args.follow_symlinks || cfg.follow_symlinks;
+    let content_index = args.content_index || cfg.content_index;
     let mut effective_watch_mode = cli_watch_mode.unwrap_or(cfg.watch_mode);
     if args.no_watch || !cfg.watch_enabled {
         effective_watch_mode = WatchMode::Off;
     }
     let watch_enabled = effective_watch_mode != WatchMode::Off;
     let http_port = args.http_port.unwrap_or(cfg.http_port);
+    let http_bind = match args.http_bind.as_deref().or(cfg.http_bind.as_deref()) {
+        Some(bind) => HttpBind::parse(bind)?,
+        None => HttpBind::localhost(http_port),
+    };
+    let mut http_auth = cfg.http_auth.clone();
+    if let Some(mode) = parse_http_auth_mode(args.http_auth.as_deref())? {
+        http_auth.mode = mode;
+    }
+    if let Some(path) = &args.http_cookie_path {
+        http_auth.cookie_path = Some(path.clone());
+    }
     let snapshot_interval_secs = args
         .snapshot_interval_secs
         .unwrap_or(cfg.snapshot_interval_secs);
@@ -186,7 +223,20 @@ async fn main() -> anyhow::Result<()> {
 
     // 2) 快照存储
     let snapshot_path = args.snapshot_path.unwrap_or_else(default_snapshot_path);
-    let startup_reconcile_cutoff_ns = modified_unix_ns(&snapshot_path.with_extension("v7"));
+    let startup_reconcile_cutoff_ns =
+        match modified_unix_ns(&partition_manifest_path_for(&snapshot_path)) {
+            0 => modified_unix_ns(&snapshot_path.with_extension("v7")),
+            ns => ns,
+        };
+    let http_auth_policy =
+        HttpAuthPolicy::from_config(&http_auth, &snapshot_path.with_file_name("http.cookie"))?;
+    if http_auth_policy.is_open() && !http_bind.is_loopback() {
+        tracing::warn!(
+            "HTTP API bound to non-loopback address {} without authentication (http_auth = none); \
+             anyone who can reach it can query the index and call admin endpoints",
+            http_bind
+        );
+    }
     let store = Arc::new(SnapshotStore::new(snapshot_path));
 
     // 3) 从快照加载或空索引启动
@@ -201,17 +251,46 @@ async fn main() -> anyhow::Result<()> {
     .await?;
     let _ = index.attach_wal(store.as_ref());
     index.set_stable_snapshot_enabled(cfg.stable_snapshot_enabled);
+    index.set_content_index_enabled(content_index);
+    index.set_slow_query_log(
+        Duration::from_millis(args.slow_query_ms.unwrap_or(cfg.slow_query_threshold_ms)),
+        cfg.slow_query_log_size,
+    );
+    // 索引期 glob 规则：立即生效于扫描与事件；已有条目的定向重过滤在就绪后转到后台。
+    let path_rules = Arc::new(PathRules::new(cfg.path_rules())?);
+    let previous_rules = read_path_rules_state(store.path()).unwrap_or_else(|e| {
+        tracing::warn!("path rules state unreadable, treating as empty: {}", e);
+        Default::default()
+    });
+    index.set_path_rules(path_rules.clone());
     let loaded_from_empty_snapshot = index.recovery_status().report.snapshot_source == "empty";
-    let repair_stats = index.startup_repair_if_needed(
+    let watch_plan = build_watch_plan(
+        effective_watch_mode,
+        &index.roots(),
+        &cfg.tiered_watch,
+        &exclude_dirs,
+    );
+    // 启动修复只占用 startup_repair_budget_ms，热目录优先；剩余目录在就绪后转到后台。
+    let hot_dirs: Vec<PathBuf> = watch_plan
+        .l0_roots
+        .iter()
+        .map(|(path, _)| path.clone())
+        .collect();
+    let repair_stats = index.startup_repair_with_cursor(
         cfg.startup_repair_enabled,
         &cfg.startup_repair_mode,
         cfg.startup_repair_max_dirs,
         cfg.startup_repair_budget_ms,
         cfg.startup_repair_force_rebuild_ratio,
+        &hot_dirs,
+        Some(store.path()),
     );
     if repair_stats.ran {
         tracing::info!(
-            "startup repair completed: scanned={} changed={} elapsed_ms={} escalated={}",
+            "startup repair {}: dirs_done={} dirs_pending={} scanned={} changed={} elapsed_ms={} escalated={}",
+            if repair_stats.in_progress { "yielded to background" } else { "completed" },
+            repair_stats.dirs_done,
+            repair_stats.dirs_pending,
             repair_stats.scanned,
             repair_stats.changed,
             repair_stats.elapsed_ms,
@@ -230,10 +309,21 @@ async fn main() -> anyhow::Result<()> {
         loaded_from_empty_snapshot || repair_stats.escalated || index.file_count() == 0;
     if needs_full_build && !index.rebuild_in_progress() {
         index.spawn_full_build();
+    } else {
+        index.spawn_startup_repair();
+        // 分区快照里缺段的 root（新增或段损坏）单独扫描，其余分区直接可用。
+        index.spawn_partition_builds(index.recovery_status().report.missing_partitions);
     }
+    // 与上次生效的规则比对，只对变化部分做定向重过滤（不触发全量重建）；完成后才落盘规则。
+    let rules_snapshot_path = store.path().to_path_buf();
+    index.spawn_path_rules_refilter(&previous_rules, move |_| {
+        if let Err(e) = write_path_rules_state(&rules_snapshot_path, path_rules.config()) {
+            tracing::warn!("path rules state write failed: {}", e);
+        }
+    });
 
     let ignore_filter = if ignore_enabled {
-        Some(IgnoreFilter::from_roots(&index.roots))
+        Some(IgnoreFilter::from_roots(&index.roots()))
     } else {
         None
     };
@@ -248,12 +338,6 @@ async fn main() -> anyhow::Result<()> {
             startup_ignore_paths.clone(),
         );
     }
-    let watch_plan = build_watch_plan(
-        effective_watch_mode,
-        &index.roots,
-        &cfg.tiered_watch,
-        &exclude_dirs,
-    );
     let tiered_runtime = if effective_watch_mode == WatchMode::Tiered {
         Some(Arc::new(TieredWatchRuntime::new(
             watch_plan.l0_roots.clone(),
@@ -265,7 +349,19 @@ async fn main() -> anyhow::Result<()> {
     } else {
         None
     };
-    let watch_state = Arc::new(watch_plan.state.clone());
+    let watch_state = Arc::new(parking_lot::Mutex::new(watch_plan.state.clone()));
+
+    // 持续校验：事件序号缺口 / 队列溢出转为定向 fast-sync，并周期性抽查已索引目录。
+    let verifier = Arc::new(
+        ElasticVerifier::new(index.clone()).with_ignore_prefixes(startup_ignore_paths.clone()),
+    );
+    if cfg.audit_enabled {
+        verifier.spawn_audit_loop(
+            Duration::from_secs(cfg.audit_interval_secs.max(1)),
+            cfg.audit_sample_dirs,
+            Duration::from_millis(cfg.audit_budget_ms),
+        );
+    }
 
     // 5) 启动事件管道（bounded + debounce）
     // 默认忽略索引自身的 snapshot/segment 写入路径，避免 watcher 反馈循环。
@@ -278,7 +374,8 @@ async fn main() -> anyhow::Result<()> {
     )
     .with_ignore_filter(ignore_filter.clone())
     .with_exclude_dirs(exclude_dirs.clone())
-    .with_tiered_runtime(tiered_runtime.clone());
+    .with_tiered_runtime(tiered_runtime.clone())
+    .with_verifier(Some(verifier.clone()));
     if let Some(roots) = watch_plan.watch_roots.clone() {
         pipeline = pipeline.with_watch_roots(roots);
     }
@@ -296,25 +393,51 @@ async fn main() -> anyhow::Result<()> {
             spawn_tiered_scan_loop(
                 index.clone(),
                 runtime,
-                watch_command_tx,
+                watch_command_tx.clone(),
                 cfg.tiered_watch.clone(),
             );
         }
     }
 
+    // 5.5) 运行期 root 增删（HTTP `/roots` / UDS `cmd:`）：同步 watcher 计划并写回配置文件
+    {
+        let tiered_runtime = tiered_runtime.clone();
+        let watch_state = watch_state.clone();
+        let exclude_dirs = exclude_dirs.clone();
+        let ignore_filter = ignore_filter.clone();
+        index.set_root_change_hook(Arc::new(move |change: &RootChange| {
+            if let Some(filter) = ignore_filter.as_ref() {
+                filter.set_roots(&change.roots);
+            }
+            if watch_enabled {
+                apply_root_change_to_watcher(
+                    change,
+                    &watch_command_tx,
+                    tiered_runtime.as_deref(),
+                    &watch_state,
+                    &exclude_dirs,
+                );
+            }
+            persist_roots(&change.roots);
+        }));
+    }
+
     // 6) 启动 HTTP 查询服务
     let health_provider = {
         let index = index.clone();
         let pipeline = pipeline.clone();
         let health_watch_state = watch_state.clone();
         let health_tiered_runtime = tiered_runtime.clone();
+        let verifier = verifier.clone();
+        let audit_enabled = cfg.audit_enabled;
         Arc::new(move || {
             let stats = pipeline.stats();
             let watch_state = health_tiered_runtime
                 .as_ref()
                 .map(|runtime| runtime.report())
-                .unwrap_or_else(|| health_watch_state.as_ref().clone());
+                .unwrap_or_else(|| health_watch_state.lock().clone());
             let recovery = index.recovery_status();
+            let audit = verifier.report();
             HealthTelemetry {
                 last_snapshot_time: index.last_snapshot_time(),
                 watch_enabled,
@@ -334,12 +457,23 @@ async fn main() -> anyhow::Result<()> {
                 startup_repair_escalated: recovery.repair.escalated,
                 startup_repair_scanned: recovery.repair.scanned,
                 startup_repair_changed: recovery.repair.changed,
+                startup_repair_in_progress: recovery.repair.in_progress,
+                startup_repair_resumed: recovery.repair.resumed,
+                startup_repair_dirs_done: recovery.repair.dirs_done,
+                startup_repair_dirs_pending: recovery.repair.dirs_pending,
                 last_clean_shutdown: recovery.report.previous_clean_shutdown,
                 l1_dirs: watch_state.l1_dirs,
                 l2_dirs: watch_state.l2_dirs,
                 l3_dirs: watch_state.l3_dirs,
                 watch_budget_utilization_pct: watch_state.watch_budget_utilization_pct,
                 promotion_budget_blocked: watch_state.promotion_budget_blocked,
+                audit_enabled,
+                audit_last_unix_secs: audit.last_audit_unix_secs,
+                audit_drift_ratio: audit.drift_ratio,
+                audit_drifted_total: audit.total_drifted,
+                audit_overflow_rescans: audit.overflow_rescans,
+                audit_journal_gaps: audit.journal_gaps,
+                audit_repairs: audit.repairs_triggered,
             }
         })
     };
@@ -354,15 +488,17 @@ async fn main() -> anyhow::Result<()> {
             tiered_runtime
                 .as_ref()
                 .map(|runtime| runtime.report())
-                .unwrap_or_else(|| watch_state.as_ref().clone())
+                .unwrap_or_else(|| watch_state.lock().clone())
         })
     };
     let query_server = QueryServer::new(index.clone())
         .with_health_provider(health_provider)
         .with_stats_provider(stats_provider.clone())
-        .with_watch_state_provider(watch_state_provider);
+        .with_watch_state_provider(watch_state_provider)
+        .with_auth(http_auth_policy);
+    let http_endpoint = http_bind.to_string();
     tokio::spawn(async move {
-        if let Err(e) = query_server.run(http_port).await {
+        if let Err(e) = query_server.run_on(http_bind).await {
             tracing::error!("Query server error: {}", e);
         }
     });
@@ -403,8 +539,8 @@ async fn main() -> anyhow::Result<()> {
     }
 
     info!(
-        "fd-rdd ready. Query via: http://localhost:{}/search?q=keyword",
-        http_port
+        "fd-rdd ready. Query via: {}/search?q=keyword",
+        http_endpoint
     );
 
     // 9) 优雅退出：SIGINT/SIGTERM → 最终快照
@@ -430,10 +566,12 @@ fn mark_runtime_state(
     startup_source: &str,
     recovery_mode: &str,
 ) {
+    // 保留快照写入的 WAL 回放起点：启动 / 退出标记不应让下次启动重放已落盘的 sealed WAL。
+    let previous = read_recovery_runtime_state(snapshot_path).unwrap_or_default();
     let state = RecoveryRuntimeState {
         last_clean_shutdown: clean_shutdown,
         last_snapshot_unix_secs: unix_secs(),
-        last_wal_seal_id: 0,
+        last_wal_seal_id: previous.last_wal_seal_id,
         last_startup_source: startup_source.to_string(),
         last_recovery_mode: recovery_mode.to_string(),
     };
@@ -489,6 +627,18 @@ fn parse_watch_mode(value: Option<&str>) -> anyhow::Result<Option<WatchMode>> {
     }
 }
 
+fn parse_http_auth_mode(value: Option<&str>) -> anyhow::Result<Option<HttpAuthMode>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match value {
+        "none" => Ok(Some(HttpAuthMode::None)),
+        "token" => Ok(Some(HttpAuthMode::Token)),
+        "cookie" => Ok(Some(HttpAuthMode::Cookie)),
+        _ => anyhow::bail!("invalid http auth mode: {value}"),
+    }
+}
+
 fn watch_mode_label(mode: WatchMode) -> &'static str {
     match mode {
         WatchMode::Recursive => "recursive",
@@ -700,6 +850,66 @@ fn spawn_tiered_scan_loop(
     });
 }
 
+/// root 增删后更新 watcher：recursive 模式直接监听/取消 root；tiered 模式按预算登记为候选。
+fn apply_root_change_to_watcher(
+    change: &RootChange,
+    watch_command_tx: &tokio::sync::mpsc::Sender<WatchCommand>,
+    tiered_runtime: Option<&TieredWatchRuntime>,
+    watch_state: &parking_lot::Mutex<WatchStateReport>,
+    exclude_dirs: &[String],
+) {
+    let mut commands = Vec::new();
+    match (change.kind, tiered_runtime) {
+        (RootChangeKind::Added, None) => commands.push(WatchCommand::AddRoot(change.root.clone())),
+        (RootChangeKind::Removed, None) => commands.push(WatchCommand::Remove(change.root.clone())),
+        (RootChangeKind::Added, Some(runtime)) => {
+            let cost =
+                estimate_recursive_dir_count(&change.root, runtime.max_watch_dirs(), exclude_dirs);
+            match runtime.register_dynamic_candidate(change.root.clone(), cost) {
+                fd_rdd::event::tiered_watch::PromotionDecision::SendAdd => {
+                    commands.push(WatchCommand::AddRoot(change.root.clone()))
+                }
+                // 预算不足：留在 L1，由 warm-scan 循环定期补扫
+                fd_rdd::event::tiered_watch::PromotionDecision::BudgetBlocked
+                | fd_rdd::event::tiered_watch::PromotionDecision::NotEligible => {}
+            }
+        }
+        (RootChangeKind::Removed, Some(runtime)) => {
+            commands.extend(
+                runtime
+                    .forget_under(&change.root)
+                    .into_iter()
+                    .map(WatchCommand::Remove),
+            );
+        }
+    }
+    for command in commands {
+        if let Err(e) = watch_command_tx.try_send(command) {
+            tracing::warn!("failed to update watcher for root {:?}: {}", change.root, e);
+        }
+    }
+    if tiered_runtime.is_none() {
+        let mut state = watch_state.lock();
+        state.l0_dirs = change.roots.len();
+        state.l0_admitted = change.roots.len();
+    }
+}
+
+/// 把运行期变更后的 root 列表写回配置文件，下次启动（未显式传 `--root` 时）沿用同一组 root。
+fn persist_roots(roots: &[PathBuf]) {
+    let mut cfg = match Config::load() {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            tracing::warn!("failed to load config, roots change not persisted: {}", e);
+            return;
+        }
+    };
+    cfg.roots = roots.to_vec();
+    if let Err(e) = cfg.save() {
+        tracing::warn!("failed to persist roots to config: {}", e);
+    }
+}
+
 fn initial_hot_candidates(
     roots: &[PathBuf],
     hot_dirs: &[PathBuf],
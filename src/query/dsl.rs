@@ -1,4 +1,4 @@
-use crate::core::FileMeta;
+use crate::core::{FileKind, FileMeta};
 use crate::query::matcher::{
     contains_path_separator, create_matcher, ExtMatcher, MatchAllMatcher, Matcher,
     PathInitialsMatcher, PathScope, RegexMatcher, WfnMatcher,
@@ -9,19 +9,27 @@ use std::sync::Arc;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Op {
     Or,
+    And,
     Not,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum Token {
     Op(Op),
+    LParen,
+    RParen,
     Word(String),
 }
 
+/// 词法单元及其在输入中的起始字节偏移（用于语法错误定位）。
+type Spanned = (Token, usize);
+
 #[derive(Debug, Clone)]
 pub enum Expr {
     Or(Vec<Expr>),
     And(Vec<Expr>),
+    /// 括号分组内的 `!`（顶层 `!` 仍是全局排除）
+    Not(Box<Expr>),
     True,
     Atom(Atom),
 }
@@ -52,9 +60,15 @@ pub enum Atom {
     Depth(CmpOp, usize),
     /// len:>30 (filename byte length)
     NameLen(CmpOp, usize),
-    /// type:file / type:folder
+    /// links:>1 (hard link count; 未知时不匹配)
+    Links(CmpOp, usize),
+    /// type:file / type:folder / type:symlink
     EntryType(EntryKind),
-    /// content:keyword (全文搜索，占位)
+    /// target:pattern (符号链接目标；无通配符 => contains，带 `*`/`?` => glob)
+    Target(String),
+    /// broken: / broken:no (目标是否失效；仅符号链接参与)
+    Broken(bool),
+    /// content:keyword (全文搜索；需启用内容索引)
     Content(String),
 }
 
@@ -62,6 +76,7 @@ pub enum Atom {
 pub enum EntryKind {
     File,
     Folder,
+    Symlink,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -92,10 +107,19 @@ pub struct DateRange {
     pub end: std::time::SystemTime,
 }
 
+/// `content:` 过滤的求值上下文：由索引层提供。
+///
+/// 未提供（内容索引未启用）时 `content:` 恒不命中。
+pub trait ContentLookup {
+    fn contains(&self, meta: &FileMeta, needle: &str, case_sensitive: bool) -> bool;
+}
+
 #[derive(Clone)]
 pub struct CompiledQuery {
     pub case_sensitive: bool,
     anchors: Vec<Arc<dyn Matcher>>,
+    /// 路径侧选不出 anchor 时必须满足的 `content:` 条件：由内容索引的 posting 生成候选
+    content_anchor: Option<(String, bool)>,
     include: CompiledExpr,
     excludes: Vec<CompiledExpr>,
 }
@@ -105,18 +129,40 @@ impl CompiledQuery {
         &self.anchors
     }
 
+    /// 路径 anchor 退化为 MatchAll、且每个命中都必须满足的 `content:` 条件（needle, 大小写敏感）。
+    pub fn content_anchor(&self) -> Option<(&str, bool)> {
+        self.content_anchor
+            .as_ref()
+            .map(|(needle, case_sensitive)| (needle.as_str(), *case_sensitive))
+    }
+
     pub fn matches(&self, meta: &FileMeta) -> bool {
-        if !self.include.matches(meta) {
+        self.matches_with_content(meta, None)
+    }
+
+    pub fn matches_with_content(
+        &self,
+        meta: &FileMeta,
+        content: Option<&dyn ContentLookup>,
+    ) -> bool {
+        if !self.include.matches(meta, content) {
             return false;
         }
         for ex in &self.excludes {
-            if ex.matches(meta) {
+            if ex.matches(meta, content) {
                 return false;
             }
         }
         true
     }
 
+    /// 是否包含 `content:` 过滤（决定查询是否需要挂载内容索引）。
+    pub fn has_content_filter(&self) -> bool {
+        std::iter::once(&self.include)
+            .chain(self.excludes.iter())
+            .any(CompiledExpr::has_content_filter)
+    }
+
     pub fn extract_parent_filter(&self) -> Option<String> {
         Self::find_parent_in_expr(&self.include)
     }
@@ -127,6 +173,7 @@ impl CompiledQuery {
             CompiledExpr::And(v) | CompiledExpr::Or(v) => {
                 v.iter().find_map(Self::find_parent_in_expr)
             }
+            // 取反分支里的 parent: 不能作为候选目录
             _ => None,
         }
     }
@@ -136,28 +183,52 @@ impl CompiledQuery {
 enum CompiledExpr {
     Or(Vec<CompiledExpr>),
     And(Vec<CompiledExpr>),
+    Not(Box<CompiledExpr>),
     True,
     Path(Arc<dyn Matcher>),
     Filter(Filter),
 }
 
 impl CompiledExpr {
-    fn matches(&self, meta: &FileMeta) -> bool {
+    fn matches(&self, meta: &FileMeta, content: Option<&dyn ContentLookup>) -> bool {
         match self {
-            CompiledExpr::Or(v) => v.iter().any(|e| e.matches(meta)),
-            CompiledExpr::And(v) => v.iter().all(|e| e.matches(meta)),
+            CompiledExpr::Or(v) => v.iter().any(|e| e.matches(meta, content)),
+            CompiledExpr::And(v) => v.iter().all(|e| e.matches(meta, content)),
+            CompiledExpr::Not(e) => !e.matches(meta, content),
             CompiledExpr::True => true,
             CompiledExpr::Path(m) => {
                 let s = meta.path.to_string_lossy();
                 m.matches(&s)
             }
-            CompiledExpr::Filter(f) => f.matches(meta),
+            CompiledExpr::Filter(f) => f.matches(meta, content),
+        }
+    }
+
+    fn has_content_filter(&self) -> bool {
+        match self {
+            CompiledExpr::Or(v) | CompiledExpr::And(v) => {
+                v.iter().any(CompiledExpr::has_content_filter)
+            }
+            CompiledExpr::Not(e) => e.has_content_filter(),
+            CompiledExpr::Filter(Filter::Content { .. }) => true,
+            _ => false,
+        }
+    }
+
+    /// 每个命中都必须满足的 `content:` 条件：自身或 And 的某个因子（Or / Not 下的不算）。
+    fn required_content(&self) -> Option<(String, bool)> {
+        match self {
+            CompiledExpr::And(v) => v.iter().find_map(CompiledExpr::required_content),
+            CompiledExpr::Filter(Filter::Content {
+                needle,
+                case_sensitive,
+            }) => Some((needle.clone(), *case_sensitive)),
+            _ => None,
         }
     }
 }
 
 /// Filter enum for compiled query expressions.
-/// `Content(String)` is intentionally kept as a placeholder for future full-text search integration.
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
 enum Filter {
@@ -169,12 +240,18 @@ enum Filter {
     Parent(String),
     Depth(CmpOp, usize),
     NameLen(CmpOp, usize),
+    Links(CmpOp, usize),
     EntryType(EntryKind),
-    Content(String),
+    Target(TargetMatcher),
+    Broken(bool),
+    Content {
+        needle: String,
+        case_sensitive: bool,
+    },
 }
 
 impl Filter {
-    fn matches(&self, meta: &FileMeta) -> bool {
+    fn matches(&self, meta: &FileMeta, content: Option<&dyn ContentLookup>) -> bool {
         match self {
             Filter::ExtAny(exts) => {
                 let Some(ext) = meta.path.extension() else {
@@ -217,27 +294,64 @@ impl Filter {
                 let len = meta.path.file_name().map(|f| f.len()).unwrap_or(0);
                 apply_cmp(*op, len as u64, *n as u64)
             }
+            Filter::Links(op, n) => meta.nlink != 0 && apply_cmp(*op, meta.nlink as u64, *n as u64),
             Filter::EntryType(kind) => match kind {
-                // We only index files currently; folder matches always false
-                EntryKind::File => true,
-                EntryKind::Folder => false,
+                EntryKind::File => meta.kind == FileKind::File,
+                EntryKind::Folder => meta.kind.is_dir(),
+                EntryKind::Symlink => meta.kind.is_symlink(),
             },
-            Filter::Content(_) => {
-                // TODO: 接入全文索引后实现真正的内容匹配
-                false
-            }
+            Filter::Target(m) => meta
+                .target
+                .as_ref()
+                .is_some_and(|t| m.0.matches(&t.path.to_string_lossy())),
+            Filter::Broken(want) => meta.target.as_ref().is_some_and(|t| t.broken == *want),
+            Filter::Content {
+                needle,
+                case_sensitive,
+            } => content.is_some_and(|c| c.contains(meta, needle, *case_sensitive)),
         }
     }
 }
 
+/// `target:` 的路径匹配器（匹配链接目标而非条目自身路径）。
+#[derive(Clone)]
+struct TargetMatcher(Arc<dyn Matcher>);
+
+impl std::fmt::Debug for TargetMatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0.describe())
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum QueryCompileError {
-    #[error("invalid query syntax: {0}")]
-    Syntax(String),
+    /// `offset`：出错位置在原始查询串中的字节偏移。
+    #[error("invalid query syntax at byte {offset}: {message}")]
+    Syntax { offset: usize, message: String },
     #[error("invalid filter: {0}")]
     Filter(String),
 }
 
+impl QueryCompileError {
+    fn syntax(offset: usize, message: impl Into<String>) -> Self {
+        Self::Syntax {
+            offset,
+            message: message.into(),
+        }
+    }
+
+    /// 词内部报出的语法错误以词首为基准平移到整串偏移。
+    fn rebase(self, base: usize) -> Self {
+        match self {
+            Self::Syntax { offset, message } => Self::Syntax {
+                offset: base + offset,
+                message,
+            },
+            other => other,
+        }
+    }
+}
+
 fn is_path_initials_query(input: &str) -> bool {
     let has_separator = input.contains('\\') || input.contains('/');
     let has_glob = input.contains('*') || input.contains('?');
@@ -254,26 +368,45 @@ fn is_path_initials_query(input: &str) -> bool {
         || input.starts_with("infolder:")
         || input.starts_with("depth:")
         || input.starts_with("len:")
+        || input.starts_with("links:")
         || input.starts_with("type:")
+        || input.starts_with("target:")
+        || input.starts_with("broken:")
         || input.starts_with("case:")
         || input.starts_with("content:");
     has_separator && !has_glob && !has_special_prefix
 }
 
+/// 编译查询 DSL。
+///
+/// 优先级（高 → 低）：`( )` 分组 > `!` 取反 > AND（空格 / `AND` / `&&`）> OR（`|`）。
+/// 即 `a b | c` 等价于 `(a b) | c`，`foo (bar | baz)` 要求 foo 且 bar/baz 之一。
+/// 顶层的 `!term` 沿用全局排除语义（对所有 OR 分支生效）；括号内的 `!` 只作用于所在分组。
 pub fn compile_query(input: &str) -> Result<CompiledQuery, QueryCompileError> {
+    parse_and_compile(input).map(|q| q.compiled)
+}
+
+/// 编译中间产物：解析树与编译结果（`explain_query` 需要两者对照）。
+struct ParsedQuery {
+    include: Expr,
+    excludes: Vec<Expr>,
+    path_initials: bool,
+    compiled: CompiledQuery,
+}
+
+fn parse_and_compile(input: &str) -> Result<ParsedQuery, QueryCompileError> {
     let tokens = tokenize(input)?;
-    let mut p = Parser::new(tokens);
+    // `AND` 关键字本身不触发 Smart-Case，只看词
+    let has_uppercase = tokens
+        .iter()
+        .any(|(t, _)| matches!(t, Token::Word(w) if w.chars().any(char::is_uppercase)));
+    let mut p = Parser::new(tokens, input.len());
 
     // Smart-Case：默认不敏感；显式 case: 或包含大写字母则敏感
     let mut case_sensitive = false;
     let (include_expr, exclude_exprs) = p.parse_query(&mut case_sensitive)?;
-    if p.has_remaining_tokens() {
-        return Err(QueryCompileError::Syntax(
-            "unexpected trailing tokens".into(),
-        ));
-    }
 
-    if !case_sensitive && input.chars().any(|c| c.is_uppercase()) {
+    if !case_sensitive && has_uppercase {
         case_sensitive = true;
     }
 
@@ -284,11 +417,18 @@ pub fn compile_query(input: &str) -> Result<CompiledQuery, QueryCompileError> {
         .map(|e| compile_expr(e, case_sensitive))
         .collect::<Result<Vec<_>, _>>()?;
 
-    // 选 anchor：每个 OR 分支选 1 个；若任一分支无法选出 anchor，则退化为 MatchAll
-    let mut anchors = select_anchors(&include_expr, case_sensitive)?;
+    // 选 anchor：每个 OR 分支（含括号内嵌套的 OR）至少选 1 个；若任一分支无法选出 anchor，则退化为 MatchAll
+    let selected = select_anchors(&include_expr, case_sensitive)?;
 
     // 路径段首匹配：自动检测并追加 PathInitialsMatcher 作为 OR 分支
-    if is_path_initials_query(input) {
+    let path_initials = is_path_initials_query(input);
+    // MatchAll 时若 `content:` 是必要条件，改由内容索引给出候选，避免逐条回读全部文件
+    let content_anchor = match &selected {
+        None if !path_initials => include.required_content(),
+        _ => None,
+    };
+    let mut anchors = selected.unwrap_or_else(|| vec![Arc::new(MatchAllMatcher)]);
+    if path_initials {
         let pim: Arc<dyn Matcher> = Arc::new(PathInitialsMatcher::new(input));
         // Wrap include as OR with PathInitialsMatcher
         include = CompiledExpr::Or(vec![include, CompiledExpr::Path(Arc::clone(&pim))]);
@@ -296,11 +436,17 @@ pub fn compile_query(input: &str) -> Result<CompiledQuery, QueryCompileError> {
         anchors.push(pim);
     }
 
-    Ok(CompiledQuery {
-        case_sensitive,
-        anchors,
-        include,
-        excludes,
+    Ok(ParsedQuery {
+        include: include_expr,
+        excludes: exclude_exprs,
+        path_initials,
+        compiled: CompiledQuery {
+            case_sensitive,
+            anchors,
+            content_anchor,
+            include,
+            excludes,
+        },
     })
 }
 
@@ -316,6 +462,10 @@ fn compile_expr(expr: &Expr, case_sensitive: bool) -> Result<CompiledExpr, Query
                 .map(|e| compile_expr(e, case_sensitive))
                 .collect::<Result<Vec<_>, _>>()?,
         )),
+        Expr::Not(e) => Ok(CompiledExpr::Not(Box::new(compile_expr(
+            e,
+            case_sensitive,
+        )?))),
         Expr::True => Ok(CompiledExpr::True),
         Expr::Atom(a) => compile_atom(a, case_sensitive),
     }
@@ -376,86 +526,96 @@ fn compile_atom(atom: &Atom, case_sensitive: bool) -> Result<CompiledExpr, Query
         Atom::Parent(p) => Ok(CompiledExpr::Filter(Filter::Parent(p.clone()))),
         Atom::Depth(op, n) => Ok(CompiledExpr::Filter(Filter::Depth(*op, *n))),
         Atom::NameLen(op, n) => Ok(CompiledExpr::Filter(Filter::NameLen(*op, *n))),
+        Atom::Links(op, n) => Ok(CompiledExpr::Filter(Filter::Links(*op, *n))),
         Atom::EntryType(k) => Ok(CompiledExpr::Filter(Filter::EntryType(*k))),
-        Atom::Content(s) => Ok(CompiledExpr::Filter(Filter::Content(s.clone()))),
+        Atom::Target(pat) => Ok(CompiledExpr::Filter(Filter::Target(TargetMatcher(
+            create_matcher(pat, case_sensitive),
+        )))),
+        Atom::Broken(b) => Ok(CompiledExpr::Filter(Filter::Broken(*b))),
+        Atom::Content(s) => Ok(CompiledExpr::Filter(Filter::Content {
+            needle: s.clone(),
+            case_sensitive,
+        })),
     }
 }
 
+/// 选不出 anchor（需要退化为 MatchAll）时返回 None。
 fn select_anchors(
     expr: &Expr,
     case_sensitive: bool,
-) -> Result<Vec<Arc<dyn Matcher>>, QueryCompileError> {
+) -> Result<Option<Vec<Arc<dyn Matcher>>>, QueryCompileError> {
     if matches!(expr, Expr::True) {
-        return Ok(vec![Arc::new(MatchAllMatcher)]);
-    }
-    let branches = match expr {
-        Expr::Or(v) => v.as_slice(),
-        _ => std::slice::from_ref(expr),
-    };
-
-    let mut out: Vec<Arc<dyn Matcher>> = Vec::new();
-
-    for b in branches {
-        let Some(anchor) = best_anchor_in_branch(b, case_sensitive)? else {
-            return Ok(vec![Arc::new(MatchAllMatcher)]);
-        };
-        out.push(anchor);
+        return Ok(None);
     }
-
-    Ok(out)
+    Ok(best_anchor_in_branch(expr, case_sensitive)?.map(|(_, anchors)| anchors))
 }
 
+/// anchor 覆盖集及其区分度评分：表达式的任一命中都至少命中集合中的一个 anchor。
+type AnchorSet = (i64, Vec<Arc<dyn Matcher>>);
+
+/// 为表达式选出覆盖集；无法保证不漏结果时返回 None（由上层退化为 MatchAll）。
+///
+/// - Atom：自身 matcher；
+/// - And：任一因子的覆盖集都成立，取评分最高者（同分取先出现的）；
+/// - Or（含括号嵌套）：各分支覆盖集的并集，评分取最弱分支；任一分支选不出则整体选不出；
+/// - Not / True：不提供 anchor。
 fn best_anchor_in_branch(
     expr: &Expr,
     case_sensitive: bool,
-) -> Result<Option<Arc<dyn Matcher>>, QueryCompileError> {
+) -> Result<Option<AnchorSet>, QueryCompileError> {
     match expr {
         Expr::Or(v) => {
-            // 分支内部出现 OR：取所有子分支里“最差情况下”都存在的 anchor 非易事；
-            // 为保证不漏结果，这里直接要求 MatchAll（由上层处理）。
-            let _ = v;
-            Ok(None)
+            let mut score = i64::MAX;
+            let mut out: Vec<Arc<dyn Matcher>> = Vec::new();
+            for e in v {
+                let Some((s, set)) = best_anchor_in_branch(e, case_sensitive)? else {
+                    return Ok(None);
+                };
+                score = score.min(s);
+                out.extend(set);
+            }
+            if out.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some((score, out)))
         }
         Expr::And(v) => {
-            let mut best: Option<(i64, Arc<dyn Matcher>)> = None;
+            let mut best: Option<AnchorSet> = None;
             for e in v {
-                if let Some((score, m)) = best_anchor_in_branch_scored(e, case_sensitive)? {
+                if let Some((score, set)) = best_anchor_in_branch(e, case_sensitive)? {
                     match &best {
                         Some((s, _)) if *s >= score => {}
-                        _ => best = Some((score, m)),
+                        _ => best = Some((score, set)),
                     }
                 }
             }
-            Ok(best.map(|(_, m)| m))
+            Ok(best)
         }
-        Expr::True => Ok(None),
-        Expr::Atom(a) => best_anchor_for_atom(a, case_sensitive),
-    }
-}
-
-type AnchorScoreResult = Result<Option<(i64, Arc<dyn Matcher>)>, QueryCompileError>;
-
-fn best_anchor_in_branch_scored(expr: &Expr, case_sensitive: bool) -> AnchorScoreResult {
-    match expr {
-        Expr::Or(_) => Ok(None),
-        Expr::True => Ok(None),
-        Expr::And(_) | Expr::Atom(_) => {
-            let Some(m) = best_anchor_in_branch(expr, case_sensitive)? else {
+        Expr::Not(_) | Expr::True => Ok(None),
+        Expr::Atom(a) => {
+            let Some(m) = best_anchor_for_atom(a, case_sensitive)? else {
                 return Ok(None);
             };
-            // score：尽量选更“有区分度”的 matcher
-            let mut score: i64 = 0;
-            if let Some(h) = m.literal_hint() {
-                score += (h.len() as i64).min(1024);
-            }
-            if let Some(p) = m.prefix() {
-                score += (p.len() as i64).min(1024) / 2;
-            }
-            Ok(Some((score, m)))
+            Ok(Some((anchor_score(m.as_ref()), vec![m])))
         }
     }
 }
 
+/// score：尽量选更“有区分度”的 matcher
+fn anchor_score(m: &dyn Matcher) -> i64 {
+    let mut score: i64 = 0;
+    if let Some(h) = m.literal_hint() {
+        score += (h.len() as i64).min(1024);
+    }
+    if let Some(plan) = m.trigram_plan() {
+        score += (plan.min_literal_len() as i64).min(1024);
+    }
+    if let Some(p) = m.prefix() {
+        score += (p.len() as i64).min(1024) / 2;
+    }
+    score
+}
+
 fn best_anchor_for_atom(
     atom: &Atom,
     case_sensitive: bool,
@@ -503,7 +663,10 @@ fn best_anchor_for_atom(
         | Atom::Parent(_)
         | Atom::Depth(_, _)
         | Atom::NameLen(_, _)
+        | Atom::Links(_, _)
         | Atom::EntryType(_)
+        | Atom::Target(_)
+        | Atom::Broken(_)
         | Atom::Content(_) => Ok(None),
     }
 }
@@ -518,25 +681,282 @@ fn regex_has_path_separator(pat: &str) -> bool {
     pat.contains('/') || pat.contains("\\\\")
 }
 
+/// 查询计划说明（`/explain`）：解析树、编译结构与 anchor 选择。
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct QueryExplain {
+    pub case_sensitive: bool,
+    /// 解析后的 `Expr` 树（顶层 `!term` 单列为全局排除）
+    pub parsed: ExplainTree,
+    /// 编译后的 include/exclude 结构（路径匹配器 + 元数据过滤器）
+    pub compiled: ExplainTree,
+    /// `select_anchors` 选出的 anchor；任一命中至少命中其中一个
+    pub anchors: Vec<AnchorExplain>,
+    /// 是否按路径段首匹配追加了 PathInitialsMatcher 分支
+    pub path_initials: bool,
+    /// 可走 ParentIndex 捷径的 `parent:` 目录
+    pub parent_filter: Option<String>,
+    pub content_filter: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ExplainTree {
+    pub include: ExplainNode,
+    pub excludes: Vec<ExplainNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum ExplainNode {
+    Or {
+        children: Vec<ExplainNode>,
+    },
+    And {
+        children: Vec<ExplainNode>,
+    },
+    Not {
+        child: Box<ExplainNode>,
+    },
+    True,
+    /// 解析树叶子
+    Atom {
+        atom: String,
+    },
+    /// 编译后的路径匹配器
+    Path {
+        matcher: String,
+    },
+    /// 编译后的元数据过滤器
+    Filter {
+        filter: String,
+    },
+}
+
+/// anchor 在 base 层的候选预过滤方式（与 `BaseIndexData` 的取候选顺序一致）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnchorPrefilter {
+    /// 正则推导的 AND/OR trigram 计划
+    TrigramPlan,
+    /// literal_hint >= 3 字节：trigram posting 求交
+    Trigram,
+    /// literal_hint 为 1~2 字节：unigram/bigram 索引
+    ShortGram,
+    /// 无 hint：全量扫描
+    FullScan,
+}
+
+impl AnchorPrefilter {
+    pub fn of(m: &dyn Matcher) -> Self {
+        if m.trigram_plan().is_some() {
+            return Self::TrigramPlan;
+        }
+        match m.literal_hint().map(<[u8]>::len) {
+            Some(n) if n >= 3 => Self::Trigram,
+            Some(n) if n > 0 => Self::ShortGram,
+            _ => Self::FullScan,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AnchorExplain {
+    pub matcher: String,
+    /// anchor 选择评分（越高区分度越好）
+    pub score: i64,
+    pub literal_hint: Option<String>,
+    pub trigram_plan: Option<String>,
+    pub prefilter: AnchorPrefilter,
+}
+
+impl AnchorExplain {
+    pub fn of(m: &dyn Matcher) -> Self {
+        Self {
+            matcher: m.describe(),
+            score: anchor_score(m),
+            literal_hint: m
+                .literal_hint()
+                .map(|h| String::from_utf8_lossy(h).into_owned()),
+            trigram_plan: m.trigram_plan().map(|p| p.to_string()),
+            prefilter: AnchorPrefilter::of(m),
+        }
+    }
+}
+
+/// 按 `compile_query` 的同一流程编译，并返回各阶段的可读结构。
+pub fn explain_query(input: &str) -> Result<QueryExplain, QueryCompileError> {
+    let q = parse_and_compile(input)?;
+    let compiled = &q.compiled;
+    Ok(QueryExplain {
+        case_sensitive: compiled.case_sensitive,
+        parsed: ExplainTree {
+            include: explain_expr(&q.include),
+            excludes: q.excludes.iter().map(explain_expr).collect(),
+        },
+        compiled: ExplainTree {
+            include: compiled.include.explain(),
+            excludes: compiled
+                .excludes
+                .iter()
+                .map(CompiledExpr::explain)
+                .collect(),
+        },
+        anchors: compiled
+            .anchors
+            .iter()
+            .map(|m| AnchorExplain::of(m.as_ref()))
+            .collect(),
+        path_initials: q.path_initials,
+        parent_filter: compiled.extract_parent_filter(),
+        content_filter: compiled.has_content_filter(),
+    })
+}
+
+fn explain_expr(expr: &Expr) -> ExplainNode {
+    match expr {
+        Expr::Or(v) => ExplainNode::Or {
+            children: v.iter().map(explain_expr).collect(),
+        },
+        Expr::And(v) => ExplainNode::And {
+            children: v.iter().map(explain_expr).collect(),
+        },
+        Expr::Not(e) => ExplainNode::Not {
+            child: Box::new(explain_expr(e)),
+        },
+        Expr::True => ExplainNode::True,
+        Expr::Atom(a) => ExplainNode::Atom {
+            atom: describe_atom(a),
+        },
+    }
+}
+
+impl CompiledExpr {
+    fn explain(&self) -> ExplainNode {
+        match self {
+            CompiledExpr::Or(v) => ExplainNode::Or {
+                children: v.iter().map(CompiledExpr::explain).collect(),
+            },
+            CompiledExpr::And(v) => ExplainNode::And {
+                children: v.iter().map(CompiledExpr::explain).collect(),
+            },
+            CompiledExpr::Not(e) => ExplainNode::Not {
+                child: Box::new(e.explain()),
+            },
+            CompiledExpr::True => ExplainNode::True,
+            CompiledExpr::Path(m) => ExplainNode::Path {
+                matcher: m.describe(),
+            },
+            CompiledExpr::Filter(f) => ExplainNode::Filter {
+                filter: f.describe(),
+            },
+        }
+    }
+}
+
+impl Filter {
+    fn describe(&self) -> String {
+        match self {
+            Filter::ExtAny(exts) => format!("ext({})", join_exts(exts)),
+            Filter::Size(sf) => format!("size({}{})", cmp_symbol(sf.op), sf.bytes),
+            Filter::DateModified(dr) => format!("dm({})", describe_range(dr)),
+            Filter::DateCreated(dr) => format!("dc({})", describe_range(dr)),
+            Filter::DateAccessed(dr) => format!("da({})", describe_range(dr)),
+            Filter::Parent(p) => format!("parent({:?})", p),
+            Filter::Depth(op, n) => format!("depth({}{})", cmp_symbol(*op), n),
+            Filter::NameLen(op, n) => format!("len({}{})", cmp_symbol(*op), n),
+            Filter::Links(op, n) => format!("links({}{})", cmp_symbol(*op), n),
+            Filter::EntryType(k) => format!("type({})", entry_kind_str(*k)),
+            Filter::Target(m) => format!("target({})", m.0.describe()),
+            Filter::Broken(b) => format!("broken({})", b),
+            Filter::Content {
+                needle,
+                case_sensitive,
+            } => format!("content({:?}, case_sensitive={})", needle, case_sensitive),
+        }
+    }
+}
+
+fn describe_atom(atom: &Atom) -> String {
+    match atom {
+        Atom::Text(s) => format!("text({:?})", s),
+        Atom::Wfn(s) => format!("wfn({:?})", s),
+        Atom::Regex(s) => format!("regex({:?})", s),
+        Atom::Ext(exts) => format!("ext({})", exts.join(";")),
+        Atom::Type(kind) => match kind {
+            MediaKind::Doc => "doc".to_string(),
+            MediaKind::Pic => "pic".to_string(),
+            MediaKind::Video => "video".to_string(),
+        },
+        Atom::DateModified(dr) => format!("dm({})", describe_range(dr)),
+        Atom::DateCreated(dr) => format!("dc({})", describe_range(dr)),
+        Atom::DateAccessed(dr) => format!("da({})", describe_range(dr)),
+        Atom::Size(sf) => format!("size({}{})", cmp_symbol(sf.op), sf.bytes),
+        Atom::Parent(p) => format!("parent({:?})", p),
+        Atom::Depth(op, n) => format!("depth({}{})", cmp_symbol(*op), n),
+        Atom::NameLen(op, n) => format!("len({}{})", cmp_symbol(*op), n),
+        Atom::Links(op, n) => format!("links({}{})", cmp_symbol(*op), n),
+        Atom::EntryType(k) => format!("type({})", entry_kind_str(*k)),
+        Atom::Target(s) => format!("target({:?})", s),
+        Atom::Broken(b) => format!("broken({})", b),
+        Atom::Content(s) => format!("content({:?})", s),
+    }
+}
+
+fn cmp_symbol(op: CmpOp) -> &'static str {
+    match op {
+        CmpOp::Lt => "<",
+        CmpOp::Le => "<=",
+        CmpOp::Eq => "=",
+        CmpOp::Ge => ">=",
+        CmpOp::Gt => ">",
+    }
+}
+
+fn entry_kind_str(kind: EntryKind) -> &'static str {
+    match kind {
+        EntryKind::File => "file",
+        EntryKind::Folder => "folder",
+        EntryKind::Symlink => "symlink",
+    }
+}
+
+fn join_exts(exts: &[Vec<u8>]) -> String {
+    exts.iter()
+        .map(|e| String::from_utf8_lossy(e).into_owned())
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// 日期区间按 Unix 秒输出：`[start, end)`。
+fn describe_range(dr: &DateRange) -> String {
+    let secs = |t: std::time::SystemTime| {
+        t.duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    };
+    format!("[{}, {})", secs(dr.start), secs(dr.end))
+}
+
 struct Parser {
-    tokens: Vec<Token>,
+    tokens: Vec<Spanned>,
     pos: usize,
+    /// 输入总长：在输入末尾报错时使用
+    end: usize,
 }
 
 impl Parser {
-    fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, pos: 0 }
-    }
-
-    fn has_remaining_tokens(&self) -> bool {
-        self.pos < self.tokens.len()
+    fn new(tokens: Vec<Spanned>, end: usize) -> Self {
+        Self {
+            tokens,
+            pos: 0,
+            end,
+        }
     }
 
-    fn peek(&self) -> Option<&Token> {
+    fn peek(&self) -> Option<&Spanned> {
         self.tokens.get(self.pos)
     }
 
-    fn bump(&mut self) -> Option<Token> {
+    fn bump(&mut self) -> Option<Spanned> {
         let t = self.tokens.get(self.pos).cloned();
         if t.is_some() {
             self.pos += 1;
@@ -544,51 +964,117 @@ impl Parser {
         t
     }
 
-    fn bump_word(&mut self) -> Option<String> {
-        match self.bump() {
-            Some(Token::Word(s)) => Some(s),
-            _ => None,
-        }
-    }
-
     fn parse_query(
         &mut self,
         case_sensitive: &mut bool,
     ) -> Result<(Expr, Vec<Expr>), QueryCompileError> {
-        let mut branches: Vec<Vec<Expr>> = vec![Vec::new()];
         let mut excludes: Vec<Expr> = Vec::new();
+        let include = self.parse_or(case_sensitive, Some(&mut excludes), 0)?;
+        // parse_or 只会停在输入末尾或 `)` 上
+        if let Some((_, offset)) = self.peek() {
+            return Err(QueryCompileError::syntax(*offset, "unmatched ')'"));
+        }
+        Ok((include, excludes))
+    }
 
-        while let Some(tok) = self.peek() {
+    /// 解析一条 OR 链，直到输入结束或遇到 `)`（不消费）。
+    ///
+    /// `excludes` 为 Some（顶层）时 `!term` 收集为全局排除；否则在所在分组内取反。
+    /// `start` 为本链起点偏移，用于“空表达式”的报错定位。
+    fn parse_or(
+        &mut self,
+        case_sensitive: &mut bool,
+        mut excludes: Option<&mut Vec<Expr>>,
+        start: usize,
+    ) -> Result<Expr, QueryCompileError> {
+        let mut branches: Vec<Vec<Expr>> = vec![Vec::new()];
+        let mut or_offsets: Vec<usize> = Vec::new();
+        let mut excluded = false;
+        // 当前分支是否已有左操作数（显式 AND 的前置条件）
+        let mut has_lhs = false;
+        // 显式 AND 之后尚未出现右操作数
+        let mut pending_and: Option<usize> = None;
+
+        while let Some((tok, offset)) = self.peek().cloned() {
             match tok {
+                Token::RParen => break,
                 Token::Op(Op::Or) => {
+                    if let Some(at) = pending_and {
+                        return Err(QueryCompileError::syntax(at, "expected term after AND"));
+                    }
                     self.bump();
+                    or_offsets.push(offset);
                     branches.push(Vec::new());
+                    has_lhs = false;
+                }
+                Token::Op(Op::And) => {
+                    if !has_lhs || pending_and.is_some() {
+                        return Err(QueryCompileError::syntax(
+                            offset,
+                            "expected term before AND",
+                        ));
+                    }
+                    self.bump();
+                    pending_and = Some(offset);
                 }
                 Token::Op(Op::Not) => {
                     self.bump();
-                    let Some(word) = self.bump_word() else {
-                        return Err(QueryCompileError::Syntax("expected term after '!'".into()));
-                    };
-                    let ex = parse_atom_expr(&word, case_sensitive)?;
-                    if !matches!(ex, Expr::True) {
-                        excludes.push(ex);
+                    if !matches!(self.peek(), Some((Token::Word(_) | Token::LParen, _))) {
+                        return Err(QueryCompileError::syntax(offset, "expected term after '!'"));
+                    }
+                    let operand = self.parse_operand(case_sensitive)?;
+                    if !matches!(operand, Expr::True) {
+                        match excludes.as_deref_mut() {
+                            Some(ex) => {
+                                ex.push(operand);
+                                excluded = true;
+                            }
+                            None => branches
+                                .last_mut()
+                                .expect("branches non-empty")
+                                .push(Expr::Not(Box::new(operand))),
+                        }
                     }
+                    has_lhs = true;
+                    pending_and = None;
                 }
-                Token::Word(_) => {
-                    let Some(word) = self.bump_word() else {
-                        break;
-                    };
-                    let e = parse_atom_expr(&word, case_sensitive)?;
+                Token::LParen | Token::Word(_) => {
+                    let e = self.parse_operand(case_sensitive)?;
                     if !matches!(e, Expr::True) {
                         branches.last_mut().expect("branches non-empty").push(e);
                     }
+                    has_lhs = true;
+                    pending_and = None;
                 }
             }
         }
+        if let Some(at) = pending_and {
+            return Err(QueryCompileError::syntax(at, "expected term after AND"));
+        }
 
         // 构造 include 表达式：只有排除项时 include=TRUE
-        let include = build_or_and(branches, !excludes.is_empty())?;
-        Ok((include, excludes))
+        build_or_and(branches, excluded, start, &or_offsets)
+    }
+
+    /// 单个操作数：一个词，或一个括号分组。
+    fn parse_operand(&mut self, case_sensitive: &mut bool) -> Result<Expr, QueryCompileError> {
+        match self.bump() {
+            Some((Token::Word(word), offset)) => {
+                parse_atom_expr(&word, case_sensitive).map_err(|e| e.rebase(offset))
+            }
+            Some((Token::LParen, offset)) => {
+                if matches!(self.peek(), Some((Token::RParen, _))) {
+                    return Err(QueryCompileError::syntax(offset, "empty group"));
+                }
+                let inner = self.parse_or(case_sensitive, None, offset)?;
+                match self.bump() {
+                    Some((Token::RParen, _)) => Ok(inner),
+                    _ => Err(QueryCompileError::syntax(offset, "unclosed '('")),
+                }
+            }
+            Some((_, offset)) => Err(QueryCompileError::syntax(offset, "expected term")),
+            None => Err(QueryCompileError::syntax(self.end, "expected term")),
+        }
     }
 }
 
@@ -692,14 +1178,32 @@ fn parse_atom_expr(word: &str, case_sensitive: &mut bool) -> Result<Expr, QueryC
             let (op, n) = parse_cmp_usize(&v)?;
             Ok(Expr::Atom(Atom::NameLen(op, n)))
         }
+        Some("links") => {
+            let v = unquote(tail)?;
+            let (op, n) = parse_cmp_usize(&v)?;
+            Ok(Expr::Atom(Atom::Links(op, n)))
+        }
         Some("type") => {
             let v = unquote(tail)?.to_lowercase();
             let kind = match v.as_str() {
                 "folder" | "dir" | "directory" => EntryKind::Folder,
+                "symlink" | "link" => EntryKind::Symlink,
                 _ => EntryKind::File,
             };
             Ok(Expr::Atom(Atom::EntryType(kind)))
         }
+        Some("target") => {
+            let v = unquote(tail)?;
+            if v.is_empty() {
+                return Err(QueryCompileError::Filter("target: empty pattern".into()));
+            }
+            Ok(Expr::Atom(Atom::Target(v)))
+        }
+        Some("broken") => {
+            let v = unquote(tail)?.to_lowercase();
+            let broken = !matches!(v.as_str(), "no" | "n" | "false" | "0" | "off");
+            Ok(Expr::Atom(Atom::Broken(broken)))
+        }
         Some("content") => {
             let v = unquote(tail)?;
             if v.is_empty() {
@@ -748,7 +1252,7 @@ fn unescape_quoted(s: &str) -> Result<String, QueryCompileError> {
     while let Some(c) = it.next() {
         if c == '\\' {
             let Some(n) = it.next() else {
-                return Err(QueryCompileError::Syntax("dangling escape".into()));
+                return Err(QueryCompileError::syntax(0, "dangling escape"));
             };
             match n {
                 '\\' => out.push('\\'),
@@ -770,14 +1274,21 @@ fn unescape_quoted(s: &str) -> Result<String, QueryCompileError> {
 fn build_or_and(
     branches: Vec<Vec<Expr>>,
     allow_single_empty: bool,
+    start: usize,
+    or_offsets: &[usize],
 ) -> Result<Expr, QueryCompileError> {
     let mut built: Vec<Expr> = Vec::new();
 
     for b in branches {
-        let factors = b
-            .into_iter()
-            .filter(|e| !matches!(e, Expr::True))
-            .collect::<Vec<_>>();
+        // 嵌套分组 `a (b c)` 拍平为同一层 AND
+        let mut factors = Vec::with_capacity(b.len());
+        for e in b {
+            match e {
+                Expr::True => {}
+                Expr::And(inner) => factors.extend(inner),
+                other => factors.push(other),
+            }
+        }
 
         if factors.is_empty() {
             built.push(Expr::True);
@@ -791,25 +1302,40 @@ fn build_or_and(
     }
 
     // 若显式使用 OR（多分支），则不允许空分支（避免 `a| !b` 等歧义）
-    if built.len() > 1 && built.iter().any(|e| matches!(e, Expr::True)) {
-        return Err(QueryCompileError::Syntax("empty OR branch".into()));
+    if built.len() > 1 {
+        if let Some(i) = built.iter().position(|e| matches!(e, Expr::True)) {
+            // 定位到与空分支相邻的那个 `|`
+            let offset = or_offsets[i.saturating_sub(1)];
+            return Err(QueryCompileError::syntax(offset, "empty OR branch"));
+        }
     }
 
     if built.len() == 1 {
         let one = built.into_iter().next().unwrap_or(Expr::True);
         if matches!(one, Expr::True) && !allow_single_empty {
-            return Err(QueryCompileError::Syntax("empty expression".into()));
+            return Err(QueryCompileError::syntax(start, "empty expression"));
         }
         return Ok(one);
     }
 
-    Ok(Expr::Or(built))
+    // `a | (b | c)` 拍平为同一层 OR
+    let mut flat = Vec::with_capacity(built.len());
+    for e in built {
+        match e {
+            Expr::Or(inner) => flat.extend(inner),
+            other => flat.push(other),
+        }
+    }
+    Ok(Expr::Or(flat))
 }
 
-fn tokenize(input: &str) -> Result<Vec<Token>, QueryCompileError> {
+/// 切词。`(` 只在词首开启分组；词内的 `(`…`)` 成对保留（如 `regex:^(a|b)$` 需加引号，
+/// `copy(1).txt` 则无需）；词内多出的 `)` 仅在分组内时结束分组，否则按字面量处理。
+fn tokenize(input: &str) -> Result<Vec<Spanned>, QueryCompileError> {
     let mut out = Vec::new();
     let mut i = 0usize;
     let b = input.as_bytes();
+    let mut depth = 0usize;
 
     while i < b.len() {
         // skip ws
@@ -822,21 +1348,40 @@ fn tokenize(input: &str) -> Result<Vec<Token>, QueryCompileError> {
 
         match b[i] {
             b'|' => {
-                out.push(Token::Op(Op::Or));
+                out.push((Token::Op(Op::Or), i));
                 i += 1;
                 continue;
             }
             b'!' => {
-                out.push(Token::Op(Op::Not));
+                out.push((Token::Op(Op::Not), i));
+                i += 1;
+                continue;
+            }
+            b'&' if b.get(i + 1) == Some(&b'&') => {
+                out.push((Token::Op(Op::And), i));
+                i += 2;
+                continue;
+            }
+            b'(' => {
+                out.push((Token::LParen, i));
+                depth += 1;
+                i += 1;
+                continue;
+            }
+            b')' => {
+                out.push((Token::RParen, i));
+                depth = depth.saturating_sub(1);
                 i += 1;
                 continue;
             }
             _ => {}
         }
 
-        // word: 允许 token 内出现引号，且引号内允许空格/|/!
+        // word: 允许 token 内出现引号，且引号内允许空格/|/!/括号
         let start = i;
         let mut in_quote = false;
+        let mut quote_start = start;
+        let mut inner_parens = 0usize;
         while i < b.len() {
             let c = b[i];
             if in_quote {
@@ -860,8 +1405,21 @@ fn tokenize(input: &str) -> Result<Vec<Token>, QueryCompileError> {
             if c.is_ascii_whitespace() || c == b'|' || c == b'!' {
                 break;
             }
+            if c == b'&' && b.get(i + 1) == Some(&b'&') {
+                break;
+            }
+            if c == b'(' {
+                inner_parens += 1;
+            } else if c == b')' {
+                if inner_parens > 0 {
+                    inner_parens -= 1;
+                } else if depth > 0 {
+                    break;
+                }
+            }
             if c == b'"' {
                 in_quote = true;
+                quote_start = i;
                 i += 1;
                 continue;
             }
@@ -869,12 +1427,14 @@ fn tokenize(input: &str) -> Result<Vec<Token>, QueryCompileError> {
         }
 
         if in_quote {
-            return Err(QueryCompileError::Syntax("unclosed quote".into()));
+            return Err(QueryCompileError::syntax(quote_start, "unclosed quote"));
         }
 
-        let s = input[start..i].trim();
-        if !s.is_empty() {
-            out.push(Token::Word(s.to_string()));
+        let s = &input[start..i];
+        if s == "AND" {
+            out.push((Token::Op(Op::And), start));
+        } else {
+            out.push((Token::Word(s.to_string()), start));
         }
     }
 
@@ -1180,7 +1740,7 @@ fn time_t_to_system_time(t: libc::time_t) -> std::time::SystemTime {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{FileKey, FileMeta};
+    use crate::core::{FileKey, FileKind, FileMeta};
     use std::path::PathBuf;
     use std::time::{Duration, SystemTime};
 
@@ -1196,6 +1756,9 @@ mod tests {
             mtime,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         }
     }
 
@@ -1248,6 +1811,56 @@ mod tests {
         assert!(!q.matches(&m2));
     }
 
+    #[test]
+    fn links_filter_skips_unknown_link_count() {
+        let q = compile_query("links:>1").unwrap();
+        let mut linked = meta("/a/x.txt", 1, None);
+        linked.nlink = 2;
+        let mut single = meta("/a/y.txt", 1, None);
+        single.nlink = 1;
+        assert!(q.matches(&linked));
+        assert!(!q.matches(&single));
+        assert!(!q.matches(&meta("/a/z.txt", 1, None)));
+    }
+
+    #[test]
+    fn symlink_filters_match_kind_target_and_broken() {
+        let link = |path: &str, target: &str, broken: bool| {
+            let mut m = meta(path, 1, None);
+            m.kind = FileKind::Symlink;
+            m.target = Some(crate::core::LinkTarget {
+                path: PathBuf::from(target),
+                broken,
+            });
+            m
+        };
+        let live = link("/a/current", "releases/v2", false);
+        let dangling = link("/a/old.so", "/usr/lib/libgone.so.1", true);
+        let plain = meta("/a/releases/v2", 1, None);
+
+        let q = compile_query("type:symlink").unwrap();
+        assert!(q.matches(&live) && q.matches(&dangling));
+        assert!(!q.matches(&plain));
+        assert!(!compile_query("type:file").unwrap().matches(&live));
+
+        let q = compile_query("target:releases").unwrap();
+        assert!(q.matches(&live));
+        assert!(!q.matches(&dangling));
+        assert!(!q.matches(&plain));
+        let q = compile_query("target:*.so.1").unwrap();
+        assert!(q.matches(&dangling));
+        assert!(!q.matches(&live));
+
+        let q = compile_query("broken:").unwrap();
+        assert!(q.matches(&dangling));
+        assert!(!q.matches(&live));
+        assert!(!q.matches(&plain));
+        let q = compile_query("broken:no").unwrap();
+        assert!(q.matches(&live));
+        assert!(!q.matches(&dangling));
+        assert!(compile_query("target:").is_err());
+    }
+
     #[test]
     fn dm_fixed_date_range_includes_start_excludes_end() {
         // 仅验证区间逻辑（不依赖具体 epoch）
@@ -1275,4 +1888,155 @@ mod tests {
         let q3 = compile_query(r#"regex:"/a/x/.*\\.js$""#).unwrap();
         assert!(q3.matches(&meta("/a/x/VCPPlugin.js", 1, None))); // fullpath 模式
     }
+
+    #[test]
+    fn entry_type_filter_uses_meta_kind() {
+        let file = meta("/work/proj/src", 1, None);
+        let mut dir = meta("/work/proj/src", 0, None);
+        dir.kind = FileKind::Dir;
+
+        let folder = compile_query("wfn:src type:dir").unwrap();
+        assert!(folder.matches(&dir));
+        assert!(!folder.matches(&file));
+
+        let files = compile_query("src type:file").unwrap();
+        assert!(files.matches(&file));
+        assert!(!files.matches(&dir));
+    }
+
+    #[test]
+    fn groups_and_explicit_and_follow_precedence() {
+        let q = compile_query("(ext:rs | ext:toml) !target").unwrap();
+        assert!(q.matches(&meta("/p/src/main.rs", 1, None)));
+        assert!(q.matches(&meta("/p/Cargo.toml", 1, None)));
+        assert!(!q.matches(&meta("/p/target/debug/build.rs", 1, None)));
+        assert!(!q.matches(&meta("/p/README.md", 1, None)));
+
+        let q = compile_query("foo (bar | baz)").unwrap();
+        assert!(q.matches(&meta("/x/foo_baz.txt", 1, None)));
+        assert!(!q.matches(&meta("/x/foo.txt", 1, None)));
+        assert!(!q.matches(&meta("/x/bar_baz.txt", 1, None)));
+
+        // AND 高于 OR：`a AND b | c` == `(a b) | c`
+        for input in ["alpha AND beta | gamma", "alpha && beta | gamma"] {
+            let q = compile_query(input).unwrap();
+            assert!(q.matches(&meta("/x/alpha_beta", 1, None)), "{input}");
+            assert!(q.matches(&meta("/x/gamma", 1, None)), "{input}");
+            assert!(!q.matches(&meta("/x/alpha", 1, None)), "{input}");
+        }
+        // `AND` 关键字不触发 Smart-Case
+        assert!(!compile_query("alpha AND beta").unwrap().case_sensitive);
+
+        // 分组内 `!` 只作用于分组
+        let q = compile_query("(draft !old) | archive").unwrap();
+        assert!(q.matches(&meta("/d/draft_new.md", 1, None)));
+        assert!(!q.matches(&meta("/d/draft_old.md", 1, None)));
+        assert!(q.matches(&meta("/d/archive/old.md", 1, None)));
+
+        // 词内成对括号是字面量
+        let q = compile_query("copy(1).txt").unwrap();
+        assert!(q.matches(&meta("/d/copy(1).txt", 1, None)));
+    }
+
+    #[test]
+    fn syntax_errors_report_offsets() {
+        let offset_of = |input: &str| match compile_query(input) {
+            Err(QueryCompileError::Syntax { offset, message }) => (offset, message),
+            other => panic!("{input}: expected syntax error, got {:?}", other.err()),
+        };
+        assert_eq!(offset_of("foo (bar | baz"), (4, "unclosed '('".into()));
+        assert_eq!(offset_of("foo ) baz"), (4, "unmatched ')'".into()));
+        assert_eq!(offset_of("foo ()"), (4, "empty group".into()));
+        assert_eq!(offset_of("foo | | bar"), (4, "empty OR branch".into()));
+        assert_eq!(offset_of("foo AND"), (4, "expected term after AND".into()));
+        assert_eq!(offset_of("&& foo"), (0, "expected term before AND".into()));
+        assert_eq!(
+            offset_of("foo (bar !)"),
+            (9, "expected term after '!'".into())
+        );
+        assert_eq!(offset_of("foo \"wfn"), (4, "unclosed quote".into()));
+    }
+
+    #[test]
+    fn anchors_cover_nested_groups() {
+        // 分组 OR 的并集比短词更有区分度
+        let q = compile_query("ab (config | settings)").unwrap();
+        let hints: Vec<_> = q
+            .anchors()
+            .iter()
+            .map(|a| a.literal_hint().map(|h| h.to_vec()))
+            .collect();
+        assert_eq!(
+            hints,
+            vec![Some(b"config".to_vec()), Some(b"settings".to_vec())]
+        );
+
+        let q = compile_query("alpha | (beta | gamma)").unwrap();
+        assert_eq!(q.anchors().len(), 3);
+
+        // 任一分支选不出 anchor（或仅有取反）则退化为 MatchAll
+        for input in ["(alpha | size:>1kb)", "(!alpha)"] {
+            let q = compile_query(input).unwrap();
+            assert_eq!(q.anchors().len(), 1, "{input}");
+            assert!(q.anchors()[0].literal_hint().is_none(), "{input}");
+        }
+    }
+
+    #[test]
+    fn explain_reports_trees_and_anchor_choice() {
+        let ex = explain_query("report ext:pdf !draft").unwrap();
+        assert!(!ex.case_sensitive);
+        assert_eq!(
+            ex.parsed.include,
+            ExplainNode::And {
+                children: vec![
+                    ExplainNode::Atom {
+                        atom: "text(\"report\")".into()
+                    },
+                    ExplainNode::Atom {
+                        atom: "ext(pdf)".into()
+                    },
+                ]
+            }
+        );
+        assert_eq!(
+            ex.parsed.excludes,
+            vec![ExplainNode::Atom {
+                atom: "text(\"draft\")".into()
+            }]
+        );
+        assert_eq!(
+            ex.compiled.include,
+            ExplainNode::And {
+                children: vec![
+                    ExplainNode::Path {
+                        matcher: "contains(\"report\")".into()
+                    },
+                    ExplainNode::Filter {
+                        filter: "ext(pdf)".into()
+                    },
+                ]
+            }
+        );
+        // report（6）比 ext:pdf（3）更有区分度
+        assert_eq!(ex.anchors.len(), 1);
+        assert_eq!(ex.anchors[0].matcher, "contains(\"report\")");
+        assert_eq!(ex.anchors[0].score, 6);
+        assert_eq!(ex.anchors[0].prefilter, AnchorPrefilter::Trigram);
+        assert_eq!(ex.parent_filter, None);
+
+        let ex = explain_query(r"regex:^inv_\d+\.pdf$ parent:/docs").unwrap();
+        assert_eq!(ex.parent_filter.as_deref(), Some("/docs"));
+        assert_eq!(ex.anchors[0].prefilter, AnchorPrefilter::TrigramPlan);
+        assert_eq!(
+            ex.anchors[0].trigram_plan.as_deref(),
+            Some(r#"("inv_" AND ".pdf")"#)
+        );
+
+        let ex = explain_query("ab").unwrap();
+        assert_eq!(ex.anchors[0].prefilter, AnchorPrefilter::ShortGram);
+        let ex = explain_query("size:>1kb").unwrap();
+        assert_eq!(ex.anchors[0].matcher, "match_all");
+        assert_eq!(ex.anchors[0].prefilter, AnchorPrefilter::FullScan);
+    }
 }
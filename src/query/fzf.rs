@@ -1,8 +1,13 @@
-use crate::core::FileMeta;
+use crate::core::{FileKey, FileMeta};
 use crate::index::TieredIndex;
 use crate::query::scoring::{score_result, ScoreConfig};
+use crate::query::top_k::sort_ranked;
+use crate::stats::query_profile::QueryProfile;
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 const FUZZY_CANDIDATE_MULTIPLIER: usize = 20;
 const FUZZY_MIN_CANDIDATES: usize = 512;
@@ -32,6 +37,57 @@ impl QueryMode {
     }
 }
 
+/// 结果按 inode（FileKey）的处理方式：硬链接的多个路径各自是一条结果。
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum InodeMode {
+    /// 每个路径一条（默认）
+    #[default]
+    All,
+    /// 同一 inode 只保留排名最前的路径
+    Dedupe,
+    /// 同 Dedupe，其余路径挂在该结果下
+    Group,
+}
+
+impl InodeMode {
+    pub fn parse_label(value: Option<&str>) -> Result<Self, &'static str> {
+        match value.map(str::trim).filter(|s| !s.is_empty()) {
+            None | Some("all") => Ok(Self::All),
+            Some("dedupe") => Ok(Self::Dedupe),
+            Some("group") => Ok(Self::Group),
+            Some(_) => Err("expected one of: all, dedupe, group"),
+        }
+    }
+}
+
+/// 按 `mode` 合并同一 inode 的结果，保持原有顺序；返回每条结果及其其余硬链接路径
+/// （只在 `Group` 下填充）。目录不参与合并。
+pub fn group_by_inode(results: Vec<FileMeta>, mode: InodeMode) -> Vec<(FileMeta, Vec<PathBuf>)> {
+    if mode == InodeMode::All {
+        return results.into_iter().map(|m| (m, Vec::new())).collect();
+    }
+    let mut first: HashMap<FileKey, usize> = HashMap::new();
+    let mut out: Vec<(FileMeta, Vec<PathBuf>)> = Vec::with_capacity(results.len());
+    for meta in results {
+        if meta.kind.is_dir() {
+            out.push((meta, Vec::new()));
+            continue;
+        }
+        match first.get(&meta.file_key) {
+            Some(&i) => {
+                if mode == InodeMode::Group {
+                    out[i].1.push(meta.path);
+                }
+            }
+            None => {
+                first.insert(meta.file_key, out.len());
+                out.push((meta, Vec::new()));
+            }
+        }
+    }
+    out
+}
+
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub enum SortColumn {
     /// Relevance score (default)
@@ -85,82 +141,71 @@ pub fn execute_query(
     sort: SortColumn,
     order: SortOrder,
 ) -> Vec<FileMeta> {
-    let mut results = match mode {
-        QueryMode::Exact => index.query_limit(keyword, limit),
-        QueryMode::Fuzzy => FzfIntegration::new().query_index(index, keyword, limit),
-    };
-
-    sort_results(&mut results, keyword, sort, order);
-    results
+    execute_query_cancellable(
+        index,
+        keyword,
+        limit,
+        mode,
+        sort,
+        order,
+        &AtomicBool::new(false),
+    )
 }
 
-fn sort_results(results: &mut [FileMeta], keyword: &str, sort: SortColumn, order: SortOrder) {
-    results.sort_by(|a, b| {
-        let cmp = match sort {
-            SortColumn::Score => {
-                let config = ScoreConfig::from_query(keyword);
-                let sa = score_result(a, &config);
-                let sb = score_result(b, &config);
-                // Score: higher is better, so default desc
-                sb.cmp(&sa).then_with(|| a.path.cmp(&b.path))
-            }
-            SortColumn::Name => {
-                let na = a
-                    .path
-                    .file_name()
-                    .map(|f| f.to_string_lossy().to_lowercase())
-                    .unwrap_or_default();
-                let nb = b
-                    .path
-                    .file_name()
-                    .map(|f| f.to_string_lossy().to_lowercase())
-                    .unwrap_or_default();
-                na.cmp(&nb).then_with(|| a.path.cmp(&b.path))
-            }
-            SortColumn::Path => a.path.cmp(&b.path),
-            SortColumn::Size => a.size.cmp(&b.size).then_with(|| a.path.cmp(&b.path)),
-            SortColumn::Ext => {
-                let ea = a
-                    .path
-                    .extension()
-                    .map(|e| e.to_string_lossy().to_lowercase())
-                    .unwrap_or_default();
-                let eb = b
-                    .path
-                    .extension()
-                    .map(|e| e.to_string_lossy().to_lowercase())
-                    .unwrap_or_default();
-                ea.cmp(&eb).then_with(|| a.path.cmp(&b.path))
-            }
-            SortColumn::DateModified => {
-                cmp_time(a.mtime, b.mtime).then_with(|| a.path.cmp(&b.path))
-            }
-            SortColumn::DateCreated => cmp_time(a.ctime, b.ctime).then_with(|| a.path.cmp(&b.path)),
-            SortColumn::DateAccessed => {
-                cmp_time(a.atime, b.atime).then_with(|| a.path.cmp(&b.path))
-            }
-        };
-
-        // Score column is already desc by default; all others respect order param
-        if sort == SortColumn::Score {
-            cmp
-        } else if order == SortOrder::Desc {
-            cmp.reverse()
-        } else {
-            cmp
+/// 同 `execute_query`；`cancel` 置位后索引扫描在下一个候选块处停下（UDS 会话取消用）。
+pub fn execute_query_cancellable(
+    index: &TieredIndex,
+    keyword: &str,
+    limit: usize,
+    mode: QueryMode,
+    sort: SortColumn,
+    order: SortOrder,
+    cancel: &AtomicBool,
+) -> Vec<FileMeta> {
+    let started = std::time::Instant::now();
+    let (results, mut profile) = match mode {
+        // 排序在索引层的有界 top-k 里完成：先排名、后截断。
+        QueryMode::Exact => index.query_top_k_cancellable(keyword, limit, sort, order, cancel),
+        QueryMode::Fuzzy => {
+            let (mut results, mut profile) =
+                FzfIntegration::new().query_index_cancellable(index, keyword, limit, cancel);
+            let sort_started = std::time::Instant::now();
+            sort_ranked(&mut results, keyword, sort, order);
+            profile.sort_us += sort_started.elapsed().as_micros() as u64;
+            (results, profile)
         }
-    });
+    };
+    if cancel.load(Ordering::Relaxed) {
+        // 被取消的查询只有部分结果，不计入慢查询统计。
+        return results;
+    }
+    profile.results = results.len();
+    profile.total_us = started.elapsed().as_micros() as u64;
+    index.record_query_profile(keyword, mode.as_str(), &profile);
+    results
 }
 
-fn cmp_time(
-    a: Option<std::time::SystemTime>,
-    b: Option<std::time::SystemTime>,
-) -> std::cmp::Ordering {
-    match (a, b) {
-        (Some(ta), Some(tb)) => ta.cmp(&tb),
-        (Some(_), None) => std::cmp::Ordering::Greater,
-        (None, Some(_)) => std::cmp::Ordering::Less,
-        (None, None) => std::cmp::Ordering::Equal,
+/// 带 inode 合并的查询：合并会吞掉同一 inode 的其余路径，结果不足 `limit` 时加倍取数重查，
+/// 直到凑满 `limit` 或候选耗尽。
+pub fn execute_query_grouped(
+    index: &TieredIndex,
+    keyword: &str,
+    limit: usize,
+    mode: QueryMode,
+    sort: SortColumn,
+    order: SortOrder,
+    inode: InodeMode,
+) -> Vec<(FileMeta, Vec<PathBuf>)> {
+    let mut fetch = limit;
+    loop {
+        let results = execute_query(index, keyword, fetch, mode, sort, order);
+        let exhausted = results.len() < fetch || inode == InodeMode::All;
+        let mut grouped = group_by_inode(results, inode);
+        if grouped.len() >= limit || exhausted {
+            grouped.truncate(limit);
+            return grouped;
+        }
+        fetch = fetch.saturating_mul(2);
     }
 }
 
@@ -200,26 +245,64 @@ impl FzfIntegration {
     }
 
     pub fn query_index(&self, index: &TieredIndex, keyword: &str, limit: usize) -> Vec<FileMeta> {
+        self.query_index_profiled(index, keyword, limit).0
+    }
+
+    /// 同 `query_index`；fuzzy 打分计入 sort 阶段，候选回退全量时标记 full_scan。
+    pub fn query_index_profiled(
+        &self,
+        index: &TieredIndex,
+        keyword: &str,
+        limit: usize,
+    ) -> (Vec<FileMeta>, QueryProfile) {
+        self.query_index_cancellable(index, keyword, limit, &AtomicBool::new(false))
+    }
+
+    /// 同 `query_index_profiled`；取消后跳过全量回退与 fuzzy 打分。
+    pub fn query_index_cancellable(
+        &self,
+        index: &TieredIndex,
+        keyword: &str,
+        limit: usize,
+        cancel: &AtomicBool,
+    ) -> (Vec<FileMeta>, QueryProfile) {
         if limit == 0 {
-            return Vec::new();
+            return (Vec::new(), QueryProfile::default());
         }
 
         let keyword = keyword.trim();
         if keyword.is_empty() {
-            return index.query_limit(keyword, limit);
+            return index.query_limit_profiled(keyword, limit);
         }
 
         let candidate_limit = fuzzy_candidate_limit(index.file_count(), limit);
-        let mut candidates = index.query_limit(keyword, candidate_limit);
+        let (mut candidates, mut profile) = index.query_top_k_cancellable(
+            keyword,
+            candidate_limit,
+            SortColumn::Score,
+            SortOrder::Asc,
+            cancel,
+        );
+        if cancel.load(Ordering::Relaxed) {
+            return (Vec::new(), profile);
+        }
         if candidates.is_empty() {
+            let scan_started = std::time::Instant::now();
             candidates = index.collect_all_live_metas();
+            profile.base_scan_us += scan_started.elapsed().as_micros() as u64;
+            profile.base_candidates = candidates.len();
+            profile.full_scan = true;
         }
 
-        self.match_query(keyword, candidates)
+        let sort_started = std::time::Instant::now();
+        let results: Vec<FileMeta> = self
+            .match_query(keyword, candidates)
             .into_iter()
             .take(limit)
             .map(|(meta, _)| meta)
-            .collect()
+            .collect();
+        profile.sort_us += sort_started.elapsed().as_micros() as u64;
+        (results, profile)
     }
 }
 
@@ -232,9 +315,8 @@ fn fuzzy_candidate_limit(file_count: usize, limit: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{EventRecord, EventType, FileIdentifier};
+    use crate::core::{EventRecord, EventType, FileIdentifier, FileKind};
     use std::fs;
-    use std::path::PathBuf;
     use std::time::{SystemTime, UNIX_EPOCH};
 
     fn unique_tmp_dir(prefix: &str) -> PathBuf {
@@ -277,6 +359,75 @@ mod tests {
         assert!(QueryMode::parse_label(Some("bogus")).is_err());
     }
 
+    #[test]
+    fn group_by_inode_merges_hard_links_in_rank_order() {
+        let meta = |path: &str, ino: u64| FileMeta {
+            file_key: FileKey {
+                dev: 1,
+                ino,
+                generation: 0,
+            },
+            path: PathBuf::from(path),
+            size: 1,
+            mtime: None,
+            ctime: None,
+            atime: None,
+            kind: FileKind::File,
+            nlink: 2,
+            target: None,
+        };
+        let results = vec![meta("/a/x", 7), meta("/a/y", 8), meta("/b/x", 7)];
+
+        assert_eq!(group_by_inode(results.clone(), InodeMode::All).len(), 3);
+        let deduped = group_by_inode(results.clone(), InodeMode::Dedupe);
+        assert_eq!(deduped.len(), 2);
+        assert!(deduped.iter().all(|(_, links)| links.is_empty()));
+        let grouped = group_by_inode(results, InodeMode::Group);
+        assert_eq!(grouped[0].0.path, PathBuf::from("/a/x"));
+        assert_eq!(grouped[0].1, vec![PathBuf::from("/b/x")]);
+        assert!(InodeMode::parse_label(Some("bogus")).is_err());
+    }
+
+    #[test]
+    fn grouped_query_fills_limit_past_hard_links() -> anyhow::Result<()> {
+        let root = unique_tmp_dir("inode-limit");
+        let first = root.join("a_link_0.txt");
+        fs::write(&first, b"x")?;
+        for i in 1..6 {
+            fs::hard_link(&first, root.join(format!("a_link_{i}.txt")))?;
+        }
+        fs::write(root.join("z_solo_a.txt"), b"a")?;
+        fs::write(root.join("z_solo_b.txt"), b"b")?;
+
+        let index = TieredIndex::empty(vec![root.clone()]);
+        index.fast_sync(crate::event::sync::DirtyScope::All { cutoff_ns: 0 }, &[]);
+
+        let query = |inode| {
+            execute_query_grouped(
+                &index,
+                "txt",
+                3,
+                QueryMode::Exact,
+                SortColumn::Path,
+                SortOrder::Asc,
+                inode,
+            )
+        };
+        assert_eq!(query(InodeMode::All).len(), 3);
+        let deduped = query(InodeMode::Dedupe);
+        let names: Vec<_> = deduped
+            .iter()
+            .map(|(m, _)| m.path.file_name().unwrap().to_owned())
+            .collect();
+        assert_eq!(names, ["a_link_0.txt", "z_solo_a.txt", "z_solo_b.txt"]);
+        let grouped = query(InodeMode::Group);
+        assert_eq!(grouped.len(), 3);
+        assert_eq!(grouped[0].1.len(), 5);
+
+        let _ = fs::remove_dir_all(&root);
+        Ok(())
+    }
+
     #[test]
     fn fuzzy_query_can_fallback_to_match_all_candidates() -> anyhow::Result<()> {
         let root = unique_tmp_dir("fallback");
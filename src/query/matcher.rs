@@ -2,6 +2,8 @@ use std::path::Path;
 use std::sync::Arc;
 use unicode_normalization::UnicodeNormalization;
 
+use crate::query::trigram_plan::TrigramPlan;
+
 /// Glob 匹配模式
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum GlobMode {
@@ -31,6 +33,12 @@ pub trait Matcher: Send + Sync {
     fn literal_hint(&self) -> Option<&[u8]> {
         None
     }
+    /// 给不出单一 literal 时的 trigram 候选计划（AND/OR 组合的必需 literal，如正则）。
+    ///
+    /// 与 `literal_hint` 相同的约束：计划只能收窄到真实结果的超集。
+    fn trigram_plan(&self) -> Option<&TrigramPlan> {
+        None
+    }
     /// 获取 glob 模式（非 glob 匹配器返回 None）
     fn glob_mode(&self) -> Option<GlobMode> {
         None
@@ -39,6 +47,10 @@ pub trait Matcher: Send + Sync {
     fn exact_path(&self) -> Option<&Path> {
         None
     }
+    /// 人类可读的匹配器描述（`/explain` 输出用）
+    fn describe(&self) -> String {
+        "custom".to_string()
+    }
 }
 
 fn normalize_match_input(input: &str) -> String {
@@ -80,6 +92,10 @@ impl Matcher for ExactMatcher {
         }
         Some(self.pattern.as_bytes())
     }
+
+    fn describe(&self) -> String {
+        format!("contains({:?})", self.pattern)
+    }
 }
 
 /// 通配符匹配 (Glob)
@@ -152,6 +168,15 @@ impl Matcher for GlobMatcher {
     fn glob_mode(&self) -> Option<GlobMode> {
         Some(self.mode)
     }
+
+    fn describe(&self) -> String {
+        let pattern: String = self.pattern.iter().collect();
+        let mode = match self.mode {
+            GlobMode::FullPath => "fullpath",
+            GlobMode::Segment => "segment",
+        };
+        format!("glob({:?}, {})", pattern, mode)
+    }
 }
 
 /// 判断模式是否包含路径分隔符
@@ -316,6 +341,15 @@ pub enum PathScope {
     Basename,
 }
 
+impl PathScope {
+    fn as_str(self) -> &'static str {
+        match self {
+            PathScope::FullPath => "fullpath",
+            PathScope::Basename => "basename",
+        }
+    }
+}
+
 /// wfn: 完整文件名匹配（默认 basename；含分隔符则 fullpath）
 pub struct WfnMatcher {
     pattern: String,
@@ -371,6 +405,10 @@ impl Matcher for WfnMatcher {
             None
         }
     }
+
+    fn describe(&self) -> String {
+        format!("wfn({:?}, {})", self.pattern, self.scope.as_str())
+    }
 }
 
 /// regex: 正则匹配（默认 basename；含分隔符则 fullpath）
@@ -378,14 +416,17 @@ pub struct RegexMatcher {
     re: regex::Regex,
     scope: PathScope,
     case_sensitive: bool,
+    plan: Option<TrigramPlan>,
 }
 
 impl RegexMatcher {
     pub fn new(re: regex::Regex, scope: PathScope, case_sensitive: bool) -> Self {
+        let plan = TrigramPlan::from_regex(re.as_str(), case_sensitive);
         Self {
             re,
             scope,
             case_sensitive,
+            plan,
         }
     }
 }
@@ -407,6 +448,14 @@ impl Matcher for RegexMatcher {
     fn case_sensitive(&self) -> bool {
         self.case_sensitive
     }
+
+    fn trigram_plan(&self) -> Option<&TrigramPlan> {
+        self.plan.as_ref()
+    }
+
+    fn describe(&self) -> String {
+        format!("regex({:?}, {})", self.re.as_str(), self.scope.as_str())
+    }
 }
 
 /// ext: / doc:/pic:/video: 过滤器的 anchor matcher（按扩展名匹配）。
@@ -453,6 +502,15 @@ impl Matcher for ExtMatcher {
         }
         None
     }
+
+    fn describe(&self) -> String {
+        let exts: Vec<String> = self
+            .exts_lc
+            .iter()
+            .map(|e| String::from_utf8_lossy(e).into_owned())
+            .collect();
+        format!("ext({})", exts.join(";"))
+    }
 }
 
 /// 路径段首字母匹配：`c\use\shi\pro` 匹配 `C:\Users\shiyi\project`
@@ -533,6 +591,10 @@ impl Matcher for PathInitialsMatcher {
         // Path initials mode queries contain separators; cannot use single-segment literal hints.
         None
     }
+
+    fn describe(&self) -> String {
+        format!("path_initials({:?})", self.segments.join("/"))
+    }
 }
 
 /// Match-all：用于无正向 anchor 的退化扫描。
@@ -546,6 +608,10 @@ impl Matcher for MatchAllMatcher {
     fn case_sensitive(&self) -> bool {
         false
     }
+
+    fn describe(&self) -> String {
+        "match_all".to_string()
+    }
 }
 
 #[cfg(test)]
@@ -4,9 +4,13 @@ pub mod matcher;
 pub mod scoring;
 pub mod server;
 pub mod socket;
+pub mod top_k;
+pub mod trigram_plan;
 
 pub use dsl::*;
 pub use fzf::*;
 pub use matcher::*;
 pub use server::*;
 pub use socket::*;
+pub use top_k::*;
+pub use trigram_plan::TrigramPlan;
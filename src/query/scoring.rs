@@ -568,7 +568,7 @@ fn split_path_with_offsets(path: &str) -> Vec<(&str, usize)> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{FileKey, FileMeta};
+    use crate::core::{FileKey, FileKind, FileMeta};
     use std::path::PathBuf;
     use std::time::SystemTime;
 
@@ -584,6 +584,9 @@ mod tests {
             mtime,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         }
     }
 
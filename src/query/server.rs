@@ -1,24 +1,44 @@
+use crate::config::{HttpAuthConfig, HttpAuthMode};
+use crate::core::FileMeta;
+use crate::index::tiered::{
+    ChangeEntry, ChangesError, IndexExplain, PartitionRebuild, PartitionStatus, RootChange,
+    RootsError, SubscribeError, Subscription, SubscriptionUpdate,
+};
 use crate::index::TieredIndex;
 use crate::query::scoring::{compute_highlights, score_result, ScoreConfig};
-use crate::query::{execute_query, QueryMode, SortColumn, SortOrder};
+use crate::query::{
+    execute_query, execute_query_grouped, InodeMode, QueryMode, SortColumn, SortOrder,
+};
+use crate::stats::prometheus::{self, MetricKind, PromText};
+use crate::stats::query_profile::SlowQuery;
 use crate::stats::{EventPipelineStats, MemoryReport, StatsReport, WatchStateReport};
 use crate::util::maybe_trim_rss;
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::sse::{Event, KeepAlive, Sse},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::Duration;
 use std::time::Instant;
+use tokio::sync::mpsc;
 
 const DEFAULT_SEARCH_LIMIT: usize = 100;
 const MAX_SEARCH_LIMIT: usize = 10_000;
 const SEARCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// 单个 SSE 连接待写出的事件上限（写不出去时背压到转发任务，不影响事件管线）。
+const SSE_BUFFER: usize = 64;
+const DEFAULT_CHANGES_LIMIT: usize = 1_000;
 
 #[derive(Clone, Debug, Default)]
 pub struct HealthTelemetry {
@@ -36,12 +56,28 @@ pub struct HealthTelemetry {
     pub startup_repair_escalated: bool,
     pub startup_repair_scanned: usize,
     pub startup_repair_changed: usize,
+    /// 前台预算用尽后仍在后台继续的启动修复
+    pub startup_repair_in_progress: bool,
+    pub startup_repair_resumed: bool,
+    pub startup_repair_dirs_done: usize,
+    pub startup_repair_dirs_pending: usize,
     pub last_clean_shutdown: bool,
     pub l1_dirs: usize,
     pub l2_dirs: usize,
     pub l3_dirs: usize,
     pub watch_budget_utilization_pct: u8,
     pub promotion_budget_blocked: u64,
+    pub audit_enabled: bool,
+    /// 最近一轮一致性抽查的完成时间（unix 秒，0 = 尚未抽查）
+    pub audit_last_unix_secs: u64,
+    /// 最近一轮抽查的漂移率（drifted / checked）
+    pub audit_drift_ratio: f64,
+    pub audit_drifted_total: u64,
+    /// watcher 队列溢出触发的补扫次数（内核丢弃的事件只能由溢出信号得知）
+    pub audit_overflow_rescans: u64,
+    /// 批次已应用但 WAL 未跟上的日志缺口次数（每次触发一次快照落盘）
+    pub audit_journal_gaps: u64,
+    pub audit_repairs: u64,
 }
 
 #[derive(Deserialize)]
@@ -51,14 +87,104 @@ pub struct SearchParams {
     pub mode: Option<String>,
     pub sort: Option<String>,
     pub order: Option<String>,
+    /// `all`（默认）/ `dedupe` / `group`：硬链接按 inode 去重或分组
+    pub inode: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct SubscribeParams {
+    pub q: String,
+    pub limit: Option<usize>,
+    pub sort: Option<String>,
+    pub order: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MetricsParams {
+    /// `json` / `prometheus`；省略时按 `Accept` 协商（默认 Prometheus 文本）
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ExplainParams {
+    pub q: String,
+    /// 为 true 时按 `limit` 实际执行一次，返回 overlay / base 分层计数
+    #[serde(default)]
+    pub execute: bool,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct SlowQueriesParams {
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct SlowQueriesResponse {
+    pub threshold_ms: u64,
+    pub queries: Vec<SlowQuery>,
+}
+
+#[derive(Deserialize)]
+pub struct ChangesParams {
+    pub since: Option<u64>,
+    pub q: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct ChangeItem {
+    pub seq: u64,
+    pub kind: &'static str,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub timestamp_ms: u64,
+    /// 读取时文件已不存在则省略
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_dir: Option<bool>,
+}
+
+#[derive(Serialize)]
+pub struct ChangesResponse {
+    pub since: u64,
+    pub next: u64,
+    pub latest: u64,
+    pub has_more: bool,
+    pub changes: Vec<ChangeItem>,
+}
+
+#[derive(Serialize)]
+pub struct ChangesExpired {
+    pub error: &'static str,
+    pub since: u64,
+    pub oldest: u64,
+    pub latest: u64,
+}
+
+#[derive(Serialize)]
+pub struct RemovedPath {
+    pub path: String,
 }
 
 #[derive(Serialize)]
 pub struct SearchResult {
     pub path: String,
     pub size: u64,
+    pub is_dir: bool,
     pub score: i64,
     pub highlights: Vec<[usize; 2]>,
+    /// `inode=group` 时同一 inode 在结果中的其余路径
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<String>,
+    /// 符号链接的目标（`read_link` 原文）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// 符号链接目标是否已失效
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub broken: Option<bool>,
 }
 
 #[derive(Deserialize)]
@@ -71,10 +197,50 @@ pub struct ScanResponse {
     pub scanned: usize,
     pub elapsed_ms: u64,
 }
+#[derive(Deserialize)]
+pub struct RootParams {
+    pub path: String,
+}
+
+#[derive(Serialize)]
+pub struct RootsResponse {
+    pub roots: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct RootChangeResponse {
+    pub change: &'static str,
+    pub root: String,
+    /// Added：扫描到的条目数；Removed：被 tombstone 的条目数
+    pub entries: usize,
+    pub elapsed_ms: u64,
+    pub roots: Vec<String>,
+}
+
+impl From<RootChange> for RootChangeResponse {
+    fn from(change: RootChange) -> Self {
+        Self {
+            change: change.kind.as_str(),
+            root: change.root.to_string_lossy().into_owned(),
+            entries: change.entries,
+            elapsed_ms: change.elapsed_ms,
+            roots: roots_as_strings(&change.roots),
+        }
+    }
+}
+
+pub(crate) fn roots_as_strings(roots: &[PathBuf]) -> Vec<String> {
+    roots
+        .iter()
+        .map(|r| r.to_string_lossy().into_owned())
+        .collect()
+}
+
 #[derive(Serialize)]
 pub struct StatusResponse {
     pub indexed_count: usize,
     pub is_rebuilding: bool,
+    pub partitions: Vec<PartitionStatus>,
 }
 
 #[derive(Serialize)]
@@ -98,12 +264,28 @@ pub struct HealthResponse {
     pub startup_repair_escalated: bool,
     pub startup_repair_scanned: usize,
     pub startup_repair_changed: usize,
+    /// 前台预算用尽后仍在后台继续的启动修复
+    pub startup_repair_in_progress: bool,
+    pub startup_repair_resumed: bool,
+    pub startup_repair_dirs_done: usize,
+    pub startup_repair_dirs_pending: usize,
     pub last_clean_shutdown: bool,
     pub l1_dirs: usize,
     pub l2_dirs: usize,
     pub l3_dirs: usize,
     pub watch_budget_utilization_pct: u8,
     pub promotion_budget_blocked: u64,
+    pub audit_enabled: bool,
+    /// 最近一轮一致性抽查的完成时间（unix 秒，0 = 尚未抽查）
+    pub audit_last_unix_secs: u64,
+    /// 最近一轮抽查的漂移率（drifted / checked）
+    pub audit_drift_ratio: f64,
+    pub audit_drifted_total: u64,
+    /// watcher 队列溢出触发的补扫次数（内核丢弃的事件只能由溢出信号得知）
+    pub audit_overflow_rescans: u64,
+    /// 批次已应用但 WAL 未跟上的日志缺口次数（每次触发一次快照落盘）
+    pub audit_journal_gaps: u64,
+    pub audit_repairs: u64,
     pub issues: Vec<String>,
 }
 
@@ -114,6 +296,205 @@ pub struct TrimResponse {
     pub reclaimed_bytes: u64,
 }
 
+/// HTTP 监听地址：TCP（`host:port`）或 Unix socket（`unix:<path>`）。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HttpBind {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl HttpBind {
+    /// 兼容旧行为的默认地址：`127.0.0.1:<port>`。
+    pub fn localhost(port: u16) -> Self {
+        HttpBind::Tcp(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))
+    }
+
+    /// 只有本机可达：回环地址或 Unix socket。
+    pub fn is_loopback(&self) -> bool {
+        match self {
+            HttpBind::Tcp(addr) => addr.ip().is_loopback(),
+            HttpBind::Unix(_) => true,
+        }
+    }
+
+    pub fn parse(s: &str) -> anyhow::Result<Self> {
+        let s = s.trim();
+        if let Some(path) = s.strip_prefix("unix:") {
+            if path.is_empty() {
+                anyhow::bail!("invalid http bind {:?}: empty unix socket path", s);
+            }
+            return Ok(HttpBind::Unix(PathBuf::from(path)));
+        }
+        s.parse::<SocketAddr>().map(HttpBind::Tcp).map_err(|e| {
+            anyhow::anyhow!(
+                "invalid http bind {:?}: expected host:port or unix:<path> ({})",
+                s,
+                e
+            )
+        })
+    }
+}
+
+impl std::fmt::Display for HttpBind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HttpBind::Tcp(addr) => write!(f, "http://{}", addr),
+            HttpBind::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// 访问范围：只读查询 / 会改变 daemon 状态的管理操作（`/scan`、`/trim`、`POST/DELETE /roots`、`/partitions/rebuild`）。
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HttpScope {
+    Read,
+    Admin,
+}
+
+/// HTTP 鉴权策略（对应 UDS 侧的 `PeerAuthPolicy`）：`Authorization: Bearer <token>`。
+///
+/// admin token 同时拥有 read 范围；两类 token 都未配置时不鉴权（旧行为）。
+/// `/health` 始终开放，供存活探测使用。
+#[derive(Clone, Debug, Default)]
+pub struct HttpAuthPolicy {
+    admin_tokens: Vec<String>,
+    read_tokens: Vec<String>,
+}
+
+impl HttpAuthPolicy {
+    /// 不鉴权。
+    pub fn open() -> Self {
+        Self::default()
+    }
+
+    pub fn with_tokens(admin: Option<String>, read: Option<String>) -> Self {
+        let keep = |t: Option<String>| t.filter(|t| !t.is_empty()).into_iter().collect();
+        Self {
+            admin_tokens: keep(admin),
+            read_tokens: keep(read),
+        }
+    }
+
+    /// 按配置构建；cookie 模式会生成随机 admin token 并写入 cookie 文件（0600）。
+    pub fn from_config(cfg: &HttpAuthConfig, default_cookie_path: &Path) -> anyhow::Result<Self> {
+        match cfg.mode {
+            HttpAuthMode::None => Ok(Self::open()),
+            HttpAuthMode::Token => {
+                let policy = Self::with_tokens(cfg.token.clone(), cfg.read_token.clone());
+                if policy.is_open() {
+                    anyhow::bail!("http_auth.mode = \"token\" requires token or read_token");
+                }
+                Ok(policy)
+            }
+            HttpAuthMode::Cookie => {
+                let path = cfg.cookie_path.as_deref().unwrap_or(default_cookie_path);
+                let token = random_token()?;
+                write_cookie_file(path, &token)?;
+                tracing::info!("HTTP auth cookie written to {}", path.display());
+                Ok(Self::with_tokens(Some(token), cfg.read_token.clone()))
+            }
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.admin_tokens.is_empty() && self.read_tokens.is_empty()
+    }
+
+    fn authorize(&self, scope: HttpScope, headers: &HeaderMap) -> Result<(), StatusCode> {
+        if self.is_open() {
+            return Ok(());
+        }
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::trim)
+            .ok_or(StatusCode::UNAUTHORIZED)?;
+        let matches = |tokens: &[String]| tokens.iter().any(|t| constant_time_eq(t, token));
+        if matches(&self.admin_tokens) {
+            return Ok(());
+        }
+        if matches(&self.read_tokens) {
+            return match scope {
+                HttpScope::Read => Ok(()),
+                HttpScope::Admin => Err(StatusCode::FORBIDDEN),
+            };
+        }
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// 比较两侧的定长摘要（进程内随机 seed 的 xxh3-128），耗时与 token 长度及首个差异位置无关。
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    let seed = token_digest_seed();
+    let da = xxhash_rust::xxh3::xxh3_128_with_seed(a.as_bytes(), seed).to_le_bytes();
+    let db = xxhash_rust::xxh3::xxh3_128_with_seed(b.as_bytes(), seed).to_le_bytes();
+    da.iter()
+        .zip(db.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+fn token_digest_seed() -> u64 {
+    static SEED: std::sync::OnceLock<u64> = std::sync::OnceLock::new();
+    *SEED.get_or_init(|| {
+        use std::io::Read;
+        let mut buf = [0u8; 8];
+        match std::fs::File::open("/dev/urandom").and_then(|mut f| f.read_exact(&mut buf)) {
+            Ok(()) => u64::from_le_bytes(buf),
+            Err(_) => std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or(0x9e37_79b9_7f4a_7c15),
+        }
+    })
+}
+
+fn random_token() -> anyhow::Result<String> {
+    use std::io::Read;
+    let mut buf = [0u8; 32];
+    std::fs::File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(&mut buf))
+        .map_err(|e| anyhow::anyhow!("failed to generate http auth cookie: {}", e))?;
+    Ok(buf.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn write_cookie_file(path: &Path, token: &str) -> anyhow::Result<()> {
+    use std::io::Write;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // 先删除再以 0600 新建，避免沿用旧文件的宽松权限
+    let _ = std::fs::remove_file(path);
+    let mut opts = std::fs::OpenOptions::new();
+    opts.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(0o600);
+    }
+    let mut file = opts.open(path)?;
+    file.write_all(token.as_bytes())?;
+    Ok(())
+}
+
+async fn require_scope(
+    State((policy, scope)): State<(Arc<HttpAuthPolicy>, HttpScope)>,
+    req: Request,
+    next: Next,
+) -> Response {
+    match policy.authorize(scope, req.headers()) {
+        Ok(()) => next.run(req).await,
+        Err(StatusCode::UNAUTHORIZED) => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "missing or invalid bearer token",
+        )
+            .into_response(),
+        Err(code) => (code, "token lacks required scope").into_response(),
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 struct QueryServerConfig {
     default_limit: usize,
@@ -147,6 +528,7 @@ pub struct QueryServer {
     health_provider: Arc<dyn Fn() -> HealthTelemetry + Send + Sync>,
     stats_provider: Arc<dyn Fn() -> EventPipelineStats + Send + Sync>,
     watch_state_provider: Arc<dyn Fn() -> WatchStateReport + Send + Sync>,
+    auth: Arc<HttpAuthPolicy>,
 }
 
 impl QueryServer {
@@ -157,9 +539,15 @@ impl QueryServer {
             health_provider: Arc::new(HealthTelemetry::default),
             stats_provider: Arc::new(EventPipelineStats::default),
             watch_state_provider: Arc::new(WatchStateReport::default),
+            auth: Arc::new(HttpAuthPolicy::open()),
         }
     }
 
+    pub fn with_auth(mut self, policy: HttpAuthPolicy) -> Self {
+        self.auth = Arc::new(policy);
+        self
+    }
+
     pub fn with_health_provider(
         mut self,
         provider: Arc<dyn Fn() -> HealthTelemetry + Send + Sync>,
@@ -185,6 +573,23 @@ impl QueryServer {
     }
 
     pub async fn run(self, port: u16) -> anyhow::Result<()> {
+        self.run_on(HttpBind::localhost(port)).await
+    }
+
+    pub async fn run_on(self, bind: HttpBind) -> anyhow::Result<()> {
+        let app = self.router();
+        match bind {
+            HttpBind::Tcp(addr) => {
+                let listener = tokio::net::TcpListener::bind(addr).await?;
+                tracing::info!("HTTP Query Server listening on {}", addr);
+                axum::serve(listener, app).await?;
+                Ok(())
+            }
+            HttpBind::Unix(path) => serve_unix(&path, app).await,
+        }
+    }
+
+    fn router(self) -> Router {
         let state = QueryServerState {
             index: self.index,
             config: self.config,
@@ -193,24 +598,102 @@ impl QueryServer {
             stats_provider: self.stats_provider,
             watch_state_provider: self.watch_state_provider,
         };
-        let app = Router::new()
+        let read = Router::new()
             .route("/search", get(search_handler))
+            .route("/subscribe", get(subscribe_handler))
+            .route("/changes", get(changes_handler))
             .route("/status", get(status_handler))
-            .route("/health", get(health_handler))
             .route("/memory", get(memory_handler))
             .route("/watch-state", get(watch_state_handler))
-            .route("/trim", get(trim_handler).post(trim_handler))
             .route("/metrics", get(metrics_handler))
+            .route("/slow-queries", get(slow_queries_handler))
+            .route("/explain", get(explain_handler))
+            .route("/roots", get(roots_handler))
+            .route_layer(middleware::from_fn_with_state(
+                (self.auth.clone(), HttpScope::Read),
+                require_scope,
+            ));
+        let admin = Router::new()
+            .route("/trim", get(trim_handler).post(trim_handler))
             .route("/scan", post(scan_handler))
-            .with_state(state);
+            .route("/roots", post(add_root_handler).delete(remove_root_handler))
+            .route("/partitions/rebuild", post(rebuild_partition_handler))
+            .route_layer(middleware::from_fn_with_state(
+                (self.auth, HttpScope::Admin),
+                require_scope,
+            ));
+        Router::new()
+            .route("/health", get(health_handler))
+            .merge(read)
+            .merge(admin)
+            .with_state(state)
+    }
+}
 
-        let listener = tokio::net::TcpListener::bind(format!("127.0.0.1:{}", port)).await?;
-        tracing::info!("HTTP Query Server listening on port {}", port);
-        axum::serve(listener, app).await?;
-        Ok(())
+/// `axum::serve` 只接受 TCP listener：Unix socket 上逐连接交给 hyper 驱动。
+#[cfg(unix)]
+async fn serve_unix(path: &Path, app: Router) -> anyhow::Result<()> {
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use hyper_util::server::conn::auto::Builder;
+    use hyper_util::service::TowerToHyperService;
+
+    if let Some(parent) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(parent) {
+            tracing::warn!(
+                "Failed to create http socket parent dir {}: {e}",
+                parent.display()
+            );
+        }
+    }
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            tracing::warn!(
+                "Failed to remove old http socket file {}: {e}",
+                path.display()
+            );
+        }
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Err(e) = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600)) {
+            tracing::warn!(
+                "Failed to set http socket permissions on {}: {e}",
+                path.display()
+            );
+        }
+    }
+    tracing::info!("HTTP Query Server listening on unix:{}", path.display());
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                tracing::warn!("http socket accept failed: {}", e);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                continue;
+            }
+        };
+        let service = TowerToHyperService::new(app.clone());
+        tokio::spawn(async move {
+            if let Err(e) = Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await
+            {
+                tracing::debug!("http socket connection error: {}", e);
+            }
+        });
     }
 }
 
+#[cfg(not(unix))]
+async fn serve_unix(path: &Path, _app: Router) -> anyhow::Result<()> {
+    anyhow::bail!(
+        "unix socket http bind is not supported on this platform: {}",
+        path.display()
+    )
+}
+
 fn normalize_search_limit(limit: Option<usize>, config: QueryServerConfig) -> usize {
     limit
         .unwrap_or(config.default_limit)
@@ -229,6 +712,12 @@ async fn search_handler(
     let limit = normalize_search_limit(params.limit, state.config);
     let mode =
         resolve_query_mode(params.mode.as_deref()).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let inode = InodeMode::parse_label(params.inode.as_deref()).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("invalid inode mode: {}", e),
+        )
+    })?;
     let keyword = params.q;
     let index = state.index.clone();
 
@@ -237,7 +726,7 @@ async fn search_handler(
     let sort = SortColumn::parse(params.sort.as_deref());
     let order = SortOrder::parse(params.order.as_deref());
     let search_task = tokio::task::spawn_blocking(move || {
-        execute_query(index.as_ref(), &kw_clone, limit, mode, sort, order)
+        execute_query_grouped(index.as_ref(), &kw_clone, limit, mode, sort, order, inode)
     });
     let results = match tokio::time::timeout(state.config.query_timeout, search_task).await {
         Ok(Ok(results)) => results,
@@ -271,26 +760,280 @@ async fn search_handler(
     let config = ScoreConfig::from_query(&keyword);
     let response = results
         .into_iter()
-        .map(|m| {
-            let path_str = m.path.to_string_lossy().into_owned();
-            let score = score_result(&m, &config);
-            let highlights = compute_highlights(&path_str, &keyword);
-            SearchResult {
-                path: path_str,
-                size: m.size,
-                score,
-                highlights,
-            }
+        .map(|(m, links)| {
+            let mut result = to_search_result(&m, &config, &keyword);
+            result.links = links
+                .iter()
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect();
+            result
         })
         .collect();
 
     Ok(Json(response))
 }
 
+fn to_search_result(m: &FileMeta, config: &ScoreConfig, keyword: &str) -> SearchResult {
+    let path_str = m.path.to_string_lossy().into_owned();
+    let score = score_result(m, config);
+    let highlights = compute_highlights(&path_str, keyword);
+    SearchResult {
+        path: path_str,
+        size: m.size,
+        is_dir: m.kind.is_dir(),
+        score,
+        highlights,
+        links: Vec::new(),
+        target: m
+            .target
+            .as_ref()
+            .map(|t| t.path.to_string_lossy().into_owned()),
+        broken: m.target.as_ref().map(|t| t.broken),
+    }
+}
+
+/// SSE 事件流：由转发任务写入，连接断开时 drop 使转发任务退出并退订。
+struct SubscriptionStream {
+    rx: mpsc::Receiver<Event>,
+}
+
+impl futures_core::Stream for SubscriptionStream {
+    type Item = Result<Event, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx).map(|ev| ev.map(Ok))
+    }
+}
+
+/// `GET /subscribe?q=...`：先推送 `snapshot`（初始结果集），之后推送 `add` / `update` / `remove` 差异；
+/// 积压丢弃时推送 `reset`（重新查询得到的全量结果），客户端应整体替换视图。
+async fn subscribe_handler(
+    Query(params): Query<SubscribeParams>,
+    State(state): State<QueryServerState>,
+) -> Result<Sse<SubscriptionStream>, (StatusCode, String)> {
+    let subscription = state.index.subscribe(&params.q).map_err(|e| match e {
+        SubscribeError::Query(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+        SubscribeError::TooManySubscribers(_) => (StatusCode::SERVICE_UNAVAILABLE, e.to_string()),
+    })?;
+    let limit = normalize_search_limit(params.limit, state.config);
+    let sort = SortColumn::parse(params.sort.as_deref());
+    let order = SortOrder::parse(params.order.as_deref());
+
+    let (tx, rx) = mpsc::channel(SSE_BUFFER);
+    let forward = SubscriptionForward {
+        index: state.index.clone(),
+        keyword: params.q,
+        limit,
+        sort,
+        order,
+        config: state.config,
+    };
+    tokio::spawn(forward.run(subscription, tx));
+    Ok(Sse::new(SubscriptionStream { rx }).keep_alive(KeepAlive::default()))
+}
+
+struct SubscriptionForward {
+    index: Arc<TieredIndex>,
+    keyword: String,
+    limit: usize,
+    sort: SortColumn,
+    order: SortOrder,
+    config: QueryServerConfig,
+}
+
+impl SubscriptionForward {
+    async fn run(self, mut subscription: Subscription, tx: mpsc::Sender<Event>) {
+        let score_config = ScoreConfig::from_query(&self.keyword);
+        if !self.send_full(&subscription, &tx, "snapshot").await {
+            return;
+        }
+        loop {
+            let update = tokio::select! {
+                update = subscription.recv() => update,
+                _ = tx.closed() => return,
+            };
+            let Some(update) = update else {
+                return;
+            };
+            let event = match update {
+                SubscriptionUpdate::Added(m) => Event::default()
+                    .event("add")
+                    .json_data(to_search_result(&m, &score_config, &self.keyword)),
+                SubscriptionUpdate::Updated(m) => Event::default()
+                    .event("update")
+                    .json_data(to_search_result(&m, &score_config, &self.keyword)),
+                SubscriptionUpdate::Removed(p) => {
+                    Event::default().event("remove").json_data(RemovedPath {
+                        path: p.to_string_lossy().into_owned(),
+                    })
+                }
+                SubscriptionUpdate::Lagged => {
+                    if !self.send_full(&subscription, &tx, "reset").await {
+                        return;
+                    }
+                    continue;
+                }
+            };
+            let Ok(event) = event else {
+                continue;
+            };
+            if tx.send(event).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// 执行一次完整查询，重置订阅视图并推送全量结果；连接已断开时返回 false。
+    async fn send_full(
+        &self,
+        subscription: &Subscription,
+        tx: &mpsc::Sender<Event>,
+        name: &'static str,
+    ) -> bool {
+        let index = self.index.clone();
+        let keyword = self.keyword.clone();
+        let (limit, sort, order) = (self.limit, self.sort, self.order);
+        let task = tokio::task::spawn_blocking(move || {
+            execute_query(
+                index.as_ref(),
+                &keyword,
+                limit,
+                QueryMode::Exact,
+                sort,
+                order,
+            )
+        });
+        let results = match tokio::time::timeout(self.config.query_timeout, task).await {
+            Ok(Ok(results)) => results,
+            Ok(Err(e)) => {
+                tracing::error!("subscription query task failed: {}", e);
+                return false;
+            }
+            Err(_) => {
+                tracing::warn!(
+                    "subscription query timed out after {:?}",
+                    self.config.query_timeout
+                );
+                return false;
+            }
+        };
+        subscription.reset_visible(results.iter().map(|m| m.path.clone()));
+
+        let score_config = ScoreConfig::from_query(&self.keyword);
+        let payload: Vec<SearchResult> = results
+            .iter()
+            .map(|m| to_search_result(m, &score_config, &self.keyword))
+            .collect();
+        match Event::default().event(name).json_data(payload) {
+            Ok(event) => tx.send(event).await.is_ok(),
+            Err(e) => {
+                tracing::error!("subscription payload encode failed: {}", e);
+                false
+            }
+        }
+    }
+}
+
+/// `GET /changes?since=<seq>&q=...`：读取游标之后的变更（来自仍保留的 WAL）。
+///
+/// 省略 `since` 时只返回当前游标；游标已被 WAL 清理时返回 410，客户端需全量重新同步。
+async fn changes_handler(
+    Query(params): Query<ChangesParams>,
+    State(state): State<QueryServerState>,
+) -> Result<Json<ChangesResponse>, Response> {
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_CHANGES_LIMIT)
+        .clamp(1, state.config.max_limit);
+    let index = state.index.clone();
+    let task = tokio::task::spawn_blocking(move || {
+        let since = match params.since {
+            Some(since) => since,
+            None => index.change_cursor().ok_or(ChangesError::Unavailable)?,
+        };
+        index
+            .changes_since(since, params.q.as_deref().unwrap_or(""), limit)
+            .map(|feed| (since, feed))
+    });
+    let (since, feed) = match tokio::time::timeout(state.config.query_timeout, task).await {
+        Ok(Ok(Ok(v))) => v,
+        Ok(Ok(Err(e))) => return Err(changes_error_response(e)),
+        Ok(Err(e)) => {
+            tracing::error!("HTTP changes task failed: {}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "changes task failed").into_response());
+        }
+        Err(_) => {
+            return Err((
+                StatusCode::REQUEST_TIMEOUT,
+                format!(
+                    "changes timed out after {} ms",
+                    state.config.query_timeout.as_millis()
+                ),
+            )
+                .into_response());
+        }
+    };
+
+    Ok(Json(ChangesResponse {
+        since,
+        next: feed.next,
+        latest: feed.latest,
+        has_more: feed.next < feed.latest,
+        changes: feed.changes.iter().map(to_change_item).collect(),
+    }))
+}
+
+fn changes_error_response(e: ChangesError) -> Response {
+    match e {
+        ChangesError::Query(e) => (StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+        ChangesError::Expired {
+            since,
+            oldest,
+            latest,
+        } => (
+            StatusCode::GONE,
+            Json(ChangesExpired {
+                error: "cursor too old, resync required",
+                since,
+                oldest,
+                latest,
+            }),
+        )
+            .into_response(),
+        ChangesError::Unavailable => {
+            (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response()
+        }
+        ChangesError::Read(_) => {
+            tracing::warn!("HTTP changes failed: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+fn to_change_item(change: &ChangeEntry) -> ChangeItem {
+    ChangeItem {
+        seq: change.seq,
+        kind: change.kind.as_str(),
+        path: change.path.to_string_lossy().into_owned(),
+        from: change
+            .from
+            .as_ref()
+            .map(|p| p.to_string_lossy().into_owned()),
+        timestamp_ms: change
+            .timestamp
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        size: change.meta.as_ref().map(|m| m.size),
+        is_dir: change.meta.as_ref().map(|m| m.kind.is_dir()),
+    }
+}
+
 async fn status_handler(State(state): State<QueryServerState>) -> Json<StatusResponse> {
     Json(StatusResponse {
         indexed_count: state.index.file_count(),
         is_rebuilding: state.index.rebuild_in_progress(),
+        partitions: state.index.partition_status(),
     })
 }
 
@@ -336,6 +1079,18 @@ async fn health_handler(State(state): State<QueryServerState>) -> Json<HealthRes
     if health.startup_repair_escalated {
         issues.push("startup_repair: escalated to rebuild policy".to_string());
     }
+    if health.startup_repair_in_progress {
+        issues.push(format!(
+            "startup_repair: in progress dirs_done={} dirs_pending={}",
+            health.startup_repair_dirs_done, health.startup_repair_dirs_pending
+        ));
+    }
+    if health.audit_drift_ratio > 0.0 {
+        issues.push(format!(
+            "audit_drift: ratio={:.4} drifted_total={} repairs={}",
+            health.audit_drift_ratio, health.audit_drifted_total, health.audit_repairs
+        ));
+    }
     if health.last_snapshot_time == 0 {
         issues.push("snapshot_not_written_yet".to_string());
     }
@@ -368,23 +1123,237 @@ async fn health_handler(State(state): State<QueryServerState>) -> Json<HealthRes
         startup_repair_escalated: health.startup_repair_escalated,
         startup_repair_scanned: health.startup_repair_scanned,
         startup_repair_changed: health.startup_repair_changed,
+        startup_repair_in_progress: health.startup_repair_in_progress,
+        startup_repair_resumed: health.startup_repair_resumed,
+        startup_repair_dirs_done: health.startup_repair_dirs_done,
+        startup_repair_dirs_pending: health.startup_repair_dirs_pending,
         last_clean_shutdown: health.last_clean_shutdown,
         l1_dirs: health.l1_dirs,
         l2_dirs: health.l2_dirs,
         l3_dirs: health.l3_dirs,
         watch_budget_utilization_pct: health.watch_budget_utilization_pct,
         promotion_budget_blocked: health.promotion_budget_blocked,
+        audit_enabled: health.audit_enabled,
+        audit_last_unix_secs: health.audit_last_unix_secs,
+        audit_drift_ratio: health.audit_drift_ratio,
+        audit_drifted_total: health.audit_drifted_total,
+        audit_overflow_rescans: health.audit_overflow_rescans,
+        audit_journal_gaps: health.audit_journal_gaps,
+        audit_repairs: health.audit_repairs,
         issues,
     })
 }
 
-async fn metrics_handler(State(state): State<QueryServerState>) -> impl IntoResponse {
+/// `GET /metrics`：默认 Prometheus 文本格式；`?format=json` 或 `Accept: application/json` 返回 JSON。
+async fn metrics_handler(
+    Query(params): Query<MetricsParams>,
+    headers: HeaderMap,
+    State(state): State<QueryServerState>,
+) -> Response {
+    let json = match metrics_wants_json(params.format.as_deref(), &headers) {
+        Ok(json) => json,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
     let mut report: StatsReport = state.index.stats_report();
     let pipeline = (state.stats_provider)();
     report.events_dropped = report
         .events_dropped
         .saturating_add(pipeline.overflow_drops);
-    Json(report)
+    if json {
+        return Json(report).into_response();
+    }
+
+    let index = state.index.clone();
+    let memory = match tokio::task::spawn_blocking(move || index.memory_report(pipeline)).await {
+        Ok(memory) => memory,
+        Err(e) => {
+            tracing::error!("HTTP metrics task failed: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, "metrics task failed").into_response();
+        }
+    };
+    let mut out = PromText::new();
+    prometheus::encode_stats_report(&mut out, &report);
+    prometheus::encode_event_pipeline(&mut out, &memory.event_pipeline);
+    prometheus::encode_memory_report(&mut out, &memory);
+    prometheus::encode_watch_state(&mut out, &(state.watch_state_provider)());
+    encode_health(
+        &mut out,
+        &(state.health_provider)(),
+        state.start_time.elapsed().as_secs(),
+    );
+    (
+        [(header::CONTENT_TYPE, prometheus::CONTENT_TYPE)],
+        out.finish(),
+    )
+        .into_response()
+}
+
+fn metrics_wants_json(format: Option<&str>, headers: &HeaderMap) -> Result<bool, String> {
+    match format {
+        Some("json") => Ok(true),
+        Some("prometheus") | Some("text") => Ok(false),
+        Some(other) => Err(format!(
+            "invalid metrics format: {} (expected json or prometheus)",
+            other
+        )),
+        None => Ok(headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|accept| accept.contains("application/json"))),
+    }
+}
+
+/// 恢复 / 运行状态（`HealthTelemetry`）的 Prometheus 指标。
+fn encode_health(out: &mut PromText, health: &HealthTelemetry, uptime_secs: u64) {
+    let flag = |b: bool| if b { 1.0 } else { 0.0 };
+    out.gauge(
+        "fd_rdd_uptime_seconds",
+        "Seconds since the HTTP server started.",
+        uptime_secs as f64,
+    )
+    .gauge(
+        "fd_rdd_last_snapshot_timestamp_seconds",
+        "Unix time of the last successful snapshot (0 = never).",
+        health.last_snapshot_time as f64,
+    )
+    .gauge(
+        "fd_rdd_watch_enabled",
+        "1 if the filesystem watcher is enabled.",
+        flag(health.watch_enabled),
+    )
+    .family(
+        "fd_rdd_recovery_info",
+        "Snapshot source used at startup (value is always 1).",
+        MetricKind::Gauge,
+    )
+    .sample(
+        "fd_rdd_recovery_info",
+        &[("snapshot_source", &health.snapshot_source)],
+        1.0,
+    )
+    .gauge(
+        "fd_rdd_recovery_wal_events_replayed",
+        "WAL events replayed at startup.",
+        health.wal_events_replayed as f64,
+    )
+    .gauge(
+        "fd_rdd_recovery_wal_truncated_tail_records",
+        "Torn WAL tail records discarded at startup.",
+        health.wal_truncated_tail_records as f64,
+    )
+    .gauge(
+        "fd_rdd_recovery_last_clean_shutdown",
+        "1 if the previous run shut down cleanly.",
+        flag(health.last_clean_shutdown),
+    )
+    .gauge(
+        "fd_rdd_startup_repair_ran",
+        "1 if startup repair ran.",
+        flag(health.startup_repair_ran),
+    )
+    .gauge(
+        "fd_rdd_startup_repair_escalated",
+        "1 if startup repair escalated to a full rebuild.",
+        flag(health.startup_repair_escalated),
+    )
+    .gauge(
+        "fd_rdd_startup_repair_scanned_dirs",
+        "Directories scanned by startup repair.",
+        health.startup_repair_scanned as f64,
+    )
+    .gauge(
+        "fd_rdd_startup_repair_changed",
+        "Entries changed by startup repair.",
+        health.startup_repair_changed as f64,
+    )
+    .gauge(
+        "fd_rdd_startup_repair_in_progress",
+        "1 while startup repair continues in the background.",
+        flag(health.startup_repair_in_progress),
+    )
+    .gauge(
+        "fd_rdd_startup_repair_dirs_done",
+        "Directories repaired so far (including resumed runs).",
+        health.startup_repair_dirs_done as f64,
+    )
+    .gauge(
+        "fd_rdd_startup_repair_dirs_pending",
+        "Directories still queued for startup repair.",
+        health.startup_repair_dirs_pending as f64,
+    )
+    .gauge(
+        "fd_rdd_audit_last_timestamp_seconds",
+        "Unix time of the last consistency audit round (0 = never).",
+        health.audit_last_unix_secs as f64,
+    )
+    .gauge(
+        "fd_rdd_audit_drift_ratio",
+        "Share of audited entries that disagreed with the filesystem in the last round.",
+        health.audit_drift_ratio,
+    )
+    .counter(
+        "fd_rdd_audit_drifted_entries_total",
+        "Audited entries found out of sync with the filesystem.",
+        health.audit_drifted_total,
+    )
+    .counter(
+        "fd_rdd_audit_overflow_rescans_total",
+        "Watcher queue overflows (Rescan) that triggered a reconcile.",
+        health.audit_overflow_rescans,
+    )
+    .counter(
+        "fd_rdd_audit_journal_gaps_total",
+        "Applied event batches the WAL journal did not fully record.",
+        health.audit_journal_gaps,
+    )
+    .counter(
+        "fd_rdd_audit_repairs_total",
+        "Targeted fast-syncs triggered by the consistency auditor.",
+        health.audit_repairs,
+    );
+}
+
+/// `GET /slow-queries?limit=N`：最近超过阈值的查询（最新在前），含阶段耗时与候选规模。
+async fn slow_queries_handler(
+    Query(params): Query<SlowQueriesParams>,
+    State(state): State<QueryServerState>,
+) -> Json<SlowQueriesResponse> {
+    let mut queries = state.index.slow_queries();
+    if let Some(limit) = params.limit {
+        queries.truncate(limit);
+    }
+    Json(SlowQueriesResponse {
+        threshold_ms: state.index.slow_query_threshold().as_millis() as u64,
+        queries,
+    })
+}
+
+async fn explain_handler(
+    Query(params): Query<ExplainParams>,
+    State(state): State<QueryServerState>,
+) -> Result<Json<IndexExplain>, (StatusCode, String)> {
+    let execute_limit = params
+        .execute
+        .then(|| normalize_search_limit(params.limit, state.config));
+    let index = state.index.clone();
+    let task = tokio::task::spawn_blocking(move || index.explain(&params.q, execute_limit));
+    match tokio::time::timeout(state.config.query_timeout, task).await {
+        Ok(Ok(explain)) => Ok(Json(explain)),
+        Ok(Err(e)) => {
+            tracing::error!("HTTP explain task failed: {}", e);
+            Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "explain task failed".to_string(),
+            ))
+        }
+        Err(_) => Err((
+            StatusCode::REQUEST_TIMEOUT,
+            format!(
+                "explain timed out after {} ms",
+                state.config.query_timeout.as_millis()
+            ),
+        )),
+    }
 }
 
 async fn memory_handler(State(state): State<QueryServerState>) -> Json<MemoryReport> {
@@ -431,6 +1400,65 @@ async fn scan_handler(
     }))
 }
 
+async fn roots_handler(State(state): State<QueryServerState>) -> Json<RootsResponse> {
+    Json(RootsResponse {
+        roots: roots_as_strings(&state.index.roots()),
+    })
+}
+
+/// `POST /roots {"path": ...}`：新增 root 并扫描其子树（同步完成后返回）。
+async fn add_root_handler(
+    State(state): State<QueryServerState>,
+    Json(params): Json<RootParams>,
+) -> Result<Json<RootChangeResponse>, (StatusCode, String)> {
+    let index = state.index.clone();
+    let root = PathBuf::from(params.path);
+    tokio::task::spawn_blocking(move || index.add_root(&root))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(|change| Json(change.into()))
+        .map_err(roots_error_response)
+}
+
+/// `DELETE /roots {"path": ...}`：移除 root，并 tombstone 其子树下的全部条目。
+async fn remove_root_handler(
+    State(state): State<QueryServerState>,
+    Json(params): Json<RootParams>,
+) -> Result<Json<RootChangeResponse>, (StatusCode, String)> {
+    let index = state.index.clone();
+    let root = PathBuf::from(params.path);
+    tokio::task::spawn_blocking(move || index.remove_root(&root))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(|change| Json(change.into()))
+        .map_err(roots_error_response)
+}
+
+/// `POST /partitions/rebuild {"path": ...}`：只重扫一个 root 的分区，其他分区不受影响。
+async fn rebuild_partition_handler(
+    State(state): State<QueryServerState>,
+    Json(params): Json<RootParams>,
+) -> Result<Json<PartitionRebuild>, (StatusCode, String)> {
+    let index = state.index.clone();
+    let root = PathBuf::from(params.path);
+    tokio::task::spawn_blocking(move || index.rebuild_partition(&root))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .map_err(roots_error_response)
+}
+
+fn roots_error_response(e: RootsError) -> (StatusCode, String) {
+    let status = match &e {
+        RootsError::NotAbsolute(_) | RootsError::NotADirectory(_) => StatusCode::BAD_REQUEST,
+        RootsError::Overlaps { .. } | RootsError::LastRoot | RootsError::RebuildInProgress => {
+            StatusCode::CONFLICT
+        }
+        RootsError::NotFound(_) => StatusCode::NOT_FOUND,
+    };
+    (status, e.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -447,6 +1475,168 @@ mod tests {
         );
     }
 
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn http_bind_parses_tcp_and_unix_addresses() {
+        assert_eq!(
+            HttpBind::parse("0.0.0.0:7070").unwrap(),
+            HttpBind::Tcp("0.0.0.0:7070".parse().unwrap())
+        );
+        assert_eq!(
+            HttpBind::parse("unix:/run/fd-rdd/http.sock").unwrap(),
+            HttpBind::Unix(PathBuf::from("/run/fd-rdd/http.sock"))
+        );
+        assert!(HttpBind::parse("unix:").is_err());
+        assert!(HttpBind::parse("localhost").is_err());
+        assert_eq!(
+            HttpBind::localhost(6060).to_string(),
+            "http://127.0.0.1:6060"
+        );
+        assert!(HttpBind::localhost(6060).is_loopback());
+        assert!(HttpBind::parse("[::1]:7070").unwrap().is_loopback());
+        assert!(HttpBind::parse("unix:/run/fd-rdd/http.sock")
+            .unwrap()
+            .is_loopback());
+        assert!(!HttpBind::parse("0.0.0.0:7070").unwrap().is_loopback());
+    }
+
+    #[test]
+    fn token_compare_uses_fixed_length_digests() {
+        assert!(constant_time_eq("secret-token", "secret-token"));
+        assert!(!constant_time_eq("secret-token", "secret-tokeN"));
+        assert!(!constant_time_eq("secret-token", "secret"));
+        assert!(!constant_time_eq("", "secret"));
+    }
+
+    #[test]
+    fn open_policy_allows_everything() {
+        let policy = HttpAuthPolicy::open();
+        assert!(policy
+            .authorize(HttpScope::Admin, &HeaderMap::new())
+            .is_ok());
+    }
+
+    #[test]
+    fn read_token_cannot_reach_admin_scope() {
+        let policy = HttpAuthPolicy::with_tokens(Some("admin".into()), Some("reader".into()));
+
+        assert_eq!(
+            policy.authorize(HttpScope::Read, &HeaderMap::new()),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            policy.authorize(HttpScope::Read, &bearer("wrong")),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        assert!(policy.authorize(HttpScope::Read, &bearer("reader")).is_ok());
+        assert_eq!(
+            policy.authorize(HttpScope::Admin, &bearer("reader")),
+            Err(StatusCode::FORBIDDEN)
+        );
+        assert!(policy.authorize(HttpScope::Read, &bearer("admin")).is_ok());
+        assert!(policy.authorize(HttpScope::Admin, &bearer("admin")).is_ok());
+    }
+
+    #[test]
+    fn token_mode_requires_a_token_and_cookie_mode_writes_private_file() {
+        let cfg = HttpAuthConfig {
+            mode: HttpAuthMode::Token,
+            ..HttpAuthConfig::default()
+        };
+        assert!(HttpAuthPolicy::from_config(&cfg, Path::new("unused")).is_err());
+
+        let dir = std::env::temp_dir().join(format!("fd-rdd-http-cookie-{}", std::process::id()));
+        let cookie = dir.join("http.cookie");
+        let cfg = HttpAuthConfig {
+            mode: HttpAuthMode::Cookie,
+            ..HttpAuthConfig::default()
+        };
+        let policy = HttpAuthPolicy::from_config(&cfg, &cookie).unwrap();
+        let token = std::fs::read_to_string(&cookie).unwrap();
+        assert_eq!(token.len(), 64);
+        assert!(policy.authorize(HttpScope::Admin, &bearer(&token)).is_ok());
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&cookie).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // 每次启动重新生成，旧 cookie 失效
+        let _ = HttpAuthPolicy::from_config(&cfg, &cookie).unwrap();
+        assert_ne!(std::fs::read_to_string(&cookie).unwrap(), token);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn metrics_format_negotiation() {
+        let mut accept_json = HeaderMap::new();
+        accept_json.insert(header::ACCEPT, "application/json".parse().unwrap());
+        let mut scraper = HeaderMap::new();
+        scraper.insert(
+            header::ACCEPT,
+            "text/plain;version=0.0.4;q=0.5,*/*;q=0.1".parse().unwrap(),
+        );
+
+        assert_eq!(metrics_wants_json(None, &HeaderMap::new()), Ok(false));
+        assert_eq!(metrics_wants_json(None, &scraper), Ok(false));
+        assert_eq!(metrics_wants_json(None, &accept_json), Ok(true));
+        assert_eq!(metrics_wants_json(Some("json"), &scraper), Ok(true));
+        assert_eq!(
+            metrics_wants_json(Some("prometheus"), &accept_json),
+            Ok(false)
+        );
+        assert!(metrics_wants_json(Some("xml"), &HeaderMap::new()).is_err());
+    }
+
+    #[test]
+    fn health_metrics_cover_recovery_fields() {
+        let health = HealthTelemetry {
+            snapshot_source: "stable".to_string(),
+            wal_events_replayed: 12,
+            last_clean_shutdown: true,
+            ..HealthTelemetry::default()
+        };
+        let mut out = PromText::new();
+        encode_health(&mut out, &health, 5);
+        let text = out.finish();
+        assert!(text.contains("fd_rdd_recovery_info{snapshot_source=\"stable\"} 1\n"));
+        assert!(text.contains("fd_rdd_recovery_wal_events_replayed 12\n"));
+        assert!(text.contains("fd_rdd_recovery_last_clean_shutdown 1\n"));
+        assert!(text.contains("fd_rdd_uptime_seconds 5\n"));
+    }
+
+    #[test]
+    fn health_metrics_cover_audit_fields() {
+        let health = HealthTelemetry {
+            audit_enabled: true,
+            audit_last_unix_secs: 1_700_000_000,
+            audit_drift_ratio: 0.25,
+            audit_drifted_total: 3,
+            audit_overflow_rescans: 2,
+            audit_journal_gaps: 1,
+            audit_repairs: 4,
+            ..HealthTelemetry::default()
+        };
+        let mut out = PromText::new();
+        encode_health(&mut out, &health, 0);
+        let text = out.finish();
+        assert!(text.contains("fd_rdd_audit_last_timestamp_seconds 1700000000\n"));
+        assert!(text.contains("fd_rdd_audit_drift_ratio 0.25\n"));
+        assert!(text.contains("fd_rdd_audit_drifted_entries_total 3\n"));
+        assert!(text.contains("fd_rdd_audit_overflow_rescans_total 2\n"));
+        assert!(text.contains("fd_rdd_audit_journal_gaps_total 1\n"));
+        assert!(text.contains("fd_rdd_audit_repairs_total 4\n"));
+    }
+
     #[test]
     fn resolve_query_mode_supports_fuzzy() {
         assert_eq!(resolve_query_mode(None).unwrap(), QueryMode::Exact);
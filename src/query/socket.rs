@@ -1,12 +1,59 @@
 use crate::index::TieredIndex;
 use std::sync::Arc;
 
+/// 版本化请求头：请求首行为该值时启用结构化协议（输出格式 / 排序 / 状态行）；
+/// 否则按旧的 `q:` / `limit:` 文本协议处理。
+///
+/// ```text
+/// FDRDD/1
+/// q:<query>
+/// limit:<n>
+/// mode:exact|fuzzy
+/// sort:<column>
+/// order:asc|desc
+/// format:lines|nul|json
+/// ```
+///
+/// 响应首行为状态行 `FDRDD/1 ok` 或 `FDRDD/1 error <message>`，之后按 `format` 输出结果：
+/// 换行分隔、NUL 分隔（配合 `xargs -0`），或每行一个 JSON 对象
+/// （`path`/`size`/`mtime`（unix 毫秒）/`is_dir`/`score`/`highlights`）。
+///
+/// root 管理（与 HTTP `/roots` 对应）用 `cmd:` 代替 `q:`：`cmd:roots` 列出 root，
+/// `cmd:add-root` / `cmd:remove-root` 配合 `path:<dir>` 增删 root。成功后按 `format`
+/// 输出变更后的 root 列表（`json` 时为一个与 HTTP 响应相同的对象）；失败时状态行带错误信息。
+pub const UDS_PROTOCOL_V1: &str = "FDRDD/1";
+
+/// 会话模式首行：同一连接上可连续发送多个请求，供 search-as-you-type 前端复用连接。
+///
+/// 服务端先回 `FDRDD/1 ok`。之后每个请求帧为若干 `key:value` 行（同 `FDRDD/1`，
+/// 另可带 `id:<token>`，缺省按连接内序号 1, 2, ... 编号），以空行结束。每个请求的响应为：
+///
+/// ```text
+/// ok <id>                 （或 error <id> <message>）
+/// data <len>\n<len 字节>   按 format 编码的一批结果，可出现多次
+/// end <id> <count>        （或 cancelled <id>）
+/// ```
+///
+/// 新请求到达时，同一连接上仍在进行的请求被取消（回 `cancelled <id>`），
+/// 其响应总是先于新请求的 `ok` 结束。客户端关闭写端后，服务端完成当前请求再断开。
+pub const UDS_SESSION_V1: &str = "FDRDD/1 session";
+
 #[cfg(unix)]
 mod imp {
     use super::*;
-    use crate::query::{execute_query, QueryMode, SortColumn, SortOrder};
+    use crate::core::FileMeta;
+    use crate::query::scoring::{compute_highlights, score_result, ScoreConfig};
+    use crate::query::server::{roots_as_strings, RootChangeResponse, RootsResponse};
+    use crate::query::{
+        execute_query, execute_query_cancellable, QueryMode, SortColumn, SortOrder,
+    };
+    use serde::Serialize;
     use std::path::{Path, PathBuf};
-    use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufWriter};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tokio::io::{
+        AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+        BufReader, BufWriter,
+    };
     use tokio::net::{unix::UCred, UnixListener, UnixStream};
     use tokio::sync::oneshot;
 
@@ -185,14 +232,27 @@ mod imp {
         handle_connection_io(index, cfg, socket).await
     }
 
-    async fn handle_connection_io(
+    async fn handle_connection_io<S>(
         index: Arc<TieredIndex>,
         cfg: SocketConfig,
-        mut socket: impl AsyncRead + AsyncWrite + Unpin,
-    ) -> anyhow::Result<()> {
+        socket: S,
+    ) -> anyhow::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        // 先只读首行判断是否为会话模式：会话连接不会关闭写端，不能 read_to_end。
+        let mut socket = BufReader::new(socket);
         let mut req: Vec<u8> = Vec::with_capacity(256);
-        let n = socket.read_to_end(&mut req).await?;
-        if n == 0 {
+        (&mut socket)
+            .take(cfg.max_request_bytes as u64 + 1)
+            .read_until(b'\n', &mut req)
+            .await?;
+        if req.trim_ascii() == UDS_SESSION_V1.as_bytes() {
+            return handle_session(index, cfg, socket).await;
+        }
+
+        socket.read_to_end(&mut req).await?;
+        if req.is_empty() {
             return Ok(());
         }
         if req.len() > cfg.max_request_bytes {
@@ -200,6 +260,563 @@ mod imp {
         }
 
         let request = String::from_utf8_lossy(&req);
+        let parsed = match request
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .and_then(|l| l.strip_prefix("FDRDD/"))
+        {
+            Some(version) => {
+                let parsed = if version == "1" {
+                    let body = request
+                        .trim_start()
+                        .split_once('\n')
+                        .map_or("", |(_, rest)| rest);
+                    parse_v1_request(body, &cfg)
+                } else {
+                    Err(format!("unsupported protocol version: FDRDD/{version}"))
+                };
+                if let Ok(SocketRequest {
+                    command: Some(command),
+                    format,
+                    ..
+                }) = parsed
+                {
+                    let (status, body) = match run_root_command(index, command, format).await? {
+                        Ok(body) => (format!("{UDS_PROTOCOL_V1} ok\n"), body),
+                        Err(e) => (format!("{UDS_PROTOCOL_V1} error {e}\n"), Vec::new()),
+                    };
+                    socket.write_all(status.as_bytes()).await?;
+                    socket.write_all(&body).await?;
+                    let _ = socket.shutdown().await;
+                    return Ok(());
+                }
+                let status = match &parsed {
+                    Ok(_) => format!("{UDS_PROTOCOL_V1} ok\n"),
+                    Err(e) => format!("{UDS_PROTOCOL_V1} error {e}\n"),
+                };
+                socket.write_all(status.as_bytes()).await?;
+                match parsed {
+                    Ok(parsed) => Some(parsed).filter(|r| !r.keyword.is_empty()),
+                    Err(_) => {
+                        let _ = socket.shutdown().await;
+                        return Ok(());
+                    }
+                }
+            }
+            None => parse_legacy_request(&request, &cfg)?,
+        };
+        let Some(req) = parsed else {
+            let _ = socket.shutdown().await;
+            return Ok(());
+        };
+
+        let results = execute_query(
+            index.as_ref(),
+            &req.keyword,
+            req.limit,
+            req.mode,
+            req.sort,
+            req.order,
+        );
+
+        // 流式写回：不要在内存里拼接巨大 String/JSON。
+        let encoder = RecordEncoder::new(&req);
+        let mut record = Vec::new();
+        let mut w = BufWriter::new(&mut socket);
+        for (i, meta) in results.iter().enumerate() {
+            record.clear();
+            encoder.encode(meta, &mut record)?;
+            w.write_all(&record).await?;
+            if cfg.flush_every > 0 && (i + 1) % cfg.flush_every == 0 {
+                w.flush().await?;
+            }
+        }
+        w.flush().await?;
+        let _ = socket.shutdown().await;
+        Ok(())
+    }
+
+    /// 在进程内执行一个 `FDRDD/1` 请求体（不含首行），按其 `format` 把结果写入 `out`，
+    /// 返回结果条数。解析、查询与编码与 socket 服务端完全一致，供离线查询复用。
+    pub fn execute_v1_request<W: std::io::Write>(
+        index: &TieredIndex,
+        body: &str,
+        out: &mut W,
+    ) -> anyhow::Result<usize> {
+        let req = parse_v1_request(body, &SocketConfig::default()).map_err(anyhow::Error::msg)?;
+        if req.command.is_some() {
+            anyhow::bail!("root management requires a running daemon");
+        }
+        if req.keyword.is_empty() {
+            return Ok(0);
+        }
+        let results = execute_query(
+            index,
+            &req.keyword,
+            req.limit,
+            req.mode,
+            req.sort,
+            req.order,
+        );
+        let encoder = RecordEncoder::new(&req);
+        let mut record = Vec::new();
+        for meta in &results {
+            record.clear();
+            encoder.encode(meta, &mut record)?;
+            out.write_all(&record)?;
+        }
+        out.flush()?;
+        Ok(results.len())
+    }
+
+    /// 会话中正在执行的请求：发送取消信号后等待其交还写端。
+    struct InFlight<W> {
+        cancel: oneshot::Sender<()>,
+        handle: tokio::task::JoinHandle<anyhow::Result<W>>,
+    }
+
+    async fn handle_session<S>(
+        index: Arc<TieredIndex>,
+        cfg: SocketConfig,
+        socket: BufReader<S>,
+    ) -> anyhow::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (reader, mut writer) = tokio::io::split(socket);
+        let mut reader = BufReader::new(reader);
+        writer
+            .write_all(format!("{UDS_PROTOCOL_V1} ok\n").as_bytes())
+            .await?;
+        writer.flush().await?;
+
+        // 写端在任一时刻只归一个请求所有：上一个请求结束（或被取消）后才交给下一个，
+        // 保证响应不交错。
+        let mut idle_writer = Some(writer);
+        let mut inflight: Option<InFlight<_>> = None;
+        let mut seq = 0u64;
+        while let Some(frame) = read_frame(&mut reader, cfg.max_request_bytes).await? {
+            if let Some(prev) = inflight.take() {
+                let _ = prev.cancel.send(());
+                idle_writer = Some(prev.handle.await??);
+            }
+            seq += 1;
+            let id = frame_id(&frame).unwrap_or_else(|| seq.to_string());
+            let writer = idle_writer
+                .take()
+                .expect("session writer is returned by the previous request");
+            let (cancel, cancelled) = oneshot::channel();
+            let handle = tokio::spawn(run_session_request(
+                index.clone(),
+                cfg,
+                frame,
+                id,
+                writer,
+                cancelled,
+            ));
+            inflight = Some(InFlight { cancel, handle });
+        }
+
+        let mut writer = match inflight {
+            Some(prev) => prev.handle.await??,
+            None => idle_writer.expect("session writer is idle"),
+        };
+        let _ = writer.shutdown().await;
+        Ok(())
+    }
+
+    /// 读取一个请求帧（以空行结束）；连接关闭时返回 None。
+    async fn read_frame<R>(reader: &mut R, max_bytes: usize) -> anyhow::Result<Option<String>>
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        let mut frame: Vec<u8> = Vec::new();
+        loop {
+            let start = frame.len();
+            let n = (&mut *reader)
+                .take((max_bytes + 1 - start) as u64)
+                .read_until(b'\n', &mut frame)
+                .await?;
+            if frame.len() > max_bytes {
+                anyhow::bail!("request frame too large: > {} bytes", max_bytes);
+            }
+            let blank = frame[start..].trim_ascii().is_empty();
+            if n == 0 || blank {
+                frame.truncate(start);
+                if !frame.trim_ascii().is_empty() {
+                    return Ok(Some(String::from_utf8_lossy(&frame).into_owned()));
+                }
+                if n == 0 {
+                    return Ok(None);
+                }
+                // 帧之间多余的空行
+                frame.clear();
+            }
+        }
+    }
+
+    fn frame_id(frame: &str) -> Option<String> {
+        frame.lines().map(str::trim).find_map(|line| {
+            line.strip_prefix("id:")
+                .or_else(|| line.strip_prefix("id="))
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+        })
+    }
+
+    async fn run_session_request<W>(
+        index: Arc<TieredIndex>,
+        cfg: SocketConfig,
+        frame: String,
+        id: String,
+        mut writer: W,
+        mut cancelled: oneshot::Receiver<()>,
+    ) -> anyhow::Result<W>
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let req = match parse_v1_request(&frame, &cfg) {
+            Ok(req) => req,
+            Err(e) => {
+                writer
+                    .write_all(format!("error {id} {e}\n").as_bytes())
+                    .await?;
+                writer.flush().await?;
+                return Ok(writer);
+            }
+        };
+        if let Some(command) = req.command {
+            match run_root_command(index, command, req.format).await? {
+                Ok(body) => {
+                    writer.write_all(format!("ok {id}\n").as_bytes()).await?;
+                    writer
+                        .write_all(format!("data {}\n", body.len()).as_bytes())
+                        .await?;
+                    writer.write_all(&body).await?;
+                    writer.write_all(format!("end {id} 0\n").as_bytes()).await?;
+                }
+                Err(e) => {
+                    writer
+                        .write_all(format!("error {id} {e}\n").as_bytes())
+                        .await?;
+                }
+            }
+            writer.flush().await?;
+            return Ok(writer);
+        }
+        writer.write_all(format!("ok {id}\n").as_bytes()).await?;
+        writer.flush().await?;
+        if req.keyword.is_empty() {
+            writer.write_all(format!("end {id} 0\n").as_bytes()).await?;
+            writer.flush().await?;
+            return Ok(writer);
+        }
+
+        let keyword = req.keyword.clone();
+        let (limit, mode, sort, order) = (req.limit, req.mode, req.sort, req.order);
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_query = stop.clone();
+        let mut task = tokio::task::spawn_blocking(move || {
+            execute_query_cancellable(
+                index.as_ref(),
+                &keyword,
+                limit,
+                mode,
+                sort,
+                order,
+                &stop_query,
+            )
+        });
+        // 取消时置位标志：扫描在下一个候选块处停下，等它退出后再交还写端，
+        // 避免被取消的查询与下一个请求争抢 CPU。
+        let results = tokio::select! {
+            results = &mut task => results?,
+            _ = &mut cancelled => {
+                stop.store(true, Ordering::Relaxed);
+                let _ = task.await;
+                writer.write_all(format!("cancelled {id}\n").as_bytes()).await?;
+                writer.flush().await?;
+                return Ok(writer);
+            }
+        };
+
+        let encoder = RecordEncoder::new(&req);
+        let mut chunk = Vec::new();
+        for batch in results.chunks(cfg.flush_every.max(1)) {
+            if !matches!(
+                cancelled.try_recv(),
+                Err(oneshot::error::TryRecvError::Empty)
+            ) {
+                writer
+                    .write_all(format!("cancelled {id}\n").as_bytes())
+                    .await?;
+                writer.flush().await?;
+                return Ok(writer);
+            }
+            chunk.clear();
+            for meta in batch {
+                encoder.encode(meta, &mut chunk)?;
+            }
+            writer
+                .write_all(format!("data {}\n", chunk.len()).as_bytes())
+                .await?;
+            writer.write_all(&chunk).await?;
+            writer.flush().await?;
+        }
+        writer
+            .write_all(format!("end {id} {}\n", results.len()).as_bytes())
+            .await?;
+        writer.flush().await?;
+        Ok(writer)
+    }
+
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+    enum OutputFormat {
+        #[default]
+        Lines,
+        Nul,
+        Json,
+    }
+
+    impl OutputFormat {
+        fn parse(value: &str) -> Result<Self, String> {
+            match value {
+                "lines" | "newline" => Ok(Self::Lines),
+                "nul" | "null" | "0" => Ok(Self::Nul),
+                "json" | "jsonl" => Ok(Self::Json),
+                other => Err(format!(
+                    "invalid format: {other} (expected one of: lines, nul, json)"
+                )),
+            }
+        }
+    }
+
+    struct SocketRequest {
+        keyword: String,
+        limit: usize,
+        mode: QueryMode,
+        sort: SortColumn,
+        order: SortOrder,
+        format: OutputFormat,
+        command: Option<RootCommand>,
+    }
+
+    /// `cmd:` 给出的 root 管理命令。
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    enum RootCommand {
+        List,
+        Add(PathBuf),
+        Remove(PathBuf),
+    }
+
+    impl RootCommand {
+        fn parse(cmd: &str, path: Option<&str>) -> Result<Self, String> {
+            let path = || {
+                path.filter(|p| !p.is_empty())
+                    .map(PathBuf::from)
+                    .ok_or_else(|| format!("cmd:{cmd} requires path"))
+            };
+            match cmd {
+                "roots" => Ok(Self::List),
+                "add-root" => Ok(Self::Add(path()?)),
+                "remove-root" => Ok(Self::Remove(path()?)),
+                other => Err(format!(
+                    "invalid cmd: {other} (expected one of: roots, add-root, remove-root)"
+                )),
+            }
+        }
+    }
+
+    /// 执行 root 命令（增删在阻塞线程池上完成），成功时返回按 `format` 编码的响应体。
+    async fn run_root_command(
+        index: Arc<TieredIndex>,
+        command: RootCommand,
+        format: OutputFormat,
+    ) -> anyhow::Result<Result<Vec<u8>, String>> {
+        let outcome = tokio::task::spawn_blocking(move || match command {
+            RootCommand::List => Ok((None, index.roots())),
+            RootCommand::Add(root) => index
+                .add_root(&root)
+                .map(|change| (Some(change.clone()), change.roots)),
+            RootCommand::Remove(root) => index
+                .remove_root(&root)
+                .map(|change| (Some(change.clone()), change.roots)),
+        })
+        .await?;
+        let (change, roots) = match outcome {
+            Ok(ok) => ok,
+            Err(e) => return Ok(Err(e.to_string())),
+        };
+
+        let mut body = Vec::new();
+        match (format, change) {
+            (OutputFormat::Json, Some(change)) => {
+                serde_json::to_writer(&mut body, &RootChangeResponse::from(change))?;
+                body.push(b'\n');
+            }
+            (OutputFormat::Json, None) => {
+                serde_json::to_writer(
+                    &mut body,
+                    &RootsResponse {
+                        roots: roots_as_strings(&roots),
+                    },
+                )?;
+                body.push(b'\n');
+            }
+            (format, _) => {
+                for root in &roots {
+                    body.extend_from_slice(root.as_os_str().as_encoded_bytes());
+                    body.push(if format == OutputFormat::Nul {
+                        b'\0'
+                    } else {
+                        b'\n'
+                    });
+                }
+            }
+        }
+        Ok(Ok(body))
+    }
+
+    /// 按输出格式编码单条结果（仅 JSON 需要打分）。
+    struct RecordEncoder<'a> {
+        format: OutputFormat,
+        keyword: &'a str,
+        score_config: Option<ScoreConfig>,
+    }
+
+    impl<'a> RecordEncoder<'a> {
+        fn new(req: &'a SocketRequest) -> Self {
+            Self {
+                format: req.format,
+                keyword: &req.keyword,
+                score_config: (req.format == OutputFormat::Json)
+                    .then(|| ScoreConfig::from_query(&req.keyword)),
+            }
+        }
+
+        fn encode(&self, meta: &FileMeta, out: &mut Vec<u8>) -> anyhow::Result<()> {
+            match (&self.score_config, self.format) {
+                (Some(config), _) => {
+                    serde_json::to_writer(&mut *out, &JsonRecord::new(meta, config, self.keyword))?;
+                    out.push(b'\n');
+                }
+                (None, format) => {
+                    out.extend_from_slice(meta.path.as_os_str().as_encoded_bytes());
+                    out.push(if format == OutputFormat::Nul {
+                        b'\0'
+                    } else {
+                        b'\n'
+                    });
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Serialize)]
+    struct JsonRecord {
+        path: String,
+        size: u64,
+        /// unix 毫秒；未知时为 null
+        mtime: Option<i64>,
+        is_dir: bool,
+        score: i64,
+        highlights: Vec<[usize; 2]>,
+        /// 符号链接目标；非链接时省略
+        #[serde(skip_serializing_if = "Option::is_none")]
+        target: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        broken: Option<bool>,
+    }
+
+    impl JsonRecord {
+        fn new(meta: &FileMeta, config: &ScoreConfig, keyword: &str) -> Self {
+            let path = meta.path.to_string_lossy().into_owned();
+            let highlights = compute_highlights(&path, keyword);
+            Self {
+                size: meta.size,
+                mtime: meta
+                    .mtime
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_millis() as i64),
+                is_dir: meta.kind.is_dir(),
+                score: score_result(meta, config),
+                highlights,
+                target: meta
+                    .target
+                    .as_ref()
+                    .map(|t| t.path.to_string_lossy().into_owned()),
+                broken: meta.target.as_ref().map(|t| t.broken),
+                path,
+            }
+        }
+    }
+
+    fn normalize_limit(limit: Option<usize>, cfg: &SocketConfig) -> usize {
+        let mut limit = limit.unwrap_or(cfg.default_limit);
+        if limit == 0 {
+            limit = cfg.default_limit;
+        }
+        limit.min(cfg.max_limit).max(1)
+    }
+
+    /// `FDRDD/1` 请求体（不含首行）：逐行 `key:value`，未知 key 忽略（向前兼容），
+    /// 取值非法时报错。未给出 `q` 时 keyword 为空。
+    fn parse_v1_request(request: &str, cfg: &SocketConfig) -> Result<SocketRequest, String> {
+        let mut keyword: Option<&str> = None;
+        let mut limit: Option<usize> = None;
+        let mut mode = QueryMode::Exact;
+        let mut sort = SortColumn::default();
+        let mut order = SortOrder::default();
+        let mut format = OutputFormat::default();
+        let mut cmd: Option<&str> = None;
+        let mut path: Option<&str> = None;
+
+        for line in request.lines().map(str::trim) {
+            let Some(sep) = line.find([':', '=']) else {
+                continue;
+            };
+            let value = line[sep + 1..].trim();
+            match line[..sep].trim() {
+                "q" => keyword = Some(value),
+                "limit" => {
+                    limit = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid limit: {value}"))?,
+                    )
+                }
+                "mode" => {
+                    mode = QueryMode::parse_label(Some(value))
+                        .map_err(|e| format!("invalid mode: {e}"))?
+                }
+                "sort" => sort = SortColumn::parse(Some(value)),
+                "order" => order = SortOrder::parse(Some(value)),
+                "format" => format = OutputFormat::parse(value)?,
+                "cmd" => cmd = Some(value),
+                "path" => path = Some(value),
+                _ => {}
+            }
+        }
+        let command = cmd.map(|cmd| RootCommand::parse(cmd, path)).transpose()?;
+
+        Ok(SocketRequest {
+            keyword: keyword.unwrap_or_default().to_string(),
+            limit: normalize_limit(limit, cfg),
+            mode,
+            sort,
+            order,
+            format,
+            command,
+        })
+    }
+
+    /// 旧文本协议：`q:` / `limit:` / `mode:`，结果按换行输出、默认排序。
+    fn parse_legacy_request(
+        request: &str,
+        cfg: &SocketConfig,
+    ) -> anyhow::Result<Option<SocketRequest>> {
         let mut keyword: Option<&str> = None;
         let mut limit: Option<usize> = None;
         let mut mode = QueryMode::Exact;
@@ -243,37 +860,18 @@ mod imp {
 
         let keyword = match keyword.map(str::trim).filter(|s| !s.is_empty()) {
             Some(k) => k,
-            None => return Ok(()),
+            None => return Ok(None),
         };
 
-        let mut limit = limit.unwrap_or(cfg.default_limit);
-        if limit == 0 {
-            limit = cfg.default_limit;
-        }
-        limit = limit.min(cfg.max_limit).max(1);
-
-        let results = execute_query(
-            index.as_ref(),
-            keyword,
-            limit,
+        Ok(Some(SocketRequest {
+            keyword: keyword.to_string(),
+            limit: normalize_limit(limit, cfg),
             mode,
-            SortColumn::default(),
-            SortOrder::default(),
-        );
-
-        // 流式写回：不要在内存里拼接巨大 String/JSON。
-        let mut w = BufWriter::new(&mut socket);
-        for (i, meta) in results.iter().enumerate() {
-            w.write_all(meta.path.as_os_str().as_encoded_bytes())
-                .await?;
-            w.write_all(b"\n").await?;
-            if cfg.flush_every > 0 && (i + 1) % cfg.flush_every == 0 {
-                w.flush().await?;
-            }
-        }
-        w.flush().await?;
-        let _ = socket.shutdown().await;
-        Ok(())
+            sort: SortColumn::default(),
+            order: SortOrder::default(),
+            format: OutputFormat::Lines,
+            command: None,
+        }))
     }
 
     #[cfg(test)]
@@ -464,6 +1062,249 @@ mod imp {
             Ok(())
         }
 
+        async fn roundtrip(index: Arc<TieredIndex>, request: &[u8]) -> anyhow::Result<Vec<u8>> {
+            let (mut client, server) = duplex(64 * 1024);
+            let server_task =
+                tokio::spawn(handle_connection_io(index, SocketConfig::default(), server));
+            client.write_all(request).await?;
+            client.shutdown().await?;
+            let mut out: Vec<u8> = Vec::new();
+            client.read_to_end(&mut out).await?;
+            server_task.await??;
+            Ok(out)
+        }
+
+        #[tokio::test]
+        async fn v1_header_selects_framing_and_sort() -> anyhow::Result<()> {
+            let root = unique_tmp_dir("socket-v1");
+            let small = root.join("frame_small.txt");
+            let large = root.join("frame_large.txt");
+            let newline = root.join("frame_multi\nline.txt");
+            std::fs::write(&small, b"1")?;
+            std::fs::write(&large, b"12345")?;
+            std::fs::write(&newline, b"123")?;
+
+            let index = Arc::new(TieredIndex::empty(vec![root.clone()]));
+            index.apply_events(&[
+                ev(1, EventType::Create, small.clone()),
+                ev(2, EventType::Create, large.clone()),
+                ev(3, EventType::Create, newline.clone()),
+            ]);
+
+            // NUL 分隔：含换行的文件名保持完整。
+            let out = roundtrip(
+                index.clone(),
+                b"FDRDD/1\nq:frame_\nformat:nul\nsort:size\norder:desc\n",
+            )
+            .await?;
+            let body = out
+                .strip_prefix(b"FDRDD/1 ok\n".as_slice())
+                .expect("status line");
+            let paths: Vec<&[u8]> = body.split(|b| *b == 0).filter(|p| !p.is_empty()).collect();
+            let expected: Vec<&[u8]> = [&large, &newline, &small]
+                .iter()
+                .map(|p| p.as_os_str().as_encoded_bytes())
+                .collect();
+            assert_eq!(paths, expected);
+
+            // JSON lines：携带元数据与打分。
+            let out = roundtrip(
+                index.clone(),
+                b"FDRDD/1\nq:frame_small\nformat:json\nlimit:5\n",
+            )
+            .await?;
+            let s = String::from_utf8(out)?;
+            let mut lines = s.lines();
+            assert_eq!(lines.next(), Some("FDRDD/1 ok"));
+            let record: serde_json::Value = serde_json::from_str(lines.next().unwrap())?;
+            assert_eq!(record["path"], small.to_string_lossy().as_ref());
+            assert_eq!(record["size"], 1);
+            assert!(record["mtime"].as_i64().unwrap() > 0);
+            assert_eq!(record["is_dir"], false);
+            assert!(record["score"].is_i64());
+            assert!(!record["highlights"].as_array().unwrap().is_empty());
+            assert!(lines.next().is_none());
+
+            let _ = std::fs::remove_dir_all(&root);
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn v1_cmd_manages_roots() -> anyhow::Result<()> {
+            let root = unique_tmp_dir("socket-roots");
+            let extra = unique_tmp_dir("socket-roots-extra");
+            std::fs::write(extra.join("cmd_added.txt"), b"x")?;
+            let index = Arc::new(TieredIndex::empty(vec![root.clone()]));
+
+            let request = format!("FDRDD/1\ncmd:add-root\npath:{}\n\n", extra.display());
+            let out = roundtrip(index.clone(), request.as_bytes()).await?;
+            let expected = format!("FDRDD/1 ok\n{}\n{}\n", root.display(), extra.display());
+            assert_eq!(String::from_utf8(out)?, expected);
+            assert_eq!(index.query("cmd_added").len(), 1);
+
+            // 重复添加：错误写在状态行
+            let out = roundtrip(index.clone(), request.as_bytes()).await?;
+            assert!(String::from_utf8(out)?.starts_with("FDRDD/1 error "));
+
+            let request = format!("FDRDD/1\ncmd:remove-root\npath:{}\n\n", root.display());
+            roundtrip(index.clone(), request.as_bytes()).await?;
+            let out = roundtrip(index.clone(), b"FDRDD/1\ncmd:roots\nformat:json\n\n").await?;
+            let text = String::from_utf8(out)?;
+            let body: serde_json::Value =
+                serde_json::from_str(text.strip_prefix("FDRDD/1 ok\n").unwrap().trim())?;
+            assert_eq!(body["roots"], serde_json::json!([extra.to_string_lossy()]));
+
+            let out = roundtrip(index, b"FDRDD/1\ncmd:add-root\n\n").await?;
+            assert!(String::from_utf8(out)?.starts_with("FDRDD/1 error cmd:add-root requires path"));
+
+            let _ = std::fs::remove_dir_all(&root);
+            let _ = std::fs::remove_dir_all(&extra);
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn v1_header_reports_errors_in_status_line() -> anyhow::Result<()> {
+            let root = unique_tmp_dir("socket-v1-err");
+            let index = Arc::new(TieredIndex::empty(vec![root.clone()]));
+
+            let out = roundtrip(index.clone(), b"FDRDD/9\nq:x\n").await?;
+            assert_eq!(
+                String::from_utf8(out)?,
+                "FDRDD/1 error unsupported protocol version: FDRDD/9\n"
+            );
+            let out = roundtrip(index.clone(), b"FDRDD/1\nq:x\nformat:xml\n").await?;
+            assert!(String::from_utf8(out)?.starts_with("FDRDD/1 error invalid format: xml"));
+
+            let _ = std::fs::remove_dir_all(&root);
+            Ok(())
+        }
+
+        /// 读取会话响应直到 `end <id>` / `cancelled <id>`，返回 (结束行, data 中的路径)。
+        async fn read_session_response<R: AsyncBufRead + Unpin>(
+            reader: &mut R,
+            id: &str,
+        ) -> anyhow::Result<(String, Vec<String>)> {
+            let mut paths = Vec::new();
+            loop {
+                let mut line = String::new();
+                anyhow::ensure!(reader.read_line(&mut line).await? > 0, "unexpected eof");
+                let line = line.trim_end().to_string();
+                if let Some(len) = line.strip_prefix("data ") {
+                    let mut chunk = vec![0u8; len.parse()?];
+                    reader.read_exact(&mut chunk).await?;
+                    paths.extend(String::from_utf8(chunk)?.lines().map(str::to_string));
+                } else if line.starts_with(&format!("end {id} "))
+                    || line == format!("cancelled {id}")
+                    || line.starts_with(&format!("error {id} "))
+                {
+                    return Ok((line, paths));
+                }
+            }
+        }
+
+        #[tokio::test]
+        async fn session_serves_multiple_requests_on_one_connection() -> anyhow::Result<()> {
+            let root = unique_tmp_dir("socket-session");
+            let index = Arc::new(TieredIndex::empty(vec![root.clone()]));
+            let mut events = Vec::new();
+            for (i, name) in ["alpha_one.txt", "alpha_two.txt", "beta_one.txt"]
+                .iter()
+                .enumerate()
+            {
+                let p = root.join(name);
+                std::fs::write(&p, b"x")?;
+                events.push(ev(i as u64 + 1, EventType::Create, p));
+            }
+            index.apply_events(&events);
+
+            let (client, server) = duplex(64 * 1024);
+            let server_task = tokio::spawn(handle_connection_io(
+                index.clone(),
+                SocketConfig::default(),
+                server,
+            ));
+            let (reader, mut writer) = tokio::io::split(client);
+            let mut reader = BufReader::new(reader);
+
+            writer.write_all(b"FDRDD/1 session\n").await?;
+            let mut status = String::new();
+            reader.read_line(&mut status).await?;
+            assert_eq!(status, "FDRDD/1 ok\n");
+
+            writer.write_all(b"q:alpha\nsort:name\n\n").await?;
+            let (end, paths) = read_session_response(&mut reader, "1").await?;
+            assert_eq!(end, "end 1 2");
+            assert!(paths[0].ends_with("alpha_one.txt"));
+            assert!(paths[1].ends_with("alpha_two.txt"));
+
+            writer.write_all(b"id:beta-q\nq:beta\n\n").await?;
+            let (end, paths) = read_session_response(&mut reader, "beta-q").await?;
+            assert_eq!(end, "end beta-q 1");
+            assert!(paths[0].ends_with("beta_one.txt"));
+
+            writer.write_all(b"q:x\nformat:xml\n\n").await?;
+            let (end, _) = read_session_response(&mut reader, "3").await?;
+            assert!(end.starts_with("error 3 invalid format"));
+
+            // 关闭写端：服务端结束会话。
+            writer.shutdown().await?;
+            let mut rest = Vec::new();
+            reader.read_to_end(&mut rest).await?;
+            assert!(rest.is_empty());
+            server_task.await??;
+
+            let _ = std::fs::remove_dir_all(&root);
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn session_newer_request_cancels_in_flight_one() -> anyhow::Result<()> {
+            let root = unique_tmp_dir("socket-session-cancel");
+            let index = Arc::new(TieredIndex::empty(vec![root.clone()]));
+            let mut events = Vec::new();
+            for i in 0..64u64 {
+                let p = root.join(format!("cancel_probe_{i:02}.txt"));
+                std::fs::write(&p, b"x")?;
+                events.push(ev(i + 1, EventType::Create, p));
+            }
+            index.apply_events(&events);
+
+            // 小缓冲 + 每条一个 data 块：客户端不读时，第一个请求会卡在写出途中。
+            let (client, server) = duplex(256);
+            let cfg = SocketConfig {
+                flush_every: 1,
+                ..SocketConfig::default()
+            };
+            let server_task = tokio::spawn(handle_connection_io(index.clone(), cfg, server));
+            let (reader, mut writer) = tokio::io::split(client);
+            let mut reader = BufReader::new(reader);
+
+            writer
+                .write_all(b"FDRDD/1 session\nid:slow\nq:cancel_probe\n\n")
+                .await?;
+            let mut line = String::new();
+            reader.read_line(&mut line).await?;
+            assert_eq!(line, "FDRDD/1 ok\n");
+            line.clear();
+            reader.read_line(&mut line).await?;
+            assert_eq!(line, "ok slow\n");
+
+            writer.write_all(b"id:fast\nq:cancel_probe_07\n\n").await?;
+            let (end, paths) = read_session_response(&mut reader, "slow").await?;
+            assert_eq!(end, "cancelled slow");
+            assert!(paths.len() < 64);
+
+            let (end, paths) = read_session_response(&mut reader, "fast").await?;
+            assert_eq!(end, "end fast 1");
+            assert!(paths[0].ends_with("cancel_probe_07.txt"));
+
+            writer.shutdown().await?;
+            server_task.await??;
+
+            let _ = std::fs::remove_dir_all(&root);
+            Ok(())
+        }
+
         #[test]
         fn peer_auth_policy_defaults_to_same_uid_or_root() {
             let policy = PeerAuthPolicy {
@@ -518,4 +1359,6 @@ mod imp {
     }
 }
 
+#[cfg(unix)]
+pub use imp::execute_v1_request;
 pub use imp::SocketServer;
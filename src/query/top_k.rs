@@ -0,0 +1,240 @@
+//! 排序感知的 top-k 收集：先排名、后截断。
+//!
+//! 查询层按"先到先得"截断时，`limit=20` 拿到的只是索引顺序里最先命中的 20 条。
+//! `TopK` 在扫描过程中用有界大顶堆（堆顶为当前最差者）维护最优的 k 条，
+//! 比较规则与 `SortColumn` / `SortOrder` 的既有语义一致（Score 列固定降序）。
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::core::FileMeta;
+use crate::query::fzf::{SortColumn, SortOrder};
+use crate::query::scoring::{score_result, ScoreConfig};
+
+/// 预先计算好的排序键：避免在堆比较中重复打分/分配小写字符串。
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum SortKey {
+    Score(i64),
+    Text(String),
+    Field,
+}
+
+struct Ranked {
+    key: SortKey,
+    meta: FileMeta,
+    sort: SortColumn,
+    order: SortOrder,
+}
+
+impl Ranked {
+    fn new(meta: FileMeta, sort: SortColumn, order: SortOrder, config: &ScoreConfig) -> Self {
+        let key = match sort {
+            SortColumn::Score => SortKey::Score(score_result(&meta, config)),
+            SortColumn::Name => SortKey::Text(
+                meta.path
+                    .file_name()
+                    .map(|f| f.to_string_lossy().to_lowercase())
+                    .unwrap_or_default(),
+            ),
+            SortColumn::Ext => SortKey::Text(
+                meta.path
+                    .extension()
+                    .map(|e| e.to_string_lossy().to_lowercase())
+                    .unwrap_or_default(),
+            ),
+            _ => SortKey::Field,
+        };
+        Self {
+            key,
+            meta,
+            sort,
+            order,
+        }
+    }
+
+    /// `Less` 表示 self 排在 other 之前（更优）。
+    fn rank_cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (&self.meta, &other.meta);
+        let cmp = match (&self.key, &other.key) {
+            // Score: higher is better, so default desc
+            (SortKey::Score(sa), SortKey::Score(sb)) => {
+                return sb.cmp(sa).then_with(|| a.path.cmp(&b.path));
+            }
+            (SortKey::Text(ta), SortKey::Text(tb)) => ta.cmp(tb).then_with(|| a.path.cmp(&b.path)),
+            _ => match self.sort {
+                SortColumn::Size => a.size.cmp(&b.size).then_with(|| a.path.cmp(&b.path)),
+                SortColumn::DateModified => {
+                    cmp_time(a.mtime, b.mtime).then_with(|| a.path.cmp(&b.path))
+                }
+                SortColumn::DateCreated => {
+                    cmp_time(a.ctime, b.ctime).then_with(|| a.path.cmp(&b.path))
+                }
+                SortColumn::DateAccessed => {
+                    cmp_time(a.atime, b.atime).then_with(|| a.path.cmp(&b.path))
+                }
+                _ => a.path.cmp(&b.path),
+            },
+        };
+        if self.order == SortOrder::Desc {
+            cmp.reverse()
+        } else {
+            cmp
+        }
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank_cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank_cmp(other)
+    }
+}
+
+fn cmp_time(a: Option<std::time::SystemTime>, b: Option<std::time::SystemTime>) -> Ordering {
+    match (a, b) {
+        (Some(ta), Some(tb)) => ta.cmp(&tb),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+/// 有界 top-k 收集器：`push` 任意多条，始终只保留排名最优的 `k` 条。
+pub struct TopK {
+    k: usize,
+    sort: SortColumn,
+    order: SortOrder,
+    config: ScoreConfig,
+    heap: BinaryHeap<Ranked>,
+}
+
+impl TopK {
+    pub fn new(k: usize, keyword: &str, sort: SortColumn, order: SortOrder) -> Self {
+        Self {
+            k,
+            sort,
+            order,
+            config: ScoreConfig::from_query(keyword),
+            heap: BinaryHeap::with_capacity(k.min(1024).saturating_add(1)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn push(&mut self, meta: FileMeta) {
+        if self.k == 0 {
+            return;
+        }
+        let ranked = Ranked::new(meta, self.sort, self.order, &self.config);
+        if self.heap.len() >= self.k {
+            // 堆顶是当前第 k 名；不比它更优的直接丢弃，避免一次 push+pop。
+            match self.heap.peek() {
+                Some(worst) if ranked < *worst => {}
+                _ => return,
+            }
+            self.heap.pop();
+        }
+        self.heap.push(ranked);
+    }
+
+    /// 按排名输出（最优在前）。
+    pub fn into_sorted_vec(self) -> Vec<FileMeta> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|r| r.meta)
+            .collect()
+    }
+}
+
+/// 对已收集的结果做全量排序（与 `TopK` 共用比较规则）。
+pub fn sort_ranked(results: &mut Vec<FileMeta>, keyword: &str, sort: SortColumn, order: SortOrder) {
+    let config = ScoreConfig::from_query(keyword);
+    let mut ranked: Vec<Ranked> = results
+        .drain(..)
+        .map(|m| Ranked::new(m, sort, order, &config))
+        .collect();
+    ranked.sort();
+    results.extend(ranked.into_iter().map(|r| r.meta));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{FileKey, FileKind};
+    use std::path::PathBuf;
+
+    fn meta(path: &str, size: u64) -> FileMeta {
+        FileMeta {
+            file_key: FileKey {
+                dev: 0,
+                ino: size,
+                generation: 0,
+            },
+            path: PathBuf::from(path),
+            size,
+            mtime: None,
+            ctime: None,
+            atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
+        }
+    }
+
+    #[test]
+    fn top_k_keeps_best_scores_regardless_of_arrival_order() {
+        let mut top = TopK::new(2, "config", SortColumn::Score, SortOrder::Asc);
+        top.push(meta("/a/target/debug/deep/config_noise.rs", 1));
+        top.push(meta("/a/src/unrelated_config_helper.rs", 2));
+        top.push(meta("/a/config.toml", 3));
+        top.push(meta("/a/config", 4));
+        assert_eq!(top.len(), 2);
+
+        let out = top.into_sorted_vec();
+        let paths: Vec<_> = out.iter().map(|m| m.path.to_str().unwrap()).collect();
+        assert_eq!(paths, vec!["/a/config", "/a/config.toml"]);
+    }
+
+    #[test]
+    fn top_k_matches_full_sort_for_field_columns() {
+        let metas: Vec<FileMeta> = (0..50u64)
+            .map(|i| meta(&format!("/d/f{:02}.txt", (i * 37) % 50), (i * 7919) % 97))
+            .collect();
+        for (sort, order) in [
+            (SortColumn::Size, SortOrder::Desc),
+            (SortColumn::Size, SortOrder::Asc),
+            (SortColumn::Name, SortOrder::Asc),
+            (SortColumn::Path, SortOrder::Desc),
+        ] {
+            let mut full = metas.clone();
+            sort_ranked(&mut full, "", sort, order);
+            let mut top = TopK::new(5, "", sort, order);
+            for m in metas.iter().cloned() {
+                top.push(m);
+            }
+            let expected: Vec<_> = full.iter().take(5).map(|m| m.path.clone()).collect();
+            let got: Vec<_> = top.into_sorted_vec().into_iter().map(|m| m.path).collect();
+            assert_eq!(got, expected, "{sort:?} {order:?}");
+        }
+    }
+}
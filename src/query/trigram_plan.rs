@@ -0,0 +1,335 @@
+//! 正则 → trigram 候选计划。
+//!
+//! `regex:` 查询给不出单一 literal hint，过去只能全量扫描。这里从解析后的正则 AST
+//! 推导"任何匹配都必然包含"的 literal：有限小集合（如 `(jpg|png)`、`[0-9]`）按精确字符串集合
+//! 展开，其余部分退化为必需条件的 AND / 分支的 OR，最终交给索引层在 posting list 上求值。
+//!
+//! 索引只对路径组件（小写）建 trigram，因此 literal 一律小写、按路径分隔符切开，
+//! 计划求得的候选集始终是真实结果的超集，由匹配器逐条精确复核。
+
+use std::collections::BTreeSet;
+
+use regex_syntax::hir::{Class, Hir, HirKind};
+
+/// 精确字符串集合的大小上限；超出后改用 AND/OR 条件，避免笛卡尔积膨胀。
+const MAX_EXACT_SET: usize = 16;
+
+/// 字符类展开为精确集合的上限（小写去重前）。
+const MAX_CLASS_CHARS: usize = 16;
+
+/// trigram 候选计划：叶子为 literal（其全部 trigram 均需命中），内部节点为 AND / OR。
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrigramPlan {
+    /// 已小写、不含路径分隔符、长度 >= 3 的 literal
+    Literal(Vec<u8>),
+    /// 所有子计划均需满足；空集表示无约束
+    All(Vec<TrigramPlan>),
+    /// 至少一个子计划满足；空集表示不可能匹配
+    Any(Vec<TrigramPlan>),
+}
+
+impl TrigramPlan {
+    /// 从正则推导候选计划；无法缩小候选集（如 `.*`、`\d+`）或解析失败时返回 None。
+    pub fn from_regex(pattern: &str, case_sensitive: bool) -> Option<Self> {
+        let hir = regex_syntax::ParserBuilder::new()
+            .case_insensitive(!case_sensitive)
+            .build()
+            .parse(pattern)
+            .ok()?;
+        let plan = Info::of(&hir).into_plan();
+        (!plan.is_unconstrained()).then_some(plan)
+    }
+
+    /// 无约束（候选集为全集）
+    pub fn is_unconstrained(&self) -> bool {
+        matches!(self, TrigramPlan::All(v) if v.is_empty())
+    }
+
+    /// 粗略的区分度：任一匹配至少必须包含的 literal 长度（用于 anchor 选择）。
+    pub fn min_literal_len(&self) -> usize {
+        match self {
+            TrigramPlan::Literal(l) => l.len(),
+            TrigramPlan::All(v) => v.iter().map(Self::min_literal_len).max().unwrap_or(0),
+            TrigramPlan::Any(v) => v.iter().map(Self::min_literal_len).min().unwrap_or(0),
+        }
+    }
+
+    fn and(children: Vec<TrigramPlan>) -> Self {
+        let mut out = Vec::with_capacity(children.len());
+        for child in children {
+            match child {
+                TrigramPlan::All(v) => out.extend(v),
+                other => out.push(other),
+            }
+        }
+        out.dedup();
+        if out.len() == 1 {
+            return out.pop().unwrap_or(TrigramPlan::All(Vec::new()));
+        }
+        TrigramPlan::All(out)
+    }
+
+    fn or(children: Vec<TrigramPlan>) -> Self {
+        let mut out = Vec::with_capacity(children.len());
+        for child in children {
+            match child {
+                c if c.is_unconstrained() => return c,
+                TrigramPlan::Any(v) => out.extend(v),
+                other => out.push(other),
+            }
+        }
+        out.dedup();
+        if out.len() == 1 {
+            return out.pop().unwrap_or(TrigramPlan::Any(Vec::new()));
+        }
+        TrigramPlan::Any(out)
+    }
+
+    fn of_set(set: &BTreeSet<String>) -> Self {
+        Self::or(set.iter().map(|s| Self::of_str(s)).collect())
+    }
+
+    /// 单个必需字符串：按路径分隔符切开，只保留能产生 trigram 的片段。
+    fn of_str(s: &str) -> Self {
+        Self::and(
+            s.split(['/', '\\'])
+                .filter(|piece| piece.len() >= 3)
+                .map(|piece| TrigramPlan::Literal(piece.as_bytes().to_vec()))
+                .collect(),
+        )
+    }
+}
+
+/// `/explain` 用的紧凑表示：`"abc"`、`("abc" AND "def")`、`("jpg" OR "png")`。
+impl std::fmt::Display for TrigramPlan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (children, sep) = match self {
+            TrigramPlan::Literal(l) => return write!(f, "{:?}", String::from_utf8_lossy(l)),
+            TrigramPlan::All(v) if v.is_empty() => return f.write_str("*"),
+            TrigramPlan::Any(v) if v.is_empty() => return f.write_str("none"),
+            TrigramPlan::All(v) => (v, " AND "),
+            TrigramPlan::Any(v) => (v, " OR "),
+        };
+        f.write_str("(")?;
+        for (i, child) in children.iter().enumerate() {
+            if i > 0 {
+                f.write_str(sep)?;
+            }
+            write!(f, "{}", child)?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// 子表达式的分析结果：能精确枚举时保留字符串集合，否则只保留必需条件。
+struct Info {
+    exact: Option<BTreeSet<String>>,
+    plan: TrigramPlan,
+}
+
+impl Info {
+    fn exact(set: BTreeSet<String>) -> Self {
+        Self {
+            plan: TrigramPlan::of_set(&set),
+            exact: Some(set),
+        }
+    }
+
+    fn empty_string() -> Self {
+        Self::exact(BTreeSet::from([String::new()]))
+    }
+
+    fn inexact(plan: TrigramPlan) -> Self {
+        Self { exact: None, plan }
+    }
+
+    fn anything() -> Self {
+        Self::inexact(TrigramPlan::All(Vec::new()))
+    }
+
+    fn into_plan(self) -> TrigramPlan {
+        self.plan
+    }
+
+    fn of(hir: &Hir) -> Self {
+        match hir.kind() {
+            HirKind::Empty | HirKind::Look(_) => Self::empty_string(),
+            HirKind::Literal(lit) => Self::exact(BTreeSet::from([
+                String::from_utf8_lossy(&lit.0).to_lowercase()
+            ])),
+            HirKind::Class(class) => match class_chars(class) {
+                Some(set) => Self::exact(set),
+                None => Self::anything(),
+            },
+            HirKind::Capture(cap) => Self::of(&cap.sub),
+            HirKind::Repetition(rep) => {
+                if rep.min == 0 {
+                    return Self::anything();
+                }
+                let sub = Self::of(&rep.sub);
+                if rep.max == Some(1) {
+                    sub
+                } else {
+                    Self::inexact(sub.plan)
+                }
+            }
+            HirKind::Concat(subs) => Self::concat(subs.iter().map(Self::of)),
+            HirKind::Alternation(subs) => {
+                let infos: Vec<Info> = subs.iter().map(Self::of).collect();
+                if infos.iter().all(|i| i.exact.is_some()) {
+                    let union: BTreeSet<String> = infos
+                        .iter()
+                        .flat_map(|i| i.exact.iter().flatten().cloned())
+                        .collect();
+                    if union.len() <= MAX_EXACT_SET {
+                        return Self::exact(union);
+                    }
+                }
+                Self::inexact(TrigramPlan::or(infos.into_iter().map(|i| i.plan).collect()))
+            }
+        }
+    }
+
+    /// 连续的可枚举部分做笛卡尔积拼成更长的字符串；遇到不可枚举部分或集合过大时，
+    /// 把已拼好的一段作为必需条件收束，再从下一部分重新开始。
+    fn concat(parts: impl Iterator<Item = Info>) -> Self {
+        let mut plans = Vec::new();
+        let mut run = BTreeSet::from([String::new()]);
+        let mut all_exact = true;
+        for part in parts {
+            match part.exact {
+                Some(next) if run.len().saturating_mul(next.len()) <= MAX_EXACT_SET => {
+                    run = run
+                        .iter()
+                        .flat_map(|x| next.iter().map(move |y| format!("{x}{y}")))
+                        .collect();
+                }
+                Some(next) => {
+                    all_exact = false;
+                    plans.push(TrigramPlan::of_set(&run));
+                    run = next;
+                }
+                None => {
+                    all_exact = false;
+                    plans.push(TrigramPlan::of_set(&run));
+                    plans.push(part.plan);
+                    run = BTreeSet::from([String::new()]);
+                }
+            }
+        }
+        if all_exact {
+            return Self::exact(run);
+        }
+        plans.push(TrigramPlan::of_set(&run));
+        Self::inexact(TrigramPlan::and(plans))
+    }
+}
+
+/// 小字符类按字符展开（小写去重）；过大的类（Unicode `\d`、`\w`、`.` 等）返回 None。
+fn class_chars(class: &Class) -> Option<BTreeSet<String>> {
+    let mut chars = Vec::new();
+    match class {
+        Class::Unicode(cls) => {
+            for r in cls.ranges() {
+                let (start, end) = (r.start() as u32, r.end() as u32);
+                if chars.len() + (end - start) as usize + 1 > MAX_CLASS_CHARS {
+                    return None;
+                }
+                chars.extend((start..=end).filter_map(char::from_u32));
+            }
+        }
+        Class::Bytes(cls) => {
+            for r in cls.ranges() {
+                if !r.end().is_ascii()
+                    || chars.len() + (r.end() - r.start()) as usize + 1 > MAX_CLASS_CHARS
+                {
+                    return None;
+                }
+                chars.extend((r.start()..=r.end()).map(char::from));
+            }
+        }
+    }
+    Some(
+        chars
+            .into_iter()
+            .map(|c| c.to_lowercase().collect())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lit(s: &str) -> TrigramPlan {
+        TrigramPlan::Literal(s.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn required_literals_form_conjunction() {
+        assert_eq!(
+            TrigramPlan::from_regex(r"^report_20\d\d\.pdf$", false),
+            Some(TrigramPlan::All(vec![lit("report_20"), lit(".pdf")]))
+        );
+    }
+
+    #[test]
+    fn small_classes_expand_into_exact_strings() {
+        let plan = TrigramPlan::from_regex(r"^log_[0-9]\.txt$", true).unwrap();
+        let TrigramPlan::Any(names) = &plan else {
+            panic!("expected class expansion, got {plan:?}");
+        };
+        assert_eq!(names.len(), 10);
+        assert!(names.contains(&lit("log_7.txt")));
+    }
+
+    #[test]
+    fn alternation_becomes_exact_set_or_disjunction() {
+        assert_eq!(
+            TrigramPlan::from_regex(r"\.(jpg|png)$", true),
+            Some(TrigramPlan::Any(vec![lit(".jpg"), lit(".png")]))
+        );
+        // 分支含不可枚举部分：每个分支保留自己的必需 literal
+        assert_eq!(
+            TrigramPlan::from_regex(r"invoice.*|receipt\d+", true),
+            Some(TrigramPlan::Any(vec![lit("invoice"), lit("receipt")]))
+        );
+    }
+
+    #[test]
+    fn case_insensitive_literals_are_lowercased() {
+        assert_eq!(
+            TrigramPlan::from_regex(r"Report", false),
+            Some(lit("report"))
+        );
+        assert_eq!(
+            TrigramPlan::from_regex(r"README", true),
+            Some(lit("readme"))
+        );
+    }
+
+    #[test]
+    fn literals_split_on_path_separators() {
+        assert_eq!(
+            TrigramPlan::from_regex(r"src/main\.rs$", true),
+            Some(TrigramPlan::All(vec![lit("src"), lit("main.rs")]))
+        );
+    }
+
+    #[test]
+    fn display_renders_nested_plans() {
+        let plan = TrigramPlan::from_regex(r"(invoice|receipt)_\d+\.pdf$", true).unwrap();
+        assert_eq!(
+            plan.to_string(),
+            r#"(("invoice_" OR "receipt_") AND ".pdf")"#
+        );
+    }
+
+    #[test]
+    fn unconstrained_patterns_yield_no_plan() {
+        for pat in [r".*", r"\d+", r"ab", r"(abc)?x", r"foo|.*", r"\w{3,}"] {
+            assert_eq!(TrigramPlan::from_regex(pat, true), None, "{pat}");
+        }
+        assert_eq!(TrigramPlan::from_regex(r"(unclosed", true), None);
+    }
+}
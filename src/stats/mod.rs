@@ -1,5 +1,12 @@
+pub mod prometheus;
+pub mod query_profile;
+
 use std::fmt;
 
+use query_profile::{
+    HistogramSnapshot, LatencyHistogram, QueryPhase, QueryProfile, SlowQuery, SlowQueryLog,
+};
+
 /// 内存占用统计（字节级精确）
 #[derive(Clone, Debug, Default, serde::Serialize)]
 pub struct MemoryReport {
@@ -88,6 +95,10 @@ pub struct BaseStats {
     pub trigram_distinct: usize,
     pub trigram_postings_total: usize,
     pub trigram_bytes: u64,
+    /// 1/2 字节 gram 倒排（短查询加速）
+    pub short_gram_distinct: usize,
+    pub short_gram_postings_total: usize,
+    pub short_gram_bytes: u64,
     pub parent_file_dirs: usize,
     pub parent_subdir_dirs: usize,
     pub parent_bytes: u64,
@@ -389,6 +400,11 @@ impl fmt::Display for MemoryReport {
             "║   trigram:      {:>10}                       ║",
             human_bytes(self.base.trigram_bytes)
         )?;
+        writeln!(
+            f,
+            "║   short gram:   {:>10}                       ║",
+            human_bytes(self.base.short_gram_bytes)
+        )?;
         writeln!(
             f,
             "║   parent:       {:>10}                       ║",
@@ -593,6 +609,8 @@ pub struct StatsReport {
     pub events_dropped: u64,
     pub snapshot_count: u64,
     pub fast_sync_count: u64,
+    /// 各查询阶段的耗时直方图（plan / overlay / base_scan / sort / total）
+    pub query_phase_latency: Vec<HistogramSnapshot>,
 }
 
 /// Thread-safe runtime stats collector.
@@ -604,6 +622,9 @@ pub struct StatsCollector {
     events_dropped: std::sync::atomic::AtomicU64,
     snapshot_count: std::sync::atomic::AtomicU64,
     fast_sync_count: std::sync::atomic::AtomicU64,
+    /// 按 `QueryPhase::ALL` 顺序
+    phase_latency: [LatencyHistogram; 5],
+    slow_queries: SlowQueryLog,
 }
 
 impl StatsCollector {
@@ -628,6 +649,22 @@ impl StatsCollector {
             .fetch_add(count, std::sync::atomic::Ordering::Relaxed);
     }
 
+    /// 记录一次查询的阶段剖析：计入各阶段直方图，超过阈值时进入慢查询日志。
+    pub fn record_query_profile(&self, query: &str, mode: &str, profile: &QueryProfile) {
+        for (phase, histogram) in QueryPhase::ALL.iter().zip(self.phase_latency.iter()) {
+            histogram.observe(profile.phase_us(*phase));
+        }
+        self.slow_queries.record(query, mode, profile);
+    }
+
+    pub fn slow_query_log(&self) -> &SlowQueryLog {
+        &self.slow_queries
+    }
+
+    pub fn slow_queries(&self) -> Vec<SlowQuery> {
+        self.slow_queries.recent()
+    }
+
     pub fn record_snapshot(&self) {
         self.snapshot_count
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
@@ -660,13 +697,18 @@ impl StatsCollector {
             fast_sync_count: self
                 .fast_sync_count
                 .load(std::sync::atomic::Ordering::Relaxed),
+            query_phase_latency: QueryPhase::ALL
+                .iter()
+                .zip(self.phase_latency.iter())
+                .map(|(phase, histogram)| histogram.snapshot(*phase))
+                .collect(),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{infer_heap_high_water, StatsCollector};
+    use super::{infer_heap_high_water, QueryProfile, StatsCollector};
 
     #[test]
     fn heap_high_water_detects_large_non_index_dirty() {
@@ -703,4 +745,37 @@ mod tests {
         assert_eq!(report.snapshot_count, 1);
         assert_eq!(report.fast_sync_count, 1);
     }
+
+    #[test]
+    fn query_profiles_feed_phase_histograms_and_slow_log() {
+        let stats = StatsCollector::new();
+        stats.slow_query_log().configure(1_000, 8);
+        let profile = QueryProfile {
+            plan_us: 10,
+            base_scan_us: 4_000,
+            total_us: 4_200,
+            base_candidates: 900,
+            ..QueryProfile::default()
+        };
+        stats.record_query_profile("ext:rs main", "exact", &profile);
+        stats.record_query_profile(
+            "quick",
+            "exact",
+            &QueryProfile {
+                total_us: 20,
+                ..QueryProfile::default()
+            },
+        );
+
+        let report = stats.report();
+        let phases: Vec<&str> = report.query_phase_latency.iter().map(|h| h.phase).collect();
+        assert_eq!(phases, ["plan", "overlay", "base_scan", "sort", "total"]);
+        assert!(report.query_phase_latency.iter().all(|h| h.count == 2));
+        assert_eq!(report.query_phase_latency[2].sum_us, 4_000);
+
+        let slow = stats.slow_queries();
+        assert_eq!(slow.len(), 1);
+        assert_eq!(slow[0].query, "ext:rs main");
+        assert_eq!(slow[0].profile.base_candidates, 900);
+    }
 }
@@ -0,0 +1,488 @@
+//! Prometheus 文本格式（exposition format 0.0.4）编码。
+//!
+//! 命名约定：统一 `fd_rdd_` 前缀；单调递增计数以 `_total` 结尾（counter），
+//! 字节量以 `_bytes` 结尾，比例以 `_ratio` 结尾（0~1），其余为 gauge。
+//! 同类拆项用 label 区分（如 `fd_rdd_index_memory_bytes{component="base"}`）。
+
+use std::fmt::Write as _;
+
+use super::query_profile::HistogramSnapshot;
+use super::{EventPipelineStats, MemoryReport, StatsReport, WatchStateReport};
+
+/// `/metrics` 文本响应的 Content-Type。
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl MetricKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Histogram => "histogram",
+        }
+    }
+}
+
+/// 文本格式累加器：每个指标族先写 `# HELP` / `# TYPE`，再写样本。
+#[derive(Debug, Default)]
+pub struct PromText {
+    out: String,
+}
+
+impl PromText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 开始一个指标族；随后用 `sample` 写入带 label 的样本。
+    pub fn family(&mut self, name: &str, help: &str, kind: MetricKind) -> &mut Self {
+        let _ = writeln!(self.out, "# HELP {} {}", name, escape_help(help));
+        let _ = writeln!(self.out, "# TYPE {} {}", name, kind.as_str());
+        self
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) -> &mut Self {
+        self.out.push_str(name);
+        if !labels.is_empty() {
+            self.out.push('{');
+            for (i, (k, v)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.out.push(',');
+                }
+                let _ = write!(self.out, "{}=\"{}\"", k, escape_label(v));
+            }
+            self.out.push('}');
+        }
+        let _ = writeln!(self.out, " {}", format_value(value));
+        self
+    }
+
+    pub fn counter(&mut self, name: &str, help: &str, value: u64) -> &mut Self {
+        self.family(name, help, MetricKind::Counter)
+            .sample(name, &[], value as f64)
+    }
+
+    pub fn gauge(&mut self, name: &str, help: &str, value: f64) -> &mut Self {
+        self.family(name, help, MetricKind::Gauge)
+            .sample(name, &[], value)
+    }
+
+    /// 单 label 的 gauge 族。
+    pub fn gauge_by(
+        &mut self,
+        name: &str,
+        help: &str,
+        label: &str,
+        values: &[(&str, f64)],
+    ) -> &mut Self {
+        self.family(name, help, MetricKind::Gauge);
+        for (label_value, value) in values {
+            self.sample(name, &[(label, label_value)], *value);
+        }
+        self
+    }
+
+    /// 微秒直方图按秒输出（`_bucket{le}` / `_sum` / `_count`），`label` 区分各快照。
+    pub fn histogram_us(
+        &mut self,
+        name: &str,
+        help: &str,
+        label: &str,
+        snapshots: &[HistogramSnapshot],
+    ) -> &mut Self {
+        self.family(name, help, MetricKind::Histogram);
+        let bucket = format!("{}_bucket", name);
+        let sum = format!("{}_sum", name);
+        let count = format!("{}_count", name);
+        for snap in snapshots {
+            for b in &snap.buckets {
+                let le = format_value(b.le_us as f64 / 1e6);
+                self.sample(&bucket, &[(label, snap.phase), ("le", &le)], b.count as f64);
+            }
+            self.sample(
+                &bucket,
+                &[(label, snap.phase), ("le", "+Inf")],
+                snap.count as f64,
+            )
+            .sample(&sum, &[(label, snap.phase)], snap.sum_us as f64 / 1e6)
+            .sample(&count, &[(label, snap.phase)], snap.count as f64);
+        }
+        self
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_help(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn escape_label(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn flag(b: bool) -> f64 {
+    if b {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+pub fn encode_stats_report(out: &mut PromText, report: &StatsReport) {
+    out.counter(
+        "fd_rdd_queries_total",
+        "Queries executed since start.",
+        report.queries_total,
+    )
+    .gauge(
+        "fd_rdd_query_duration_avg_seconds",
+        "Mean query latency since start.",
+        report.queries_avg_us as f64 / 1e6,
+    )
+    .counter(
+        "fd_rdd_events_applied_total",
+        "Filesystem events applied to the index.",
+        report.events_applied,
+    )
+    .counter(
+        "fd_rdd_events_dropped_total",
+        "Filesystem events dropped (index side plus pipeline overflow).",
+        report.events_dropped,
+    )
+    .counter(
+        "fd_rdd_snapshots_total",
+        "Snapshots written since start.",
+        report.snapshot_count,
+    )
+    .counter(
+        "fd_rdd_fast_syncs_total",
+        "Fast-sync reconciliations run since start.",
+        report.fast_sync_count,
+    )
+    .histogram_us(
+        "fd_rdd_query_phase_duration_seconds",
+        "Query latency per execution phase (plan, overlay, base_scan, sort, total).",
+        "phase",
+        &report.query_phase_latency,
+    );
+}
+
+pub fn encode_event_pipeline(out: &mut PromText, stats: &EventPipelineStats) {
+    out.counter(
+        "fd_rdd_pipeline_events_processed_total",
+        "Events processed by the watcher pipeline.",
+        stats.total_events_processed,
+    )
+    .counter(
+        "fd_rdd_pipeline_overflow_drops_total",
+        "Watcher channel overflow drops.",
+        stats.overflow_drops,
+    )
+    .counter(
+        "fd_rdd_pipeline_rescan_signals_total",
+        "Rescan signals from notify (possible missed events).",
+        stats.rescan_signals,
+    )
+    .counter(
+        "fd_rdd_watch_failures_total",
+        "Watcher registration or runtime failures.",
+        stats.watch_failures,
+    )
+    .gauge(
+        "fd_rdd_pipeline_last_batch_size",
+        "Events in the most recent debounce batch.",
+        stats.last_batch_size as f64,
+    )
+    .gauge(
+        "fd_rdd_watcher_degraded",
+        "1 if some directories are not watched and fall back to polling.",
+        flag(stats.watcher_degraded),
+    )
+    .gauge(
+        "fd_rdd_watch_degraded_roots",
+        "Directories currently in degraded polling.",
+        stats.degraded_roots as f64,
+    );
+}
+
+pub fn encode_memory_report(out: &mut PromText, report: &MemoryReport) {
+    out.gauge(
+        "fd_rdd_process_resident_memory_bytes",
+        "Process RSS from /proc/self/statm.",
+        report.process_rss_bytes as f64,
+    );
+    if let Some(smaps) = &report.process_smaps_rollup {
+        out.gauge_by(
+            "fd_rdd_process_smaps_bytes",
+            "Process memory breakdown from /proc/self/smaps_rollup.",
+            "kind",
+            &[
+                ("rss", smaps.rss_bytes as f64),
+                ("pss", smaps.pss_bytes as f64),
+                ("private_clean", smaps.private_clean_bytes as f64),
+                ("private_dirty", smaps.private_dirty_bytes as f64),
+            ],
+        );
+    }
+    if let Some(faults) = &report.process_faults {
+        out.family(
+            "fd_rdd_process_page_faults_total",
+            "Process page faults from /proc/self/stat.",
+            MetricKind::Counter,
+        )
+        .sample(
+            "fd_rdd_process_page_faults_total",
+            &[("kind", "minor")],
+            faults.minflt as f64,
+        )
+        .sample(
+            "fd_rdd_process_page_faults_total",
+            &[("kind", "major")],
+            faults.majflt as f64,
+        );
+    }
+    out.gauge_by(
+        "fd_rdd_index_memory_bytes",
+        "Estimated heap bytes per index component.",
+        "component",
+        &[
+            ("l1", report.l1.estimated_bytes as f64),
+            ("base", report.base.estimated_bytes as f64),
+            ("l2", report.l2.estimated_bytes as f64),
+            ("overlay", report.overlay.estimated_bytes as f64),
+            ("rebuild", report.rebuild.estimated_bytes as f64),
+            (
+                "disk_tombstones",
+                report.disk_deleted_estimated_bytes as f64,
+            ),
+        ],
+    )
+    .gauge(
+        "fd_rdd_index_estimated_bytes",
+        "Sum of estimated index heap bytes.",
+        report.index_estimated_bytes as f64,
+    );
+    if let Some(bytes) = report.non_index_private_dirty_bytes {
+        out.gauge(
+            "fd_rdd_non_index_private_dirty_bytes",
+            "Private dirty bytes not attributed to the index.",
+            bytes as f64,
+        );
+    }
+    out.gauge(
+        "fd_rdd_heap_high_water_suspected",
+        "1 if non-index dirty memory suggests a retained heap high-water mark.",
+        flag(report.heap_high_water_suspected),
+    )
+    .gauge_by(
+        "fd_rdd_index_entries",
+        "Entries per index layer.",
+        "layer",
+        &[
+            ("l1", report.l1.entry_count as f64),
+            ("base", report.base.file_count as f64),
+            ("l2", report.l2.file_count as f64),
+        ],
+    )
+    .gauge(
+        "fd_rdd_disk_segments",
+        "Read-only disk segments (mmap base plus deltas).",
+        report.disk_segments as f64,
+    )
+    .gauge_by(
+        "fd_rdd_overlay_paths",
+        "Paths shadowed by the overlay.",
+        "kind",
+        &[
+            ("deleted", report.overlay.deleted_paths as f64),
+            ("upserted", report.overlay.upserted_paths as f64),
+        ],
+    )
+    .gauge(
+        "fd_rdd_rebuild_in_progress",
+        "1 while a background full rebuild is running.",
+        flag(report.rebuild.in_progress),
+    )
+    .gauge(
+        "fd_rdd_rebuild_pending_paths",
+        "Events queued for replay after the running rebuild.",
+        report.rebuild.pending_paths as f64,
+    );
+}
+
+pub fn encode_watch_state(out: &mut PromText, state: &WatchStateReport) {
+    out.family(
+        "fd_rdd_watch_info",
+        "Watcher mode and backend (value is always 1).",
+        MetricKind::Gauge,
+    )
+    .sample(
+        "fd_rdd_watch_info",
+        &[("mode", &state.mode), ("backend", &state.backend)],
+        1.0,
+    )
+    .gauge_by(
+        "fd_rdd_watch_dirs",
+        "Directories per watch tier.",
+        "tier",
+        &[
+            ("l0", state.l0_dirs as f64),
+            ("l1", state.l1_dirs as f64),
+            ("l2", state.l2_dirs as f64),
+            ("l3", state.l3_dirs as f64),
+        ],
+    )
+    .gauge(
+        "fd_rdd_watch_dirs_estimated",
+        "Estimated inotify watches in use.",
+        state.watched_dirs_estimated as f64,
+    )
+    .gauge(
+        "fd_rdd_watch_max_dirs",
+        "Configured inotify watch budget.",
+        state.max_watch_dirs as f64,
+    )
+    .gauge(
+        "fd_rdd_watch_budget_utilization_ratio",
+        "Fraction of the watch budget in use.",
+        f64::from(state.watch_budget_utilization_pct) / 100.0,
+    )
+    .gauge_by(
+        "fd_rdd_watch_l0_roots",
+        "L0 admission outcome for hot directory candidates.",
+        "state",
+        &[
+            ("candidate", state.l0_candidates as f64),
+            ("admitted", state.l0_admitted as f64),
+            ("rejected", state.l0_rejected as f64),
+        ],
+    )
+    .gauge(
+        "fd_rdd_watch_scan_backlog",
+        "Directories waiting for a tiered scan.",
+        state.scan_backlog as f64,
+    )
+    .counter(
+        "fd_rdd_watch_promotions_total",
+        "Tier promotions since start.",
+        state.promotions,
+    )
+    .counter(
+        "fd_rdd_watch_demotions_total",
+        "Tier demotions since start.",
+        state.demotions,
+    )
+    .counter(
+        "fd_rdd_watch_promotion_budget_blocked_total",
+        "Promotions refused because the watch budget was exhausted.",
+        state.promotion_budget_blocked,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::SmapsRollupStats;
+
+    #[test]
+    fn samples_carry_help_type_and_escaped_labels() {
+        let mut out = PromText::new();
+        out.counter("fd_rdd_x_total", "Line one\nline two.", 3)
+            .family("fd_rdd_info", "Info.", MetricKind::Gauge)
+            .sample("fd_rdd_info", &[("src", "a\"b\\c")], 1.0)
+            .gauge("fd_rdd_ratio", "Ratio.", 0.25);
+        assert_eq!(
+            out.finish(),
+            "# HELP fd_rdd_x_total Line one\\nline two.\n\
+             # TYPE fd_rdd_x_total counter\n\
+             fd_rdd_x_total 3\n\
+             # HELP fd_rdd_info Info.\n\
+             # TYPE fd_rdd_info gauge\n\
+             fd_rdd_info{src=\"a\\\"b\\\\c\"} 1\n\
+             # HELP fd_rdd_ratio Ratio.\n\
+             # TYPE fd_rdd_ratio gauge\n\
+             fd_rdd_ratio 0.25\n"
+        );
+    }
+
+    #[test]
+    fn every_family_is_declared_once_with_fd_rdd_prefix() {
+        let memory = MemoryReport {
+            process_rss_bytes: 4096,
+            process_smaps_rollup: Some(SmapsRollupStats {
+                rss_bytes: 4096,
+                ..SmapsRollupStats::default()
+            }),
+            ..MemoryReport::default()
+        };
+        let mut out = PromText::new();
+        encode_stats_report(&mut out, &StatsReport::default());
+        encode_event_pipeline(&mut out, &memory.event_pipeline);
+        encode_memory_report(&mut out, &memory);
+        encode_watch_state(&mut out, &WatchStateReport::default());
+        let text = out.finish();
+
+        let mut seen = std::collections::HashSet::new();
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("# TYPE ") {
+                let name = rest.split(' ').next().unwrap();
+                assert!(name.starts_with("fd_rdd_"), "{name}");
+                assert!(seen.insert(name.to_string()), "duplicate family {name}");
+                let is_counter = rest.ends_with(" counter");
+                assert_eq!(is_counter, name.ends_with("_total"), "{rest}");
+            }
+        }
+        assert!(text.contains("fd_rdd_process_resident_memory_bytes 4096\n"));
+        assert!(text.contains("fd_rdd_process_smaps_bytes{kind=\"rss\"} 4096\n"));
+        assert!(text.contains("fd_rdd_index_memory_bytes{component=\"base\"} 0\n"));
+    }
+
+    #[test]
+    fn phase_histograms_render_buckets_in_seconds() {
+        let collector = crate::stats::StatsCollector::new();
+        collector.record_query_profile(
+            "q",
+            "exact",
+            &crate::stats::query_profile::QueryProfile {
+                total_us: 3_000,
+                ..Default::default()
+            },
+        );
+        let mut out = PromText::new();
+        encode_stats_report(&mut out, &collector.report());
+        let text = out.finish();
+        assert!(text.contains("# TYPE fd_rdd_query_phase_duration_seconds histogram\n"));
+        assert!(text.contains(
+            "fd_rdd_query_phase_duration_seconds_bucket{phase=\"total\",le=\"0.0025\"} 0\n"
+        ));
+        assert!(text.contains(
+            "fd_rdd_query_phase_duration_seconds_bucket{phase=\"total\",le=\"0.005\"} 1\n"
+        ));
+        assert!(text.contains(
+            "fd_rdd_query_phase_duration_seconds_bucket{phase=\"total\",le=\"+Inf\"} 1\n"
+        ));
+        assert!(text.contains("fd_rdd_query_phase_duration_seconds_sum{phase=\"total\"} 0.003\n"));
+        assert!(text.contains("fd_rdd_query_phase_duration_seconds_count{phase=\"plan\"} 1\n"));
+    }
+}
@@ -0,0 +1,275 @@
+//! 查询分阶段剖析：阶段耗时直方图 + 有界慢查询日志。
+//!
+//! `TieredIndex` 在执行计划时填充 `QueryProfile`（anchor 选择 / overlay 合并 / base 扫描 / 排序），
+//! `StatsCollector` 把各阶段耗时计入直方图，总耗时超过阈值的查询连同候选规模进入环形缓冲区。
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use parking_lot::Mutex;
+
+/// 直方图桶上界（微秒）；最后隐含 `+Inf` 桶。
+pub const LATENCY_BUCKETS_US: [u64; 15] = [
+    100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000,
+    1_000_000, 2_500_000, 5_000_000,
+];
+
+pub const DEFAULT_SLOW_QUERY_THRESHOLD_MS: u64 = 200;
+pub const DEFAULT_SLOW_QUERY_CAPACITY: usize = 128;
+
+/// 慢查询日志中单条查询文本的最大保留字节数。
+const MAX_LOGGED_QUERY_BYTES: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueryPhase {
+    /// DSL 编译与 anchor 选择
+    Plan,
+    /// DeltaBuffer overlay 物化与合并
+    Overlay,
+    /// base 候选扫描与精确匹配
+    BaseScan,
+    /// top-k 排序 / fuzzy 重排
+    Sort,
+    Total,
+}
+
+impl QueryPhase {
+    pub const ALL: [QueryPhase; 5] = [
+        QueryPhase::Plan,
+        QueryPhase::Overlay,
+        QueryPhase::BaseScan,
+        QueryPhase::Sort,
+        QueryPhase::Total,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            QueryPhase::Plan => "plan",
+            QueryPhase::Overlay => "overlay",
+            QueryPhase::BaseScan => "base_scan",
+            QueryPhase::Sort => "sort",
+            QueryPhase::Total => "total",
+        }
+    }
+}
+
+/// 单次查询的阶段耗时与候选规模。
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct QueryProfile {
+    pub plan_us: u64,
+    pub overlay_us: u64,
+    pub base_scan_us: u64,
+    pub sort_us: u64,
+    pub total_us: u64,
+    /// 参与匹配的 overlay 条目数
+    pub overlay_candidates: usize,
+    /// overlay 中命中的结果数（其余结果来自 base）
+    pub overlay_matches: usize,
+    /// base 层经 trigram / parent 索引得到的候选数（全量扫描时为 base 文件数）
+    pub base_candidates: usize,
+    /// 是否有 anchor 退化为 base 全量扫描
+    pub full_scan: bool,
+    pub results: usize,
+}
+
+impl QueryProfile {
+    pub fn phase_us(&self, phase: QueryPhase) -> u64 {
+        match phase {
+            QueryPhase::Plan => self.plan_us,
+            QueryPhase::Overlay => self.overlay_us,
+            QueryPhase::BaseScan => self.base_scan_us,
+            QueryPhase::Sort => self.sort_us,
+            QueryPhase::Total => self.total_us,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LatencyHistogram {
+    /// 非累积计数：`buckets[i]` 落在 `(LATENCY_BUCKETS_US[i-1], LATENCY_BUCKETS_US[i]]`，末位为 +Inf
+    buckets: [AtomicU64; LATENCY_BUCKETS_US.len() + 1],
+    sum_us: AtomicU64,
+    count: AtomicU64,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct HistogramBucket {
+    pub le_us: u64,
+    /// 累积计数（<= le_us）
+    pub count: u64,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct HistogramSnapshot {
+    pub phase: &'static str,
+    pub buckets: Vec<HistogramBucket>,
+    pub sum_us: u64,
+    pub count: u64,
+}
+
+impl LatencyHistogram {
+    pub fn observe(&self, us: u64) {
+        let idx = LATENCY_BUCKETS_US.partition_point(|&le| le < us);
+        self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        self.sum_us.fetch_add(us, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self, phase: QueryPhase) -> HistogramSnapshot {
+        let mut cumulative = 0u64;
+        let buckets = LATENCY_BUCKETS_US
+            .iter()
+            .zip(self.buckets.iter())
+            .map(|(&le_us, n)| {
+                cumulative += n.load(Ordering::Relaxed);
+                HistogramBucket {
+                    le_us,
+                    count: cumulative,
+                }
+            })
+            .collect();
+        HistogramSnapshot {
+            phase: phase.as_str(),
+            buckets,
+            sum_us: self.sum_us.load(Ordering::Relaxed),
+            count: self.count.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct SlowQuery {
+    pub timestamp_ms: u64,
+    pub query: String,
+    pub mode: String,
+    pub profile: QueryProfile,
+}
+
+/// 慢查询环形缓冲区：满后丢弃最旧的条目。
+#[derive(Debug)]
+pub struct SlowQueryLog {
+    threshold_us: AtomicU64,
+    capacity: AtomicUsize,
+    entries: Mutex<VecDeque<SlowQuery>>,
+}
+
+impl Default for SlowQueryLog {
+    fn default() -> Self {
+        Self {
+            threshold_us: AtomicU64::new(DEFAULT_SLOW_QUERY_THRESHOLD_MS * 1_000),
+            capacity: AtomicUsize::new(DEFAULT_SLOW_QUERY_CAPACITY),
+            entries: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+impl SlowQueryLog {
+    pub fn threshold_us(&self) -> u64 {
+        self.threshold_us.load(Ordering::Relaxed)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity.load(Ordering::Relaxed)
+    }
+
+    /// 阈值 0 表示记录全部查询；容量 0 关闭慢查询日志。
+    pub fn configure(&self, threshold_us: u64, capacity: usize) {
+        self.threshold_us.store(threshold_us, Ordering::Relaxed);
+        self.capacity.store(capacity, Ordering::Relaxed);
+        let mut entries = self.entries.lock();
+        while entries.len() > capacity {
+            entries.pop_front();
+        }
+    }
+
+    pub fn record(&self, query: &str, mode: &str, profile: &QueryProfile) {
+        let capacity = self.capacity();
+        if capacity == 0 || profile.total_us < self.threshold_us() {
+            return;
+        }
+        let entry = SlowQuery {
+            timestamp_ms: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            query: truncate_query(query),
+            mode: mode.to_string(),
+            profile: profile.clone(),
+        };
+        let mut entries = self.entries.lock();
+        while entries.len() >= capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// 最新的在前。
+    pub fn recent(&self) -> Vec<SlowQuery> {
+        self.entries.lock().iter().rev().cloned().collect()
+    }
+}
+
+fn truncate_query(query: &str) -> String {
+    if query.len() <= MAX_LOGGED_QUERY_BYTES {
+        return query.to_string();
+    }
+    let mut end = MAX_LOGGED_QUERY_BYTES;
+    while !query.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &query[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(total_us: u64) -> QueryProfile {
+        QueryProfile {
+            total_us,
+            ..QueryProfile::default()
+        }
+    }
+
+    #[test]
+    fn histogram_buckets_are_cumulative() {
+        let h = LatencyHistogram::default();
+        h.observe(50);
+        h.observe(100);
+        h.observe(3_000);
+        h.observe(60_000_000);
+        let snap = h.snapshot(QueryPhase::Total);
+        assert_eq!(snap.count, 4);
+        assert_eq!(snap.sum_us, 60_003_150);
+        assert_eq!(snap.buckets[0].le_us, 100);
+        assert_eq!(snap.buckets[0].count, 2);
+        assert_eq!(snap.buckets[4].count, 2); // le 2_500
+        assert_eq!(snap.buckets[5].count, 3); // le 5_000
+        assert_eq!(snap.buckets.last().unwrap().count, 3); // 60s 只落入 +Inf
+    }
+
+    #[test]
+    fn slow_query_log_applies_threshold_and_capacity() {
+        let log = SlowQueryLog::default();
+        log.configure(1_000, 2);
+        log.record("fast", "exact", &profile(999));
+        log.record("a", "exact", &profile(1_000));
+        log.record("b", "fuzzy", &profile(5_000));
+        log.record("c", "exact", &profile(2_000));
+
+        let recent: Vec<String> = log.recent().into_iter().map(|q| q.query).collect();
+        assert_eq!(recent, vec!["c".to_string(), "b".to_string()]);
+
+        log.configure(1_000, 0);
+        log.record("d", "exact", &profile(9_000));
+        assert!(log.recent().is_empty());
+    }
+
+    #[test]
+    fn long_queries_are_truncated_on_char_boundary() {
+        let q = "é".repeat(MAX_LOGGED_QUERY_BYTES);
+        let t = truncate_query(&q);
+        assert!(t.len() <= MAX_LOGGED_QUERY_BYTES + '…'.len_utf8());
+        assert!(t.ends_with('…'));
+    }
+}
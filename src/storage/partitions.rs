@@ -0,0 +1,198 @@
+//! 分区快照段：`<index>.d/parts/` 下每个 root 一个 v7 段文件，外加 MANIFEST.json。
+//!
+//! - 段文件名取 `Partition::segment_key()`（root 字节的 xxh3），与 root 顺序无关；
+//! - 只重写变化过的分区段，其余段原样保留；
+//! - `keep_prev` 时沿用 stable 轮转语义：先写 `.next` 并校验，再把旧段挪到 `.prev`；
+//! - MANIFEST 最后原子替换，未被引用的段文件随之清理；
+//! - 内容索引不分区，单独写入 `content.v7`（空 base + 内容段）。
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::index::base_index::BaseIndexData;
+use crate::index::content_index::ContentIndex;
+use crate::storage::snapshot::stable_snapshot_dir_for;
+use crate::storage::snapshot_v7::{
+    try_load_v7, try_load_v7_with_content, write_v7_snapshot_atomic,
+    write_v7_snapshot_atomic_with_content,
+};
+
+pub const PARTITION_MANIFEST_VERSION: u32 = 1;
+const CONTENT_SEGMENT: &str = "content";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PartitionManifest {
+    pub version: u32,
+    pub partitions: Vec<PartitionManifestEntry>,
+    /// 是否写入了 `content.v7`
+    #[serde(default)]
+    pub content: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PartitionManifestEntry {
+    pub root: PathBuf,
+    pub segment: String,
+    pub files: usize,
+    pub written_unix_secs: u64,
+}
+
+impl PartitionManifest {
+    pub fn entry(&self, root: &Path) -> Option<&PartitionManifestEntry> {
+        self.partitions.iter().find(|e| e.root == root)
+    }
+}
+
+pub fn partitions_dir_for(snapshot_path: &Path) -> PathBuf {
+    stable_snapshot_dir_for(snapshot_path).join("parts")
+}
+
+pub fn partition_manifest_path_for(snapshot_path: &Path) -> PathBuf {
+    partitions_dir_for(snapshot_path).join("MANIFEST.json")
+}
+
+pub fn partition_segment_path_for(snapshot_path: &Path, segment: &str) -> PathBuf {
+    partitions_dir_for(snapshot_path).join(format!("{segment}.v7"))
+}
+
+fn partition_prev_segment_path_for(snapshot_path: &Path, segment: &str) -> PathBuf {
+    partitions_dir_for(snapshot_path).join(format!("{segment}.prev.v7"))
+}
+
+fn partition_next_segment_path_for(snapshot_path: &Path, segment: &str) -> PathBuf {
+    partitions_dir_for(snapshot_path).join(format!("{segment}.next.v7"))
+}
+
+/// MANIFEST 不存在返回 `Ok(None)`；存在但损坏返回错误（调用方回退到单体快照）。
+pub fn read_partition_manifest(snapshot_path: &Path) -> anyhow::Result<Option<PartitionManifest>> {
+    let path = partition_manifest_path_for(snapshot_path);
+    let bytes = match std::fs::read(&path) {
+        Ok(b) => b,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let manifest: PartitionManifest = serde_json::from_slice(&bytes)?;
+    if manifest.version != PARTITION_MANIFEST_VERSION {
+        anyhow::bail!(
+            "unsupported partition manifest version {}",
+            manifest.version
+        );
+    }
+    Ok(Some(manifest))
+}
+
+/// 原子写 MANIFEST，并删除不再被引用的段文件（best-effort）。
+pub fn write_partition_manifest(
+    snapshot_path: &Path,
+    manifest: &PartitionManifest,
+) -> anyhow::Result<()> {
+    let dir = partitions_dir_for(snapshot_path);
+    std::fs::create_dir_all(&dir)?;
+    let path = partition_manifest_path_for(snapshot_path);
+    let tmp = path.with_extension("json.tmp");
+    {
+        let mut file = std::fs::File::create(&tmp)?;
+        serde_json::to_writer_pretty(&mut file, manifest)?;
+        file.write_all(b"\n")?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, &path)?;
+    if let Ok(dir_file) = std::fs::File::open(&dir) {
+        let _ = dir_file.sync_all();
+    }
+
+    let live: std::collections::HashSet<&str> = manifest
+        .partitions
+        .iter()
+        .map(|e| e.segment.as_str())
+        .chain(manifest.content.then_some(CONTENT_SEGMENT))
+        .collect();
+    for entry in std::fs::read_dir(&dir)?.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        let Some(stem) = name.strip_suffix(".v7") else {
+            continue;
+        };
+        let segment = stem
+            .strip_suffix(".prev")
+            .or_else(|| stem.strip_suffix(".next"))
+            .unwrap_or(stem);
+        if !live.contains(segment) {
+            if let Err(e) = std::fs::remove_file(entry.path()) {
+                tracing::warn!("stale partition segment remove failed: {:?}: {}", name, e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 写一个分区段；`keep_prev` 时保留上一版本作为 `.prev.v7`。
+pub fn write_partition_segment(
+    snapshot_path: &Path,
+    segment: &str,
+    data: &BaseIndexData,
+    keep_prev: bool,
+) -> anyhow::Result<()> {
+    std::fs::create_dir_all(partitions_dir_for(snapshot_path))?;
+    let current = partition_segment_path_for(snapshot_path, segment);
+    if !keep_prev {
+        return write_v7_snapshot_atomic(&current, data);
+    }
+
+    let next = partition_next_segment_path_for(snapshot_path, segment);
+    let prev = partition_prev_segment_path_for(snapshot_path, segment);
+    write_v7_snapshot_atomic(&next, data)?;
+    if try_load_v7(&next)?.is_none() {
+        anyhow::bail!("partition segment {segment}.next.v7 validation failed");
+    }
+    if current.exists() {
+        std::fs::rename(&current, &prev)?;
+    }
+    std::fs::rename(&next, &current)?;
+    Ok(())
+}
+
+/// 读取分区段：先当前版本，再 `.prev`；返回数据与是否用了 prev。
+pub fn load_partition_segment(
+    snapshot_path: &Path,
+    segment: &str,
+) -> Option<(BaseIndexData, bool)> {
+    let candidates = [
+        (partition_segment_path_for(snapshot_path, segment), false),
+        (
+            partition_prev_segment_path_for(snapshot_path, segment),
+            true,
+        ),
+    ];
+    for (path, is_prev) in candidates {
+        match try_load_v7(&path) {
+            Ok(Some(data)) => return Some((data, is_prev)),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("partition segment {:?} load failed: {}", path, e),
+        }
+    }
+    None
+}
+
+pub fn write_partition_content(snapshot_path: &Path, content: &ContentIndex) -> anyhow::Result<()> {
+    std::fs::create_dir_all(partitions_dir_for(snapshot_path))?;
+    write_v7_snapshot_atomic_with_content(
+        &partition_segment_path_for(snapshot_path, CONTENT_SEGMENT),
+        &BaseIndexData::default(),
+        Some(content),
+    )
+}
+
+pub fn load_partition_content(snapshot_path: &Path) -> Option<ContentIndex> {
+    let path = partition_segment_path_for(snapshot_path, CONTENT_SEGMENT);
+    match try_load_v7_with_content(&path) {
+        Ok(Some((_, content))) => Some(content),
+        Ok(None) => None,
+        Err(e) => {
+            tracing::warn!("partition content segment load failed: {}", e);
+            None
+        }
+    }
+}
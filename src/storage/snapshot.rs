@@ -1,3 +1,4 @@
+use crate::core::path_rules::PathRulesConfig;
 use crate::index::base_index::BaseIndexData;
 use crate::index::l2_partition::IndexSnapshotV2;
 use crate::index::l2_partition::IndexSnapshotV3;
@@ -71,6 +72,31 @@ impl Default for RecoveryRuntimeState {
     }
 }
 
+/// 启动修复的续传游标：优先队列中尚未扫描的目录、各分区的遍历位置与累计进度。
+///
+/// 只记录位置而不展开全部待扫目录，游标大小与索引规模无关。
+/// 修复跑完即删除；进程在修复中途退出时，下次启动从这里继续。
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RepairCursor {
+    /// 热目录、roots、最近修改的目录与新发现的子目录中尚未扫描的部分
+    pub hot: Vec<PathBuf>,
+    pub positions: Vec<RepairPosition>,
+    pub dirs_done: usize,
+    pub scanned: usize,
+    pub changed: usize,
+    pub started_unix_secs: u64,
+}
+
+/// 单个分区按 DocId 顺序遍历目录条目的位置。
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepairPosition {
+    pub root: PathBuf,
+    /// 下一个待检查的 DocId
+    pub next_docid: usize,
+    /// 记录位置时分区的条目数；与续跑时不一致说明分区已重建，从头遍历
+    pub entries: usize,
+}
+
 pub fn stable_snapshot_dir_for(snapshot_path: &Path) -> PathBuf {
     if snapshot_path.extension().and_then(|s| s.to_str()) == Some("d") || snapshot_path.is_dir() {
         snapshot_path.to_path_buf()
@@ -99,6 +125,70 @@ pub fn repair_meta_path_for(snapshot_path: &Path) -> PathBuf {
     stable_snapshot_dir_for(snapshot_path).join("repair-meta.json")
 }
 
+pub fn path_rules_state_path_for(snapshot_path: &Path) -> PathBuf {
+    stable_snapshot_dir_for(snapshot_path).join("path-rules.json")
+}
+
+pub fn repair_cursor_path_for(snapshot_path: &Path) -> PathBuf {
+    stable_snapshot_dir_for(snapshot_path).join("repair-cursor.json")
+}
+
+/// 上一次生效的索引期 glob 规则；文件不存在时视为无规则。
+pub fn read_path_rules_state(snapshot_path: &Path) -> anyhow::Result<PathRulesConfig> {
+    let path = path_rules_state_path_for(snapshot_path);
+    if !path.exists() {
+        return Ok(PathRulesConfig::default());
+    }
+    let bytes = std::fs::read(path)?;
+    Ok(serde_json::from_slice(&bytes)?)
+}
+
+pub fn write_path_rules_state(snapshot_path: &Path, rules: &PathRulesConfig) -> anyhow::Result<()> {
+    let dir = stable_snapshot_dir_for(snapshot_path);
+    std::fs::create_dir_all(&dir)?;
+    let path = path_rules_state_path_for(snapshot_path);
+    let tmp = path.with_extension("json.tmp");
+    {
+        let mut file = std::fs::File::create(&tmp)?;
+        serde_json::to_writer_pretty(&mut file, rules)?;
+        file.write_all(b"\n")?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// 未完成的启动修复游标；不存在时返回 `None`。
+pub fn read_repair_cursor(snapshot_path: &Path) -> anyhow::Result<Option<RepairCursor>> {
+    let path = repair_cursor_path_for(snapshot_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes = std::fs::read(path)?;
+    Ok(Some(serde_json::from_slice(&bytes)?))
+}
+
+pub fn write_repair_cursor(snapshot_path: &Path, cursor: &RepairCursor) -> anyhow::Result<()> {
+    let dir = stable_snapshot_dir_for(snapshot_path);
+    std::fs::create_dir_all(&dir)?;
+    let path = repair_cursor_path_for(snapshot_path);
+    let tmp = path.with_extension("json.tmp");
+    {
+        let mut file = std::fs::File::create(&tmp)?;
+        serde_json::to_writer(&mut file, cursor)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+pub fn clear_repair_cursor(snapshot_path: &Path) -> anyhow::Result<()> {
+    match std::fs::remove_file(repair_cursor_path_for(snapshot_path)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
 pub fn read_recovery_runtime_state(snapshot_path: &Path) -> anyhow::Result<RecoveryRuntimeState> {
     let path = runtime_state_path_for(snapshot_path);
     if !path.exists() {
@@ -130,13 +220,21 @@ pub fn write_recovery_runtime_state(
 }
 
 pub fn write_stable_v7_atomic(snapshot_path: &Path, base: &BaseIndexData) -> anyhow::Result<()> {
+    write_stable_v7_atomic_with_content(snapshot_path, base, None)
+}
+
+pub fn write_stable_v7_atomic_with_content(
+    snapshot_path: &Path,
+    base: &BaseIndexData,
+    content: Option<&crate::index::content_index::ContentIndex>,
+) -> anyhow::Result<()> {
     let dir = stable_snapshot_dir_for(snapshot_path);
     std::fs::create_dir_all(&dir)?;
     let next = stable_next_v7_path_for(snapshot_path);
     let stable = stable_v7_path_for(snapshot_path);
     let prev = stable_prev_v7_path_for(snapshot_path);
 
-    crate::storage::snapshot_v7::write_v7_snapshot_atomic(&next, base)?;
+    crate::storage::snapshot_v7::write_v7_snapshot_atomic_with_content(&next, base, content)?;
     if crate::storage::snapshot_v7::try_load_v7(&next)?.is_none() {
         anyhow::bail!("stable.next.v7 validation failed");
     }
@@ -1666,7 +1764,7 @@ impl crate::storage::traits::WalFactory for SnapshotStore {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{FileKey, FileMeta};
+    use crate::core::{FileKey, FileKind, FileMeta};
     use crate::index::{MmapIndex, PersistentIndex};
     use crate::query::matcher::create_matcher;
 
@@ -1701,6 +1799,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
         idx.upsert(FileMeta {
             file_key: FileKey {
@@ -1713,6 +1814,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         let store = SnapshotStore::new(root.join("index.db"));
@@ -1762,6 +1866,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         let store = SnapshotStore::new(root.join("index.db"));
@@ -1808,6 +1915,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         let store = SnapshotStore::new(root.join("index.db"));
@@ -1838,6 +1948,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
 
         let store = SnapshotStore::new(root.join("index.db"));
@@ -1918,6 +2031,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
         let base = idx.export_segments_v6();
         store
@@ -1939,6 +2055,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
         let delta = delta_idx.export_segments_v6();
         let appended = store
@@ -1973,6 +2092,9 @@ mod tests {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
         let segs = idx.export_segments_v6();
         let appended = store
@@ -5,7 +5,10 @@ use std::io::{Seek, SeekFrom, Write};
 use std::path::Path;
 use std::sync::Arc;
 
-use crate::index::base_index::{BaseIndexData, FileEntryIndex, TrigramIndex};
+use crate::core::FileKind;
+use crate::index::base_index::{BaseIndexData, FileEntryIndex, ShortGramIndex, TrigramIndex};
+use crate::index::content_index::{ContentDoc, ContentIndex};
+use crate::index::dir_mtimes::{DirMtimeTable, DirStamp};
 use crate::index::file_entry_v2::FileEntry;
 use crate::index::parent_index::ParentIndex;
 use crate::index::path_table_v2::{PathTableBuilder, PathTableV2};
@@ -52,6 +55,9 @@ pub enum V7SegKind {
     TrigramIndex = 4,
     ParentIndex = 5,
     Tombstones = 6,
+    ContentIndex = 7,
+    ShortGramIndex = 8,
+    DirMtimes = 9,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -99,10 +105,27 @@ fn decode_path_table(bytes: &[u8]) -> anyhow::Result<PathTableV2> {
 // FileEntryIndex 序列化 / 反序列化
 // ─────────────────────────────────────────────────────────────────────────────
 
+/// 时间列扩展的 flags：是否写入 ctime / atime 列。
+const TIMES_HAS_CTIME: u32 = 1;
+const TIMES_HAS_ATIME: u32 = 1 << 1;
+/// symlinks 扩展中每条记录的 flags：目标不可达。
+const SYMLINK_BROKEN: u8 = 1;
+
+/// EntriesByKey 段布局：
+/// - count u32 + count × 40B 定长记录（dev+ino+generation+path_idx+size+mtime_ns）
+/// - 可选尾部扩展 1：dir_len u32 + RoaringBitmap（kind=Dir 的 docid 集合）
+/// - 可选尾部扩展 2：times_len u32 + flags u32 + [count × ctime_ns i64] + [count × atime_ns i64]
+///   （列按 flags 存在；全部未知的列不写，例如 noatime 挂载时省掉 atime 列）
+/// - 可选尾部扩展 3：links_len u32 + [count × nlink u32]（全部未知时 links_len=0，不写列）
+/// - 可选尾部扩展 4：symlinks_len u32 + n u32 + n × (docid u32 + flags u8 + target_len u32 + target)
+///   （kind=Symlink 的条目及其目标；没有链接时 symlinks_len=0）
+///
+/// 旧快照缺少的扩展按“未知”读取（普通文件 / 无 ctime、atime、nlink）；旧读取器会忽略多出的尾部字节。
 fn encode_file_entry_index(fei: &FileEntryIndex) -> Vec<u8> {
     let mut out = Vec::new();
     let len = fei.len() as u32;
     out.extend_from_slice(&len.to_le_bytes());
+    let mut dirs = RoaringBitmap::new();
     for i in 0..fei.len() {
         if let Some(e) = fei.get(i) {
             out.extend_from_slice(&e.dev.to_le_bytes());
@@ -111,8 +134,68 @@ fn encode_file_entry_index(fei: &FileEntryIndex) -> Vec<u8> {
             out.extend_from_slice(&e.path_idx.to_le_bytes());
             out.extend_from_slice(&e.size.to_le_bytes());
             out.extend_from_slice(&e.mtime_ns.to_le_bytes());
+            if e.kind().is_dir() {
+                dirs.insert(i as u32);
+            }
         }
     }
+    let mut dir_bytes = Vec::with_capacity(dirs.serialized_size());
+    dirs.serialize_into(&mut dir_bytes)
+        .expect("serialize roaring into Vec");
+    out.extend_from_slice(&(dir_bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(&dir_bytes);
+
+    let entry_times = fei.times();
+    let mut flags = 0u32;
+    if entry_times.has_ctime() {
+        flags |= TIMES_HAS_CTIME;
+    }
+    if entry_times.has_atime() {
+        flags |= TIMES_HAS_ATIME;
+    }
+    let mut times = Vec::new();
+    times.extend_from_slice(&flags.to_le_bytes());
+    if flags & TIMES_HAS_CTIME != 0 {
+        for i in 0..fei.len() {
+            times.extend_from_slice(&entry_times.ctime_ns(i).to_le_bytes());
+        }
+    }
+    if flags & TIMES_HAS_ATIME != 0 {
+        for i in 0..fei.len() {
+            times.extend_from_slice(&entry_times.atime_ns(i).to_le_bytes());
+        }
+    }
+    out.extend_from_slice(&(times.len() as u32).to_le_bytes());
+    out.extend_from_slice(&times);
+
+    if fei.iter().any(|e| e.nlink != 0) {
+        out.extend_from_slice(&((fei.len() * 4) as u32).to_le_bytes());
+        for e in fei.iter() {
+            out.extend_from_slice(&e.nlink.to_le_bytes());
+        }
+    } else {
+        out.extend_from_slice(&0u32.to_le_bytes());
+    }
+
+    let mut symlinks = Vec::new();
+    let mut n = 0u32;
+    for (docid, target) in fei.link_targets() {
+        let Some(e) = fei.get(docid as usize) else {
+            continue;
+        };
+        symlinks.extend_from_slice(&docid.to_le_bytes());
+        symlinks.push(if e.is_broken() { SYMLINK_BROKEN } else { 0 });
+        symlinks.extend_from_slice(&(target.len() as u32).to_le_bytes());
+        symlinks.extend_from_slice(target);
+        n += 1;
+    }
+    if n > 0 {
+        out.extend_from_slice(&((symlinks.len() + 4) as u32).to_le_bytes());
+        out.extend_from_slice(&n.to_le_bytes());
+        out.extend_from_slice(&symlinks);
+    } else {
+        out.extend_from_slice(&0u32.to_le_bytes());
+    }
     out
 }
 
@@ -126,7 +209,7 @@ fn decode_file_entry_index(bytes: &[u8]) -> anyhow::Result<FileEntryIndex> {
     if bytes.len() < expected {
         anyhow::bail!("file entry index truncated");
     }
-    let mut fei = FileEntryIndex::with_capacity(count);
+    let mut entries = Vec::with_capacity(count);
     let mut off = 4usize;
     for _ in 0..count {
         let dev = u64::from_le_bytes(bytes[off..off + 8].try_into()?);
@@ -137,7 +220,7 @@ fn decode_file_entry_index(bytes: &[u8]) -> anyhow::Result<FileEntryIndex> {
         let mtime_ns = i64::from_le_bytes(bytes[off + 32..off + 40].try_into()?);
         off += REC_SIZE;
 
-        fei.push(FileEntry::from_file_key(
+        entries.push(FileEntry::from_file_key(
             crate::core::FileKey {
                 dev,
                 ino,
@@ -148,6 +231,122 @@ fn decode_file_entry_index(bytes: &[u8]) -> anyhow::Result<FileEntryIndex> {
             mtime_ns,
         ));
     }
+
+    // 尾部 kind 扩展（旧快照没有）
+    if bytes.len() >= off + 4 {
+        let dir_len = u32::from_le_bytes(bytes[off..off + 4].try_into()?) as usize;
+        off += 4;
+        if bytes.len() < off + dir_len {
+            anyhow::bail!("file entry kind bitmap truncated");
+        }
+        let dirs = RoaringBitmap::deserialize_from(&bytes[off..off + dir_len])?;
+        off += dir_len;
+        for docid in dirs.iter() {
+            if let Some(e) = entries.get_mut(docid as usize) {
+                e.set_kind(FileKind::Dir);
+            }
+        }
+    }
+
+    // 尾部时间列扩展（旧快照没有：ctime/atime 保持未知）
+    let mut ctimes: &[u8] = &[];
+    let mut atimes: &[u8] = &[];
+    if bytes.len() >= off + 8 {
+        let times_len = u32::from_le_bytes(bytes[off..off + 4].try_into()?) as usize;
+        off += 4;
+        if times_len < 4 || bytes.len() < off + times_len {
+            anyhow::bail!("file entry times truncated");
+        }
+        let flags = u32::from_le_bytes(bytes[off..off + 4].try_into()?);
+        let has_ctime = flags & TIMES_HAS_CTIME != 0;
+        let has_atime = flags & TIMES_HAS_ATIME != 0;
+        let columns = has_ctime as usize + has_atime as usize;
+        if times_len != 4 + columns * count * 8 {
+            anyhow::bail!("file entry times size mismatch");
+        }
+        let mut col = off + 4;
+        if has_ctime {
+            ctimes = &bytes[col..col + count * 8];
+            col += count * 8;
+        }
+        if has_atime {
+            atimes = &bytes[col..col + count * 8];
+        }
+        off += times_len;
+    }
+
+    // 尾部 links 扩展（旧快照没有：nlink 保持未知）
+    if bytes.len() >= off + 4 {
+        let links_len = u32::from_le_bytes(bytes[off..off + 4].try_into()?) as usize;
+        off += 4;
+        if links_len != 0 {
+            if links_len != count * 4 || bytes.len() < off + links_len {
+                anyhow::bail!("file entry links size mismatch");
+            }
+            let links = bytes[off..off + links_len]
+                .chunks_exact(4)
+                .map(|c| u32::from_le_bytes(c.try_into().expect("4-byte chunk")));
+            for (e, nlink) in entries.iter_mut().zip(links) {
+                e.nlink = nlink;
+            }
+            off += links_len;
+        }
+    }
+
+    // 尾部 symlinks 扩展（旧快照没有：全部按普通条目读取）
+    let mut targets: Vec<(u32, &[u8])> = Vec::new();
+    if bytes.len() >= off + 4 {
+        let symlinks_len = u32::from_le_bytes(bytes[off..off + 4].try_into()?) as usize;
+        off += 4;
+        if symlinks_len != 0 {
+            if symlinks_len < 4 || bytes.len() < off + symlinks_len {
+                anyhow::bail!("file entry symlinks truncated");
+            }
+            let end = off + symlinks_len;
+            let n = u32::from_le_bytes(bytes[off..off + 4].try_into()?) as usize;
+            let mut p = off + 4;
+            for _ in 0..n {
+                if end < p + 9 {
+                    anyhow::bail!("file entry symlinks truncated");
+                }
+                let docid = u32::from_le_bytes(bytes[p..p + 4].try_into()?);
+                let flags = bytes[p + 4];
+                let len = u32::from_le_bytes(bytes[p + 5..p + 9].try_into()?) as usize;
+                p += 9;
+                if end < p + len {
+                    anyhow::bail!("file entry symlinks truncated");
+                }
+                let Some(e) = entries.get_mut(docid as usize) else {
+                    anyhow::bail!("file entry symlink docid out of range");
+                };
+                if targets.last().is_some_and(|(d, _)| *d >= docid) {
+                    anyhow::bail!("file entry symlinks not sorted");
+                }
+                e.set_kind(FileKind::Symlink);
+                e.set_broken(flags & SYMLINK_BROKEN != 0);
+                targets.push((docid, &bytes[p..p + len]));
+                p += len;
+            }
+        }
+    }
+
+    let mut fei = FileEntryIndex::with_capacity(count);
+    for e in entries {
+        fei.push(e);
+    }
+    if !ctimes.is_empty() || !atimes.is_empty() {
+        let ns_at = |col: &[u8], i: usize| {
+            col.get(i * 8..i * 8 + 8).map_or(-1, |c| {
+                i64::from_le_bytes(c.try_into().expect("8-byte chunk"))
+            })
+        };
+        for i in 0..count {
+            fei.set_times(i as u32, ns_at(ctimes, i), ns_at(atimes, i));
+        }
+    }
+    for (docid, target) in targets {
+        fei.set_link_target(docid, target);
+    }
     Ok(fei.build())
 }
 
@@ -199,6 +398,65 @@ fn decode_trigram_index(bytes: &[u8]) -> anyhow::Result<TrigramIndex> {
     Ok(ti)
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// ShortGramIndex 序列化 / 反序列化
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// 每条记录：gram_len u8（1|2）+ gram [u8; 2]（不足补 0）+ pad u8 + posting_len u32 + posting。
+fn encode_short_gram_index(sg: &ShortGramIndex) -> Vec<u8> {
+    let mut out = Vec::new();
+    let len = sg.len() as u32;
+    out.extend_from_slice(&len.to_le_bytes());
+    let unigrams = sg.unigrams.iter().map(|(b, bm)| (1u8, [*b, 0], bm));
+    let bigrams = sg.bigrams.iter().map(|(g, bm)| (2u8, *g, bm));
+    for (gram_len, gram, bitmap) in unigrams.chain(bigrams) {
+        out.push(gram_len);
+        out.extend_from_slice(&gram);
+        out.push(0); // pad
+        let mut posting = Vec::new();
+        bitmap
+            .serialize_into(&mut posting)
+            .expect("roaring serialize");
+        let posting_len: u32 = posting.len().try_into().unwrap_or(u32::MAX);
+        out.extend_from_slice(&posting_len.to_le_bytes());
+        out.extend_from_slice(&posting);
+    }
+    out
+}
+
+fn decode_short_gram_index(bytes: &[u8]) -> anyhow::Result<ShortGramIndex> {
+    if bytes.len() < 4 {
+        anyhow::bail!("short gram index too small");
+    }
+    let count = u32::from_le_bytes(bytes[0..4].try_into()?) as usize;
+    let mut sg = ShortGramIndex::new();
+    let mut off = 4usize;
+    for _ in 0..count {
+        if off + 8 > bytes.len() {
+            anyhow::bail!("short gram index truncated");
+        }
+        let (gram_len, gram) = (bytes[off], [bytes[off + 1], bytes[off + 2]]);
+        let posting_len = u32::from_le_bytes(bytes[off + 4..off + 8].try_into()?) as usize;
+        off += 8;
+        if off + posting_len > bytes.len() {
+            anyhow::bail!("short gram index posting truncated");
+        }
+        let bitmap = RoaringBitmap::deserialize_from(&bytes[off..off + posting_len])
+            .map_err(|e| anyhow::anyhow!("roaring deserialize failed: {}", e))?;
+        off += posting_len;
+        match gram_len {
+            1 => {
+                sg.unigrams.insert(gram[0], bitmap);
+            }
+            2 => {
+                sg.bigrams.insert(gram, bitmap);
+            }
+            other => anyhow::bail!("short gram index invalid gram length {}", other),
+        }
+    }
+    Ok(sg)
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // ParentIndex 序列化 / 反序列化
 // ─────────────────────────────────────────────────────────────────────────────
@@ -278,6 +536,47 @@ fn decode_parent_index(bytes: &[u8]) -> anyhow::Result<ParentIndex> {
     Ok(ParentIndex { dir_to_files })
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// 目录 mtime 表序列化 / 反序列化
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Layout:
+///   count    u32
+///   records  [dir_path_idx u32, mtime_ns i64, children u32] * count（按 dir_path_idx 升序）
+fn encode_dir_mtimes(table: &DirMtimeTable) -> Vec<u8> {
+    let mut stamps: Vec<(&u32, &DirStamp)> = table.stamps.iter().collect();
+    stamps.sort_unstable_by_key(|(idx, _)| **idx);
+    let mut out = Vec::with_capacity(4 + stamps.len() * 16);
+    out.extend_from_slice(&(stamps.len() as u32).to_le_bytes());
+    for (idx, stamp) in stamps {
+        out.extend_from_slice(&idx.to_le_bytes());
+        out.extend_from_slice(&stamp.mtime_ns.to_le_bytes());
+        out.extend_from_slice(&stamp.children.to_le_bytes());
+    }
+    out
+}
+
+fn decode_dir_mtimes(bytes: &[u8]) -> anyhow::Result<DirMtimeTable> {
+    if bytes.len() < 4 {
+        anyhow::bail!("dir mtime table too small");
+    }
+    let count = u32::from_le_bytes(bytes[0..4].try_into()?) as usize;
+    if bytes.len() != 4 + count * 16 {
+        anyhow::bail!("dir mtime table length mismatch");
+    }
+    let mut stamps = HashMap::with_capacity(count);
+    for rec in bytes[4..].chunks_exact(16) {
+        stamps.insert(
+            u32::from_le_bytes(rec[0..4].try_into()?),
+            DirStamp {
+                mtime_ns: i64::from_le_bytes(rec[4..12].try_into()?),
+                children: u32::from_le_bytes(rec[12..16].try_into()?),
+            },
+        );
+    }
+    Ok(DirMtimeTable { stamps })
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Tombstones 序列化 / 反序列化（RoaringBitmap）
 // ─────────────────────────────────────────────────────────────────────────────
@@ -293,6 +592,88 @@ fn decode_tombstones(bytes: &[u8]) -> anyhow::Result<RoaringBitmap> {
         .map_err(|e| anyhow::anyhow!("tombstones deserialize failed: {}", e))
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// ContentIndex 序列化 / 反序列化（opt-in 内容 trigram 倒排；未启用时写空段）
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Layout:
+///   doc_count      u32
+///   docs           [path_len u32, path bytes, hash u64] * doc_count
+///   posting_count  u32
+///   postings       [trigram [u8;3], pad u8, len u32, roaring bytes] * posting_count
+///
+/// 写入前要求已压实（无 tombstone），doc id 即 docs 下标。
+fn encode_content_index(ci: &ContentIndex) -> Vec<u8> {
+    let mut out = Vec::new();
+    let docs: Vec<&ContentDoc> = ci.docs.iter().flatten().collect();
+    debug_assert!(ci.tombstones.is_empty(), "content index must be compacted");
+    out.extend_from_slice(&(docs.len() as u32).to_le_bytes());
+    for doc in docs {
+        out.extend_from_slice(&(doc.path.len() as u32).to_le_bytes());
+        out.extend_from_slice(&doc.path);
+        out.extend_from_slice(&doc.hash.to_le_bytes());
+    }
+    out.extend_from_slice(&(ci.postings.len() as u32).to_le_bytes());
+    for (tri, bitmap) in &ci.postings {
+        out.extend_from_slice(tri);
+        out.push(0); // pad
+        let mut posting = Vec::new();
+        bitmap
+            .serialize_into(&mut posting)
+            .expect("roaring serialize");
+        let posting_len: u32 = posting.len().try_into().unwrap_or(u32::MAX);
+        out.extend_from_slice(&posting_len.to_le_bytes());
+        out.extend_from_slice(&posting);
+    }
+    out
+}
+
+fn decode_content_index(bytes: &[u8]) -> anyhow::Result<ContentIndex> {
+    let mut ci = ContentIndex::new();
+    if bytes.len() < 8 {
+        anyhow::bail!("content index too small");
+    }
+    let doc_count = u32::from_le_bytes(bytes[0..4].try_into()?) as usize;
+    let mut off = 4usize;
+    for doc_id in 0..doc_count {
+        if off + 4 > bytes.len() {
+            anyhow::bail!("content index doc truncated");
+        }
+        let path_len = u32::from_le_bytes(bytes[off..off + 4].try_into()?) as usize;
+        off += 4;
+        if off + path_len + 8 > bytes.len() {
+            anyhow::bail!("content index doc path truncated");
+        }
+        let path = bytes[off..off + path_len].to_vec();
+        off += path_len;
+        let hash = u64::from_le_bytes(bytes[off..off + 8].try_into()?);
+        off += 8;
+        ci.path_to_doc.insert(path.clone(), doc_id as u32);
+        ci.docs.push(Some(ContentDoc { path, hash }));
+    }
+    if off + 4 > bytes.len() {
+        anyhow::bail!("content index posting count truncated");
+    }
+    let posting_count = u32::from_le_bytes(bytes[off..off + 4].try_into()?) as usize;
+    off += 4;
+    for _ in 0..posting_count {
+        if off + 8 > bytes.len() {
+            anyhow::bail!("content index posting header truncated");
+        }
+        let tri = [bytes[off], bytes[off + 1], bytes[off + 2]];
+        let posting_len = u32::from_le_bytes(bytes[off + 4..off + 8].try_into()?) as usize;
+        off += 8;
+        if off + posting_len > bytes.len() {
+            anyhow::bail!("content index posting truncated");
+        }
+        let bitmap = RoaringBitmap::deserialize_from(&bytes[off..off + posting_len])
+            .map_err(|e| anyhow::anyhow!("roaring deserialize failed: {}", e))?;
+        off += posting_len;
+        ci.postings.insert(tri, bitmap);
+    }
+    Ok(ci)
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Header / Trailer 编解码
 // ─────────────────────────────────────────────────────────────────────────────
@@ -478,15 +859,36 @@ impl V7Snapshot {
             .map(decode_tombstones)
             .transpose()?
             .unwrap_or_default();
+        // 旧快照没有短 gram 段：按路径表重建，保证短查询不退回全扫。
+        let short_gram_index = match self.segment(V7SegKind::ShortGramIndex) {
+            Some(bytes) => decode_short_gram_index(bytes)?,
+            None => ShortGramIndex::build(&path_table, &entries_by_key),
+        };
+        // 旧快照没有目录 mtime 表：由目录条目与 ParentIndex 推导。
+        let dir_mtimes = match self.segment(V7SegKind::DirMtimes) {
+            Some(bytes) => decode_dir_mtimes(bytes)?,
+            None => DirMtimeTable::build(&entries_by_key, &parent_index, &tombstones),
+        };
 
         Ok(BaseIndexData {
             path_table,
             entries_by_key,
             trigram_index,
+            short_gram_index,
             parent_index,
+            dir_mtimes,
             tombstones,
         })
     }
+
+    /// 反序列化内容索引段；旧快照无该段时返回空索引。
+    pub fn to_content_index(&self) -> anyhow::Result<ContentIndex> {
+        Ok(self
+            .segment(V7SegKind::ContentIndex)
+            .map(decode_content_index)
+            .transpose()?
+            .unwrap_or_default())
+    }
 }
 
 /// 从文件路径加载 v7 快照（校验 header/trailer/各段 CRC）。
@@ -546,7 +948,8 @@ pub fn load_v7_from_path(path: &Path) -> anyhow::Result<Option<V7Snapshot>> {
             return Ok(None);
         }
         // kind 需要从 header 的 SegmentDesc 表中读取，但 trailer 中没有 kind 信息。
-        // 简化：v7 固定段顺序 = PathTable, EntriesByKey, EntriesByPath, TrigramIndex, ParentIndex, Tombstones
+        // 简化：v7 固定段顺序 = PathTable, EntriesByKey, EntriesByPath, TrigramIndex, ParentIndex, Tombstones,
+        // ContentIndex, ShortGramIndex, DirMtimes（后追加的段对旧文件缺省，读取端回退为空或重建）
         let kind = match i {
             0 => V7SegKind::PathTable,
             1 => V7SegKind::EntriesByKey,
@@ -554,6 +957,9 @@ pub fn load_v7_from_path(path: &Path) -> anyhow::Result<Option<V7Snapshot>> {
             3 => V7SegKind::TrigramIndex,
             4 => V7SegKind::ParentIndex,
             5 => V7SegKind::Tombstones,
+            6 => V7SegKind::ContentIndex,
+            7 => V7SegKind::ShortGramIndex,
+            8 => V7SegKind::DirMtimes,
             _ => {
                 tracing::warn!("v7 unknown segment index {}", i);
                 return Ok(None);
@@ -595,6 +1001,16 @@ fn align_up(v: usize, a: usize) -> usize {
 /// 3) 写 header + segments + trailer 到 .tmp
 /// 4) fsync + rename
 pub fn write_v7_snapshot_atomic(path: &Path, data: &BaseIndexData) -> anyhow::Result<()> {
+    write_v7_snapshot_atomic_with_content(path, data, None)
+}
+
+/// 同 `write_v7_snapshot_atomic`，并附带内容索引段（`None` 写空段）。
+pub fn write_v7_snapshot_atomic_with_content(
+    path: &Path,
+    data: &BaseIndexData,
+    content: Option<&ContentIndex>,
+) -> anyhow::Result<()> {
+    let empty_content = ContentIndex::new();
     let segments_bytes: Vec<(V7SegKind, Vec<u8>)> = vec![
         (V7SegKind::PathTable, encode_path_table(&data.path_table)),
         (
@@ -614,6 +1030,15 @@ pub fn write_v7_snapshot_atomic(path: &Path, data: &BaseIndexData) -> anyhow::Re
             encode_parent_index(&data.parent_index),
         ),
         (V7SegKind::Tombstones, encode_tombstones(&data.tombstones)),
+        (
+            V7SegKind::ContentIndex,
+            encode_content_index(content.unwrap_or(&empty_content)),
+        ),
+        (
+            V7SegKind::ShortGramIndex,
+            encode_short_gram_index(&data.short_gram_index),
+        ),
+        (V7SegKind::DirMtimes, encode_dir_mtimes(&data.dir_mtimes)),
     ];
 
     let num_segments = segments_bytes.len() as u32;
@@ -723,7 +1148,9 @@ pub fn snapshot_now_v7(
             }
         }
         merged.trigram_index = b.trigram_index.clone();
+        merged.short_gram_index = b.short_gram_index.clone();
         merged.parent_index = b.parent_index.clone();
+        merged.dir_mtimes = b.dir_mtimes.clone();
         merged.tombstones = b.tombstones.clone();
     }
 
@@ -737,6 +1164,12 @@ pub fn snapshot_now_v7(
     for (tri, bm) in &delta.trigram_index.inner {
         merged.trigram_index.insert(*tri, bm.clone());
     }
+    for (b, bm) in &delta.short_gram_index.unigrams {
+        merged.short_gram_index.unigrams.insert(*b, bm.clone());
+    }
+    for (g, bm) in &delta.short_gram_index.bigrams {
+        merged.short_gram_index.bigrams.insert(*g, bm.clone());
+    }
     for (dir, bm) in &delta.parent_index.dir_to_files {
         merged
             .parent_index
@@ -749,6 +1182,9 @@ pub fn snapshot_now_v7(
             })
             .or_insert_with(|| bm.clone());
     }
+    for (dir, stamp) in &delta.dir_mtimes.stamps {
+        merged.dir_mtimes.stamps.insert(*dir, *stamp);
+    }
     merged.tombstones |= delta.tombstones.clone();
 
     // 排序（key）
@@ -778,10 +1214,37 @@ pub fn try_load_v7(path: &Path) -> anyhow::Result<Option<BaseIndexData>> {
     }
 }
 
+/// 同 `try_load_v7`，并一并解出内容索引段（旧快照返回空内容索引）。
+pub fn try_load_v7_with_content(
+    path: &Path,
+) -> anyhow::Result<Option<(BaseIndexData, ContentIndex)>> {
+    let Some(snap) = load_v7_from_path(path)? else {
+        return Ok(None);
+    };
+    let data = match snap.to_base_index_data() {
+        Ok(data) => data,
+        Err(e) => {
+            tracing::warn!("v7 snapshot deserialize failed: {}", e);
+            return Ok(None);
+        }
+    };
+    let content = snap.to_content_index().unwrap_or_else(|e| {
+        tracing::warn!("v7 content index deserialize failed, dropping: {}", e);
+        ContentIndex::new()
+    });
+    tracing::info!(
+        "v7 snapshot loaded: {} paths, {} content docs",
+        data.path_table.len(),
+        content.len()
+    );
+    Ok(Some((data, content)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::core::FileKey;
+    use crate::index::dir_mtimes::DirCheck;
     use std::path::PathBuf;
 
     fn tmp_v7_path(tag: &str) -> PathBuf {
@@ -816,6 +1279,242 @@ mod tests {
         assert!(decoded.tombstones.contains(42));
     }
 
+    #[test]
+    fn v7_short_gram_index_roundtrip_and_rebuilt_for_old_files() {
+        use crate::core::{FileKind, FileMeta};
+        use crate::index::l2_partition::PersistentIndex;
+
+        let path = tmp_v7_path("short-gram");
+        let l2 = PersistentIndex::new_with_roots(vec![PathBuf::from("/r")]);
+        for (ino, name) in [(1, "a.rs"), (2, "Qx.txt")] {
+            l2.upsert(FileMeta {
+                file_key: FileKey {
+                    dev: 1,
+                    ino,
+                    generation: 0,
+                },
+                path: PathBuf::from("/r").join(name),
+                size: 1,
+                mtime: None,
+                ctime: None,
+                atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
+            });
+        }
+        let data = l2.to_base_index_data();
+        write_v7_snapshot_atomic(&path, &data).unwrap();
+
+        let mut loaded = load_v7_from_path(&path).unwrap().unwrap();
+        let decoded = loaded.to_base_index_data().unwrap();
+        assert_eq!(decoded.short_gram_index.len(), data.short_gram_index.len());
+        assert_eq!(
+            decoded.short_gram_index.get(b"qx"),
+            data.short_gram_index.get(b"qx")
+        );
+        assert_eq!(
+            decoded.short_gram_index.get(b"qx").map(|b| b.len()),
+            Some(1)
+        );
+
+        // 旧快照没有该段：从路径表重建出相同的 posting
+        loaded
+            .segments
+            .retain(|(kind, _)| *kind != V7SegKind::ShortGramIndex);
+        let rebuilt = loaded.to_base_index_data().unwrap();
+        for gram in [&b"a"[..], b"r", b"qx", b".t", b"rs"] {
+            assert_eq!(
+                rebuilt.short_gram_index.get(gram),
+                data.short_gram_index.get(gram),
+                "{gram:?}"
+            );
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn v7_dir_mtimes_roundtrip_and_rebuilt_for_old_files() {
+        use crate::core::{FileKind, FileMeta};
+        use crate::index::l2_partition::PersistentIndex;
+
+        let path = tmp_v7_path("dir-mtimes");
+        let l2 = PersistentIndex::new_with_roots(vec![PathBuf::from("/r")]);
+        let mtime = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        for (ino, name, kind) in [
+            (1, "d", FileKind::Dir),
+            (2, "d/a.rs", FileKind::File),
+            (3, "d/sub", FileKind::Dir),
+        ] {
+            l2.upsert(FileMeta {
+                file_key: FileKey {
+                    dev: 1,
+                    ino,
+                    generation: 0,
+                },
+                path: PathBuf::from("/r").join(name),
+                size: 1,
+                mtime: Some(mtime),
+                ctime: None,
+                atime: None,
+                kind,
+                nlink: 0,
+                target: None,
+            });
+        }
+        let data = l2.to_base_index_data();
+        assert_eq!(data.dir_mtimes.len(), 2);
+        write_v7_snapshot_atomic(&path, &data).unwrap();
+
+        let mut loaded = load_v7_from_path(&path).unwrap().unwrap();
+        let decoded = loaded.to_base_index_data().unwrap();
+        assert_eq!(decoded.dir_mtimes.stamps, data.dir_mtimes.stamps);
+        let mtime_ns = crate::index::file_entry_v2::time_to_ns(Some(mtime));
+        assert_eq!(
+            decoded.check_dir(Path::new("/r/d"), mtime_ns),
+            DirCheck::Unchanged(vec![PathBuf::from("/r/d/sub")])
+        );
+        assert_eq!(
+            decoded.check_dir(Path::new("/r/d"), mtime_ns + 1),
+            DirCheck::Changed
+        );
+        assert_eq!(
+            decoded.check_dir(Path::new("/r"), mtime_ns),
+            DirCheck::Unknown
+        );
+
+        // 旧快照没有该段：由目录条目与 ParentIndex 推导出相同的表
+        loaded
+            .segments
+            .retain(|(kind, _)| *kind != V7SegKind::DirMtimes);
+        let rebuilt = loaded.to_base_index_data().unwrap();
+        assert_eq!(rebuilt.dir_mtimes.stamps, data.dir_mtimes.stamps);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn v7_entries_by_key_roundtrip_kind() {
+        let key = |ino| FileKey {
+            dev: 1,
+            ino,
+            generation: 0,
+        };
+        let mut fei = FileEntryIndex::new();
+        fei.push(FileEntry::from_file_key(key(1), 0, 10, -1));
+        fei.push(FileEntry::from_file_key(key(2), 1, 4096, -1).with_kind(FileKind::Dir));
+        let bytes = encode_file_entry_index(&fei.build());
+
+        let decoded = decode_file_entry_index(&bytes).unwrap();
+        assert_eq!(decoded.get(0).unwrap().kind(), FileKind::File);
+        assert_eq!(decoded.get(1).unwrap().kind(), FileKind::Dir);
+
+        // 旧布局（无尾部 kind 扩展）：全部按普通文件读取。
+        let legacy = decode_file_entry_index(&bytes[..4 + 2 * 40]).unwrap();
+        assert!(legacy.iter().all(|e| e.kind() == FileKind::File));
+    }
+
+    #[test]
+    fn v7_entries_by_key_roundtrip_times() {
+        let key = |ino| FileKey {
+            dev: 1,
+            ino,
+            generation: 0,
+        };
+        let mut fei = FileEntryIndex::new();
+        fei.push(FileEntry::from_file_key(key(1), 0, 10, 5));
+        fei.push(FileEntry::from_file_key(key(2), 1, 20, 6));
+        fei.set_times(0, 7, -1);
+        fei.set_times(1, 8, -1);
+        let bytes = encode_file_entry_index(&fei.build());
+
+        let decoded = decode_file_entry_index(&bytes).unwrap();
+        let times: Vec<_> = (0..decoded.len())
+            .map(|i| (decoded.times().ctime_ns(i), decoded.times().atime_ns(i)))
+            .collect();
+        assert_eq!(times, vec![(7, -1), (8, -1)]);
+
+        // atime 全部未知时不写 atime 列
+        let dir_ext = 4 + RoaringBitmap::new().serialized_size();
+        assert_eq!(bytes.len(), 4 + 2 * 40 + dir_ext + 4 + 4 + 2 * 8 + 4 + 4);
+
+        // 只有 kind 扩展的旧快照：时间未知
+        let legacy = decode_file_entry_index(&bytes[..4 + 2 * 40 + dir_ext]).unwrap();
+        assert!((0..legacy.len())
+            .all(|i| legacy.times().ctime(i).is_none() && legacy.times().atime(i).is_none()));
+    }
+
+    #[test]
+    fn v7_entries_by_key_roundtrip_links() {
+        let key = |ino| FileKey {
+            dev: 1,
+            ino,
+            generation: 0,
+        };
+        let mut fei = FileEntryIndex::new();
+        fei.push(FileEntry::from_file_key(key(1), 0, 10, 5).with_nlink(2));
+        fei.push(FileEntry::from_file_key(key(1), 1, 10, 5).with_nlink(2));
+        fei.push(FileEntry::from_file_key(key(2), 2, 20, 6).with_nlink(1));
+        let bytes = encode_file_entry_index(&fei.build());
+
+        let decoded = decode_file_entry_index(&bytes).unwrap();
+        let links: Vec<u32> = decoded.iter().map(|e| e.nlink).collect();
+        assert_eq!(links, vec![2, 2, 1]);
+        assert_eq!(decoded.docids_by_filekey(key(1)).len(), 2);
+
+        // 缺少 links 扩展的旧快照：nlink 未知
+        let legacy = decode_file_entry_index(&bytes[..bytes.len() - 4 - 4 - 3 * 4]).unwrap();
+        assert!(legacy.iter().all(|e| e.nlink == 0));
+    }
+
+    #[test]
+    fn v7_entries_by_key_roundtrip_symlinks() {
+        let key = |ino| FileKey {
+            dev: 1,
+            ino,
+            generation: 0,
+        };
+        let mut fei = FileEntryIndex::new();
+        fei.push(FileEntry::from_file_key(key(1), 0, 10, 5));
+        fei.push_link(
+            FileEntry::from_file_key(key(2), 1, 10, 5).with_kind(FileKind::Symlink),
+            b"../dotfiles/vimrc",
+        );
+        let mut broken = FileEntry::from_file_key(key(3), 2, 4, 6).with_kind(FileKind::Symlink);
+        broken.set_broken(true);
+        fei.push_link(broken, b"/gone");
+        let bytes = encode_file_entry_index(&fei.build());
+
+        let decoded = decode_file_entry_index(&bytes).unwrap();
+        assert_eq!(decoded.get(0).unwrap().kind(), FileKind::File);
+        assert_eq!(decoded.get(1).unwrap().kind(), FileKind::Symlink);
+        assert!(!decoded.get(1).unwrap().is_broken());
+        assert!(decoded.get(2).unwrap().is_broken());
+        assert_eq!(decoded.link_target(1), Some(&b"../dotfiles/vimrc"[..]));
+        assert_eq!(decoded.link_target(2), Some(&b"/gone"[..]));
+        assert_eq!(decoded.link_target(0), None);
+    }
+
+    #[test]
+    fn v7_roundtrip_content_index() {
+        let path = tmp_v7_path("content");
+        let mut content = ContentIndex::new();
+        content.upsert(b"/repo/a.rs", b"// TODO fixme");
+        content.upsert(b"/repo/b.rs", b"fn main() {}");
+
+        write_v7_snapshot_atomic_with_content(&path, &BaseIndexData::default(), Some(&content))
+            .unwrap();
+        let (_, decoded) = try_load_v7_with_content(&path).unwrap().unwrap();
+
+        assert_eq!(decoded.len(), 2);
+        let doc = decoded.doc_id(b"/repo/a.rs").unwrap();
+        assert!(decoded.candidates(b"todo").unwrap().contains(doc));
+
+        write_v7_snapshot_atomic(&path, &BaseIndexData::default()).unwrap();
+        let (_, empty) = try_load_v7_with_content(&path).unwrap().unwrap();
+        assert!(empty.is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
     #[test]
     fn v7_load_missing_returns_none() {
         let path = tmp_v7_path("missing");
@@ -12,7 +12,7 @@ use std::sync::Arc;
 use crate::core::EventRecord;
 use crate::index::l2_partition::V6Segments;
 use crate::storage::snapshot::{LoadedSnapshot, LsmLoadedLayers, LsmSegmentLoaded, MmapSnapshotV6};
-use crate::storage::wal::WalReplayResult;
+use crate::storage::wal::{WalReplayResult, WalTail};
 
 pub type StorageFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
 
@@ -82,6 +82,13 @@ pub trait WriteAheadLog {
     /// Replay all events from sealed WALs with id > `checkpoint_seal_id`,
     /// plus the current WAL.
     fn replay_since_seal(&self, checkpoint_seal_id: u64) -> anyhow::Result<WalReplayResult>;
+
+    /// Read up to `max` events with journal seq > `since` from the retained
+    /// WAL files, or report that the cursor is no longer covered.
+    fn read_since(&self, since: u64, max: usize) -> anyhow::Result<WalTail>;
+
+    /// Journal seq of the last appended event (0 when nothing was logged).
+    fn last_seq(&self) -> u64;
 }
 
 // ---------------------------------------------------------------------------
@@ -1,13 +1,14 @@
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
 
 use crate::core::{EventRecord, EventType, FileIdentifier};
 use crate::storage::checksum::crc32c_checksum;
 
 const WAL_MAGIC: u32 = 0x314C_4157; // "WAL1"
-const WAL_VERSION: u32 = 3;
+const WAL_VERSION: u32 = 4;
 
 // Safety guard: WAL records are expected to be small (path + metadata). Treat any huge length as
 // corruption to avoid memory DoS via `vec![0u8; len]`.
@@ -141,6 +142,19 @@ pub struct WalReplayResult {
     pub truncated_tail_records: usize,
 }
 
+/// 按日志序号游标读取 WAL 的结果（见 `WalStore::read_since`）。
+#[derive(Clone, Debug)]
+pub enum WalTail {
+    /// `since` 之后的事件（按 seq 升序，至多 `max` 条）；`latest` 为读取时的最新序号。
+    Events {
+        events: Vec<EventRecord>,
+        latest: u64,
+    },
+    /// 游标早于仍保留的最老 segment（已 seal 并被清理），或晚于最新序号（WAL 被重置）：
+    /// 调用方需要全量重新同步。`oldest` 为当前可续读的最小游标。
+    Expired { oldest: u64, latest: u64 },
+}
+
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub enum WalDurability {
     #[default]
@@ -152,23 +166,33 @@ pub enum WalDurability {
 ///
 /// - current: events.wal
 /// - sealed: events.wal.seal-<id>（snapshot 边界切分）
+///
+/// v4 起每个文件 header 记录 base_seq，文件内第 i 条记录的日志序号为 `base_seq + i`，
+/// 序号跨 seal / 重启单调递增，可作为变更日志的游标。
 pub struct WalStore {
     dir: PathBuf,
     current: PathBuf,
     file: Mutex<File>,
     durability: Mutex<WalDurability>,
+    /// 最后一条已写入记录的日志序号（只在持有 `file` 锁时推进）。
+    last_seq: AtomicU64,
 }
 
 impl WalStore {
     pub fn open_in_dir(dir: PathBuf) -> anyhow::Result<Self> {
         std::fs::create_dir_all(&dir)?;
         let current = dir.join("events.wal");
-        let f = open_or_init(&current)?;
+        // current 缺失/需要重建时，从最后一个 sealed 文件的末尾序号续接。
+        let sealed_end = last_sealed_seq(&dir)?;
+        let f = open_or_init(&current, sealed_end)?;
+        let scan = scan_wal_file(&current, u64::MAX, usize::MAX)?;
+        let last_seq = scan.base_seq.unwrap_or(sealed_end) + scan.records;
         Ok(Self {
             dir,
             current,
             file: Mutex::new(f),
             durability: Mutex::new(WalDurability::FlushOnly),
+            last_seq: AtomicU64::new(last_seq),
         })
     }
 
@@ -188,6 +212,7 @@ impl WalStore {
             f.write_all(&len.to_le_bytes())?;
             f.write_all(&crc.to_le_bytes())?;
             f.write_all(&payload[..len as usize])?;
+            self.last_seq.fetch_add(1, Ordering::AcqRel);
         }
         f.flush()?;
         if *self.durability.lock().unwrap_or_else(|e| e.into_inner())
@@ -222,11 +247,61 @@ impl WalStore {
             let _ = dir.sync_all();
         }
 
-        let newf = open_or_init(&self.current)?;
-        *self.file.lock().unwrap_or_else(|e| e.into_inner()) = newf;
+        // 持锁读取 base：rename 之后仍写入旧句柄的记录已计入 last_seq。
+        let mut f = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        *f = open_or_init(&self.current, self.last_seq.load(Ordering::Acquire))?;
         Ok(id)
     }
 
+    /// 最后一条已写入记录的日志序号。
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq.load(Ordering::Acquire)
+    }
+
+    /// 读取日志序号 > `since` 的事件（至多 `max` 条），数据来自仍保留的 sealed WAL + 当前 WAL。
+    ///
+    /// v1-v3 文件没有日志序号，不参与游标读取。
+    pub fn read_since(&self, since: u64, max: usize) -> anyhow::Result<WalTail> {
+        let latest = self.last_seq();
+        let mut chain = Vec::new();
+        for (_, p) in self.sealed_files()? {
+            if let Some(base) = read_base_seq(&p)? {
+                chain.push((base, p));
+            }
+        }
+        if let Some(base) = read_base_seq(&self.current)? {
+            chain.push((base, self.current.clone()));
+        }
+
+        let oldest = chain.first().map(|(base, _)| *base).unwrap_or(latest);
+        if since < oldest || since > latest {
+            return Ok(WalTail::Expired { oldest, latest });
+        }
+
+        // 从最后一个 base_seq <= since 的文件开始读，之前的文件整体跳过。
+        let start = chain
+            .iter()
+            .rposition(|(base, _)| *base <= since)
+            .unwrap_or(0);
+        let mut events = Vec::new();
+        for (i, (_, p)) in chain[start..].iter().enumerate() {
+            if events.len() >= max {
+                break;
+            }
+            let scan = scan_wal_file(p, since, max - events.len())?;
+            if i == 0 && scan.base_seq.is_none() {
+                // 起始文件在读取期间被 cleanup：按新的保留范围重新判定。
+                return self.read_since(since, max);
+            }
+            events.extend(scan.events.into_iter().filter(|ev| ev.seq <= latest));
+        }
+        Ok(WalTail::Events { events, latest })
+    }
+
+    fn sealed_files(&self) -> anyhow::Result<Vec<(u64, PathBuf)>> {
+        list_sealed_files(&self.dir)
+    }
+
     pub fn cleanup_sealed_up_to(&self, seal_id: u64) -> anyhow::Result<()> {
         if seal_id == 0 {
             return Ok(());
@@ -247,59 +322,72 @@ impl WalStore {
 
     /// 回放：只读取 seal_id > checkpoint 的 sealed WAL + 当前 WAL。
     pub fn replay_since_seal(&self, checkpoint_seal_id: u64) -> anyhow::Result<WalReplayResult> {
-        let mut sealed = Vec::new();
-        for ent in std::fs::read_dir(&self.dir)? {
-            let Ok(ent) = ent else { continue };
-            let p = ent.path();
-            if let Some(id) = parse_seal_id(&p) {
-                if id > checkpoint_seal_id {
-                    sealed.push((id, p));
-                }
-            }
-        }
-        sealed.sort_by_key(|(id, _)| *id);
-
-        let mut events: Vec<EventRecord> = Vec::new();
-        let mut truncated = 0usize;
-        for (_, p) in sealed.iter() {
-            let (mut evs, t) = read_wal_file(p)?;
-            truncated += t;
-            events.append(&mut evs);
-        }
-        let (mut cur, t) = read_wal_file(&self.current)?;
-        truncated += t;
-        events.append(&mut cur);
-
-        // Deduplicate by (id, timestamp), keeping the last occurrence.
-        // This prevents duplicate index entries when WAL contains duplicate
-        // records from abnormal writes or partial flushes.
-        let mut last_pos = std::collections::HashMap::new();
-        for (idx, ev) in events.iter().enumerate() {
-            last_pos.insert((ev.id.clone(), ev.timestamp), idx);
-        }
-        let mut keep = vec![false; events.len()];
-        for &idx in last_pos.values() {
-            keep[idx] = true;
-        }
-        let mut retained = Vec::with_capacity(last_pos.len());
-        for (idx, ev) in events.drain(..).enumerate() {
-            if keep[idx] {
-                retained.push(ev);
-            }
-        }
-        events = retained;
+        replay_dir(&self.dir, &self.current, checkpoint_seal_id)
+    }
+}
 
-        // 统一为单调 seq（WAL 内部 seq 只用于排序/回放稳定性）。
-        for (i, e) in events.iter_mut().enumerate() {
-            e.seq = i as u64 + 1;
+/// 只读回放目录中 seal_id > checkpoint 的 sealed WAL + 当前 WAL，不创建、升级或截断任何文件。
+///
+/// 供离线查询在 daemon 未运行时使用；目录不存在时返回空结果。
+pub fn replay_read_only(dir: &Path, checkpoint_seal_id: u64) -> anyhow::Result<WalReplayResult> {
+    if !dir.is_dir() {
+        return Ok(WalReplayResult {
+            events: Vec::new(),
+            sealed_used: 0,
+            truncated_tail_records: 0,
+        });
+    }
+    replay_dir(dir, &dir.join("events.wal"), checkpoint_seal_id)
+}
+
+fn replay_dir(
+    dir: &Path,
+    current: &Path,
+    checkpoint_seal_id: u64,
+) -> anyhow::Result<WalReplayResult> {
+    let mut sealed = list_sealed_files(dir)?;
+    sealed.retain(|(id, _)| *id > checkpoint_seal_id);
+
+    let mut events: Vec<EventRecord> = Vec::new();
+    let mut truncated = 0usize;
+    for (_, p) in sealed.iter() {
+        let (mut evs, t) = read_wal_file(p)?;
+        truncated += t;
+        events.append(&mut evs);
+    }
+    let (mut cur, t) = read_wal_file(current)?;
+    truncated += t;
+    events.append(&mut cur);
+
+    // Deduplicate by (id, timestamp), keeping the last occurrence.
+    // This prevents duplicate index entries when WAL contains duplicate
+    // records from abnormal writes or partial flushes.
+    let mut last_pos = std::collections::HashMap::new();
+    for (idx, ev) in events.iter().enumerate() {
+        last_pos.insert((ev.id.clone(), ev.timestamp), idx);
+    }
+    let mut keep = vec![false; events.len()];
+    for &idx in last_pos.values() {
+        keep[idx] = true;
+    }
+    let mut retained = Vec::with_capacity(last_pos.len());
+    for (idx, ev) in events.drain(..).enumerate() {
+        if keep[idx] {
+            retained.push(ev);
         }
+    }
+    events = retained;
 
-        Ok(WalReplayResult {
-            events,
-            sealed_used: sealed.len(),
-            truncated_tail_records: truncated,
-        })
+    // 统一为单调 seq（WAL 内部 seq 只用于排序/回放稳定性）。
+    for (i, e) in events.iter_mut().enumerate() {
+        e.seq = i as u64 + 1;
     }
+
+    Ok(WalReplayResult {
+        events,
+        sealed_used: sealed.len(),
+        truncated_tail_records: truncated,
+    })
 }
 
 // ---------------------------------------------------------------------------
@@ -326,9 +414,41 @@ impl crate::storage::traits::WriteAheadLog for WalStore {
     fn replay_since_seal(&self, checkpoint_seal_id: u64) -> anyhow::Result<WalReplayResult> {
         self.replay_since_seal(checkpoint_seal_id)
     }
+
+    fn read_since(&self, since: u64, max: usize) -> anyhow::Result<WalTail> {
+        self.read_since(since, max)
+    }
+
+    fn last_seq(&self) -> u64 {
+        self.last_seq()
+    }
 }
 
-fn open_or_init(path: &Path) -> anyhow::Result<File> {
+fn write_header(f: &mut File, base_seq: u64) -> std::io::Result<()> {
+    f.write_all(&WAL_MAGIC.to_le_bytes())?;
+    f.write_all(&WAL_VERSION.to_le_bytes())?;
+    f.write_all(&base_seq.to_le_bytes())?;
+    f.flush()
+}
+
+/// 截断并写入新 header 后，以 append 模式重新打开。
+fn reinit(path: &Path, base_seq: u64) -> anyhow::Result<File> {
+    let mut nf = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)?;
+    write_header(&mut nf, base_seq)?;
+    drop(nf);
+    Ok(OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)?)
+}
+
+/// 打开（或创建）当前 WAL。新建/重建文件时以 `base_seq` 作为 header 中的起始序号。
+fn open_or_init(path: &Path, base_seq: u64) -> anyhow::Result<File> {
     let exists = path.exists();
     let mut f = OpenOptions::new()
         .create(true)
@@ -337,45 +457,26 @@ fn open_or_init(path: &Path) -> anyhow::Result<File> {
         .open(path)?;
 
     if !exists {
-        f.write_all(&WAL_MAGIC.to_le_bytes())?;
-        f.write_all(&WAL_VERSION.to_le_bytes())?;
-        f.flush()?;
+        write_header(&mut f, base_seq)?;
         return Ok(f);
     }
 
     // 快速校验 header；不匹配则重建（避免历史垃圾文件导致读崩）。
-    let mut hdr = [0u8; 8];
     f.seek(SeekFrom::Start(0))?;
-    if f.read_exact(&mut hdr).is_err() {
+    let Some((ver, _)) = read_header(&mut f)? else {
         // 空文件/截断：重写 header
-        f = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(path)?;
-        f.write_all(&WAL_MAGIC.to_le_bytes())?;
-        f.write_all(&WAL_VERSION.to_le_bytes())?;
-        f.flush()?;
-        f = OpenOptions::new()
-            .create(true)
-            .read(true)
-            .append(true)
-            .open(path)?;
-        return Ok(f);
-    }
+        return reinit(path, base_seq);
+    };
 
-    let magic = u32::from_le_bytes(hdr[0..4].try_into()?);
-    let ver = u32::from_le_bytes(hdr[4..8].try_into()?);
-    if magic == WAL_MAGIC && (ver == 1 || ver == 2) && WAL_VERSION == 3 {
-        // v1/v2 -> v3：非破坏性升级
+    if ver < WAL_VERSION {
+        // v1/v2/v3 -> v4：非破坏性升级
         // 关键点：绝不能 truncate，否则会丢事件。
         drop(f);
         let id = now_seal_id();
-        let suffix = if ver == 1 { ".v1" } else { ".v2" };
         let sealed = path
             .parent()
             .unwrap_or_else(|| Path::new("."))
-            .join(format!("events.wal.seal-{id:016x}{suffix}"));
+            .join(format!("events.wal.seal-{id:016x}.v{ver}"));
         std::fs::rename(path, &sealed)?;
 
         // fsync(dir) after rename to ensure the directory entry is persisted.
@@ -385,45 +486,44 @@ fn open_or_init(path: &Path) -> anyhow::Result<File> {
             }
         }
 
-        let mut nf = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(path)?;
-        nf.write_all(&WAL_MAGIC.to_le_bytes())?;
-        nf.write_all(&WAL_VERSION.to_le_bytes())?;
-        nf.flush()?;
-        drop(nf);
-
-        f = OpenOptions::new()
-            .create(true)
-            .read(true)
-            .append(true)
-            .open(path)?;
-    } else if magic != WAL_MAGIC || (ver != 1 && ver != 2 && ver != 3) {
-        // 不兼容：truncate 重新开始（保守）。v1/v2/v3 以外视为垃圾文件。
-        let mut nf = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(path)?;
-        nf.write_all(&WAL_MAGIC.to_le_bytes())?;
-        nf.write_all(&WAL_VERSION.to_le_bytes())?;
-        nf.flush()?;
-        drop(nf);
-        f = OpenOptions::new()
-            .create(true)
-            .read(true)
-            .append(true)
-            .open(path)?;
-    } else if ver != WAL_VERSION {
-        // v2 读 v1 sealed 是允许的；但 current WAL 只写最新版本。
-        // 若出现 v2->未来版本等情况，会在上面的分支被 truncate。
+        f = reinit(path, base_seq)?;
     }
 
     Ok(f)
 }
 
+/// 读取并校验 header，返回 `(version, base_seq)`；v1-v3 没有 base_seq。
+/// magic/版本不识别或 header 截断时返回 None。
+fn read_header(f: &mut File) -> anyhow::Result<Option<(u32, Option<u64>)>> {
+    let mut hdr = [0u8; 8];
+    if f.read_exact(&mut hdr).is_err() {
+        return Ok(None);
+    }
+    let magic = u32::from_le_bytes(hdr[0..4].try_into()?);
+    let ver = u32::from_le_bytes(hdr[4..8].try_into()?);
+    if magic != WAL_MAGIC || !(1..=WAL_VERSION).contains(&ver) {
+        return Ok(None);
+    }
+    if ver < 4 {
+        return Ok(Some((ver, None)));
+    }
+    let mut base = [0u8; 8];
+    if f.read_exact(&mut base).is_err() {
+        return Ok(None);
+    }
+    Ok(Some((ver, Some(u64::from_le_bytes(base)))))
+}
+
+/// 文件 header 中的 base_seq；文件不存在或为旧版本时返回 None。
+fn read_base_seq(path: &Path) -> anyhow::Result<Option<u64>> {
+    let mut f = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(read_header(&mut f)?.and_then(|(_, base)| base))
+}
+
 fn parse_seal_id(path: &Path) -> Option<u64> {
     let name = path.file_name()?.to_str()?;
     let prefix = "events.wal.seal-";
@@ -438,22 +538,66 @@ fn parse_seal_id(path: &Path) -> Option<u64> {
     u64::from_str_radix(&hex, 16).ok()
 }
 
-fn read_wal_file(path: &Path) -> anyhow::Result<(Vec<EventRecord>, usize)> {
-    if !path.exists() {
-        return Ok((Vec::new(), 0));
+/// 目录中的 sealed WAL，按 seal_id 升序。
+fn list_sealed_files(dir: &Path) -> anyhow::Result<Vec<(u64, PathBuf)>> {
+    let mut sealed = Vec::new();
+    for ent in std::fs::read_dir(dir)? {
+        let Ok(ent) = ent else { continue };
+        let p = ent.path();
+        if let Some(id) = parse_seal_id(&p) {
+            sealed.push((id, p));
+        }
     }
-    let mut f = File::open(path)?;
-    let file_len = f.metadata().map(|m| m.len()).unwrap_or(u64::MAX);
+    sealed.sort_by_key(|(id, _)| *id);
+    Ok(sealed)
+}
 
-    let mut hdr = [0u8; 8];
-    if f.read_exact(&mut hdr).is_err() {
-        return Ok((Vec::new(), 0));
-    }
-    let magic = u32::from_le_bytes(hdr[0..4].try_into()?);
-    let ver = u32::from_le_bytes(hdr[4..8].try_into()?);
-    if magic != WAL_MAGIC || (ver != 1 && ver != 2 && ver != 3) {
-        return Ok((Vec::new(), 0));
+/// 最后一个带日志序号的 sealed WAL 的末尾序号；没有时为 0。
+fn last_sealed_seq(dir: &Path) -> anyhow::Result<u64> {
+    for (_, p) in list_sealed_files(dir)?.into_iter().rev() {
+        let scan = scan_wal_file(&p, u64::MAX, usize::MAX)?;
+        if let Some(base) = scan.base_seq {
+            return Ok(base + scan.records);
+        }
     }
+    Ok(0)
+}
+
+fn read_wal_file(path: &Path) -> anyhow::Result<(Vec<EventRecord>, usize)> {
+    let scan = scan_wal_file(path, 0, usize::MAX)?;
+    Ok((scan.events, scan.truncated_tail))
+}
+
+/// 单个 WAL 文件的扫描结果。
+struct WalFileScan {
+    /// v4 header 中的 base_seq；v1-v3 文件没有日志序号。
+    base_seq: Option<u64>,
+    events: Vec<EventRecord>,
+    /// 已成帧的记录数（CRC 校验失败被跳过的记录同样占用一个序号）。
+    records: u64,
+    truncated_tail: usize,
+}
+
+/// 扫描 WAL 文件：日志序号 <= `skip_through` 的记录只跳过不解码，至多解码 `limit` 条。
+/// 旧版本文件没有序号，总是全部解码（seq 置 0）。
+fn scan_wal_file(path: &Path, skip_through: u64, limit: usize) -> anyhow::Result<WalFileScan> {
+    let mut scan = WalFileScan {
+        base_seq: None,
+        events: Vec::new(),
+        records: 0,
+        truncated_tail: 0,
+    };
+    let mut f = match File::open(path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(scan),
+        Err(e) => return Err(e.into()),
+    };
+    let file_len = f.metadata().map(|m| m.len()).unwrap_or(u64::MAX);
+
+    let Some((ver, base_seq)) = read_header(&mut f)? else {
+        return Ok(scan);
+    };
+    scan.base_seq = base_seq;
 
     if ver == 1 || ver == 2 {
         tracing::warn!(
@@ -463,14 +607,12 @@ fn read_wal_file(path: &Path) -> anyhow::Result<(Vec<EventRecord>, usize)> {
         );
     }
 
-    let mut out = Vec::new();
-    let mut truncated_tail = 0usize;
-    let mut pos: u64 = 8; // header consumed
-    loop {
+    let mut pos: u64 = if base_seq.is_some() { 16 } else { 8 }; // header consumed
+    while scan.events.len() < limit {
         let mut lb = [0u8; 8];
         if f.read_exact(&mut lb).is_err() {
             if pos < file_len {
-                truncated_tail += 1;
+                scan.truncated_tail += 1;
             }
             break;
         }
@@ -478,18 +620,25 @@ fn read_wal_file(path: &Path) -> anyhow::Result<(Vec<EventRecord>, usize)> {
         let len = u32::from_le_bytes(lb[0..4].try_into()?) as usize;
         let crc = u32::from_le_bytes(lb[4..8].try_into()?);
         if len > MAX_WAL_RECORD_BYTES || pos.saturating_add(len as u64) > file_len {
-            truncated_tail += 1;
+            scan.truncated_tail += 1;
             break;
         }
+        scan.records += 1;
+        let seq = base_seq.map(|base| base + scan.records);
+        if seq.is_some_and(|seq| seq <= skip_through) {
+            f.seek(SeekFrom::Current(len as i64))?;
+            pos = pos.saturating_add(len as u64);
+            continue;
+        }
         let mut buf = vec![0u8; len];
         if f.read_exact(&mut buf).is_err() {
             // Truncated payload: real IO error, stop reading
-            truncated_tail += 1;
+            scan.truncated_tail += 1;
             break;
         }
         pos = pos.saturating_add(len as u64);
 
-        // CRC verification: v3 uses CRC32C, v1/v2 use legacy crc32_simple
+        // CRC verification: v3+ uses CRC32C, v1/v2 use legacy crc32_simple
         let crc_ok = if ver >= 3 {
             crc32c_checksum(&buf) == crc
         } else {
@@ -498,17 +647,18 @@ fn read_wal_file(path: &Path) -> anyhow::Result<(Vec<EventRecord>, usize)> {
 
         if !crc_ok {
             // CRC mismatch: skip this record and continue to the next one
-            truncated_tail += 1;
+            scan.truncated_tail += 1;
             continue;
         }
 
-        // Decode version: v3 uses v2 encoding format
+        // Decode version: v3+ uses v2 encoding format
         let decode_ver = if ver >= 3 { 2 } else { ver };
-        if let Some(ev) = decode_event(decode_ver, &buf) {
-            out.push(ev);
+        if let Some(mut ev) = decode_event(decode_ver, &buf) {
+            ev.seq = seq.unwrap_or(0);
+            scan.events.push(ev);
         }
     }
-    Ok((out, truncated_tail))
+    Ok(scan)
 }
 
 fn encode_event(ev: &EventRecord) -> Vec<u8> {
@@ -727,6 +877,71 @@ mod tests {
         assert_eq!(r2.events.len(), 1);
     }
 
+    fn create_event(path: PathBuf) -> EventRecord {
+        EventRecord {
+            seq: 0,
+            timestamp: std::time::SystemTime::now(),
+            event_type: EventType::Create,
+            id: FileIdentifier::Path(path.clone()),
+            path_hint: Some(path),
+        }
+    }
+
+    fn tail_seqs(tail: WalTail) -> Vec<u64> {
+        match tail {
+            WalTail::Events { events, .. } => events.iter().map(|ev| ev.seq).collect(),
+            WalTail::Expired { oldest, latest } => panic!("expired: {oldest}..{latest}"),
+        }
+    }
+
+    #[test]
+    fn wal_journal_seq_spans_seal_and_reopen() {
+        let dir = unique_tmp_dir("journal");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let wal = WalStore::open_in_dir(dir.clone()).unwrap();
+        wal.append(&[create_event(dir.join("a")), create_event(dir.join("b"))])
+            .unwrap();
+        let seal1 = wal.seal().unwrap();
+        wal.append(&[create_event(dir.join("c"))]).unwrap();
+        assert_eq!(wal.last_seq(), 3);
+
+        assert_eq!(tail_seqs(wal.read_since(0, 10).unwrap()), vec![1, 2, 3]);
+        assert_eq!(tail_seqs(wal.read_since(1, 10).unwrap()), vec![2, 3]);
+        assert_eq!(tail_seqs(wal.read_since(0, 2).unwrap()), vec![1, 2]);
+        assert!(tail_seqs(wal.read_since(3, 10).unwrap()).is_empty());
+        drop(wal);
+
+        // 重启后序号续接；sealed 文件清理后，早于保留范围的游标失效。
+        let wal = WalStore::open_in_dir(dir.clone()).unwrap();
+        assert_eq!(wal.last_seq(), 3);
+        wal.cleanup_sealed_up_to(seal1).unwrap();
+        wal.append(&[create_event(dir.join("d"))]).unwrap();
+        assert!(matches!(
+            wal.read_since(1, 10).unwrap(),
+            WalTail::Expired {
+                oldest: 2,
+                latest: 4
+            }
+        ));
+        assert_eq!(tail_seqs(wal.read_since(2, 10).unwrap()), vec![3, 4]);
+        assert!(matches!(
+            wal.read_since(9, 10).unwrap(),
+            WalTail::Expired { latest: 4, .. }
+        ));
+
+        // current 丢失时从最后一个 sealed 文件续接。
+        let seal2 = wal.seal().unwrap();
+        drop(wal);
+        std::fs::remove_file(dir.join("events.wal")).unwrap();
+        let wal = WalStore::open_in_dir(dir.clone()).unwrap();
+        assert_eq!(wal.last_seq(), 4);
+        wal.cleanup_sealed_up_to(seal2).unwrap();
+        assert!(tail_seqs(wal.read_since(4, 10).unwrap()).is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn wal_v1_file_is_sealed_and_replayed_after_upgrade_to_v3() {
         let dir = unique_tmp_dir("upgrade");
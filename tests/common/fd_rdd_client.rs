@@ -19,6 +19,8 @@ pub struct SearchResult {
     #[serde(default)]
     pub size: u64,
     #[serde(default)]
+    pub is_dir: bool,
+    #[serde(default)]
     pub score: i64,
     #[serde(default)]
     pub highlights: Vec<[usize; 2]>,
@@ -0,0 +1,108 @@
+//! P1 — WAL-backed change feed (`GET /changes?since=`).
+
+#[allow(dead_code)]
+mod common;
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use common::{unique_tmp_dir, wait_for_indexed_count, FdRddProcess};
+
+fn unique_port() -> u16 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .subsec_nanos();
+    20_000 + (nanos % 1_000) as u16
+}
+
+fn get_changes(port: u16, query: &str) -> (u16, serde_json::Value) {
+    let resp = reqwest::blocking::get(format!("http://127.0.0.1:{}/changes?{}", port, query))
+        .expect("changes request");
+    let status = resp.status().as_u16();
+    (status, resp.json().expect("changes json"))
+}
+
+/// 轮询直到游标之后出现满足条件的变更。
+fn wait_for_change(
+    port: u16,
+    since: u64,
+    q: &str,
+    pred: impl Fn(&serde_json::Value) -> bool,
+    timeout: Duration,
+) -> Option<serde_json::Value> {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        let (status, body) = get_changes(port, &format!("since={}&q={}", since, q));
+        assert_eq!(status, 200, "{body}");
+        if let Some(change) = body["changes"].as_array().unwrap().iter().find(|c| pred(c)) {
+            return Some(change.clone());
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    None
+}
+
+#[test]
+fn changes_feed_reports_events_since_cursor() {
+    let root = unique_tmp_dir("changes-root");
+    let state_dir = unique_tmp_dir("changes-state");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::create_dir_all(&state_dir).unwrap();
+    std::fs::write(root.join("existing.txt"), "x").unwrap();
+
+    let port = unique_port();
+    let proc = FdRddProcess::spawn(
+        &root,
+        port,
+        &state_dir.join("index.db"),
+        &["--snapshot-interval-secs", "3600", "--debounce-ms", "20"],
+    );
+    wait_for_indexed_count(port, 1, 15).expect("initial file should be indexed");
+
+    // 省略 since：只返回当前游标。
+    let (status, body) = get_changes(port, "");
+    assert_eq!(status, 200, "{body}");
+    assert!(body["changes"].as_array().unwrap().is_empty());
+    let cursor = body["next"].as_u64().unwrap();
+
+    let probe = root.join("journal_probe.log");
+    std::fs::write(&probe, "y").unwrap();
+    // watcher 会在 debounce 窗口内合并同一路径的事件（create + write 可能报告为 modified）。
+    let created = wait_for_change(
+        port,
+        cursor,
+        "journal_probe",
+        |c| c["kind"] == "created" || c["kind"] == "modified",
+        Duration::from_secs(10),
+    )
+    .expect("new file should appear in the change feed");
+    assert!(created["path"]
+        .as_str()
+        .unwrap()
+        .ends_with("journal_probe.log"));
+    assert!(created["seq"].as_u64().unwrap() > cursor);
+
+    std::fs::remove_file(&probe).unwrap();
+    wait_for_change(
+        port,
+        cursor,
+        "journal_probe",
+        |c| c["kind"] == "deleted",
+        Duration::from_secs(10),
+    )
+    .expect("deleted file should appear in the change feed");
+
+    // 过滤掉的变更不返回，但游标照常前进。
+    let (_, body) = get_changes(port, &format!("since={}&q=ext:rs", cursor));
+    assert!(body["changes"].as_array().unwrap().is_empty());
+    assert!(body["next"].as_u64().unwrap() > cursor);
+
+    // 超出保留范围的游标：410 + 重新同步提示。
+    let (status, body) = get_changes(port, "since=999999999");
+    assert_eq!(status, 410);
+    assert_eq!(body["error"], "cursor too old, resync required");
+
+    proc.kill();
+    let _ = std::fs::remove_dir_all(&root);
+    let _ = std::fs::remove_dir_all(&state_dir);
+}
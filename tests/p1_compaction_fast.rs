@@ -5,7 +5,7 @@
 
 use std::path::PathBuf;
 
-use fd_rdd::core::{FileKey, FileMeta};
+use fd_rdd::core::{FileKey, FileKind, FileMeta};
 use fd_rdd::index::PersistentIndex;
 
 fn unique_tmp_dir(tag: &str) -> PathBuf {
@@ -35,6 +35,9 @@ fn fast_compaction_equivalence() {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         };
         idx.upsert(meta);
     }
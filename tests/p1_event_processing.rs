@@ -59,7 +59,7 @@ fn rename_event_updates_index() {
     let new_path = root.join("new_name.txt");
 
     // First, upsert the file directly into L2 so it exists in the index
-    use fd_rdd::core::{FileKey, FileMeta};
+    use fd_rdd::core::{FileKey, FileKind, FileMeta};
     let l2 = index.l2.load_full();
     l2.upsert(FileMeta {
         file_key: FileKey {
@@ -72,6 +72,9 @@ fn rename_event_updates_index() {
         mtime: None,
         ctime: None,
         atime: None,
+        kind: FileKind::File,
+        nlink: 0,
+        target: None,
     });
 
     // L2 被外部直接修改后需要刷新 base 索引
@@ -0,0 +1,90 @@
+//! P1 — `GET /explain` 查询计划说明：anchor 选择、候选估算与分层实际计数。
+
+#[allow(dead_code)]
+mod common;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use common::{unique_tmp_dir, wait_for_indexed_count, FdRddProcess};
+
+fn unique_port() -> u16 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .subsec_nanos();
+    22_000 + (nanos % 1_000) as u16
+}
+
+fn explain(port: u16, query: &[(&str, &str)]) -> serde_json::Value {
+    let resp = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap()
+        .get(format!("http://127.0.0.1:{}/explain", port))
+        .query(query)
+        .send()
+        .expect("explain request");
+    assert!(resp.status().is_success(), "{}", resp.status());
+    resp.json().expect("explain json")
+}
+
+#[test]
+fn explain_reports_plan_and_layer_counts() {
+    let root = unique_tmp_dir("explain-root");
+    let state_dir = unique_tmp_dir("explain-state");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::create_dir_all(&state_dir).unwrap();
+    std::fs::write(root.join("quarterly_report.pdf"), b"x").unwrap();
+    std::fs::write(root.join("notes.txt"), b"x").unwrap();
+
+    let port = unique_port();
+    let proc = FdRddProcess::spawn(
+        &root,
+        port,
+        &state_dir.join("index.db"),
+        &["--snapshot-interval-secs", "3600"],
+    );
+    wait_for_indexed_count(port, 2, 15).expect("files should be indexed");
+
+    let body = explain(port, &[("q", "report ext:pdf !draft")]);
+    assert!(body["compile_error"].is_null(), "{body}");
+    assert_eq!(body["plan"]["parsed"]["include"]["op"], "and", "{body}");
+    assert_eq!(
+        body["plan"]["parsed"]["excludes"][0]["atom"],
+        "text(\"draft\")"
+    );
+    assert_eq!(
+        body["plan"]["anchors"][0]["matcher"],
+        "contains(\"report\")"
+    );
+    assert_eq!(body["anchors"][0]["prefilter"], "trigram");
+    assert!(body["actual"].is_null());
+
+    let body = explain(
+        port,
+        &[
+            ("q", "report ext:pdf"),
+            ("execute", "true"),
+            ("limit", "10"),
+        ],
+    );
+    let actual = &body["actual"];
+    assert_eq!(actual["results"], 1, "{body}");
+    assert_eq!(
+        actual["overlay_matches"].as_u64().unwrap() + actual["base_matches"].as_u64().unwrap(),
+        1
+    );
+
+    // 编译失败时报告错误并说明 legacy 退化
+    let body = explain(port, &[("q", "(unclosed")]);
+    assert!(body["compile_error"]
+        .as_str()
+        .unwrap()
+        .contains("invalid query syntax"));
+    assert!(body["plan"].is_null());
+    assert_eq!(body["anchors"].as_array().unwrap().len(), 1);
+
+    proc.kill();
+    let _ = std::fs::remove_dir_all(&root);
+    let _ = std::fs::remove_dir_all(&state_dir);
+}
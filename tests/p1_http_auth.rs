@@ -0,0 +1,142 @@
+//! P1 — HTTP 监听地址与鉴权：cookie token、read/admin 范围、Unix socket 监听。
+
+#[allow(dead_code)]
+mod common;
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use common::{unique_tmp_dir, FdRddProcess};
+
+fn unique_port() -> u16 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .subsec_nanos();
+    21_000 + (nanos % 1_000) as u16
+}
+
+fn wait_for_file(path: &std::path::Path, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if path.exists() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    false
+}
+
+#[test]
+fn cookie_auth_guards_read_and_admin_endpoints() {
+    let root = unique_tmp_dir("http-auth-root");
+    let state_dir = unique_tmp_dir("http-auth-state");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::create_dir_all(&state_dir).unwrap();
+    std::fs::write(root.join("secret_plan.txt"), b"x").unwrap();
+    let cookie = state_dir.join("http.cookie");
+
+    let port = unique_port();
+    let proc = FdRddProcess::spawn(
+        &root,
+        port,
+        &state_dir.join("index.db"),
+        &[
+            "--snapshot-interval-secs",
+            "3600",
+            "--http-auth",
+            "cookie",
+            "--http-cookie-path",
+            cookie.to_str().unwrap(),
+        ],
+    );
+    assert!(wait_for_file(&cookie, Duration::from_secs(10)));
+    let token = std::fs::read_to_string(&cookie).unwrap();
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap();
+    let base = format!("http://127.0.0.1:{}", port);
+
+    // 存活探测不鉴权
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !proc.health_check() {
+        assert!(Instant::now() < deadline, "server did not become healthy");
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    let anonymous = client
+        .get(format!("{base}/search?q=secret"))
+        .send()
+        .unwrap();
+    assert_eq!(anonymous.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let wrong = client
+        .get(format!("{base}/search?q=secret"))
+        .bearer_auth("not-the-token")
+        .send()
+        .unwrap();
+    assert_eq!(wrong.status(), reqwest::StatusCode::UNAUTHORIZED);
+    let denied_scan = client
+        .post(format!("{base}/scan"))
+        .json(&serde_json::json!({ "paths": [root.to_str().unwrap()] }))
+        .send()
+        .unwrap();
+    assert_eq!(denied_scan.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let authed = client
+        .get(format!("{base}/search?q=secret"))
+        .bearer_auth(&token)
+        .send()
+        .unwrap();
+    assert!(authed.status().is_success(), "{}", authed.status());
+    let scan = client
+        .post(format!("{base}/scan"))
+        .bearer_auth(&token)
+        .json(&serde_json::json!({ "paths": [root.to_str().unwrap()] }))
+        .send()
+        .unwrap();
+    assert!(scan.status().is_success(), "{}", scan.status());
+
+    proc.kill();
+    let _ = std::fs::remove_dir_all(&root);
+    let _ = std::fs::remove_dir_all(&state_dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn http_api_can_listen_on_unix_socket() {
+    use std::io::{Read, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::UnixStream;
+
+    let root = unique_tmp_dir("http-unix-root");
+    let state_dir = unique_tmp_dir("http-unix-state");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::create_dir_all(&state_dir).unwrap();
+    let sock = state_dir.join("http.sock");
+
+    let bind = format!("unix:{}", sock.display());
+    let proc = FdRddProcess::spawn(
+        &root,
+        unique_port(),
+        &state_dir.join("index.db"),
+        &["--snapshot-interval-secs", "3600", "--http-bind", &bind],
+    );
+    assert!(wait_for_file(&sock, Duration::from_secs(10)));
+    let mut stream = UnixStream::connect(&sock).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    stream
+        .write_all(b"GET /status HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    assert!(response.contains("indexed_count"), "{response}");
+    let mode = std::fs::metadata(&sock).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    proc.kill();
+    let _ = std::fs::remove_dir_all(&root);
+    let _ = std::fs::remove_dir_all(&state_dir);
+}
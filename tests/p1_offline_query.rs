@@ -0,0 +1,231 @@
+//! P1 — `fd-rdd-query` 离线查询：daemon 未运行时只读加载快照 + WAL 回放。
+
+#![cfg(unix)]
+
+#[allow(dead_code)]
+mod common;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::SystemTime;
+
+use fd_rdd::core::{EventRecord, EventType, FileIdentifier, FileKey, FileKind, FileMeta};
+use fd_rdd::index::l2_partition::PersistentIndex;
+use fd_rdd::storage::snapshot::{
+    stable_snapshot_dir_for, write_recovery_runtime_state, write_stable_v7_atomic,
+    RecoveryRuntimeState,
+};
+use fd_rdd::storage::wal::WalStore;
+
+use common::unique_tmp_dir;
+
+/// 快照中包含 `in_snapshot.txt`，WAL 中追加 `from_wal.txt` 的创建事件。
+fn prepare_state(root: &Path, snap_path: &Path) -> (PathBuf, PathBuf) {
+    let in_snapshot = root.join("in_snapshot.txt");
+    let from_wal = root.join("from_wal.txt");
+    std::fs::write(&in_snapshot, b"snapshot").unwrap();
+    std::fs::write(&from_wal, b"wal").unwrap();
+
+    let idx = PersistentIndex::new_with_roots(vec![root.to_path_buf()]);
+    idx.upsert(FileMeta {
+        file_key: FileKey {
+            dev: 1,
+            ino: 100,
+            generation: 0,
+        },
+        path: in_snapshot.clone(),
+        size: 8,
+        mtime: None,
+        ctime: None,
+        atime: None,
+        kind: FileKind::File,
+        nlink: 0,
+        target: None,
+    });
+    write_stable_v7_atomic(snap_path, &idx.to_base_index_data()).unwrap();
+
+    let wal = WalStore::open_in_dir(stable_snapshot_dir_for(snap_path)).unwrap();
+    wal.append(&[EventRecord {
+        seq: 1,
+        timestamp: SystemTime::now(),
+        event_type: EventType::Create,
+        id: FileIdentifier::Path(from_wal.clone()),
+        path_hint: Some(from_wal.clone()),
+    }])
+    .unwrap();
+    (in_snapshot, from_wal)
+}
+
+/// 索引目录下每个文件的 (长度, 修改时间)，用于确认离线查询没有写入。
+fn dir_fingerprint(dir: &Path) -> BTreeMap<PathBuf, (u64, SystemTime)> {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|e| {
+            let e = e.unwrap();
+            let m = e.metadata().unwrap();
+            (e.path(), (m.len(), m.modified().unwrap()))
+        })
+        .collect()
+}
+
+fn run_query(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_fd-rdd-query"))
+        .args(args)
+        .output()
+        .expect("run fd-rdd-query")
+}
+
+#[test]
+fn offline_query_reads_snapshot_and_wal_without_writing() {
+    let root = unique_tmp_dir("offline-explicit");
+    std::fs::create_dir_all(&root).unwrap();
+    let snap_path = root.join("index.db");
+    let (in_snapshot, from_wal) = prepare_state(&root, &snap_path);
+    let index_dir = stable_snapshot_dir_for(&snap_path);
+    let before = dir_fingerprint(&index_dir);
+
+    let out = run_query(&[
+        "--offline",
+        "--snapshot-path",
+        snap_path.to_str().unwrap(),
+        "--sort",
+        "name",
+        ".txt",
+    ]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines,
+        vec![from_wal.to_str().unwrap(), in_snapshot.to_str().unwrap()]
+    );
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(stderr.contains("may be stale"), "stderr: {stderr}");
+    assert!(stderr.contains("1 WAL events replayed"), "stderr: {stderr}");
+
+    assert_eq!(dir_fingerprint(&index_dir), before);
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn offline_query_skips_sealed_wal_already_in_snapshot() {
+    let root = unique_tmp_dir("offline-checkpoint");
+    std::fs::create_dir_all(&root).unwrap();
+    let snap_path = root.join("index.db");
+    let (in_snapshot, from_wal) = prepare_state(&root, &snap_path);
+    let after_seal = root.join("after_seal.txt");
+    std::fs::write(&after_seal, b"after").unwrap();
+
+    // 快照边界 seal 之后清理失败：sealed WAL 残留，但 runtime state 记录它已包含在快照里。
+    let wal = WalStore::open_in_dir(stable_snapshot_dir_for(&snap_path)).unwrap();
+    wal.append(&[EventRecord {
+        seq: 2,
+        timestamp: SystemTime::now(),
+        event_type: EventType::Delete,
+        id: FileIdentifier::Path(in_snapshot.clone()),
+        path_hint: Some(in_snapshot.clone()),
+    }])
+    .unwrap();
+    let seal_id = wal.seal().unwrap();
+    write_recovery_runtime_state(
+        &snap_path,
+        &RecoveryRuntimeState {
+            last_wal_seal_id: seal_id,
+            ..RecoveryRuntimeState::default()
+        },
+    )
+    .unwrap();
+    wal.append(&[EventRecord {
+        seq: 3,
+        timestamp: SystemTime::now(),
+        event_type: EventType::Create,
+        id: FileIdentifier::Path(after_seal.clone()),
+        path_hint: Some(after_seal.clone()),
+    }])
+    .unwrap();
+    drop(wal);
+
+    let out = run_query(&[
+        "--offline",
+        "--snapshot-path",
+        snap_path.to_str().unwrap(),
+        "--sort",
+        "name",
+        ".txt",
+    ]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(
+        lines,
+        vec![after_seal.to_str().unwrap(), in_snapshot.to_str().unwrap()]
+    );
+    assert!(!stdout.contains(from_wal.to_str().unwrap()));
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(stderr.contains("1 WAL events replayed"), "stderr: {stderr}");
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn unreachable_daemon_falls_back_to_offline_unless_disabled() {
+    let root = unique_tmp_dir("offline-fallback");
+    std::fs::create_dir_all(&root).unwrap();
+    let snap_path = root.join("index.db");
+    let (in_snapshot, _) = prepare_state(&root, &snap_path);
+    let socket = root.join("missing.sock");
+
+    let out = run_query(&[
+        "--socket",
+        socket.to_str().unwrap(),
+        "--snapshot-path",
+        snap_path.to_str().unwrap(),
+        "--json",
+        "in_snapshot",
+    ]);
+    assert!(out.status.success(), "{:?}", out);
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    let records: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["path"], in_snapshot.to_str().unwrap());
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(stderr.contains("cannot connect"), "stderr: {stderr}");
+    assert!(stderr.contains("may be stale"), "stderr: {stderr}");
+
+    let out = run_query(&[
+        "--socket",
+        socket.to_str().unwrap(),
+        "--snapshot-path",
+        snap_path.to_str().unwrap(),
+        "--no-fallback",
+        "in_snapshot",
+    ]);
+    assert!(!out.status.success());
+    assert!(out.stdout.is_empty());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn offline_query_without_snapshot_reports_error() {
+    let root = unique_tmp_dir("offline-missing");
+    std::fs::create_dir_all(&root).unwrap();
+    let snap_path = root.join("index.db");
+
+    let out = run_query(&[
+        "--offline",
+        "--snapshot-path",
+        snap_path.to_str().unwrap(),
+        "anything",
+    ]);
+    assert!(!out.status.success());
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(stderr.contains("no usable snapshot"), "stderr: {stderr}");
+    assert!(!stable_snapshot_dir_for(&snap_path).exists());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
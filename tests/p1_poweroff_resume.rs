@@ -6,7 +6,8 @@ mod common;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use common::{unique_tmp_dir, wait_for_file_visible, wait_for_indexed_count, FdRddProcess};
-use fd_rdd::storage::snapshot::{read_recovery_runtime_state, stable_v7_path_for};
+use fd_rdd::storage::partitions::partition_manifest_path_for;
+use fd_rdd::storage::snapshot::read_recovery_runtime_state;
 
 fn unique_port() -> u16 {
     let nanos = SystemTime::now()
@@ -58,7 +59,7 @@ fn sigterm_final_snapshot_resumes_incremental_changes() {
 
     let runtime_state = read_recovery_runtime_state(&snapshot_path).unwrap();
     assert!(runtime_state.last_clean_shutdown);
-    assert!(stable_v7_path_for(&snapshot_path).exists());
+    assert!(partition_manifest_path_for(&snapshot_path).exists());
 
     let restart = FdRddProcess::spawn(
         &root,
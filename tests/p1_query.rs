@@ -7,7 +7,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
-use fd_rdd::core::{FileKey, FileMeta};
+use fd_rdd::core::{FileKey, FileKind, FileMeta};
 use fd_rdd::index::TieredIndex;
 use fd_rdd::query::{execute_query, QueryMode, SortColumn, SortOrder};
 
@@ -35,6 +35,9 @@ fn build_index_with_files(root: &Path, files: &[(&str, u64)]) -> Arc<TieredIndex
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
     }
     index
@@ -125,6 +128,9 @@ fn smart_case_handles_case_distinct_siblings() {
                 mtime: None,
                 ctime: None,
                 atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
             },
             FileMeta {
                 file_key: FileKey {
@@ -137,6 +143,9 @@ fn smart_case_handles_case_distinct_siblings() {
                 mtime: None,
                 ctime: None,
                 atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
             },
         ],
     );
@@ -185,6 +194,9 @@ fn score_sort_prefers_basename_match_over_path_segment_match() {
                 mtime: None,
                 ctime: None,
                 atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
             },
             FileMeta {
                 file_key: FileKey {
@@ -197,6 +209,9 @@ fn score_sort_prefers_basename_match_over_path_segment_match() {
                 mtime: None,
                 ctime: None,
                 atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
             },
         ],
     );
@@ -235,6 +250,9 @@ fn score_sort_prefers_boundary_hit_for_env_style_files() {
                 mtime: None,
                 ctime: None,
                 atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
             },
             FileMeta {
                 file_key: FileKey {
@@ -247,6 +265,9 @@ fn score_sort_prefers_boundary_hit_for_env_style_files() {
                 mtime: None,
                 ctime: None,
                 atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
             },
         ],
     );
@@ -285,6 +306,9 @@ fn score_sort_demotes_node_modules_when_query_has_no_node_hint() {
                 mtime: None,
                 ctime: None,
                 atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
             },
             FileMeta {
                 file_key: FileKey {
@@ -297,6 +321,9 @@ fn score_sort_demotes_node_modules_when_query_has_no_node_hint() {
                 mtime: None,
                 ctime: None,
                 atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
             },
         ],
     );
@@ -335,6 +362,9 @@ fn score_sort_uses_shorter_basename_as_tiebreaker() {
                 mtime: None,
                 ctime: None,
                 atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
             },
             FileMeta {
                 file_key: FileKey {
@@ -347,6 +377,9 @@ fn score_sort_uses_shorter_basename_as_tiebreaker() {
                 mtime: None,
                 ctime: None,
                 atime: None,
+                kind: FileKind::File,
+                nlink: 0,
+                target: None,
             },
         ],
     );
@@ -388,6 +421,9 @@ fn large_result_set_query_does_not_oom() {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         });
     }
 
@@ -495,6 +531,9 @@ fn depth_len_and_type_filters_work() {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         },
         FileMeta {
             file_key: FileKey {
@@ -507,6 +546,9 @@ fn depth_len_and_type_filters_work() {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         },
         FileMeta {
             file_key: FileKey {
@@ -519,6 +561,9 @@ fn depth_len_and_type_filters_work() {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         },
         FileMeta {
             file_key: FileKey {
@@ -531,6 +576,9 @@ fn depth_len_and_type_filters_work() {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         },
         FileMeta {
             file_key: FileKey {
@@ -543,6 +591,9 @@ fn depth_len_and_type_filters_work() {
             mtime: None,
             ctime: None,
             atime: None,
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         },
     ];
     let index = build_index_with_metas(&root, &metas);
@@ -599,6 +650,9 @@ fn execute_query_sorts_by_size_and_modified_time() {
             mtime: Some(oldest),
             ctime: Some(older),
             atime: Some(oldest),
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         },
         FileMeta {
             file_key: FileKey {
@@ -611,6 +665,9 @@ fn execute_query_sorts_by_size_and_modified_time() {
             mtime: Some(older),
             ctime: Some(oldest),
             atime: Some(older),
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         },
         FileMeta {
             file_key: FileKey {
@@ -623,6 +680,9 @@ fn execute_query_sorts_by_size_and_modified_time() {
             mtime: Some(now),
             ctime: Some(now),
             atime: Some(now),
+            kind: FileKind::File,
+            nlink: 0,
+            target: None,
         },
     ];
     let index = build_index_with_metas(&root, &metas);
@@ -649,3 +709,46 @@ fn execute_query_sorts_by_size_and_modified_time() {
 
     let _ = std::fs::remove_dir_all(&root);
 }
+
+#[test]
+fn execute_query_ranks_before_truncating() {
+    let root = unique_tmp_dir("query-top-k");
+    std::fs::create_dir_all(&root).unwrap();
+
+    // 大量弱命中先入索引，最相关的 config.toml 最后入索引。
+    let mut files: Vec<(String, u64)> = (0..300)
+        .map(|i| {
+            (
+                format!("deps/vendor/pkg{i}/src/loader_config_helper_{i}.rs"),
+                i,
+            )
+        })
+        .collect();
+    files.push(("config.toml".to_string(), 1));
+    let files_ref: Vec<(&str, u64)> = files.iter().map(|(p, s)| (p.as_str(), *s)).collect();
+    let index = build_index_with_files(&root, &files_ref);
+
+    let results = execute_query(
+        index.as_ref(),
+        "config",
+        1,
+        QueryMode::Exact,
+        SortColumn::Score,
+        SortOrder::Desc,
+    );
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].path, root.join("config.toml"));
+
+    let largest = execute_query(
+        index.as_ref(),
+        "config",
+        3,
+        QueryMode::Exact,
+        SortColumn::Size,
+        SortOrder::Desc,
+    );
+    let sizes: Vec<u64> = largest.iter().map(|m| m.size).collect();
+    assert_eq!(sizes, vec![299, 298, 297]);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
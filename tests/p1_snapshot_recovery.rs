@@ -5,7 +5,7 @@
 
 use std::path::PathBuf;
 
-use fd_rdd::core::{FileKey, FileMeta};
+use fd_rdd::core::{FileKey, FileKind, FileMeta};
 use fd_rdd::index::l2_partition::PersistentIndex;
 use fd_rdd::index::TieredIndex;
 use fd_rdd::storage::snapshot::SnapshotStore;
@@ -36,6 +36,9 @@ fn one_file_base(root: &std::path::Path, name: &str) -> fd_rdd::index::base_inde
         mtime: None,
         ctime: None,
         atime: None,
+        kind: FileKind::File,
+        nlink: 0,
+        target: None,
     });
     idx.to_base_index_data()
 }
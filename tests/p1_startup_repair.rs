@@ -2,11 +2,12 @@
 
 use std::path::PathBuf;
 
-use fd_rdd::core::{FileKey, FileMeta};
+use fd_rdd::core::{FileKey, FileKind, FileMeta};
 use fd_rdd::index::l2_partition::PersistentIndex;
 use fd_rdd::index::TieredIndex;
 use fd_rdd::storage::snapshot::{
-    write_recovery_runtime_state, write_stable_v7_atomic, RecoveryRuntimeState, SnapshotStore,
+    read_repair_cursor, repair_cursor_path_for, write_recovery_runtime_state,
+    write_stable_v7_atomic, RecoveryRuntimeState, SnapshotStore,
 };
 
 fn unique_tmp_dir(tag: &str) -> PathBuf {
@@ -34,6 +35,9 @@ fn one_physical_file_base(
         mtime: meta.modified().ok(),
         ctime: meta.created().ok(),
         atime: meta.accessed().ok(),
+        kind: FileKind::File,
+        nlink: 0,
+        target: None,
     });
     idx.to_base_index_data()
 }
@@ -90,9 +94,12 @@ async fn startup_repair_dirty_only_skips_after_clean_shutdown() {
 
 #[tokio::test]
 async fn startup_repair_dirty_only_runs_after_unclean_shutdown() {
-    let root = unique_tmp_dir("dirty-unclean");
+    let tmp = unique_tmp_dir("dirty-unclean");
+    // 快照放在 root 之外：修复会逐层进入新发现的目录，`index.d` 不应计入结果。
+    let root = tmp.join("data");
     std::fs::create_dir_all(&root).unwrap();
-    let snap_path = root.join("index.db");
+    let snap_path = tmp.join("state").join("index.db");
+    std::fs::create_dir_all(snap_path.parent().unwrap()).unwrap();
     let store = SnapshotStore::new(snap_path.clone());
 
     let base = one_physical_file_base(&root, "indexed.txt");
@@ -117,10 +124,62 @@ async fn startup_repair_dirty_only_runs_after_unclean_shutdown() {
 
     assert!(index.recovery_status().report.requires_repair);
     assert!(stats.ran);
+    // indexed.txt + offline-new.txt
     assert_eq!(stats.scanned, 2);
     assert_eq!(stats.changed, 1);
+    assert!(!stats.in_progress);
 
-    let _ = std::fs::remove_dir_all(&root);
+    let _ = std::fs::remove_dir_all(&tmp);
+}
+
+#[tokio::test]
+async fn startup_repair_yields_at_budget_and_resumes_from_cursor() {
+    let tmp = unique_tmp_dir("resume");
+    let root = tmp.join("data");
+    for sub in ["a", "b", "c"] {
+        std::fs::create_dir_all(root.join(sub)).unwrap();
+        std::fs::write(root.join(sub).join("f.txt"), b"x").unwrap();
+    }
+    let snap_path = tmp.join("state").join("index.db");
+    std::fs::create_dir_all(snap_path.parent().unwrap()).unwrap();
+    let cursor_path = repair_cursor_path_for(&snap_path);
+    let store = SnapshotStore::new(snap_path.clone());
+    let base = one_physical_file_base(&root, "indexed.txt");
+    write_stable_v7_atomic(&snap_path, &base).unwrap();
+
+    // 预算为 0：前台一批都不扫，直接让出并留下游标。
+    let index = TieredIndex::load_or_empty(&store, vec![root.clone()])
+        .await
+        .unwrap();
+    let stats = index.startup_repair_with_cursor(true, "always", 1, 0, 1.0, &[], Some(&snap_path));
+    assert!(stats.ran);
+    assert!(stats.in_progress);
+    assert!(!stats.escalated);
+    assert_eq!(stats.dirs_done, 0);
+    assert!(index.recovery_status().repair.in_progress);
+    let cursor = read_repair_cursor(&snap_path).unwrap().unwrap();
+    assert_eq!(cursor.hot, vec![root.clone()]);
+    // 游标只记录分区遍历位置，不展开目录列表。
+    assert_eq!(cursor.positions.len(), 1);
+    assert_eq!(cursor.positions[0].root, root);
+    assert_eq!(cursor.positions[0].next_docid, 0);
+
+    // 下次启动：存在未完成游标时不论 mode 判定如何都续跑到结束。
+    let index = TieredIndex::load_or_empty(&store, vec![root.clone()])
+        .await
+        .unwrap();
+    let stats =
+        index.startup_repair_with_cursor(true, "dirty-only", 1, 10_000, 1.0, &[], Some(&snap_path));
+    assert!(stats.resumed);
+    assert!(!stats.in_progress);
+    // root + a/b/c
+    assert_eq!(stats.dirs_done, 4);
+    assert_eq!(stats.dirs_pending, 0);
+    assert_eq!(stats.scanned, 7);
+    assert!(index.file_count() >= 4);
+    assert!(!cursor_path.exists());
+
+    let _ = std::fs::remove_dir_all(&tmp);
 }
 
 #[test]
@@ -0,0 +1,123 @@
+//! P1 — Live query subscriptions over SSE (`GET /subscribe`).
+
+#[allow(dead_code)]
+mod common;
+
+use std::io::{BufRead, BufReader};
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use common::{unique_tmp_dir, wait_for_indexed_count, FdRddProcess};
+
+fn unique_port() -> u16 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .subsec_nanos();
+    19_000 + (nanos % 1_000) as u16
+}
+
+/// 读取 SSE 流，按 (event, data) 逐条送出。
+fn spawn_sse_reader(url: String) -> mpsc::Receiver<(String, String)> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let resp = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(60))
+            .build()
+            .unwrap()
+            .get(&url)
+            .send()
+            .unwrap();
+        assert!(resp.status().is_success(), "{}", resp.status());
+        let mut event = String::new();
+        for line in BufReader::new(resp).lines() {
+            let Ok(line) = line else {
+                return;
+            };
+            if let Some(name) = line.strip_prefix("event:") {
+                event = name.trim().to_string();
+            } else if let Some(data) = line.strip_prefix("data:") {
+                if tx.send((event.clone(), data.trim().to_string())).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    rx
+}
+
+fn wait_for_event(
+    rx: &mpsc::Receiver<(String, String)>,
+    name: &str,
+    needle: &str,
+    timeout: Duration,
+) -> bool {
+    let deadline = Instant::now() + timeout;
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        match rx.recv_timeout(left) {
+            Ok((event, data)) if event == name && data.contains(needle) => return true,
+            Ok(_) => {}
+            Err(_) => return false,
+        }
+    }
+    false
+}
+
+#[test]
+fn subscribe_streams_snapshot_then_diffs() {
+    let root = unique_tmp_dir("subscribe-root");
+    let state_dir = unique_tmp_dir("subscribe-state");
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::create_dir_all(&state_dir).unwrap();
+    std::fs::write(root.join("existing_sub_probe.log"), "x").unwrap();
+
+    let port = unique_port();
+    let proc = FdRddProcess::spawn(
+        &root,
+        port,
+        &state_dir.join("index.db"),
+        &["--snapshot-interval-secs", "3600", "--debounce-ms", "20"],
+    );
+    wait_for_indexed_count(port, 1, 15).expect("initial file should be indexed");
+
+    let events = spawn_sse_reader(format!(
+        "http://127.0.0.1:{}/subscribe?q=sub_probe%20ext:log",
+        port
+    ));
+    assert!(
+        wait_for_event(
+            &events,
+            "snapshot",
+            "existing_sub_probe.log",
+            Duration::from_secs(10)
+        ),
+        "initial snapshot should contain the existing file"
+    );
+
+    let added = root.join("added_sub_probe.log");
+    std::fs::write(&added, "y").unwrap();
+    assert!(
+        wait_for_event(
+            &events,
+            "add",
+            "added_sub_probe.log",
+            Duration::from_secs(10)
+        ),
+        "new matching file should be pushed as add"
+    );
+
+    std::fs::remove_file(&added).unwrap();
+    assert!(
+        wait_for_event(
+            &events,
+            "remove",
+            "added_sub_probe.log",
+            Duration::from_secs(10)
+        ),
+        "deleted file should be pushed as remove"
+    );
+
+    proc.kill();
+    let _ = std::fs::remove_dir_all(&root);
+    let _ = std::fs::remove_dir_all(&state_dir);
+}
@@ -181,3 +181,39 @@ fn steam_proton_dosdevices_scenario() {
 
     let _ = std::fs::remove_dir_all(&root);
 }
+
+/// 6. 指向根内目录的链接排在目标之前：目录只遍历一次，文件不会以两条路径重复收录
+#[test]
+fn follow_links_walks_in_root_target_once_whichever_path_comes_first() {
+    let root = unique_tmp_dir("in-root-alias");
+    // a_link 排在 z_real 之前，z_link 排在 a_real 之后：两种到达顺序都覆盖到。
+    std::fs::create_dir_all(root.join("z_real")).unwrap();
+    std::fs::create_dir_all(root.join("a_real")).unwrap();
+    std::fs::write(root.join("z_real/late.txt"), b"late").unwrap();
+    std::fs::write(root.join("a_real/early.txt"), b"early").unwrap();
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(root.join("z_real"), root.join("a_link")).unwrap();
+        std::os::unix::fs::symlink(root.join("a_real"), root.join("z_link")).unwrap();
+    }
+
+    let count = |seen: &[PathBuf], name: &str| seen.iter().filter(|p| p.ends_with(name)).count();
+    for parallelism in [1, 4] {
+        let rdd = FsScanRDD::from_roots(vec![root.clone()])
+            .with_follow_links(true)
+            .with_parallelism(parallelism);
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        rdd.for_each_meta(move |meta: FileMeta| sink.lock().unwrap().push(meta.path));
+        let seen = seen.lock().unwrap().clone();
+
+        assert_eq!(count(&seen, "late.txt"), 1, "{seen:?}");
+        assert_eq!(count(&seen, "early.txt"), 1, "{seen:?}");
+        // 被剪掉的路径本身仍作为条目收录。
+        for name in ["a_link", "z_link", "a_real", "z_real"] {
+            assert_eq!(count(&seen, name), 1, "{name}: {seen:?}");
+        }
+    }
+
+    let _ = std::fs::remove_dir_all(&root);
+}